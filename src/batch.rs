@@ -0,0 +1,1181 @@
+//! 批量翻译调度
+//!
+//! 将输入按行拆分为独立条目并发翻译，输出保持输入顺序；设置 [`BatchOptions::unordered`]
+//! 时按完成顺序输出。
+//! 设置截止时间后，调度器在截止时间临近时停止派发新条目，
+//! 已派发的请求继续完成，未派发的条目记为未处理。
+//!
+//! 设置了 [`BatchOptions::abort_after`] 时，若最先完成的 K 个条目全部因同一类不可重试的错误
+//! （如认证失败、模型不存在）而失败，调度器停止派发，整批任务以该错误结束。
+//!
+//! 条目因认证失败（[`TransomeError::AuthenticationError`]）而失败时，其余条目同样无法完成，
+//! 无论 [`OnError`] 如何设置都立即停止派发（见 [`BatchOptions::fail_fast_on_auth`]）。
+//!
+//! 从断点继续时（见 [`run_batch_resumed`]），已完成的条目直接使用上次的译文，不再派发，
+//! 也不计入截止时间的耗时估算。
+//!
+//! 批次结束后，[`BatchReport::failures`] 将所有失败条目汇总为 [`BatchError`]，
+//! 同类错误合并计数（如 `12 × RateLimited, 5 × ContentFiltered`）。
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::mem::{self, Discriminant};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use serde::ser::SerializeStruct;
+
+use crate::error::TransomeError;
+use crate::retry;
+
+/// 时钟抽象，便于在测试中注入可控时间
+pub trait Clock: Send + Sync {
+    /// 当前时刻
+    fn now(&self) -> Instant;
+
+    /// 等待指定时长
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+}
+
+/// 使用系统单调时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// 默认的最大并发请求数
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// 默认在最先完成的几个条目全部因同一错误失败时中止批次
+pub const DEFAULT_ABORT_AFTER: usize = 3;
+
+/// 条目翻译失败时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OnError {
+    /// 停止派发新条目，输出失败条目之前的结果；文档格式中不输出文档
+    #[value(alias = "fail")]
+    Stop,
+    /// 跳过失败的条目，继续处理其余条目；文档格式中保留原文
+    #[default]
+    Continue,
+    /// 以原文代替失败条目的译文，保持输出与输入逐行对齐
+    #[value(alias = "keep-source")]
+    KeepOriginal,
+    /// 以占位标记（--error-placeholder）代替失败条目的译文
+    Placeholder,
+}
+
+/// 批量翻译选项
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions {
+    /// 同时进行的最大请求数
+    pub concurrency: usize,
+    /// 整个批次的截止时刻
+    pub deadline: Option<Instant>,
+    /// 条目翻译失败时的处理方式
+    pub on_error: OnError,
+    /// 最先完成的这么多个条目全部因同一类不可重试的错误失败时中止批次，None 表示不中止
+    pub abort_after: Option<usize>,
+    /// 条目完成后立即交给回调，不等待之前的条目；报告中的条目仍按输入顺序排列
+    pub unordered: bool,
+    /// 有条目因认证失败而失败时立即停止派发，不受 `on_error` 影响
+    pub fail_fast_on_auth: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            deadline: None,
+            on_error: OnError::default(),
+            abort_after: None,
+            unordered: false,
+            fail_fast_on_auth: true,
+        }
+    }
+}
+
+/// 单个条目的处理状态
+#[derive(Debug)]
+pub enum ItemStatus {
+    /// 翻译成功
+    Succeeded(String),
+    /// 翻译失败
+    Failed(anyhow::Error),
+    /// 因截止时间临近而未派发
+    NotStarted,
+}
+
+/// 单个条目的处理结果
+#[derive(Debug)]
+pub struct ItemResult {
+    /// 条目在输入中的序号（从 0 开始）
+    pub index: usize,
+    /// 原文
+    pub source: String,
+    pub status: ItemStatus,
+    /// 发出的请求次数（含重试），未发出请求时为 0
+    pub attempts: u32,
+}
+
+impl ItemResult {
+    /// 该条目应输出的内容，不输出时为 None
+    ///
+    /// 失败的条目仅在 [`OnError::KeepOriginal`] 下输出原文，占位标记由调用方输出。
+    pub fn output(&self, on_error: OnError) -> Option<&str> {
+        match &self.status {
+            ItemStatus::Succeeded(translation) => Some(translation),
+            ItemStatus::Failed(_) if on_error == OnError::KeepOriginal => Some(&self.source),
+            ItemStatus::Failed(_) | ItemStatus::NotStarted => None,
+        }
+    }
+}
+
+/// 已处理条目的整体结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum BatchOutcome {
+    /// 全部成功（包括没有条目的情况）
+    AllSucceeded,
+    /// 部分成功、部分失败
+    PartialSuccess,
+    /// 全部失败
+    AllFailed,
+}
+
+impl BatchOutcome {
+    /// 根据成功与失败的条目数确定整体结果
+    pub fn from_counts(succeeded: usize, failed: usize) -> Self {
+        match (succeeded, failed) {
+            (_, 0) => Self::AllSucceeded,
+            (0, _) => Self::AllFailed,
+            _ => Self::PartialSuccess,
+        }
+    }
+}
+
+/// 批量翻译报告，条目按输入顺序排列
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub items: Vec<ItemResult>,
+    /// 是否因截止时间而停止派发
+    pub deadline_reached: bool,
+    /// 是否因 [`OnError::Stop`] 在条目失败后停止派发
+    pub stopped_on_error: bool,
+    /// 是否因最先完成的条目全部因同一错误失败而中止（见 [`BatchOptions::abort_after`]）
+    pub aborted: bool,
+    /// 是否因条目认证失败而中止（见 [`BatchOptions::fail_fast_on_auth`]）
+    pub auth_failed: bool,
+}
+
+impl BatchReport {
+    /// 取出第一个失败条目的错误
+    pub fn into_first_error(self) -> Option<anyhow::Error> {
+        self.items.into_iter().find_map(|item| match item.status {
+            ItemStatus::Failed(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// 成功条目数
+    pub fn succeeded(&self) -> usize {
+        self.count(|status| matches!(status, ItemStatus::Succeeded(_)))
+    }
+
+    /// 失败条目数
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, ItemStatus::Failed(_)))
+    }
+
+    /// 未处理条目数
+    pub fn not_started(&self) -> usize {
+        self.count(|status| matches!(status, ItemStatus::NotStarted))
+    }
+
+    /// 已处理条目的整体结果，未处理的条目不计入
+    pub fn outcome(&self) -> BatchOutcome {
+        BatchOutcome::from_counts(self.succeeded(), self.failed())
+    }
+
+    fn count(&self, predicate: impl Fn(&ItemStatus) -> bool) -> usize {
+        self.items
+            .iter()
+            .filter(|item| predicate(&item.status))
+            .count()
+    }
+
+    /// 汇总所有失败条目，没有失败时为 None
+    pub fn failures(&self) -> Option<BatchError> {
+        let failures: Vec<ItemFailure> = self
+            .items
+            .iter()
+            .filter_map(|item| match &item.status {
+                ItemStatus::Failed(error) => Some(ItemFailure {
+                    index: item.index,
+                    kind: error_kind(error).to_string(),
+                    message: format!("{:#}", error),
+                    attempts: item.attempts,
+                }),
+                _ => None,
+            })
+            .collect();
+        (!failures.is_empty()).then_some(BatchError { failures })
+    }
+}
+
+/// 错误的类别名称，见 [`TransomeError::kind_name`]；不是 [`TransomeError`] 的错误为 `Other`
+pub fn error_kind(error: &anyhow::Error) -> &'static str {
+    error
+        .downcast_ref::<TransomeError>()
+        .map_or("Other", TransomeError::kind_name)
+}
+
+/// 一个失败的条目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct ItemFailure {
+    /// 条目在输入中的序号（从 0 开始）
+    pub index: usize,
+    /// 错误类别，见 [`error_kind`]
+    pub kind: String,
+    /// 包含上下文的完整错误信息
+    pub message: String,
+    /// 发出的请求次数（含重试）
+    pub attempts: u32,
+}
+
+/// 批次中所有失败条目的汇总，条目按输入顺序排列
+///
+/// 显示为按类别合并的计数，如 `12 × RateLimited, 5 × ContentFiltered`；
+/// 序列化时同时包含分组计数与每个失败条目。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchError {
+    pub failures: Vec<ItemFailure>,
+}
+
+impl BatchError {
+    /// 按错误类别分组计数，数量多的在前，数量相同时按首次出现的顺序
+    pub fn groups(&self) -> Vec<(&str, usize)> {
+        let mut groups: Vec<(&str, usize)> = Vec::new();
+        for failure in &self.failures {
+            match groups.iter_mut().find(|(kind, _)| *kind == failure.kind) {
+                Some((_, count)) => *count += 1,
+                None => groups.push((failure.kind.as_str(), 1)),
+            }
+        }
+        // 稳定排序保留首次出现的顺序
+        groups.sort_by_key(|group| std::cmp::Reverse(group.1));
+        groups
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<String> = self
+            .groups()
+            .into_iter()
+            .map(|(kind, count)| format!("{} × {}", count, kind))
+            .collect();
+        write!(f, "{}", groups.join(", "))
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl Serialize for BatchError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        /// 一个错误类别及其条目数
+        #[derive(Serialize)]
+        struct Group<'a> {
+            kind: &'a str,
+            count: usize,
+        }
+
+        let groups: Vec<Group> = self
+            .groups()
+            .into_iter()
+            .map(|(kind, count)| Group { kind, count })
+            .collect();
+        let mut state = serializer.serialize_struct("BatchError", 3)?;
+        state.serialize_field("failed", &self.failures.len())?;
+        state.serialize_field("groups", &groups)?;
+        state.serialize_field("failures", &self.failures)?;
+        state.end()
+    }
+}
+
+/// 只读取 `failures`，`failed` 与 `groups` 由失败条目重新计算
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BatchError {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Failures {
+            failures: Vec<ItemFailure>,
+        }
+
+        let Failures { failures } = Failures::deserialize(deserializer)?;
+        Ok(Self { failures })
+    }
+}
+
+/// 将输入按行拆分为批量条目，忽略空白行
+pub fn split_lines(input: &str) -> Vec<String> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 已完成请求的平均耗时，用于估算截止前还能否派发新条目
+#[derive(Debug, Default)]
+struct LatencyTracker {
+    total: Duration,
+    count: u32,
+}
+
+impl LatencyTracker {
+    fn record(&mut self, latency: Duration) {
+        self.total += latency;
+        self.count += 1;
+    }
+
+    fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total / self.count
+        }
+    }
+}
+
+/// 不可重试错误的类别，可重试或无法识别的错误为 None
+fn fatal_class(error: &anyhow::Error) -> Option<Discriminant<TransomeError>> {
+    if retry::is_retryable(error) {
+        return None;
+    }
+    error.downcast_ref::<TransomeError>().map(mem::discriminant)
+}
+
+/// 是否为认证失败，密钥无效时所有条目都会以同样的错误失败
+fn is_auth_failure(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<TransomeError>()
+        .is_some_and(TransomeError::is_auth_error)
+}
+
+/// 熔断器：记录最先完成的条目的错误类别
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    classes: Vec<Option<Discriminant<TransomeError>>>,
+    open: bool,
+}
+
+impl CircuitBreaker {
+    /// 记录一个完成条目的结果（成功为 None），前 `threshold` 个条目类别相同时断开
+    fn record(&mut self, threshold: usize, class: Option<Discriminant<TransomeError>>) {
+        if threshold == 0 || self.classes.len() >= threshold {
+            return;
+        }
+        self.classes.push(class);
+        if self.classes.len() == threshold {
+            let first = self.classes[0];
+            self.open = first.is_some() && self.classes.iter().all(|&class| class == first);
+        }
+    }
+}
+
+/// 执行批量翻译
+///
+/// 每个条目派发前检查截止时间：剩余时间不足以完成一个平均耗时的请求时停止派发，
+/// 为已派发的请求留出完成时间。使用 [`OnError::Stop`] 时，有条目失败后同样停止派发；
+/// 熔断器断开（见 [`BatchOptions::abort_after`]）或有条目认证失败后也停止派发。
+pub async fn run_batch<C, F, Fut>(
+    items: Vec<String>,
+    options: BatchOptions,
+    clock: &C,
+    translate: F,
+) -> BatchReport
+where
+    C: Clock + ?Sized,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+{
+    run_batch_streaming(items, options, clock, translate, |_| {}).await
+}
+
+/// 执行批量翻译，`translate` 同时收到条目的序号，用于查找与条目对应的附加信息
+///
+/// 其余行为同 [`run_batch`]。
+pub async fn run_batch_indexed<C, F, Fut>(
+    items: Vec<String>,
+    options: BatchOptions,
+    clock: &C,
+    translate: F,
+) -> BatchReport
+where
+    C: Clock + ?Sized,
+    F: Fn(usize, String) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+{
+    run_indexed(items, &BTreeMap::new(), options, clock, translate, |_| {}).await
+}
+
+/// 执行批量翻译，条目按输入顺序依次交给 `on_result`
+///
+/// 某个条目及其之前的条目都已完成时立即调用 `on_result`，不必等待整批结束，
+/// 并发翻译时输出顺序仍与输入顺序一致。设置了 [`BatchOptions::unordered`] 时，
+/// 条目一完成就交给 `on_result`，顺序取决于完成的先后。其余行为同 [`run_batch`]。
+pub async fn run_batch_streaming<C, F, Fut, R>(
+    items: Vec<String>,
+    options: BatchOptions,
+    clock: &C,
+    translate: F,
+    on_result: R,
+) -> BatchReport
+where
+    C: Clock + ?Sized,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+    R: FnMut(&ItemResult),
+{
+    run_batch_resumed(
+        items,
+        &BTreeMap::new(),
+        options,
+        clock,
+        translate,
+        on_result,
+    )
+    .await
+}
+
+/// 从断点继续批量翻译，`done` 为上次已完成条目的序号与译文
+///
+/// 已完成的条目不再派发，直接以记录的译文作为成功结果交给 `on_result`。
+/// 其余行为同 [`run_batch_streaming`]。
+pub async fn run_batch_resumed<C, F, Fut, R>(
+    items: Vec<String>,
+    done: &BTreeMap<usize, String>,
+    options: BatchOptions,
+    clock: &C,
+    translate: F,
+    on_result: R,
+) -> BatchReport
+where
+    C: Clock + ?Sized,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+    R: FnMut(&ItemResult),
+{
+    run_indexed(
+        items,
+        done,
+        options,
+        clock,
+        |_, source| translate(source),
+        on_result,
+    )
+    .await
+}
+
+async fn run_indexed<C, F, Fut, R>(
+    items: Vec<String>,
+    done: &BTreeMap<usize, String>,
+    options: BatchOptions,
+    clock: &C,
+    translate: F,
+    on_result: R,
+) -> BatchReport
+where
+    C: Clock + ?Sized,
+    F: Fn(usize, String) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+    R: FnMut(&ItemResult),
+{
+    let latency = Mutex::new(LatencyTracker::default());
+    let stopped = Mutex::new(false);
+    let failed = Mutex::new(false);
+    let auth_failed = Mutex::new(false);
+    let breaker = Mutex::new(CircuitBreaker::default());
+
+    let translate = &translate;
+    let latency = &latency;
+    let stopped = &stopped;
+    let failed = &failed;
+    let auth_failed = &auth_failed;
+    let breaker = &breaker;
+
+    let items = stream::iter(items.into_iter().enumerate()).map(|(index, source)| async move {
+        if let Some(translation) = done.get(&index) {
+            return ItemResult {
+                index,
+                source,
+                status: ItemStatus::Succeeded(translation.clone()),
+                attempts: 0,
+            };
+        }
+
+        let start = clock.now();
+
+        let near_deadline = options.deadline.is_some_and(|deadline| {
+            let expected = latency.lock().unwrap().average();
+            start + expected >= deadline
+        });
+
+        let not_started = ItemResult {
+            index,
+            source: source.clone(),
+            status: ItemStatus::NotStarted,
+            attempts: 0,
+        };
+        if options.on_error == OnError::Stop && *failed.lock().unwrap() {
+            return not_started;
+        }
+        if breaker.lock().unwrap().open || *auth_failed.lock().unwrap() {
+            return not_started;
+        }
+        if near_deadline || *stopped.lock().unwrap() {
+            *stopped.lock().unwrap() = true;
+            return not_started;
+        }
+
+        let (result, attempts) = retry::count_attempts(translate(index, source.clone())).await;
+        let status = match result {
+            Ok(translation) => ItemStatus::Succeeded(translation),
+            Err(e) => {
+                *failed.lock().unwrap() = true;
+                if options.fail_fast_on_auth && is_auth_failure(&e) {
+                    *auth_failed.lock().unwrap() = true;
+                }
+                ItemStatus::Failed(e)
+            }
+        };
+        if let Some(threshold) = options.abort_after {
+            let class = match &status {
+                ItemStatus::Failed(e) => fatal_class(e),
+                _ => None,
+            };
+            breaker.lock().unwrap().record(threshold, class);
+        }
+        latency
+            .lock()
+            .unwrap()
+            .record(clock.now().saturating_duration_since(start));
+
+        ItemResult {
+            index,
+            source,
+            status,
+            attempts,
+        }
+    });
+
+    let concurrency = options.concurrency.max(1);
+    let results: Vec<ItemResult> = if options.unordered {
+        let mut results: Vec<ItemResult> = items
+            .buffer_unordered(concurrency)
+            .inspect(on_result)
+            .collect()
+            .await;
+        results.sort_by_key(|item| item.index);
+        results
+    } else {
+        items
+            .buffered(concurrency)
+            .inspect(on_result)
+            .collect()
+            .await
+    };
+
+    let deadline_reached = *stopped.lock().unwrap();
+    let stopped_on_error = options.on_error == OnError::Stop && *failed.lock().unwrap();
+    let aborted = breaker.lock().unwrap().open;
+    let auth_failed = *auth_failed.lock().unwrap();
+    BatchReport {
+        items: results,
+        deadline_reached,
+        stopped_on_error,
+        aborted,
+        auth_failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::test_util::ManualClock;
+
+    fn items(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("line {}", i)).collect()
+    }
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(
+            split_lines("first\n\n  second  \r\nthird\n   \n"),
+            vec!["first", "second", "third"]
+        );
+        assert!(split_lines("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_streaming_emits_before_batch_completes() {
+        let clock = ManualClock::new();
+        let first_emitted = tokio::sync::Notify::new();
+        let first_emitted = &first_emitted;
+        let mut emitted = Vec::new();
+
+        // 最后一个条目等到第一个条目输出后才完成：只有逐条输出时才不会卡住
+        let report = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            run_batch_streaming(
+                items(4),
+                BatchOptions {
+                    concurrency: 4,
+                    ..BatchOptions::default()
+                },
+                &clock,
+                |text| async move {
+                    if text == "line 3" {
+                        first_emitted.notified().await;
+                    } else if text != "line 0" {
+                        tokio::task::yield_now().await;
+                    }
+                    Ok(text.to_uppercase())
+                },
+                |item| {
+                    if item.index == 0 {
+                        first_emitted.notify_one();
+                    }
+                    emitted.push(item.index);
+                },
+            ),
+        )
+        .await
+        .expect("第一个条目应在整批完成前输出");
+
+        assert_eq!(report.succeeded(), 4);
+        assert_eq!(emitted, [0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_unordered_emits_each_index_once_in_completion_order() {
+        let clock = ManualClock::new();
+        let mut emitted = Vec::new();
+
+        // 越靠后的条目完成得越早
+        let report = run_batch_streaming(
+            items(6),
+            BatchOptions {
+                concurrency: 6,
+                unordered: true,
+                ..BatchOptions::default()
+            },
+            &clock,
+            |text| async move {
+                let index: u64 = text.trim_start_matches("line ").parse().unwrap();
+                tokio::time::sleep(Duration::from_millis((6 - index) * 20)).await;
+                Ok(text.to_uppercase())
+            },
+            |item| emitted.push(item.index),
+        )
+        .await;
+
+        assert_eq!(emitted[0], 5);
+        let mut sorted = emitted.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, [0, 1, 2, 3, 4, 5]);
+
+        // 报告中的条目仍按输入顺序排列
+        assert_eq!(report.succeeded(), 6);
+        let indices: Vec<usize> = report.items.iter().map(|item| item.index).collect();
+        assert_eq!(indices, [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_preserves_order() {
+        let clock = ManualClock::new();
+        let report = run_batch(
+            items(5),
+            BatchOptions::default(),
+            &clock,
+            |text| async move { Ok(text.to_uppercase()) },
+        )
+        .await;
+
+        assert_eq!(report.succeeded(), 5);
+        assert!(!report.deadline_reached);
+        for (i, item) in report.items.iter().enumerate() {
+            assert_eq!(item.index, i);
+            match &item.status {
+                ItemStatus::Succeeded(text) => assert_eq!(text, &format!("LINE {}", i)),
+                other => panic!("unexpected status: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_batch_records_failures() {
+        let clock = ManualClock::new();
+        let report = run_batch(
+            items(3),
+            BatchOptions::default(),
+            &clock,
+            |text| async move {
+                if text == "line 1" {
+                    anyhow::bail!("boom")
+                }
+                Ok(text)
+            },
+        )
+        .await;
+
+        assert_eq!(report.succeeded(), 2);
+        assert_eq!(report.failed(), 1);
+        assert!(matches!(report.items[1].status, ItemStatus::Failed(_)));
+    }
+
+    /// 第 2 行失败的批次
+    async fn run_with_failure(on_error: OnError) -> BatchReport {
+        let options = BatchOptions {
+            concurrency: 1,
+            on_error,
+            ..BatchOptions::default()
+        };
+        run_batch(items(4), options, &ManualClock::new(), |text| async move {
+            if text == "line 1" {
+                anyhow::bail!("boom")
+            }
+            Ok(text.to_uppercase())
+        })
+        .await
+    }
+
+    fn outputs(report: &BatchReport, on_error: OnError) -> Vec<&str> {
+        report
+            .items
+            .iter()
+            .filter_map(|item| item.output(on_error))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_on_error_keep_original() {
+        let report = run_with_failure(OnError::KeepOriginal).await;
+        assert_eq!(
+            outputs(&report, OnError::KeepOriginal),
+            ["LINE 0", "line 1", "LINE 2", "LINE 3"]
+        );
+        assert_eq!(report.failed(), 1);
+        assert!(!report.stopped_on_error);
+    }
+
+    #[tokio::test]
+    async fn test_on_error_continue_and_stop() {
+        let report = run_with_failure(OnError::Continue).await;
+        assert_eq!(
+            outputs(&report, OnError::Continue),
+            ["LINE 0", "LINE 2", "LINE 3"]
+        );
+
+        let report = run_with_failure(OnError::Stop).await;
+        assert!(report.stopped_on_error);
+        assert!(!report.deadline_reached);
+        assert_eq!(report.not_started(), 2);
+        assert_eq!(outputs(&report, OnError::Stop), ["LINE 0"]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_outcome() {
+        let clock = ManualClock::new();
+        let run = |fail: &'static [&'static str]| {
+            run_batch(
+                items(3),
+                BatchOptions::default(),
+                &clock,
+                move |text| async move {
+                    if fail.contains(&text.as_str()) {
+                        anyhow::bail!("boom")
+                    }
+                    Ok(text)
+                },
+            )
+        };
+
+        assert_eq!(run(&[]).await.outcome(), BatchOutcome::AllSucceeded);
+        assert_eq!(
+            run(&["line 1"]).await.outcome(),
+            BatchOutcome::PartialSuccess
+        );
+        assert_eq!(
+            run(&["line 0", "line 1", "line 2"]).await.outcome(),
+            BatchOutcome::AllFailed
+        );
+        assert_eq!(BatchOutcome::from_counts(0, 0), BatchOutcome::AllSucceeded);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_stops_dispatching() {
+        let clock = ManualClock::new();
+        let options = BatchOptions {
+            concurrency: 1,
+            deadline: Some(clock.now() + Duration::from_secs(10)),
+            ..BatchOptions::default()
+        };
+        let calls = AtomicUsize::new(0);
+
+        // 每个请求耗时 3 秒：第 4 个请求开始前剩余时间不足一个平均耗时
+        let report = run_batch(items(10), options, &clock, |text| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            clock.advance(Duration::from_secs(3));
+            async move { Ok(text) }
+        })
+        .await;
+
+        assert!(report.deadline_reached);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(report.succeeded(), 3);
+        assert_eq!(report.not_started(), 7);
+
+        // 未处理的条目是输入的后缀，已完成部分可以安全输出
+        assert!(
+            report.items[3..]
+                .iter()
+                .all(|item| matches!(item.status, ItemStatus::NotStarted))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deadline_already_passed() {
+        let clock = ManualClock::new();
+        let options = BatchOptions {
+            concurrency: 2,
+            deadline: Some(clock.now()),
+            ..BatchOptions::default()
+        };
+
+        let report = run_batch(items(3), options, &clock, |text| async move { Ok(text) }).await;
+
+        assert!(report.deadline_reached);
+        assert_eq!(report.not_started(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_deadline() {
+        let clock = ManualClock::new();
+        let translate = |text: String| {
+            clock.advance(Duration::from_secs(3));
+            async move { Ok(text.to_uppercase()) }
+        };
+
+        // 第一次运行在截止时间前完成 3 条
+        let options = BatchOptions {
+            concurrency: 1,
+            deadline: Some(clock.now() + Duration::from_secs(10)),
+            ..BatchOptions::default()
+        };
+        let first = run_batch(items(6), options, &clock, translate).await;
+        assert!(first.deadline_reached);
+        let done: BTreeMap<usize, String> = first
+            .items
+            .iter()
+            .filter_map(|item| match &item.status {
+                ItemStatus::Succeeded(translation) => Some((item.index, translation.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(done.len(), 3);
+
+        // 第二次运行只派发其余条目，已完成的条目按原顺序输出上次的译文
+        let calls = AtomicUsize::new(0);
+        let options = BatchOptions {
+            concurrency: 1,
+            deadline: Some(clock.now() + Duration::from_secs(10)),
+            ..BatchOptions::default()
+        };
+        let mut emitted = Vec::new();
+        let second = run_batch_resumed(
+            items(6),
+            &done,
+            options,
+            &clock,
+            |text| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                translate(text)
+            },
+            |item| emitted.push(item.index),
+        )
+        .await;
+
+        assert!(!second.deadline_reached);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(second.succeeded(), 6);
+        assert_eq!(emitted, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(second.items[0].attempts, 0);
+        assert!(matches!(&second.items[5].status, ItemStatus::Succeeded(t) if t == "LINE 5"));
+    }
+
+    /// 按序号注入失败：`failures` 中的条目以对应的错误失败，其余成功
+    async fn run_with_failures(
+        n: usize,
+        abort_after: Option<usize>,
+        failures: fn(usize) -> Option<TransomeError>,
+    ) -> BatchReport {
+        let clock = ManualClock::new();
+        // 熔断器的测试不受认证失败立即中止的影响
+        let options = BatchOptions {
+            concurrency: 1,
+            abort_after,
+            fail_fast_on_auth: false,
+            ..BatchOptions::default()
+        };
+        run_batch(items(n), options, &clock, |text| async move {
+            let index: usize = text.trim_start_matches("line ").parse().unwrap();
+            match failures(index) {
+                Some(error) => Err(error.into()),
+                None => Ok(text),
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_abort_after_same_fatal_error() {
+        let report = run_with_failures(10, Some(3), |_| {
+            Some(TransomeError::authentication_error("bad key"))
+        })
+        .await;
+
+        assert!(report.aborted);
+        assert_eq!(report.failed(), 3);
+        assert_eq!(report.not_started(), 7);
+        let error = report.into_first_error().unwrap();
+        assert!(
+            error
+                .downcast_ref::<TransomeError>()
+                .unwrap()
+                .is_auth_error()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_failure_stops_batch() {
+        for on_error in [
+            OnError::Continue,
+            OnError::KeepOriginal,
+            OnError::Placeholder,
+        ] {
+            let options = BatchOptions {
+                concurrency: 1,
+                on_error,
+                ..BatchOptions::default()
+            };
+            let report = run_batch(items(5), options, &ManualClock::new(), |text| async move {
+                if text == "line 0" {
+                    return Err(TransomeError::authentication_error("bad key").into());
+                }
+                Ok(text)
+            })
+            .await;
+
+            assert!(report.auth_failed, "{:?}", on_error);
+            assert!(!report.aborted);
+            assert_eq!(report.failed(), 1);
+            assert_eq!(report.succeeded(), 0);
+            assert_eq!(report.not_started(), 4);
+        }
+
+        // 其他错误不受影响
+        let report = run_with_failure(OnError::Continue).await;
+        assert!(!report.auth_failed);
+        assert_eq!(report.not_started(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_stays_closed() {
+        // 不同类别的错误
+        let report = run_with_failures(6, Some(3), |i| match i {
+            1 => Some(TransomeError::model_not_found("m", vec![])),
+            _ => Some(TransomeError::authentication_error("bad key")),
+        })
+        .await;
+        assert!(!report.aborted);
+        assert_eq!(report.failed(), 6);
+
+        // 前几个条目中有成功的
+        let report = run_with_failures(6, Some(3), |i| {
+            (i != 2).then(|| TransomeError::authentication_error("bad key"))
+        })
+        .await;
+        assert!(!report.aborted);
+        assert_eq!(report.not_started(), 0);
+
+        // 可重试的错误由重试预算处理，不触发中止
+        let report = run_with_failures(6, Some(3), |_| {
+            Some(TransomeError::timeout("请求在 1s 内未完成"))
+        })
+        .await;
+        assert!(!report.aborted);
+
+        // 熔断只看最先完成的条目，之后的连续失败不触发中止
+        let report = run_with_failures(8, Some(3), |i| {
+            (i >= 3).then(|| TransomeError::authentication_error("bad key"))
+        })
+        .await;
+        assert!(!report.aborted);
+        assert_eq!(report.failed(), 5);
+
+        // 未设置阈值时从不中止
+        let report = run_with_failures(6, None, |_| {
+            Some(TransomeError::authentication_error("bad key"))
+        })
+        .await;
+        assert!(!report.aborted);
+        assert_eq!(report.failed(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_failures_grouped_by_kind() {
+        use crate::error::EmptyResponseKind;
+
+        let report = run_with_failures(20, None, |i| match i % 4 {
+            0 | 2 => Some(TransomeError::api_call_failed(
+                "https://api.example.com",
+                Some(429),
+                "rate limit",
+            )),
+            1 => Some(TransomeError::empty_response(
+                "gpt-4o",
+                200,
+                EmptyResponseKind::Filtered,
+                "",
+                "{}",
+            )),
+            _ => None,
+        })
+        .await;
+
+        let error = report.failures().unwrap();
+        assert_eq!(error.failures.len(), 15);
+        assert_eq!(
+            error.groups(),
+            vec![("RateLimited", 10), ("ContentFiltered", 5)]
+        );
+        assert_eq!(error.to_string(), "10 × RateLimited, 5 × ContentFiltered");
+
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["failed"], 15);
+        assert_eq!(
+            json["groups"],
+            serde_json::json!([
+                { "kind": "RateLimited", "count": 10 },
+                { "kind": "ContentFiltered", "count": 5 },
+            ])
+        );
+        assert_eq!(json["failures"][1]["index"], 1);
+        assert_eq!(json["failures"][1]["kind"], "ContentFiltered");
+
+        // 没有失败时不汇总
+        let report = run_with_failures(3, None, |_| None).await;
+        assert_eq!(report.failures(), None);
+    }
+
+    #[tokio::test]
+    async fn test_failures_record_attempts() {
+        let clock = ManualClock::new();
+        let policy = retry::RetryPolicy {
+            retries: 2,
+            ..retry::RetryPolicy::default()
+        };
+        let (policy, clock_ref) = (&policy, &clock);
+
+        let report = run_batch(
+            items(2),
+            BatchOptions::default(),
+            &clock,
+            |text| async move {
+                retry::run_with_retry(policy, clock_ref, || {
+                    let text = text.clone();
+                    async move {
+                        if text == "line 0" {
+                            Err(anyhow::anyhow!("boom"))
+                        } else {
+                            Ok(text)
+                        }
+                    }
+                })
+                .await
+            },
+        )
+        .await;
+
+        assert_eq!(report.items[1].attempts, 1);
+        let error = report.failures().unwrap();
+        assert_eq!(
+            error.failures,
+            vec![ItemFailure {
+                index: 0,
+                kind: "Other".to_string(),
+                message: "boom".to_string(),
+                attempts: 3,
+            }]
+        );
+        assert_eq!(error.to_string(), "1 × Other");
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_batch_types_round_trip() {
+        let outcomes = [
+            (BatchOutcome::AllSucceeded, "\"all_succeeded\""),
+            (BatchOutcome::PartialSuccess, "\"partial_success\""),
+            (BatchOutcome::AllFailed, "\"all_failed\""),
+        ];
+        for (outcome, json) in outcomes {
+            assert_eq!(serde_json::to_string(&outcome).unwrap(), json);
+            assert_eq!(serde_json::from_str::<BatchOutcome>(json).unwrap(), outcome);
+        }
+
+        let report = run_with_failures(4, None, |i| {
+            i.is_multiple_of(2)
+                .then(|| TransomeError::authentication_error("bad key"))
+        })
+        .await;
+        let error = report.failures().unwrap();
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(
+            json["failures"][0],
+            serde_json::json!({
+                "index": 0,
+                "kind": "AuthenticationError",
+                "message": error.failures[0].message,
+                "attempts": error.failures[0].attempts,
+            })
+        );
+        // 反序列化只读取 failures，分组计数重新计算
+        let parsed: BatchError = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, error);
+        assert_eq!(parsed.groups(), vec![("AuthenticationError", 2)]);
+    }
+}
@@ -0,0 +1,174 @@
+//! 输出速率测量（`--benchmark`）
+//!
+//! 按译文增量到达的时刻统计首个 token 的等待时间（TTFT）与持续速率：持续速率只计首个增量
+//! 之后的 token 与时间，不受排队与提示词处理的影响。token 数由 [`chunk::estimate_tokens`] 估算。
+//!
+//! 目前请求不使用流式响应，每个完整的译文（分块翻译时为每个分块）作为一个增量到达，
+//! 首个 token 的等待时间即首个响应的耗时；只有一个增量时无法计算持续速率。
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::batch::Clock;
+use crate::chunk;
+
+/// 按增量到达的时刻统计输出速率
+pub struct ThroughputMeter<'a, C: Clock + ?Sized> {
+    clock: &'a C,
+    started: Instant,
+    state: Mutex<State>,
+}
+
+/// 已到达的增量
+#[derive(Debug, Default)]
+struct State {
+    /// 首个非空增量的到达时刻及其 token 数
+    first: Option<(Instant, usize)>,
+    /// 最后一个增量的到达时刻
+    last: Option<Instant>,
+    deltas: usize,
+    tokens: usize,
+}
+
+impl<'a, C: Clock + ?Sized> ThroughputMeter<'a, C> {
+    /// 从当前时刻开始计时，应在发送请求前创建
+    pub fn start(clock: &'a C) -> Self {
+        Self {
+            clock,
+            started: clock.now(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// 记录到达的一个增量，空增量不计
+    pub fn record(&self, delta: &str) {
+        let tokens = chunk::estimate_tokens(delta);
+        if tokens == 0 {
+            return;
+        }
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.first.get_or_insert((now, tokens));
+        state.last = Some(now);
+        state.deltas += 1;
+        state.tokens += tokens;
+    }
+
+    /// 截至当前时刻的测量结果
+    pub fn report(&self) -> Throughput {
+        let elapsed = self.clock.now().duration_since(self.started);
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let sustained_rate = match (state.first, state.last) {
+            (Some((first, first_tokens)), Some(last)) if last > first => {
+                let seconds = last.duration_since(first).as_secs_f64();
+                Some((state.tokens - first_tokens) as f64 / seconds)
+            }
+            _ => None,
+        };
+        Throughput {
+            time_to_first_token: state
+                .first
+                .map(|(first, _)| first.duration_since(self.started)),
+            elapsed,
+            deltas: state.deltas,
+            tokens: state.tokens,
+            sustained_rate,
+        }
+    }
+}
+
+/// 输出速率的测量结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Throughput {
+    /// 从开始到首个增量到达的时间，没有输出时为 None
+    pub time_to_first_token: Option<Duration>,
+    /// 从开始到测量时的总时间
+    pub elapsed: Duration,
+    /// 到达的增量数
+    pub deltas: usize,
+    /// 估算的输出 token 数
+    pub tokens: usize,
+    /// 首个增量之后的输出速率（token/s），增量少于两个时为 None
+    pub sustained_rate: Option<f64>,
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ttft = self.time_to_first_token.map_or("-".to_string(), |ttft| {
+            format!("{:.2}s", ttft.as_secs_f64())
+        });
+        let rate = self.sustained_rate.map_or_else(
+            || "-（需要至少两个增量）".to_string(),
+            |rate| format!("{:.1} token/s", rate),
+        );
+        write!(
+            f,
+            "首个 token: {}，持续速率: {}（共 {} token，{} 个增量，用时 {:.2}s）",
+            ttft,
+            rate,
+            self.tokens,
+            self.deltas,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::ManualClock;
+    use futures::stream::{self, StreamExt};
+
+    #[tokio::test]
+    async fn test_mock_stream_rate() {
+        let clock = ManualClock::new();
+        let meter = ThroughputMeter::start(&clock);
+
+        // 首个增量在 500ms 后到达，之后每 100ms 到达 4 个 token
+        let deltas = [(500, "abcdefghijklmnop")]
+            .into_iter()
+            .chain(std::iter::repeat_n((100, "abcdefghijklmnop"), 10));
+        let stream = stream::iter(deltas).then(|(delay, delta)| {
+            let clock = &clock;
+            async move {
+                clock.sleep(Duration::from_millis(delay)).await;
+                delta
+            }
+        });
+        let mut stream = std::pin::pin!(stream);
+        while let Some(delta) = stream.next().await {
+            meter.record(delta);
+        }
+
+        let throughput = meter.report();
+        assert_eq!(
+            throughput.time_to_first_token,
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(throughput.elapsed, Duration::from_millis(1500));
+        assert_eq!(throughput.deltas, 11);
+        assert_eq!(throughput.tokens, 44);
+        // 首个增量之后 1s 内 40 个 token
+        let rate = throughput.sustained_rate.unwrap();
+        assert!((rate - 40.0).abs() < 1e-9, "{}", rate);
+        assert!(throughput.to_string().contains("持续速率: 40.0 token/s"));
+    }
+
+    #[test]
+    fn test_single_delta_has_no_sustained_rate() {
+        let clock = ManualClock::new();
+        let meter = ThroughputMeter::start(&clock);
+        assert_eq!(meter.report().time_to_first_token, None);
+
+        clock.advance(Duration::from_secs(2));
+        meter.record("");
+        meter.record("你好世界");
+        let throughput = meter.report();
+        assert_eq!(throughput.time_to_first_token, Some(Duration::from_secs(2)));
+        assert_eq!(throughput.deltas, 1);
+        assert_eq!(throughput.sustained_rate, None);
+    }
+}
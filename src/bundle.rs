@@ -0,0 +1,445 @@
+//! 用户配置包的导出与导入
+//!
+//! 配置包是 transome 配置目录（见 [`config_file::default_dir`]）的 tar.gz 归档，
+//! 条目路径均相对于配置目录，便于迁移到另一台机器。密钥不会进入配置包：
+//! - 导出时跳过包含 API 密钥的文件
+//! - 导入时拒绝绝对路径、越出配置目录的路径、非普通文件以及包含密钥的文件
+//! - 导入的配置文件先通过校验，之后才写入任何文件
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use tar::{Archive, Builder, EntryType, Header};
+
+use crate::config::looks_like_api_key;
+use crate::config_file::{self, CONFIG_FILE_NAME, ConfigFile};
+use crate::error::{Result, TransomeError};
+use crate::store;
+
+/// 导入时目标文件已存在且内容不同的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    /// 存在冲突时中止，不写入任何文件
+    #[default]
+    Refuse,
+    /// 以配置包中的内容覆盖
+    Overwrite,
+    /// 保留现有内容；配置文件按表合并，已有的设置优先
+    Merge,
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    /// 写入配置包的文件
+    pub files: Vec<PathBuf>,
+    /// 因包含密钥而跳过的文件
+    pub skipped: Vec<PathBuf>,
+}
+
+/// 将配置目录导出为配置包
+pub fn export(config_dir: &Path, bundle: &Path) -> Result<ExportReport> {
+    let mut report = ExportReport::default();
+    let mut builder = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+
+    for relative in list_files(config_dir)? {
+        let path = config_dir.join(&relative);
+        let content = fs::read(&path).map_err(|e| io_error(e, "读取", &path))?;
+        if contains_key_material(&relative, &content) {
+            report.skipped.push(relative);
+            continue;
+        }
+
+        let mut header = Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, &relative, content.as_slice())
+            .map_err(|e| io_error(e, "打包", &path))?;
+        report.files.push(relative);
+    }
+
+    let archive = builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| io_error(e, "压缩", bundle))?;
+    store::write_atomic(bundle, &archive)?;
+
+    Ok(report)
+}
+
+/// 将配置包导入配置目录，返回写入的文件
+pub fn import(bundle: &Path, config_dir: &Path, mode: ImportMode) -> Result<Vec<PathBuf>> {
+    let entries = read_bundle(bundle)?;
+
+    // 先确定每个文件的最终内容并完成校验，再统一写入
+    let mut writes = Vec::new();
+    let mut conflicts = Vec::new();
+    for (relative, content) in entries {
+        let target = config_dir.join(&relative);
+        let existing = match fs::read(&target) {
+            Ok(existing) => Some(existing),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(io_error(e, "读取", &target)),
+        };
+        let is_config = relative == Path::new(CONFIG_FILE_NAME);
+
+        let content = match existing {
+            Some(existing) if existing == content => continue,
+            Some(existing) => match mode {
+                ImportMode::Refuse => {
+                    conflicts.push(relative);
+                    continue;
+                }
+                ImportMode::Overwrite => content,
+                ImportMode::Merge if is_config => merge_config(&existing, &content)?,
+                ImportMode::Merge => continue,
+            },
+            None => content,
+        };
+
+        if is_config {
+            validate_config(&content)?;
+        }
+        writes.push((relative, content));
+    }
+
+    if !conflicts.is_empty() {
+        let names: Vec<String> = conflicts.iter().map(|p| p.display().to_string()).collect();
+        return Err(TransomeError::General {
+            message: format!(
+                "以下文件已存在且内容不同：{}\n\n\
+                使用 --overwrite 以配置包覆盖，或使用 --merge 保留现有设置并合并",
+                names.join("、")
+            ),
+        });
+    }
+
+    for (relative, content) in &writes {
+        let target = config_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error(e, "创建", parent))?;
+        }
+        store::write_atomic(&target, content)?;
+    }
+
+    Ok(writes.into_iter().map(|(relative, _)| relative).collect())
+}
+
+/// 读取并检查配置包中的全部条目
+fn read_bundle(bundle: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>> {
+    let file = fs::File::open(bundle).map_err(|e| io_error(e, "打开", bundle))?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+    let mut entries = BTreeMap::new();
+
+    let read_error = |e| io_error(e, "读取", bundle);
+    for entry in archive.entries().map_err(read_error)? {
+        let mut entry = entry.map_err(read_error)?;
+        let path = entry.path().map_err(read_error)?.into_owned();
+
+        if !is_safe_relative(&path) {
+            return Err(refused(format!(
+                "条目 '{}' 不是配置目录内的相对路径",
+                path.display()
+            )));
+        }
+        match entry.header().entry_type() {
+            EntryType::Directory => continue,
+            EntryType::Regular => {}
+            _ => {
+                return Err(refused(format!("条目 '{}' 不是普通文件", path.display())));
+            }
+        }
+
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(read_error)?;
+        if contains_key_material(&path, &content) {
+            return Err(refused(format!(
+                "条目 '{}' 包含 API 密钥，配置包中不应包含密钥",
+                path.display()
+            )));
+        }
+        entries.insert(path, content);
+    }
+
+    Ok(entries)
+}
+
+/// 递归列出目录中的普通文件，返回相对路径
+///
+/// 跳过符号链接与写入过程中的临时文件。
+fn list_files(config_dir: &Path) -> Result<Vec<PathBuf>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        let entries = fs::read_dir(dir).map_err(|e| io_error(e, "读取", dir))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| io_error(e, "读取", dir))?;
+            let path = entry.path();
+            let file_type = entry.file_type().map_err(|e| io_error(e, "读取", &path))?;
+            if file_type.is_dir() {
+                walk(root, &path, files)?;
+            } else if file_type.is_file() && !path.to_string_lossy().ends_with(".tmp") {
+                files.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(config_dir, config_dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// 路径是否只由普通的相对路径部分组成
+fn is_safe_relative(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// 文件是否包含密钥：`.env`、私钥文件，或内容中出现形似 API 密钥的文本
+fn contains_key_material(path: &Path, content: &[u8]) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    if name == ".env" || name.ends_with(".pem") || name.ends_with(".key") {
+        return true;
+    }
+
+    String::from_utf8_lossy(content)
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .any(looks_like_api_key)
+}
+
+/// 合并配置文件：逐表合并，已有的设置优先
+fn merge_config(existing: &[u8], incoming: &[u8]) -> Result<Vec<u8>> {
+    fn merge(base: &mut toml::Table, incoming: toml::Table) {
+        for (key, value) in incoming {
+            match (base.get_mut(&key), value) {
+                (Some(toml::Value::Table(base)), toml::Value::Table(incoming)) => {
+                    merge(base, incoming)
+                }
+                (Some(_), _) => {}
+                (None, value) => {
+                    base.insert(key, value);
+                }
+            }
+        }
+    }
+
+    let mut base = parse_table(existing)?;
+    merge(&mut base, parse_table(incoming)?);
+    toml::to_string(&base)
+        .map(String::into_bytes)
+        .map_err(|e| TransomeError::config_error(CONFIG_FILE_NAME, e.to_string()))
+}
+
+fn parse_table(content: &[u8]) -> Result<toml::Table> {
+    std::str::from_utf8(content)
+        .map_err(|e| e.to_string())
+        .and_then(|content| toml::from_str(content).map_err(|e| e.to_string()))
+        .map_err(|message| TransomeError::config_error(CONFIG_FILE_NAME, message))
+}
+
+/// 校验即将写入的配置文件
+fn validate_config(content: &[u8]) -> Result<()> {
+    let content = std::str::from_utf8(content)
+        .map_err(|e| TransomeError::config_error(CONFIG_FILE_NAME, e.to_string()))?;
+    ConfigFile::from_toml_str(content).map_err(|e| match e {
+        TransomeError::ConfigError { message, .. } => TransomeError::config_error(
+            CONFIG_FILE_NAME,
+            format!("配置包中的配置文件无效，未导入任何文件：{}", message),
+        ),
+        other => other,
+    })?;
+    Ok(())
+}
+
+/// 默认的配置目录，无法确定时报错
+pub fn default_config_dir() -> Result<PathBuf> {
+    config_file::default_dir().ok_or_else(|| {
+        TransomeError::config_error(
+            "config",
+            "无法确定配置目录，请设置 XDG_CONFIG_HOME 或 HOME 环境变量",
+        )
+    })
+}
+
+fn refused(message: String) -> TransomeError {
+    TransomeError::format_error("配置包", format!("{}，已拒绝导入", message))
+}
+
+fn io_error(source: io::Error, action: &str, path: &Path) -> TransomeError {
+    TransomeError::io_error_with_context(source, format!("{} '{}'", action, path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str =
+        "temperature = 0.5\n\n[models.my-model.request_template]\nomit = [\"temperature\"]\n";
+
+    /// 在临时 HOME 下创建配置目录并写入文件
+    fn home_with(files: &[(&str, &str)]) -> (tempfile::TempDir, PathBuf) {
+        let home = tempfile::tempdir().unwrap();
+        let config_dir = home.path().join(".config").join("transome");
+        for (name, content) in files {
+            let path = config_dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        (home, config_dir)
+    }
+
+    /// 手工构造配置包，允许写入任意条目路径
+    fn raw_bundle(dir: &Path, entries: &[(&str, &str)]) -> PathBuf {
+        let mut builder = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, content) in entries {
+            let mut header = Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_entry_type(EntryType::Regular);
+            header.set_cksum();
+            builder.append(&header, content.as_bytes()).unwrap();
+        }
+        let path = dir.join("bundle.tar.gz");
+        fs::write(&path, builder.into_inner().unwrap().finish().unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (source_home, source_dir) = home_with(&[
+            (CONFIG_FILE_NAME, CONFIG),
+            ("prompts/formal.txt", "请使用正式语气翻译"),
+            ("glossary.csv", "transome,transome\n"),
+        ]);
+        let bundle = source_home.path().join("bundle.tar.gz");
+
+        let report = export(&source_dir, &bundle).unwrap();
+        assert_eq!(report.files.len(), 3);
+        assert!(report.skipped.is_empty());
+
+        let (_target_home, target_dir) = home_with(&[]);
+        let mut imported = import(&bundle, &target_dir, ImportMode::Refuse).unwrap();
+        imported.sort();
+        assert_eq!(imported, report.files);
+
+        for file in &report.files {
+            assert_eq!(
+                fs::read(source_dir.join(file)).unwrap(),
+                fs::read(target_dir.join(file)).unwrap()
+            );
+        }
+        assert!(ConfigFile::load_from(&target_dir.join(CONFIG_FILE_NAME)).is_ok());
+    }
+
+    #[test]
+    fn test_export_skips_key_material() {
+        let (home, config_dir) = home_with(&[
+            (CONFIG_FILE_NAME, CONFIG),
+            (".env", "OPENAI_API_KEY=whatever"),
+            ("notes.txt", "key: sk-proj-abcdefghijklmnopqrstuvwxyz"),
+        ]);
+        let bundle = home.path().join("bundle.tar.gz");
+
+        let report = export(&config_dir, &bundle).unwrap();
+        assert_eq!(report.files, [PathBuf::from(CONFIG_FILE_NAME)]);
+        assert_eq!(
+            report.skipped,
+            [PathBuf::from(".env"), PathBuf::from("notes.txt")]
+        );
+    }
+
+    #[test]
+    fn test_import_refuses_unsafe_bundles() {
+        let (home, config_dir) = home_with(&[]);
+
+        for (name, content, reason) in [
+            ("/etc/transome.toml", "x", "不是配置目录内的相对路径"),
+            ("../outside.txt", "x", "不是配置目录内的相对路径"),
+            (
+                "prompt.txt",
+                "AIzaSyA1234567890abcdefghijklmnopqrstu",
+                "包含 API 密钥",
+            ),
+        ] {
+            let bundle = raw_bundle(home.path(), &[(name, content)]);
+            let error = import(&bundle, &config_dir, ImportMode::Overwrite).unwrap_err();
+            assert!(error.to_string().contains(reason), "{}: {}", name, error);
+        }
+        assert!(!config_dir.exists());
+    }
+
+    #[test]
+    fn test_invalid_config_is_not_imported() {
+        let (home, config_dir) = home_with(&[]);
+        let bundle = raw_bundle(
+            home.path(),
+            &[
+                ("prompt.txt", "hi"),
+                (CONFIG_FILE_NAME, "temperature = 9.0\n"),
+            ],
+        );
+
+        let error = import(&bundle, &config_dir, ImportMode::Refuse).unwrap_err();
+        assert!(error.to_string().contains("未导入任何文件"));
+        assert!(!config_dir.exists());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let (home, config_dir) = home_with(&[
+            (CONFIG_FILE_NAME, "temperature = 0.7\n"),
+            ("prompt.txt", "old"),
+        ]);
+        let bundle = raw_bundle(
+            home.path(),
+            &[
+                (
+                    CONFIG_FILE_NAME,
+                    "temperature = 0.2\npin_temperature = false\n",
+                ),
+                ("prompt.txt", "new"),
+                ("extra.txt", "extra"),
+            ],
+        );
+
+        // 默认拒绝，不写入任何文件
+        let error = import(&bundle, &config_dir, ImportMode::Refuse).unwrap_err();
+        assert!(error.to_string().contains("config.toml、prompt.txt"));
+        assert!(!config_dir.join("extra.txt").exists());
+
+        // 合并：保留已有设置，补充缺少的设置与文件
+        import(&bundle, &config_dir, ImportMode::Merge).unwrap();
+        let merged = ConfigFile::load_from(&config_dir.join(CONFIG_FILE_NAME)).unwrap();
+        assert_eq!(merged.temperature, 0.7);
+        assert!(!merged.pin_temperature);
+        assert_eq!(
+            fs::read_to_string(config_dir.join("prompt.txt")).unwrap(),
+            "old"
+        );
+        assert_eq!(
+            fs::read_to_string(config_dir.join("extra.txt")).unwrap(),
+            "extra"
+        );
+
+        // 覆盖
+        import(&bundle, &config_dir, ImportMode::Overwrite).unwrap();
+        let overwritten = ConfigFile::load_from(&config_dir.join(CONFIG_FILE_NAME)).unwrap();
+        assert_eq!(overwritten.temperature, 0.2);
+        assert_eq!(
+            fs::read_to_string(config_dir.join("prompt.txt")).unwrap(),
+            "new"
+        );
+    }
+}
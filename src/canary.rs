@@ -0,0 +1,394 @@
+//! 提示词自检
+//!
+//! 自定义提示词有时会让模型总结或回答问题，而不是翻译。`--check-prompt` 在正式翻译前
+//! 用当前提示词发送一条已知内容的试探请求，检查返回是否像一次翻译：
+//! 输出语言与目标一致、长度比例合理、包含预期译词。
+//!
+//! 样本按目标语言选择，没有对应样本的目标语言跳过自检。
+
+use std::future::Future;
+use std::ops::RangeInclusive;
+
+use anyhow::{Result, bail};
+
+/// 文字类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    /// 中日韩文字
+    Cjk,
+    /// 拉丁字母
+    Latin,
+}
+
+impl Script {
+    /// 文本中占多数的文字类别，不含字母时为 None
+    ///
+    /// 中日韩文字按字、拉丁字母按单词计数，一个汉字与一个英文单词分量相当，
+    /// 引用了英文短语的中文句子仍判断为中文。
+    pub fn dominant(text: &str) -> Option<Self> {
+        let cjk = text.chars().filter(|&c| is_cjk(c)).count();
+        let latin = latin_words(text);
+        match (cjk, latin) {
+            (0, 0) => None,
+            (cjk, latin) if cjk >= latin => Some(Script::Cjk),
+            _ => Some(Script::Latin),
+        }
+    }
+
+    /// 文本是否同时含有中日韩文字与拉丁字母
+    pub fn is_mixed(text: &str) -> bool {
+        text.chars().any(is_cjk) && text.chars().any(|c| c.is_ascii_alphabetic())
+    }
+}
+
+/// 文本中由拉丁字母组成的单词数
+fn latin_words(text: &str) -> usize {
+    text.split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .count()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'   // 平假名、片假名
+        | '\u{3400}'..='\u{4dbf}' // 扩展 A
+        | '\u{4e00}'..='\u{9fff}' // 基本汉字
+        | '\u{ac00}'..='\u{d7af}' // 韩文音节
+    )
+}
+
+/// 译文应使用的文字
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Writing {
+    /// 汉字（中文）
+    Han,
+    /// 含假名（日文）
+    Kana,
+    /// 含韩文音节（韩文）
+    Hangul,
+    /// 拉丁字母
+    Latin,
+}
+
+impl Writing {
+    /// 文本是否使用这种文字书写
+    fn matches(self, text: &str) -> bool {
+        let dominant = Script::dominant(text);
+        match self {
+            Writing::Han => {
+                dominant == Some(Script::Cjk) && !text.chars().any(|c| is_kana(c) || is_hangul(c))
+            }
+            Writing::Kana => dominant == Some(Script::Cjk) && text.chars().any(is_kana),
+            Writing::Hangul => dominant == Some(Script::Cjk) && text.chars().any(is_hangul),
+            Writing::Latin => dominant == Some(Script::Latin),
+        }
+    }
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}')
+}
+
+fn is_hangul(c: char) -> bool {
+    matches!(c, '\u{ac00}'..='\u{d7af}')
+}
+
+/// 某一语言方向的试探样本
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canary {
+    /// 语言方向，用于提示信息
+    pub pair: &'static str,
+    /// 发送的原文
+    pub source: &'static str,
+    /// 译文应使用的文字
+    pub target: Writing,
+    /// 译文与原文的字符数之比
+    pub length_ratio: RangeInclusive<f64>,
+    /// 译文应包含其中之一的词（不区分大小写）
+    pub keywords: &'static [&'static str],
+}
+
+/// 中译英样本
+pub const ZH_TO_EN: Canary = Canary {
+    pair: "中文 → 英文",
+    source: "今天天气很好。",
+    target: Writing::Latin,
+    length_ratio: 1.5..=6.0,
+    keywords: &["weather"],
+};
+
+/// 英译中样本
+pub const EN_TO_ZH: Canary = Canary {
+    pair: "英文 → 中文",
+    source: "The weather is nice today.",
+    target: Writing::Han,
+    length_ratio: 0.15..=0.6,
+    keywords: &["天气"],
+};
+
+/// 英译繁体中文样本
+pub const EN_TO_ZH_TW: Canary = Canary {
+    pair: "英文 → 繁体中文",
+    source: "The weather is nice today.",
+    target: Writing::Han,
+    length_ratio: 0.15..=0.6,
+    keywords: &["天氣"],
+};
+
+/// 英译日样本
+pub const EN_TO_JA: Canary = Canary {
+    pair: "英文 → 日文",
+    source: "The weather is nice today.",
+    target: Writing::Kana,
+    length_ratio: 0.2..=0.8,
+    keywords: &["天気"],
+};
+
+/// 英译韩样本
+pub const EN_TO_KO: Canary = Canary {
+    pair: "英文 → 韩文",
+    source: "The weather is nice today.",
+    target: Writing::Hangul,
+    length_ratio: 0.2..=0.8,
+    keywords: &["날씨"],
+};
+
+/// 中译法样本
+pub const ZH_TO_FR: Canary = Canary {
+    pair: "中文 → 法文",
+    source: "今天天气很好。",
+    target: Writing::Latin,
+    length_ratio: 1.5..=7.0,
+    keywords: &["aujourd", "beau", "temps", "météo"],
+};
+
+/// 中译德样本
+pub const ZH_TO_DE: Canary = Canary {
+    pair: "中文 → 德文",
+    source: "今天天气很好。",
+    target: Writing::Latin,
+    length_ratio: 1.5..=7.0,
+    keywords: &["wetter", "heute"],
+};
+
+/// 中译西样本
+pub const ZH_TO_ES: Canary = Canary {
+    pair: "中文 → 西班牙文",
+    source: "今天天气很好。",
+    target: Writing::Latin,
+    length_ratio: 1.5..=7.0,
+    keywords: &["tiempo", "hoy", "clima"],
+};
+
+/// 各目标语言的试探样本，按 `--to` 的语言代码查找
+const CANARIES: &[(&str, &Canary)] = &[
+    ("en", &ZH_TO_EN),
+    ("zh", &EN_TO_ZH),
+    ("zh-tw", &EN_TO_ZH_TW),
+    ("ja", &EN_TO_JA),
+    ("ko", &EN_TO_KO),
+    ("fr", &ZH_TO_FR),
+    ("de", &ZH_TO_DE),
+    ("es", &ZH_TO_ES),
+];
+
+impl Canary {
+    /// 译为目标语言的样本，没有该语言的样本时为 None
+    pub fn for_target(lang: &str) -> Option<&'static Canary> {
+        CANARIES
+            .iter()
+            .find(|(code, _)| code.eq_ignore_ascii_case(lang))
+            .map(|&(_, canary)| canary)
+    }
+
+    /// 检查输出是否像本样本的译文，不像时返回原因
+    pub fn verify(&self, output: &str) -> std::result::Result<(), String> {
+        let output = output.trim();
+
+        if !self.target.matches(output) {
+            return Err("输出语言与翻译方向不符".to_string());
+        }
+
+        let ratio = output.chars().count() as f64 / self.source.chars().count() as f64;
+        if !self.length_ratio.contains(&ratio) {
+            return Err(format!("输出长度是原文的 {:.1} 倍，不像逐句翻译", ratio));
+        }
+
+        let lowercase = output.to_lowercase();
+        if !self
+            .keywords
+            .iter()
+            .any(|keyword| lowercase.contains(keyword))
+        {
+            return Err(format!(
+                "输出中没有预期的译词 '{}'",
+                self.keywords.join("' / '")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// 使用当前提示词发送试探请求，确认其行为像翻译
+///
+/// 输出不像翻译时返回错误，调用方应在开始正式翻译前中止。
+pub async fn check_prompt<F, Fut>(canary: &Canary, translate: F) -> Result<()>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let output = translate(canary.source.to_string()).await?;
+
+    if let Err(reason) = canary.verify(&output) {
+        bail!(
+            "提示词自检失败（{}）：{}\n\n\
+            试探原文: {}\n\
+            模型输出: {}\n\n\
+            当前提示词可能在让模型总结、回答或改写，而不是翻译，请检查 --prompt",
+            canary.pair,
+            reason,
+            canary.source,
+            output.trim()
+        );
+    }
+
+    tracing::debug!(pair = canary.pair, "提示词自检通过");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模拟服务：按提示词的意图与目标语言处理试探原文
+    fn mock_provider(
+        prompt: &'static str,
+        lang: &'static str,
+    ) -> impl FnOnce(String) -> std::future::Ready<Result<String>> {
+        move |source| {
+            let output = match (prompt, lang) {
+                ("translate", "en") => "The weather is nice today.",
+                ("translate", "zh") => "今天天气很好。",
+                ("translate", "ja") => "今日は天気がいいです。",
+                ("translate", "fr") => "Il fait beau aujourd'hui.",
+                // 目标是日文，却译成了中文
+                ("wrong-language", "ja") => "今天天气很好。",
+                // 目标是法文，却译成了英文
+                ("wrong-language", "fr") => "The weather is nice today.",
+                ("summarize", _) => "天气。",
+                ("answer", "en") => "Yes! It's a great day to go outside and enjoy the sunshine.",
+                ("answer", _) => "是的，今天阳光明媚，非常适合出门散步，记得做好防晒。",
+                ("echo", _) => return std::future::ready(Ok(source)),
+                _ => unreachable!(),
+            };
+            std::future::ready(Ok(output.to_string()))
+        }
+    }
+
+    fn canary(lang: &str) -> &'static Canary {
+        Canary::for_target(lang).unwrap()
+    }
+
+    #[test]
+    fn test_canary_selection() {
+        assert_eq!(Canary::for_target("en"), Some(&ZH_TO_EN));
+        assert_eq!(Canary::for_target("zh"), Some(&EN_TO_ZH));
+        assert_eq!(Canary::for_target("ja"), Some(&EN_TO_JA));
+        assert_eq!(Canary::for_target("ZH-TW"), Some(&EN_TO_ZH_TW));
+        assert_eq!(Canary::for_target("ar"), None);
+    }
+
+    #[test]
+    fn test_dominant_script_counts_words() {
+        // 英文按单词计数，引用的英文短语字母再多也不改变中文句子的判断
+        assert_eq!(
+            Script::dominant("他说 internationalization 很难"),
+            Some(Script::Cjk)
+        );
+        assert_eq!(
+            Script::dominant("The word 你好 means hello"),
+            Some(Script::Latin)
+        );
+        assert!(Script::is_mixed("用 Rust 写"));
+        assert!(!Script::is_mixed("Hello, world"));
+        assert!(!Script::is_mixed("你好 123"));
+    }
+
+    #[test]
+    fn test_fixtures_accept_their_translations() {
+        assert!(ZH_TO_EN.verify("The weather is nice today.").is_ok());
+        assert!(ZH_TO_EN.verify("Today's weather is very good.").is_ok());
+        assert!(EN_TO_ZH.verify("今天天气很好。").is_ok());
+        assert!(EN_TO_ZH.verify("今天的天气很不错。").is_ok());
+        assert!(EN_TO_ZH_TW.verify("今天天氣很好。").is_ok());
+        assert!(EN_TO_JA.verify("今日は天気がいいです。").is_ok());
+        assert!(EN_TO_KO.verify("오늘은 날씨가 좋네요.").is_ok());
+        assert!(ZH_TO_FR.verify("Il fait beau aujourd'hui.").is_ok());
+        assert!(ZH_TO_DE.verify("Heute ist das Wetter schön.").is_ok());
+        assert!(ZH_TO_ES.verify("Hoy hace buen tiempo.").is_ok());
+    }
+
+    #[test]
+    fn test_fixtures_reject_other_languages() {
+        // 中文与日文都以汉字为主，日文样本还要求出现假名
+        assert!(EN_TO_JA.verify("今天天气很好。").is_err());
+        assert!(EN_TO_ZH.verify("今日は天気がいいです。").is_err());
+        // 拉丁字母语言之间靠译词区分
+        assert!(ZH_TO_FR.verify("The weather is nice today.").is_err());
+        assert!(ZH_TO_EN.verify("Hoy hace buen tiempo.").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_translating_prompt_passes() {
+        for lang in ["en", "zh", "ja", "fr"] {
+            check_prompt(canary(lang), mock_provider("translate", lang))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broken_prompts_are_rejected() {
+        // 原样返回：语言不变
+        let error = check_prompt(canary("en"), mock_provider("echo", "en"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("输出语言与翻译方向不符"));
+
+        // 译成了目标以外的语言
+        let error = check_prompt(canary("ja"), mock_provider("wrong-language", "ja"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("输出语言与翻译方向不符"));
+        let error = check_prompt(canary("fr"), mock_provider("wrong-language", "fr"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("没有预期的译词"), "{}", error);
+
+        // 回答而非翻译：篇幅过长
+        for lang in ["en", "zh"] {
+            let error = check_prompt(canary(lang), mock_provider("answer", lang))
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("不像逐句翻译"), "{}", error);
+        }
+
+        // 总结：篇幅过短
+        let error = check_prompt(canary("zh"), mock_provider("summarize", "zh"))
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("提示词自检失败（英文 → 中文）")
+        );
+        assert!(error.to_string().contains("模型输出: 天气。"));
+    }
+
+    #[tokio::test]
+    async fn test_request_errors_propagate() {
+        let result = check_prompt(canary("zh"), |_| async { bail!("network down") }).await;
+        assert_eq!(result.unwrap_err().to_string(), "network down");
+    }
+}
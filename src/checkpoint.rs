@@ -0,0 +1,190 @@
+//! 批量任务的断点
+//!
+//! `--resume <FILE>` 时，批量模式（`--lines`）每完成一行就把译文追加到断点文件（见
+//! [`store::append_record`]），因 `--deadline` 到时或中断而未完成的任务再次以同一断点文件运行时，
+//! 已完成的行直接使用记录的译文，只翻译其余的行。全部行翻译成功后删除断点文件。
+//!
+//! 断点文件为 JSON Lines 格式（`{"input": "...", "index": 0, "translation": "..."}`），
+//! `input` 是模型、提示词与全部输入行的 SHA-256 哈希；输入或设置变化后原有的断点不再适用，
+//! 此时报错而不是输出与当前输入不符的译文。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, TransomeError};
+use crate::store;
+use crate::warning::Warnings;
+
+/// 断点文件中的一条记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    input: String,
+    index: usize,
+    translation: String,
+}
+
+/// 决定批量任务译文的全部输入
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BatchInput<'a> {
+    pub model: &'a str,
+    pub prompt: &'a str,
+    pub lines: &'a [String],
+}
+
+impl BatchInput<'_> {
+    /// 各项输入的 SHA-256 哈希
+    pub fn digest(&self) -> String {
+        let source = serde_json::to_string(self).expect("批量任务的输入总能序列化为 JSON");
+        format!("{:x}", Sha256::digest(source.as_bytes()))
+    }
+}
+
+/// 批量任务的断点
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    input: String,
+    done: BTreeMap<usize, String>,
+}
+
+impl Checkpoint {
+    /// 读取断点文件，文件不存在时从头开始
+    ///
+    /// 断点属于其他输入或设置时返回错误。
+    pub fn load(path: &Path, input: &BatchInput<'_>, warnings: &Warnings) -> Result<Self> {
+        let digest = input.digest();
+        let entries = store::read_records::<Entry>(path, warnings)?;
+        if entries.iter().any(|entry| entry.input != digest) {
+            return Err(TransomeError::config_error(
+                "--resume",
+                format!(
+                    "断点文件 '{}' 属于其他输入或设置（模型、提示词），请删除该文件或使用其他路径",
+                    path.display()
+                ),
+            ));
+        }
+
+        let done = entries
+            .into_iter()
+            .filter(|entry| entry.index < input.lines.len())
+            .map(|entry| (entry.index, entry.translation))
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            input: digest,
+            done,
+        })
+    }
+
+    /// 上次已完成的行的序号与译文
+    pub fn done(&self) -> &BTreeMap<usize, String> {
+        &self.done
+    }
+
+    /// 记录一行的译文，已记录的行不重复写入
+    pub fn record(&self, index: usize, translation: &str) -> Result<()> {
+        if self.done.contains_key(&index) {
+            return Ok(());
+        }
+        store::append_record(
+            &self.path,
+            &Entry {
+                input: self.input.clone(),
+                index,
+                translation: translation.to_string(),
+            },
+        )
+    }
+
+    /// 任务全部完成后删除断点文件
+    pub fn finish(self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(TransomeError::io_error_with_context(
+                e,
+                format!("删除断点文件 '{}'", self.path.display()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resume_from_recorded_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("job.checkpoint");
+        let lines = lines(&["a", "b", "c"]);
+        let input = BatchInput {
+            model: "gpt-4o",
+            prompt: "翻译",
+            lines: &lines,
+        };
+        let warnings = Warnings::new(true);
+
+        let checkpoint = Checkpoint::load(&path, &input, &warnings).unwrap();
+        assert!(checkpoint.done().is_empty());
+        checkpoint.record(0, "甲").unwrap();
+        checkpoint.record(2, "丙").unwrap();
+
+        let checkpoint = Checkpoint::load(&path, &input, &warnings).unwrap();
+        assert_eq!(
+            checkpoint.done(),
+            &BTreeMap::from([(0, "甲".to_string()), (2, "丙".to_string())])
+        );
+        // 已记录的行不重复写入
+        checkpoint.record(0, "甲").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        checkpoint.finish().unwrap();
+        assert!(!path.exists());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_of_other_input_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("job.checkpoint");
+        let warnings = Warnings::new(true);
+        let original = lines(&["a", "b"]);
+        let input = BatchInput {
+            model: "gpt-4o",
+            prompt: "翻译",
+            lines: &original,
+        };
+        Checkpoint::load(&path, &input, &warnings)
+            .unwrap()
+            .record(0, "甲")
+            .unwrap();
+
+        let edited = lines(&["a", "b2"]);
+        let other_lines = BatchInput {
+            lines: &edited,
+            ..input
+        };
+        let other_model = BatchInput {
+            model: "gpt-4o-mini",
+            ..input
+        };
+        for other in [other_lines, other_model] {
+            let error = Checkpoint::load(&path, &other, &warnings).unwrap_err();
+            assert!(
+                error.to_string().contains("属于其他输入或设置"),
+                "{}",
+                error
+            );
+        }
+    }
+}
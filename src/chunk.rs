@@ -0,0 +1,302 @@
+//! 长文本分块
+//!
+//! 超出模型上下文窗口的文本按段落自动分块，逐块翻译后再合并。
+//! `--split-on` 以正则表达式指定分块边界，`--show-chunks` 仅显示分块结果而不翻译。
+//! token 数为估算值：宽字符（CJK 等）计为 1 个，其余字符每 4 个计为 1 个。
+
+use std::fmt;
+
+use anyhow::{Result, bail};
+use regex::Regex;
+
+use crate::util::char_width;
+
+/// 分块之间的分隔，合并译文时使用
+pub const CHUNK_SEPARATOR: &str = "\n\n";
+
+/// 一个分块
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub text: String,
+    /// 首行的行号，从 1 开始
+    pub start: usize,
+    /// 末行的行号（包含）
+    pub end: usize,
+}
+
+impl Chunk {
+    /// 估算的 token 数
+    pub fn tokens(&self) -> usize {
+        estimate_tokens(&self.text)
+    }
+}
+
+/// 估算文本的 token 数
+pub fn estimate_tokens(text: &str) -> usize {
+    let (wide, other): (usize, usize) = text.chars().fold((0, 0), |(wide, other), c| {
+        if char_width(c) == 2 {
+            (wide + 1, other)
+        } else {
+            (wide, other + 1)
+        }
+    });
+    wide + other.div_ceil(4)
+}
+
+/// 自动分块时单个分块的 token 上限：上下文窗口的一半，为译文留出空间
+pub fn chunk_budget(context_window: usize) -> usize {
+    context_window / 2
+}
+
+/// 请求体中除提示词与待翻译文本外的部分（模型名称、消息结构等）预留的字节数
+const REQUEST_OVERHEAD_BYTES: usize = 1024;
+
+/// 估算的每个 token 在请求体中占用的最大字节数
+///
+/// 宽字符按 UTF-8 编码占 3～4 字节，其余字符每 4 个计为 1 个 token，
+/// 引号与换行在 JSON 中转义后占 2 字节，取 6 留出余量。
+const BYTES_PER_TOKEN: usize = 6;
+
+/// 按请求体上限（字节）计算单个分块的 token 上限，扣除提示词与请求结构占用的部分
+pub fn request_budget(max_request_bytes: usize, prompt_bytes: usize) -> usize {
+    let available = max_request_bytes.saturating_sub(prompt_bytes + REQUEST_OVERHEAD_BYTES);
+    (available / BYTES_PER_TOKEN).max(1)
+}
+
+/// 解析 `--split-on` 的正则表达式
+pub fn parse_split_on(s: &str) -> std::result::Result<Regex, String> {
+    Regex::new(s).map_err(|e| format!("无效的正则表达式 '{}'：{}", s, e))
+}
+
+/// 由第 `start` 到第 `end` 行（从 0 开始，不含 `end`）组成分块，去掉首尾的空行
+fn chunk_of(lines: &[&str], start: usize, end: usize) -> Option<Chunk> {
+    let is_blank = |i: &usize| lines[*i].trim().is_empty();
+    let first = (start..end).find(|i| !is_blank(i))?;
+    let last = (start..end).rev().find(|i| !is_blank(i))?;
+    Some(Chunk {
+        text: lines[first..=last].join("\n"),
+        start: first + 1,
+        end: last + 1,
+    })
+}
+
+/// 按段落自动分块
+///
+/// 相邻段落（以空行分隔）依次合并，直到再加入下一段会超出 `max_tokens`。
+/// 单个段落本身超出上限时自成一块，超出上下文窗口时由 [`check_fits`] 报告。
+pub fn plan(text: &str, max_tokens: usize) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    // 段落的起止行（从 0 开始，不含末行）
+    let mut paragraphs = Vec::new();
+    let mut start = None;
+    for (i, line) in lines.iter().enumerate() {
+        match (line.trim().is_empty(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                paragraphs.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        paragraphs.push((s, lines.len()));
+    }
+
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    for (p_start, p_end) in paragraphs {
+        current = match current {
+            Some((c_start, c_end)) => {
+                let merged = chunk_of(&lines, c_start, p_end);
+                if merged.is_some_and(|chunk| chunk.tokens() <= max_tokens) {
+                    Some((c_start, p_end))
+                } else {
+                    chunks.extend(chunk_of(&lines, c_start, c_end));
+                    Some((p_start, p_end))
+                }
+            }
+            None => Some((p_start, p_end)),
+        };
+    }
+    if let Some((c_start, c_end)) = current {
+        chunks.extend(chunk_of(&lines, c_start, c_end));
+    }
+    chunks
+}
+
+/// 按正则表达式分块：匹配的行作为新分块的开头
+///
+/// 第一个匹配行之前的内容单独成块，只有空行的分块被省略。
+pub fn split_on(text: &str, delimiter: &Regex) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    let mut boundaries: Vec<usize> = (1..lines.len())
+        .filter(|&i| delimiter.is_match(lines[i]))
+        .collect();
+    boundaries.insert(0, 0);
+    boundaries.push(lines.len());
+
+    boundaries
+        .windows(2)
+        .filter_map(|range| chunk_of(&lines, range[0], range[1]))
+        .collect()
+}
+
+/// 检查每个分块都不超出模型的上下文窗口，否则报错并指出超出的分块
+pub fn check_fits(chunks: &[Chunk], context_window: usize) -> Result<()> {
+    if let Some((index, chunk)) = chunks
+        .iter()
+        .enumerate()
+        .find(|(_, chunk)| chunk.tokens() > context_window)
+    {
+        bail!(
+            "第 {} 个分块（第 {}-{} 行，以 \"{}\" 开头）约 {} 个 token，\
+            超出模型的上下文窗口 {} 个 token\n\n\
+            请使用 --split-on 指定更细的分块边界，或换用上下文窗口更大的模型",
+            index + 1,
+            chunk.start,
+            chunk.end,
+            first_line(&chunk.text),
+            chunk.tokens(),
+            context_window
+        );
+    }
+    Ok(())
+}
+
+/// 分块的首行，过长时截断
+fn first_line(text: &str) -> String {
+    const MAX_CHARS: usize = 30;
+    let line = text.lines().next().unwrap_or_default().trim();
+    if line.chars().count() > MAX_CHARS {
+        format!("{}…", line.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// `--show-chunks` 显示的分块计划
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkPlan<'a> {
+    pub chunks: &'a [Chunk],
+    pub context_window: usize,
+}
+
+impl fmt::Display for ChunkPlan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            write!(
+                f,
+                "第 {} 块：第 {}-{} 行，约 {} 个 token，{}",
+                index + 1,
+                chunk.start,
+                chunk.end,
+                chunk.tokens(),
+                first_line(&chunk.text)
+            )?;
+            if chunk.tokens() > self.context_window {
+                write!(f, "（超出上下文窗口）")?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "共 {} 块，模型的上下文窗口为 {} 个 token",
+            self.chunks.len(),
+            self.context_window
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "# 合同\n\n\
+        ## 第一条 定义\n本合同中的术语定义如下。\n\n术语一。\n\n\
+        ## 第二条 期限\n合同期限为一年。\n";
+
+    fn ranges(chunks: &[Chunk]) -> Vec<(usize, usize)> {
+        chunks.iter().map(|c| (c.start, c.end)).collect()
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("合同abcd"), 3);
+    }
+
+    #[test]
+    fn test_plan_merges_paragraphs_within_budget() {
+        let chunks = plan(DOCUMENT, 10_000);
+        assert_eq!(ranges(&chunks), [(1, 9)]);
+        assert_eq!(chunks[0].text, DOCUMENT.trim_end());
+
+        // 每段单独成块
+        let chunks = plan(DOCUMENT, 1);
+        assert_eq!(ranges(&chunks), [(1, 1), (3, 4), (6, 6), (8, 9)]);
+        assert_eq!(chunks[1].text, "## 第一条 定义\n本合同中的术语定义如下。");
+
+        let chunks = plan(DOCUMENT, 20);
+        assert_eq!(ranges(&chunks), [(1, 1), (3, 4), (6, 9)]);
+        assert!(chunks.iter().all(|chunk| chunk.tokens() <= 20));
+
+        assert!(plan("\n\n", 10).is_empty());
+    }
+
+    #[test]
+    fn test_request_budget() {
+        assert_eq!(request_budget(1024 + 600 + 100, 100), 100);
+        assert_eq!(request_budget(1000, 100), 1);
+
+        // 按上限得到的分块序列化后不超出上限
+        let budget = request_budget(1200, 0);
+        for chunk in plan(DOCUMENT, budget) {
+            let bytes = serde_json::to_vec(&chunk.text).unwrap().len();
+            assert!(bytes + REQUEST_OVERHEAD_BYTES <= 1200, "{}", chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_split_on() {
+        let chunks = split_on(DOCUMENT, &parse_split_on("^## ").unwrap());
+        assert_eq!(ranges(&chunks), [(1, 1), (3, 6), (8, 9)]);
+        assert_eq!(chunks[2].text, "## 第二条 期限\n合同期限为一年。");
+
+        let chunks = split_on(DOCUMENT, &parse_split_on("^# ").unwrap());
+        assert_eq!(ranges(&chunks), [(1, 9)]);
+
+        assert!(parse_split_on("(").is_err());
+    }
+
+    #[test]
+    fn test_check_fits_names_the_chunk() {
+        let chunks = split_on(DOCUMENT, &parse_split_on("^## ").unwrap());
+        assert!(check_fits(&chunks, 100).is_ok());
+
+        let error = check_fits(&chunks, 15).unwrap_err().to_string();
+        assert!(
+            error.starts_with("第 2 个分块（第 3-6 行，以 \"## 第一条 定义\" 开头）"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_chunk_plan_display() {
+        let chunks = split_on(DOCUMENT, &parse_split_on("^## ").unwrap());
+        let plan = ChunkPlan {
+            chunks: &chunks,
+            context_window: 15,
+        }
+        .to_string();
+        let lines: Vec<&str> = plan.lines().collect();
+        assert_eq!(lines[0], "第 1 块：第 1-1 行，约 3 个 token，# 合同");
+        assert!(lines[1].ends_with("（超出上下文窗口）"));
+        assert_eq!(lines[3], "共 3 块，模型的上下文窗口为 15 个 token");
+    }
+}
@@ -1,28 +1,117 @@
 //! 命令行参数解析模块
 
-use anyhow::{Result, bail};
-use clap::Parser;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::config;
-use crate::translator::PROMPT;
+use anyhow::{Result, bail};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use encoding_rs::Encoding;
+use regex::Regex;
+
+use crate::batch::{DEFAULT_ABORT_AFTER, DEFAULT_CONCURRENCY, OnError};
+#[cfg(feature = "bundle")]
+use crate::bundle::ImportMode;
+use crate::chunk::{self, Chunk, parse_split_on};
+use crate::config::{self, ModelSort};
+use crate::config_file::ConfigFile;
+use crate::direction::DirectionSession;
+use crate::error::TransomeError;
+use crate::escalation::parse_threshold;
+use crate::estimate::DEFAULT_OUTPUT_RATIO;
+use crate::features;
+use crate::formats::code::Language;
+use crate::formats::detect::{self, Detection};
+use crate::formats::markdown::LinkTranslation;
+use crate::formats::{DEFAULT_ERROR_PLACEHOLDER, Format, SegmentOptions};
+use crate::length_limit::length_clause;
+#[cfg(feature = "logging")]
+use crate::logging::LogLevel;
+use crate::logit_bias::{LogitBias, parse_logit_bias};
+use crate::model_weights::{ModelWeights, parse_model_weights};
+use crate::multi_target::{LANG_PLACEHOLDER, parse_lang, prompt_target, target_prompt};
+use crate::output_encoding::parse_encoding;
+use crate::platform::{self, Shell};
+use crate::postprocess::{Locale, PostProcessor};
+use crate::range::{FileSlice, SourceRange, parse_byte_range, parse_line_range};
+use crate::request_id;
+use crate::retry::DEFAULT_RETRY_BUDGET;
+use crate::routing::LanguagePair;
+use crate::sampling::{SamplingOptions, parse_penalty, parse_top_p};
+use crate::sink::{self, OutputSink};
+use crate::source::{self, Source};
+use crate::stats;
+use crate::translator::{CREATIVE_TEMPERATURE, DEFAULT_MAX_INFLIGHT, PROMPT};
+use crate::util::parse_duration;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{WarningKind, Warnings};
+#[cfg(feature = "from-url")]
+use crate::web;
 
 /// 命令行参数结构体
 #[derive(Parser, Debug, Clone)]
 #[command(name = "transome")]
-#[command(version = "v0.2.0")]
+#[command(version = "v0.2.0", long_version = features::long_version())]
 #[command(about = "一个简单的命令行翻译工具", long_about = None)]
 pub struct Cli {
     /// 要翻译的文本
     pub text: Option<String>,
 
+    /// 从文件读取要翻译的文本；批量模式下可重复指定，在 stdin 之后按顺序翻译
+    #[arg(short, long, value_name = "PATH", conflicts_with = "text", value_parser = platform::parse_path)]
+    pub file: Vec<PathBuf>,
+
+    /// 批量模式下递归翻译目录中的文件，默认遵循 .gitignore 与 .ignore，跳过隐藏文件与符号链接
+    #[arg(long, value_name = "DIR", requires = "lines", conflicts_with = "text", value_parser = platform::parse_path)]
+    pub dir: Option<PathBuf>,
+
+    /// --dir 时排除匹配该模式的文件或目录（写法同 .gitignore，如 'node_modules'、'*.min.js'），可重复指定
+    #[arg(long, value_name = "GLOB", requires = "dir")]
+    pub exclude: Vec<String>,
+
+    /// --dir 时不读取 .gitignore 与 .ignore 等忽略规则
+    #[arg(long, requires = "dir")]
+    pub no_ignore: bool,
+
+    /// --dir 时进入符号链接指向的文件与目录，链接形成循环时报错
+    #[arg(long, requires = "dir")]
+    pub follow_symlinks: bool,
+
+    /// 下载网页并提取正文作为待翻译文本
+    #[cfg(feature = "from-url")]
+    #[arg(long, value_name = "URL", conflicts_with_all = ["text", "file"])]
+    pub from_url: Option<String>,
+
+    /// 只翻译文件中的指定行（如 120:180，从 1 开始，包含两端）
+    #[arg(long, value_name = "START:END", requires = "file", conflicts_with = "range_bytes", value_parser = parse_line_range)]
+    pub range: Option<SourceRange>,
+
+    /// 只翻译文件中的指定字节区间（如 0:65536，不包含终点）
+    #[arg(long, value_name = "START:END", requires = "file", value_parser = parse_byte_range)]
+    pub range_bytes: Option<SourceRange>,
+
+    /// 将译文替换回文件内容中的对应范围后输出完整内容，原文件不变
+    #[arg(long, conflicts_with_all = ["lines", "format", "to", "diff_against", "json", "pretty_json"])]
+    pub in_place_range: bool,
+
     /// 翻译使用的AI模型
-    #[arg(short, long, default_value_t = String::from("gemini-2.5-flash-lite"))]
+    #[arg(short, long, default_value_t = String::from(config::DEFAULT_MODEL))]
     pub model: String,
 
+    /// 按权重随机选择本次运行的模型（如 gpt-4o=7,gemini-2.5-flash=3），用于对比不同提供商
+    #[arg(long, value_name = "MODEL=WEIGHT,...", value_parser = parse_model_weights, conflicts_with = "model")]
+    pub model_weights: Option<ModelWeights>,
+
     /// 自定义API端点URL
     #[arg(short, long)]
     pub url: Option<String>,
 
+    /// 只允许使用内置列表中的模型，即使提供了 --url 也拒绝未知模型
+    #[arg(long)]
+    pub strict_model: bool,
+
     /// 用于身份验证的API密钥（会根据模型自动选择环境变量）
     #[arg(short, long)]
     pub key: Option<String>,
@@ -31,32 +120,636 @@ pub struct Cli {
     #[arg(short, long, default_value_t = String::from(PROMPT))]
     pub prompt: String,
 
+    /// 从 stdin 读取提示词，待翻译文本通过参数提供
+    #[arg(long, conflicts_with = "prompt")]
+    pub prompt_from_stdin: bool,
+
+    /// 发送请求前在 stderr 输出实际发送的提示词（按目标语言、翻译方向调整后），同一提示词只输出一次
+    #[arg(long)]
+    pub echo_prompt: bool,
+
+    /// 正式翻译前发送试探请求，确认提示词的行为像翻译
+    #[arg(long)]
+    pub check_prompt: bool,
+
+    /// 服务不接受 system 角色的提示消息时直接报错，而不是改用 user 消息
+    #[arg(long)]
+    pub no_system_fallback: bool,
+
+    /// 译文长度与原文之比的上限（如 1.3），宽字符计为 2，超出时重新翻译一次并给出警告
+    #[arg(long, value_name = "RATIO")]
+    pub max_output_ratio: Option<f64>,
+
+    /// 对每段原文翻译两次，自一致性得分（0 到 1）低于该阈值时改用 --escalate-model 重新翻译
+    #[arg(long, value_name = "THRESHOLD", requires = "escalate_model", value_parser = parse_threshold)]
+    pub escalate_on_low_quality: Option<f64>,
+
+    /// 自一致性得分过低时改用的更强模型；与 -m 一样解析 API 地址与密钥
+    #[arg(long, value_name = "MODEL", requires = "escalate_on_low_quality")]
+    pub escalate_model: Option<String>,
+
+    /// 翻译后把原文与译文发给该模型审校，输出审校后的译文；与 -m 一样解析 API 地址与密钥
+    #[arg(long, value_name = "MODEL")]
+    pub review_model: Option<String>,
+
+    /// 将输入视为不可信内容：原文放入分隔标记之间，译文有执行原文中指令的迹象时报错
+    #[arg(long)]
+    pub sandbox_input: bool,
+
+    /// 单次请求输出的最大 token 数（推理模型使用 max_completion_tokens 字段）
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_tokens: Option<u32>,
+
+    /// 调整指定 token 的出现概率，格式为 <TOKEN_ID>:<BIAS>，偏置范围 -100 到 100（可重复）
+    #[arg(long, value_name = "TOKEN_ID:BIAS", value_parser = parse_logit_bias)]
+    pub logit_bias: Vec<LogitBias>,
+
+    /// 核采样：只从累计概率达到该值的候选 token 中采样，范围 0 到 1
+    #[arg(long, value_name = "P", value_parser = parse_top_p)]
+    pub top_p: Option<f32>,
+
+    /// 按 token 已出现的次数降低其概率，范围 -2 到 2，正值减少重复用词
+    #[arg(long, value_name = "PENALTY", value_parser = parse_penalty, allow_negative_numbers = true)]
+    pub frequency_penalty: Option<f32>,
+
+    /// 降低已出现过的 token 的概率，范围 -2 到 2，正值鼓励使用新词
+    #[arg(long, value_name = "PENALTY", value_parser = parse_penalty, allow_negative_numbers = true)]
+    pub presence_penalty: Option<f32>,
+
+    /// 响应包含多个选项（请求模板中设置了 n 大于 1）时连接各选项内容的分隔符，默认为换行
+    #[arg(long, value_name = "STRING")]
+    pub choice_separator: Option<String>,
+
+    /// 使用较高的采样温度，译文更灵活但每次结果可能不同
+    #[arg(long)]
+    pub creative: bool,
+
     /// 列出所有支持的模型
     #[arg(long, help = "列出所有支持的模型及其 URL")]
     pub list_models: bool,
+
+    /// 配合 --list-models 使用：只列出提供商和模型名称，不显示 API 地址
+    #[arg(long, requires = "list_models")]
+    pub no_urls: bool,
+
+    /// 配合 --list-models 使用：模型的排序方式，name 不按提供商分组
+    #[arg(long, value_enum, value_name = "KEY", default_value_t = ModelSort::Provider, requires = "list_models")]
+    pub sort: ModelSort,
+
+    /// 配合 --list-models 使用：只列出名称或提供商包含该词的模型（不区分大小写）
+    #[arg(long, value_name = "TERM", requires = "list_models")]
+    pub filter: Option<String>,
+
+    /// 仅显示生效的配置及其来源，不发送翻译请求
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// 检查所有已设置环境变量的 API 密钥是否可用
+    #[arg(long)]
+    pub validate_keys: bool,
+
+    /// 按指定显示宽度折行输出（CJK 字符计为 2 列）
+    #[arg(long, value_name = "N")]
+    pub wrap: Option<usize>,
+
+    /// 译文输出前依次执行的后处理（逗号分隔），默认不处理
+    #[arg(long, value_enum, value_name = "PROCESSORS", value_delimiter = ',')]
+    pub post_process: Vec<PostProcessor>,
+
+    /// 后处理使用的目标语言，默认按译文推断
+    #[arg(long, value_enum)]
+    pub locale: Option<Locale>,
+
+    /// 去除译文中的 Markdown 行内标记（加粗、行内代码、链接等），输出纯文本
+    #[cfg(feature = "strip-markdown")]
+    #[arg(long)]
+    pub strip_markdown: bool,
+
+    /// 目标语言（如 en、ja），可重复指定以并发翻译为多种语言
+    #[arg(
+        long = "to",
+        value_name = "LANG",
+        value_parser = parse_lang,
+        conflicts_with_all = ["lines", "format", "diff_against"]
+    )]
+    pub to: Vec<String>,
+
+    /// 多目标语言时在每行译文前加上语言标签（如 `[es] hola`），`--json` 时以标签为键
+    #[arg(long, requires = "to")]
+    pub label_languages: bool,
+
+    /// 将翻译结果写入文件（已存在时覆盖），而不是标准输出；多目标语言时可用 {lang} 占位符
+    #[arg(short, long, value_name = "PATH", value_parser = platform::parse_path)]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub output: Option<PathBuf>,
+
+    /// 将翻译结果复制到系统剪贴板，而不是标准输出
+    #[cfg(feature = "clipboard")]
+    #[arg(long)]
+    pub clipboard: bool,
+
+    /// 每次输出后立即写出，不在管道或文件中缓冲
+    #[arg(long)]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub unbuffered: bool,
+
+    /// 输出的字符编码（如 gbk、big5），用于标准输出与 --output 文件；未指定时标准输出按终端的编码转码
+    #[arg(long, value_name = "ENCODING", value_parser = parse_encoding)]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub output_encoding: Option<&'static Encoding>,
+
+    /// 在翻译结果末尾补充换行（默认仅输出到标准输出时补充）
+    #[arg(long, conflicts_with = "no_trailing_newline")]
+    pub trailing_newline: bool,
+
+    /// 原样输出翻译结果，末尾不补充换行（默认仅输出到文件时不补充）
+    #[arg(long)]
+    pub no_trailing_newline: bool,
+
+    /// 以 JSON 格式输出翻译结果（包含警告列表），单行紧凑格式
+    #[arg(long)]
+    pub json: bool,
+
+    /// 以缩进的 JSON 格式输出翻译结果，便于阅读
+    #[arg(long, conflicts_with = "json")]
+    pub pretty_json: bool,
+
+    /// 批量模式：将输入的每一行作为独立条目翻译，按原顺序输出；--json 时每个条目输出一行 JSON
+    #[arg(long, conflicts_with = "pretty_json")]
+    pub lines: bool,
+
+    /// 批量模式下按第一条能判断语言的行固定翻译方向，之后的行都翻译成同一目标语言
+    #[arg(long, requires = "lines", conflicts_with = "to")]
+    pub lock_direction: bool,
+
+    /// 批量模式：每行译文前加上 `来源:行号:`（同 grep），便于区分 stdin 与各文件的结果
+    #[arg(long, requires = "lines")]
+    pub label: bool,
+
+    /// 输入文本的格式，默认按 --file 的扩展名与内容推断
+    #[arg(long, value_enum, default_value_t = Format::Auto, conflicts_with_all = ["lines", "json", "pretty_json"])]
+    pub format: Format,
+
+    /// CSV 格式下要翻译的列号（从 1 开始，逗号分隔，如 2,3）
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub columns: Vec<usize>,
+
+    /// CSV 格式下作为译者注的列号（从 1 开始），其内容作为翻译同一行单元格时的参考，不翻译
+    #[arg(long, value_name = "COLUMN")]
+    pub note_column: Option<usize>,
+
+    /// CSV 首行为表头，不翻译
+    #[arg(long)]
+    pub has_header: bool,
+
+    /// Markdown 格式下链接与图片中参与翻译的部分（默认翻译链接文字与替代文本，不翻译标题）
+    #[arg(long, value_enum, value_name = "PARTS")]
+    pub markdown_translate_links: Option<LinkTranslation>,
+
+    /// HTML 格式下同时翻译的属性（逗号分隔，如 alt,title）
+    #[arg(long, value_name = "ATTRS", value_delimiter = ',')]
+    pub translate_attrs: Vec<String>,
+
+    /// 源代码格式下的语言，未指定时按 --file 的扩展名推断
+    #[arg(long, value_enum, value_name = "LANG")]
+    pub lang: Option<Language>,
+
+    /// properties 格式下直接写出 UTF-8，不把非 ASCII 字符转义为 \uXXXX
+    #[arg(long)]
+    pub properties_utf8: bool,
+
+    /// JSON 格式下翻译所有字符串值，数字、布尔值与 null 不变
+    #[arg(long)]
+    pub translate_strings: bool,
+
+    /// JSON 格式下翻译对象的键（默认不翻译）
+    #[arg(long)]
+    pub translate_keys: bool,
+
+    /// 差量翻译：与旧版原文对比，仅重新翻译新增或修改的段落
+    #[arg(
+        long,
+        value_name = "OLD_SOURCE",
+        requires = "previous_translation",
+        conflicts_with_all = ["lines", "json", "pretty_json", "format"],
+        value_parser = platform::parse_path
+    )]
+    pub diff_against: Option<PathBuf>,
+
+    /// 差量翻译时使用的旧版译文，段落与旧版原文一一对应
+    #[arg(long, value_name = "OLD_TRANSLATION", requires = "diff_against", value_parser = platform::parse_path)]
+    pub previous_translation: Option<PathBuf>,
+
+    /// 翻译记忆文件（JSON Lines）：原文完全相同时直接使用其中的译文，新的译文追加到文件
+    #[arg(long, value_name = "PATH", conflicts_with = "to", value_parser = platform::parse_path)]
+    pub tm: Option<PathBuf>,
+
+    /// 保持人名、地名等专有名词的译名一致：要求模型以 JSON 返回译名，已确定的译名加入之后的提示词
+    #[arg(long, conflicts_with = "to")]
+    pub consistent_names: bool,
+
+    /// 专有名词译名对照表（JSON）：运行开始时载入，运行结束时写回，隐含 --consistent-names
+    #[arg(long, value_name = "PATH", conflicts_with = "to", value_parser = platform::parse_path)]
+    pub names_file: Option<PathBuf>,
+
+    /// 仅显示分块边界及每块的 token 估算，不发送请求
+    #[arg(long, conflicts_with_all = ["lines", "format", "diff_against", "to"])]
+    pub show_chunks: bool,
+
+    /// 以匹配的行作为分块的开头（正则表达式，如 '^## '），代替按段落自动分块
+    #[arg(
+        long,
+        value_name = "REGEX",
+        value_parser = parse_split_on,
+        conflicts_with_all = ["lines", "format", "diff_against", "to"]
+    )]
+    pub split_on: Option<Regex>,
+
+    /// 仅估算将要发送的请求数、token 用量与费用，不发送请求
+    #[arg(long, conflicts_with_all = ["dry_run", "show_chunks"])]
+    pub estimate: bool,
+
+    /// 估算输出 token 时采用的输出与输入 token 之比
+    #[arg(long, value_name = "RATIO", default_value_t = DEFAULT_OUTPUT_RATIO)]
+    pub estimate_output_ratio: f64,
+
+    /// 运行前估算费用（美元），超出该上限或模型价格未知时拒绝运行
+    #[arg(long, value_name = "USD")]
+    pub max_cost: Option<f64>,
+
+    /// 测量首个 token 的等待时间与持续输出速率（token/s），结束时输出到 stderr
+    #[arg(long, conflicts_with_all = ["lines", "format", "diff_against", "to", "estimate"])]
+    pub benchmark: bool,
+
+    /// 批量模式下条目、文档格式下片段翻译失败时的处理方式
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = OnError::Continue)]
+    pub on_error: OnError,
+
+    /// `--on-error placeholder` 时代替译文的标记
+    #[arg(long, value_name = "TEXT", default_value = DEFAULT_ERROR_PLACEHOLDER)]
+    pub error_placeholder: String,
+
+    /// 批量模式下同时进行的最大请求数
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_CONCURRENCY)]
+    pub concurrency: usize,
+
+    /// 所有功能合计同时进行的最大请求数（批量、多目标语言、回退与升级模型共用）
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_INFLIGHT)]
+    pub max_inflight: usize,
+
+    /// 任务的总时限（如 30s、10m）：批量模式下临近时停止派发新条目，重试不会超过该时限
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub deadline: Option<Duration>,
+
+    /// 批量模式的断点文件：记录已完成的行，再次运行时只翻译其余的行，全部完成后删除
+    #[arg(long, value_name = "FILE", requires = "lines", value_parser = platform::parse_path)]
+    pub resume: Option<PathBuf>,
+
+    /// 批量模式中配置文件变化或收到 SIGHUP 时重新加载配置，之后派发的行使用新配置
+    #[arg(long, requires = "lines")]
+    pub reload_config: bool,
+
+    /// 单次请求的超时时间（如 30s）
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// 额外信任的 PEM 根证书包，用于使用自定义根证书的内部网关，只对自定义的 API 地址生效
+    #[arg(
+        long,
+        visible_alias = "ca-cert",
+        value_name = "PATH",
+        env = "TRANSOME_CA_BUNDLE",
+        value_parser = platform::parse_path
+    )]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// 不验证 TLS 证书（极不安全，仅用于实验环境）：只对 --url 或配置档指定的自定义地址生效，
+    /// 且需在配置文件中设置 allow_insecure_tls = true
+    #[arg(long)]
+    pub insecure_skip_tls_verify: bool,
+
+    /// 请求携带的 X-Request-Id，未指定时每次翻译生成新的 UUID
+    #[arg(long, value_name = "ID", value_parser = request_id::parse_request_id)]
+    pub request_id: Option<String>,
+
+    /// 在 stderr 输出每次翻译的请求 ID，便于在网关日志中查找
+    #[arg(long)]
+    pub verbose: bool,
+
+    /// 将服务返回的原始 JSON 响应写入文件，批量等多请求模式下按顺序编号
+    #[arg(long, value_name = "PATH", value_parser = platform::parse_path)]
+    pub dump_response: Option<PathBuf>,
+
+    /// 请求失败后的最大重试次数，未指定时按提供商决定
+    #[arg(long, value_name = "N")]
+    pub retries: Option<u32>,
+
+    /// 首次重试前的等待时间（如 500ms），之后每次加倍；未指定时按提供商决定
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub backoff_base: Option<Duration>,
+
+    /// 本次运行所有请求共享的重试次数上限，用完后其余失败不再重试
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_RETRY_BUDGET)]
+    pub retry_budget: u32,
+
+    /// 只重试确定未被服务处理的失败（连接失败、429 限流），超时等可能已在服务端完成的失败不重试
+    #[arg(long)]
+    pub retries_only_for_idempotent: bool,
+
+    /// 批量模式下最先完成的 K 个条目全部因同一类不可重试的错误（如认证失败）失败时中止，0 表示不中止
+    #[arg(long, value_name = "K", default_value_t = DEFAULT_ABORT_AFTER, requires = "lines")]
+    pub abort_after: usize,
+
+    /// 批量模式下每行完成后立即以 `[行号] 译文` 输出，不按输入顺序等待
+    #[arg(long, requires = "lines")]
+    pub unordered: bool,
+
+    /// 运行结束时在 stderr 输出统计：条目数、成功与失败数、重试次数、token 用量与耗时（--quiet 时不输出）
+    #[arg(long)]
+    pub summary: bool,
+
+    /// 不把本次运行写入使用记录（见 transome stats）
+    #[arg(long)]
+    pub no_history: bool,
+
+    /// 文件格式模式下不读写片段缓存，每个片段都发送请求
+    #[arg(long)]
+    pub no_segment_cache: bool,
+
+    /// 临时模式：片段缓存只保留在内存中，不写入使用记录，用于只读文件系统的容器等临时环境
+    #[arg(long)]
+    pub ephemeral: bool,
+
+    /// 静默模式，不在 stderr 输出警告
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// 出错时不附加故障排除建议
+    #[arg(long)]
+    pub no_hints: bool,
+
+    /// 作为 Unix 过滤器运行：从 stdin 读取全部输入整体翻译，译文原样写到标准输出；
+    /// 相当于 --quiet --no-hints --no-trailing-newline，出错时只在 stderr 输出一行
+    #[arg(
+        long,
+        conflicts_with_all = ["text", "file", "output", "prompt_from_stdin", "lines", "json", "pretty_json", "trailing_newline"]
+    )]
+    #[cfg_attr(feature = "clipboard", arg(conflicts_with = "clipboard"))]
+    pub pipe: bool,
+
+    /// 将警告视为错误，出现警告时以失败状态退出（模型弃用提示除外）
+    #[arg(long)]
+    pub deny_warnings: bool,
+
+    /// 使用配置文件中的配置档（[profile.<NAME>]），其中的设置优先级低于命令行参数
+    #[arg(long, value_name = "NAME", env = "TRANSOME_PROFILE")]
+    pub profile: Option<String>,
+
+    /// 配置档指定的读取 API 密钥的环境变量
+    #[arg(skip)]
+    pub key_env: Option<String>,
+
+    /// 批量模式下从 stdin 与文件读取的输入来源，按翻译顺序排列
+    #[arg(skip)]
+    pub sources: Vec<Source>,
+
+    /// 按语言对选择模型时匹配的路由规则
+    #[arg(skip)]
+    pub route: Option<String>,
+
+    /// 在命令行中明确指定的参数，值与默认值相同时也视为指定
+    #[arg(skip)]
+    pub explicit: ExplicitArgs,
+
+    /// 配置文件路径（默认为 ~/.config/transome/config.toml）
+    #[arg(long, value_name = "PATH", env = "TRANSOME_CONFIG", value_parser = platform::parse_path)]
+    pub config: Option<PathBuf>,
+
+    /// 日志级别，日志输出到 stderr
+    #[cfg(feature = "logging")]
+    #[arg(long, value_enum, env = "TRANSOME_LOG_LEVEL", default_value_t = LogLevel::Off)]
+    pub log_level: LogLevel,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// 子命令
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// 汇总最近的使用记录：每日翻译数、常用语言对、各模型用量与费用、翻译记忆命中率与平均耗时
+    Stats {
+        /// 统计最近多少天
+        #[arg(long, value_name = "N", default_value_t = stats::DEFAULT_DAYS)]
+        days: u32,
+
+        /// 以 JSON 格式输出
+        #[arg(long)]
+        json: bool,
+    },
+    /// 查看、导出或导入配置
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// 显示编译特性与功能支持情况
+    Features,
+}
+
+/// `transome config` 的子命令
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigCommand {
+    /// 显示合并命令行参数、环境变量、配置文件与配置档后实际生效的配置及各项来源，不显示密钥
+    Show {
+        /// 以 JSON 格式输出，默认为 TOML
+        #[arg(long)]
+        json: bool,
+    },
+    /// 将配置目录导出为 tar.gz 配置包（不含密钥）
+    #[cfg(feature = "bundle")]
+    Export {
+        /// 配置包的保存路径
+        #[arg(value_name = "BUNDLE", value_parser = platform::parse_path)]
+        bundle: PathBuf,
+    },
+    /// 从配置包导入配置，内容不同的已有文件需配合 --overwrite 或 --merge
+    #[cfg(feature = "bundle")]
+    Import {
+        /// 配置包的路径
+        #[arg(value_name = "BUNDLE", value_parser = platform::parse_path)]
+        bundle: PathBuf,
+
+        /// 覆盖内容不同的已有文件
+        #[arg(long, conflicts_with = "merge")]
+        overwrite: bool,
+
+        /// 保留已有设置，仅补充缺少的设置与文件
+        #[arg(long)]
+        merge: bool,
+    },
+}
+
+#[cfg(feature = "bundle")]
+impl ConfigCommand {
+    /// `config import` 处理冲突的方式，其他子命令为 None
+    pub fn import_mode(&self) -> Option<ImportMode> {
+        let Self::Import {
+            overwrite, merge, ..
+        } = self
+        else {
+            return None;
+        };
+        Some(if *overwrite {
+            ImportMode::Overwrite
+        } else if *merge {
+            ImportMode::Merge
+        } else {
+            ImportMode::Refuse
+        })
+    }
+}
+
+/// 有默认值的参数中，在命令行中明确指定的参数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExplicitArgs {
+    pub model: bool,
+    pub prompt: bool,
+    pub concurrency: bool,
 }
 
 impl Cli {
     /// 解析命令行参数
     pub fn parse() -> Self {
-        <Self as clap::Parser>::parse()
+        Self::try_parse_tracked(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// 解析命令行参数，并记录明确指定的有默认值的参数（见 [`ExplicitArgs`]）
+    ///
+    /// 通过 `parse_from` 等方法解析时无法区分，与默认值相同的参数视为未指定。
+    pub fn try_parse_tracked<I, T>(args: I) -> std::result::Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let matches = Self::command().try_get_matches_from(args)?;
+        let mut cli = Self::from_arg_matches(&matches)?;
+        let explicit = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+        cli.explicit = ExplicitArgs {
+            model: explicit("model"),
+            prompt: explicit("prompt"),
+            concurrency: explicit("concurrency"),
+        };
+        Ok(cli)
+    }
+
+    /// 按 `--output` / `--clipboard` 选择翻译结果的输出目标
+    ///
+    /// 应在即将输出时调用：输出到文件时会立即创建（覆盖）该文件。
+    pub fn output_sink(&self, warnings: &Warnings) -> Result<Box<dyn OutputSink>> {
+        sink::select(
+            self.output.as_deref(),
+            self.clipboard(),
+            self.unbuffered,
+            self.trailing_newline(),
+            self.output_encoding,
+            warnings,
+        )
+    }
+
+    /// 是否在运行结束后写入使用记录（未指定 --no-history 且不是子命令）
+    pub fn records_history(&self) -> bool {
+        !self.no_history && !self.ephemeral && self.command.is_none()
+    }
+
+    /// 命令行指定的采样参数
+    pub fn sampling(&self) -> SamplingOptions {
+        SamplingOptions {
+            top_p: self.top_p,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
+        }
+    }
+
+    /// 是否按片段缓存译文：文件格式模式（差量翻译除外）且未指定 --no-segment-cache
+    pub fn uses_segment_cache(&self) -> bool {
+        !self.is_plain_text() && self.diff_against.is_none() && !self.no_segment_cache
+    }
+
+    /// 是否保持专有名词的译名一致：指定了 --consistent-names 或 --names-file
+    pub fn uses_name_memory(&self) -> bool {
+        self.consistent_names || self.names_file.is_some()
+    }
+
+    /// 翻译结果末尾是否补充换行，未指定时为 `None`，由输出目标决定
+    pub fn trailing_newline(&self) -> Option<bool> {
+        if self.trailing_newline {
+            Some(true)
+        } else if self.no_trailing_newline {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// 是否去除译文中的 Markdown 标记（`--strip-markdown`），未启用 strip-markdown 特性时始终为 false
+    pub fn strip_markdown(&self) -> bool {
+        #[cfg(feature = "strip-markdown")]
+        let strip = self.strip_markdown;
+        #[cfg(not(feature = "strip-markdown"))]
+        let strip = false;
+        strip
+    }
+
+    /// 是否输出到剪贴板（`--clipboard`），未启用 clipboard 特性时始终为 false
+    pub fn clipboard(&self) -> bool {
+        #[cfg(feature = "clipboard")]
+        let clipboard = self.clipboard;
+        #[cfg(not(feature = "clipboard"))]
+        let clipboard = false;
+        clipboard
+    }
+
+    /// 当前模型的上下文窗口（token），配置文件中的设置优先
+    pub fn context_window(&self, config_file: &ConfigFile) -> usize {
+        config_file
+            .context_window(&self.model)
+            .unwrap_or_else(|| config::get_context_window(&self.model))
+    }
+
+    /// 将文本分块：指定了 `--split-on` 时按其分块，否则按段落自动分块
+    pub fn chunks(&self, text: &str, max_tokens: usize) -> Vec<Chunk> {
+        match &self.split_on {
+            Some(delimiter) => chunk::split_on(text, delimiter),
+            None => chunk::plan(text, max_tokens),
+        }
     }
 
     /// 智能获取 API 密钥
     ///
     /// 密钥解析优先级：
     /// 1. 如果用户通过 -k/--key 参数提供了密钥，直接返回该密钥
-    /// 2. 否则，调用 config::get_env_var_name_for_model 获取对应的环境变量名
-    /// 3. 尝试从该环境变量读取密钥
-    /// 4. 如果环境变量不存在或为空，返回友好的错误信息，指导用户设置正确的环境变量
+    /// 2. 否则，如果自定义 URL 属于已知提供商，使用 config::provider_for_url 返回的环境变量名
+    /// 3. 否则，调用 config::get_env_var_name_for_model 获取对应的环境变量名
+    /// 4. 尝试从该环境变量读取密钥
+    /// 5. 如果环境变量不存在或为空，返回友好的错误信息，指导用户设置正确的环境变量
     pub fn resolve_api_key(&self) -> Result<String> {
         // 如果用户通过 -k/--key 参数提供了密钥，直接返回该密钥
         if let Some(key) = &self.key {
             return Ok(key.clone());
         }
 
-        // 否则，调用 config::get_env_var_name_for_model 获取对应的环境变量名
-        let env_var_name = config::get_env_var_name_for_model(&self.model)
+        // 否则，配置档指定了环境变量时使用该环境变量；自定义 URL 属于已知提供商时
+        // 使用该提供商的环境变量，再调用 config::get_env_var_name_for_model 获取对应的环境变量名
+        let env_var_name = self
+            .key_env
+            .as_deref()
+            .or_else(|| {
+                self.url
+                    .as_deref()
+                    .and_then(config::provider_for_url)
+                    .map(|(_, env_var)| env_var)
+            })
+            .or_else(|| config::get_env_var_name_for_model(&self.model))
             .ok_or_else(|| {
                 anyhow::anyhow!(
                     "无法为模型 '{}' 确定对应的环境变量。\n\n\
@@ -79,13 +772,13 @@ impl Cli {
                 bail!(
                     "环境变量 {} 已设置但为空。\n\n\
                     解决方法：\n\
-                    1. 设置环境变量: export {}=<your_api_key>\n\
+                    1. 设置环境变量: {}\n\
                     2. 或者手动提供密钥: transome -k <your_api_key> -m {} <文本>\n\n\
                     获取 API 密钥的方法：\n\
                     - OpenAI API 密钥: https://platform.openai.com/api-keys\n\
                     - Google AI API 密钥: https://aistudio.google.com/app/apikey",
                     env_var_name,
-                    env_var_name,
+                    Shell::current().set_env_var(env_var_name, "<your_api_key>"),
                     self.model
                 )
             }
@@ -94,26 +787,460 @@ impl Cli {
                 bail!(
                     "环境变量 {} 未设置。\n\n\
                     解决方法：\n\
-                    1. 设置环境变量: export {}=<your_api_key>\n\
+                    1. 设置环境变量: {}\n\
                     2. 或者手动提供密钥: transome -k <your_api_key> -m {} <文本>\n\n\
                     获取 API 密钥的方法：\n\
                     - OpenAI API 密钥: https://platform.openai.com/api-keys\n\
                     - Google AI API 密钥: https://aistudio.google.com/app/apikey",
                     env_var_name,
-                    env_var_name,
+                    Shell::current().set_env_var(env_var_name, "<your_api_key>"),
                     self.model
                 )
             }
         }
     }
 
+    /// 检查 API 密钥格式是否与所选模型的提供商相符
+    ///
+    /// 仅在使用内置端点时检查，格式不符时记录警告而不中断运行。
+    pub fn check_key_format(&self, api_key: &str, warnings: &Warnings) {
+        if self.url.is_some() {
+            return;
+        }
+
+        if let Some(prefix) = config::get_key_prefix_for_model(&self.model)
+            && !api_key.starts_with(prefix)
+        {
+            warnings.warn(
+                WarningKind::KeyFormat,
+                format!(
+                    "API 密钥格式与模型 '{}' 的提供商 {} 不符（通常以 '{}' 开头），请确认使用了正确的密钥",
+                    self.model,
+                    config::get_provider_name(&self.model),
+                    prefix
+                ),
+            );
+        }
+    }
+
+    /// 验证与输入格式相关的选项
+    fn validate_format_options(&self) -> Result<()> {
+        if self.format != Format::Markdown && self.markdown_translate_links.is_some() {
+            bail!(
+                "--markdown-translate-links 仅适用于 Markdown 格式\n\n\
+                使用方法: transome --format markdown --markdown-translate-links all"
+            );
+        }
+
+        if self.format != Format::Html && !self.translate_attrs.is_empty() {
+            bail!(
+                "--translate-attrs 仅适用于 HTML 格式\n\n\
+                使用方法: transome --format html --translate-attrs alt,title"
+            );
+        }
+
+        if self.format != Format::Code && self.lang.is_some() {
+            bail!(
+                "--lang 仅适用于源代码格式\n\n\
+                使用方法: transome --format code --lang rust"
+            );
+        }
+
+        if self.format != Format::Properties && self.properties_utf8 {
+            bail!(
+                "--properties-utf8 仅适用于 properties 格式\n\n\
+                使用方法: transome --format properties --properties-utf8 < messages.properties"
+            );
+        }
+
+        if self.format != Format::Json && (self.translate_strings || self.translate_keys) {
+            bail!(
+                "--translate-strings 和 --translate-keys 仅适用于 JSON 格式\n\n\
+                使用方法: transome --format json --translate-strings < data.json\n\
+                整体按纯文本翻译时使用 --format text"
+            );
+        }
+
+        if self.format == Format::Json && !self.translate_strings && !self.translate_keys {
+            bail!(
+                "JSON 格式需要指定要翻译的内容：--translate-strings（字符串值）和/或 --translate-keys（键）\n\n\
+                使用方法: transome --format json --translate-strings < data.json"
+            );
+        }
+
+        if self.format == Format::Code && self.code_language().is_none() {
+            bail!(
+                "无法确定源代码的语言，请使用 --lang 指定\n\n\
+                使用方法: transome --format code --lang <rust|python|js>"
+            );
+        }
+
+        if self.format == Format::Html && !cfg!(feature = "html") {
+            bail!("--format html 需使用 --features html 构建");
+        }
+
+        if self.format == Format::Docx {
+            if self.file.is_empty() || self.output.is_none() {
+                bail!(
+                    "docx 格式需要通过 --file 指定文档，并通过 -o 指定译文的保存位置\n\n\
+                    使用方法: transome --format docx --file <文档> -o <译文>"
+                );
+            }
+            if self.json_output() {
+                bail!("--json 不适用于 docx 格式");
+            }
+            if !cfg!(feature = "docx") {
+                bail!("--format docx 需使用 --features docx 构建");
+            }
+        }
+
+        if self.format != Format::Csv {
+            if !self.columns.is_empty() || self.has_header || self.note_column.is_some() {
+                bail!(
+                    "--columns、--has-header 和 --note-column 仅适用于 CSV 格式\n\n\
+                    使用方法: transome --format csv --columns <列号> [--has-header]"
+                );
+            }
+            return Ok(());
+        }
+
+        if self.columns.is_empty() {
+            bail!(
+                "CSV 格式需要通过 --columns 指定要翻译的列\n\n\
+                使用方法: transome --format csv --columns 2,3 [--has-header]"
+            );
+        }
+
+        if self.columns.contains(&0) || self.note_column == Some(0) {
+            bail!(
+                "列号从 1 开始\n\n\
+                使用方法: transome --format csv --columns 2,3 [--has-header]"
+            );
+        }
+
+        if let Some(column) = self.note_column
+            && self.columns.contains(&column)
+        {
+            bail!(
+                "第 {} 列是译者注，不能同时作为要翻译的列\n\n\
+                使用方法: transome --format csv --columns 2 --note-column 3",
+                column
+            );
+        }
+
+        Ok(())
+    }
+
+    /// `--dir` 遍历目录的选项
+    pub fn walk_options(&self) -> WalkOptions {
+        WalkOptions {
+            exclude: self.exclude.clone(),
+            no_ignore: self.no_ignore,
+            follow_symlinks: self.follow_symlinks,
+        }
+    }
+
+    /// 源代码的语言：`--lang` 优先，否则按 `--file` 的扩展名推断
+    pub fn code_language(&self) -> Option<Language> {
+        self.lang.or_else(|| {
+            let extension = self.file.first()?.extension()?.to_str()?;
+            Language::from_extension(extension)
+        })
+    }
+
+    /// 读取 `--file` 指定的文件作为待翻译文本，指定了范围时只取范围内的部分
+    ///
+    /// 使用 `--in-place-range` 时返回截取的部分，用于将译文拼接回完整内容。
+    /// 批量模式下各文件同时记录为输入来源（见 [`crate::source`]），`--dir` 收集的文件排在最后。
+    pub fn read_file(&mut self) -> Result<Option<FileSlice>> {
+        if let Some(dir) = &self.dir {
+            let files = walk::collect_files(dir, &self.walk_options())?;
+            if files.is_empty() {
+                bail!(
+                    "目录 '{}' 中没有要翻译的文件\n\n\
+                    忽略规则、--exclude 或隐藏文件的规则可能排除了全部文件，可尝试 --no-ignore",
+                    dir.display()
+                );
+            }
+            self.file.extend(files);
+        }
+
+        let path = match self.file.as_slice() {
+            [] => {
+                if self.range.is_some() || self.range_bytes.is_some() {
+                    bail!("--range 与 --range-bytes 需要配合 --file 使用");
+                }
+                if self.in_place_range {
+                    bail!("--in-place-range 需要配合 --file 与 --range 或 --range-bytes 使用");
+                }
+                return Ok(None);
+            }
+            [path] => path.clone(),
+            paths => {
+                if !self.lines {
+                    bail!(
+                        "只有批量模式可以指定多个 --file\n\n\
+                        使用方法: transome --lines -f <文件> -f <文件>"
+                    );
+                }
+                if self.range.is_some() || self.range_bytes.is_some() {
+                    bail!("--range 与 --range-bytes 只能用于单个 --file");
+                }
+                for path in paths {
+                    let text = read_text_file(path)?;
+                    self.sources
+                        .push(Source::new(path.display().to_string(), text));
+                }
+                self.text = Some(source::joined_text(&self.sources));
+                return Ok(None);
+            }
+        };
+        let path = &path;
+
+        // 未指定格式时按扩展名与内容推断，docx 需要在读取前确定
+        if self.detects_format() {
+            self.format = detect_format(path)?;
+        }
+
+        // docx 是二进制文件，这里只提取正文文字用于验证与使用记录，翻译时重新读取
+        if self.format == Format::Docx {
+            if self.range.is_some() || self.range_bytes.is_some() || self.in_place_range {
+                bail!("--range、--range-bytes 与 --in-place-range 不适用于 docx 格式");
+            }
+            self.text = Some(read_docx_text(path)?);
+            return Ok(None);
+        }
+
+        let content = read_text_file(path)?;
+
+        let range = self.range.or(self.range_bytes);
+        let span = match range {
+            Some(range) => range
+                .locate(&content, &path.display().to_string())
+                .map_err(|e| anyhow::anyhow!(e))?,
+            None if self.in_place_range => {
+                bail!("--in-place-range 需要配合 --range 或 --range-bytes 使用")
+            }
+            None => 0..content.len(),
+        };
+        let slice = FileSlice { content, span };
+        self.text = Some(slice.text().to_string());
+        if self.lines {
+            // 只读取了一部分时，行号从范围的起始行开始
+            let first_line = slice.content[..slice.span.start].matches('\n').count() + 1;
+            self.sources.push(
+                Source::new(path.display().to_string(), slice.text()).starting_at(first_line),
+            );
+        }
+        Ok(self.in_place_range.then_some(slice))
+    }
+
+    /// 指定了 `--from-url` 时下载网页，提取的正文作为待翻译文本
+    ///
+    /// 使用 `--timeout`（未指定时为 [`web::DEFAULT_FETCH_TIMEOUT`]），只信任系统根证书，
+    /// 网页大小不超过 [`web::MAX_PAGE_BYTES`]。
+    #[cfg(feature = "from-url")]
+    pub async fn read_url(&mut self) -> Result<()> {
+        let Some(url) = &self.from_url else {
+            return Ok(());
+        };
+        let timeout = self.timeout.unwrap_or(web::DEFAULT_FETCH_TIMEOUT);
+        let client = crate::translator::http_client(timeout)?;
+        self.text = Some(web::read(&client, url).await?);
+        Ok(())
+    }
+
+    /// 从 stdin 读取输入
+    ///
+    /// 使用 `--prompt-from-stdin` 时 stdin 内容作为提示词，文本必须通过参数提供；
+    /// 否则未提供文本参数且 stdin 不是终端（或使用了 `--pipe`）时，stdin 内容作为待翻译文本。
+    pub fn read_stdin<F>(&mut self, stdin_is_terminal: bool, read: F) -> Result<()>
+    where
+        F: FnOnce() -> std::io::Result<String>,
+    {
+        if self.prompt_from_stdin {
+            if self.text.is_none() {
+                bail!(
+                    "使用 --prompt-from-stdin 时 stdin 已用于读取提示词，待翻译文本需通过参数提供\n\n\
+                    使用方法: echo <提示词> | transome --prompt-from-stdin <文本>"
+                );
+            }
+            if stdin_is_terminal {
+                bail!(
+                    "--prompt-from-stdin 需要通过管道提供提示词\n\n\
+                    使用方法: echo <提示词> | transome --prompt-from-stdin <文本>"
+                );
+            }
+
+            let prompt = read()?;
+            if prompt.trim().is_empty() {
+                bail!("从 stdin 读取的提示词为空");
+            }
+            self.prompt = prompt.trim().to_string();
+        } else if self.text.is_none() && (self.pipe || !stdin_is_terminal) {
+            let text = read()?;
+            if self.lines {
+                self.sources.push(Source::stdin(text.clone()));
+            }
+            self.text = Some(text);
+        } else if !self.sources.is_empty() && (self.pipe || !stdin_is_terminal) {
+            // 批量模式下 stdin 排在各文件之前
+            self.sources.insert(0, Source::stdin(read()?));
+            self.text = Some(source::joined_text(&self.sources));
+        }
+
+        Ok(())
+    }
+
+    /// 批量模式的输入来源，未从 stdin 与文件读取时为命令行参数中的文本
+    pub fn batch_sources(&self) -> Vec<Source> {
+        if !self.sources.is_empty() {
+            return self.sources.clone();
+        }
+        vec![Source::new(
+            source::ARGUMENT_NAME,
+            self.text.clone().unwrap_or_default(),
+        )]
+    }
+
+    /// 最终发送给模型的提示词
+    ///
+    /// 所有影响提示词的参数都在这里组合。发送时还可能按目标语言、翻译方向或原文调整，
+    /// `--echo-prompt` 输出的是调整后实际发送的提示词。
+    pub fn composed_prompt(&self) -> String {
+        self.with_prompt_clauses(self.prompt.clone())
+    }
+
+    /// 翻译为 `--to` 指定的某一目标语言时的最终提示词
+    pub fn composed_prompt_for(&self, lang: &str) -> String {
+        self.with_prompt_clauses(target_prompt(&self.prompt, lang))
+    }
+
+    /// 按会话固定的翻译方向调整后的最终提示词
+    pub fn composed_prompt_in(&self, session: &DirectionSession) -> String {
+        self.with_prompt_clauses(session.prompt(&self.prompt))
+    }
+
+    /// `--check-prompt` 要检查的目标语言及翻译为该语言时的提示词
+    ///
+    /// 目标语言依次取 `--to`、自定义提示词中要求的语言；都没有时按原文推断（中英互译）。
+    pub fn prompt_check_targets(&self, text: &str) -> Vec<(String, String)> {
+        if !self.to.is_empty() {
+            return self
+                .to
+                .iter()
+                .map(|lang| (lang.clone(), self.composed_prompt_for(lang)))
+                .collect();
+        }
+        let targets = match prompt_target(&self.prompt).filter(|_| self.prompt != PROMPT) {
+            Some(lang) => vec![lang.to_string()],
+            None => LanguagePair::detect(text, &[]).to,
+        };
+        targets
+            .into_iter()
+            .map(|lang| (lang, self.composed_prompt()))
+            .collect()
+    }
+
+    fn with_prompt_clauses(&self, mut prompt: String) -> String {
+        if let Some(ratio) = self.max_output_ratio {
+            prompt.push('\n');
+            prompt.push_str(&length_clause(ratio));
+        }
+        prompt
+    }
+
+    /// 是否整体按纯文本翻译：`--format text`，或未指定格式且没有推断出其他格式
+    pub fn is_plain_text(&self) -> bool {
+        matches!(self.format, Format::Auto | Format::Text)
+    }
+
+    /// 文档格式与差量翻译中翻译片段的选项
+    pub fn segment_error_options(&self) -> SegmentOptions {
+        SegmentOptions {
+            concurrency: self.concurrency,
+            on_error: self.on_error,
+            placeholder: self.error_placeholder.clone(),
+        }
+    }
+
+    /// 是否推断 `--file` 的格式：未指定 `--format`，且没有使用与文件格式冲突的选项
+    fn detects_format(&self) -> bool {
+        self.format == Format::Auto
+            && !self.lines
+            && !self.json_output()
+            && self.to.is_empty()
+            && self.diff_against.is_none()
+            && !self.in_place_range
+            && !self.show_chunks
+            && self.split_on.is_none()
+            && !self.benchmark
+    }
+
+    /// 是否以 JSON 格式输出（`--json` 或 `--pretty-json`）
+    pub fn json_output(&self) -> bool {
+        self.json || self.pretty_json
+    }
+
+    /// 应用 `--pipe` 预设：不输出警告与进度、不附加建议、译文末尾不补充换行
+    pub fn apply_pipe(&mut self) {
+        if self.pipe {
+            self.quiet = true;
+            self.no_hints = true;
+            self.no_trailing_newline = true;
+        }
+    }
+
+    /// 出错时是否附加故障排除建议
+    ///
+    /// 仅在 stderr 为终端且未使用 `--quiet`、`--json`、`--no-hints` 时显示，
+    /// 避免在脚本输出中产生噪音。
+    pub fn show_hints(&self, stderr_is_terminal: bool) -> bool {
+        stderr_is_terminal && !self.quiet && !self.json_output() && !self.no_hints
+    }
+
+    /// 是否在 stderr 显示进度状态行
+    ///
+    /// 仅在 stderr 为终端且未使用 `--quiet`、`--json` 时显示。
+    pub fn show_progress(&self, stderr_is_terminal: bool) -> bool {
+        stderr_is_terminal && !self.quiet && !self.json_output()
+    }
+
+    /// 获取所选模型的弃用提示
+    ///
+    /// 配置中关闭了弃用提示时返回 None。提示作为警告记录，静默模式下不输出但仍计入
+    /// `--json` 的结果；`--deny-warnings` 不因弃用提示失败。
+    pub fn deprecation_notice(&self, config_file: &ConfigFile) -> Option<String> {
+        if !config_file.deprecation_notices {
+            return None;
+        }
+        config::deprecation_notice(&self.model)
+    }
+
+    /// 获取请求使用的采样温度
+    ///
+    /// `--creative` 优先；否则配置中开启了 `pin_temperature` 时使用配置的温度，
+    /// 关闭时返回 None，由服务端决定。
+    pub fn temperature(&self, config_file: &ConfigFile) -> Option<f32> {
+        if self.creative {
+            Some(CREATIVE_TEMPERATURE)
+        } else if config_file.pin_temperature {
+            Some(config_file.temperature)
+        } else {
+            None
+        }
+    }
+
     /// 解析要使用的API URL
     ///
     /// URL解析优先级：
     /// 1. 使用自定义URL（如果通过 --url 参数提供）
     /// 2. 从配置中查找模型的默认URL
     /// 3. 如果找不到模型则返回错误
+    ///
+    /// 启用 `--strict-model` 时，未知模型即使提供了自定义URL也返回错误。
     pub fn resolve_url(&self) -> Result<String> {
+        self.check_strict_model()?;
         if let Some(url) = &self.url {
             Ok(url.clone())
         } else if let Some(url) = config::get_model_url(&self.model) {
@@ -123,9 +1250,59 @@ impl Cli {
         }
     }
 
-    /// 显示所有支持的模型
-    pub fn list_all_models() {
-        config::list_models();
+    /// 检查 `--insecure-skip-tls-verify` 能否用于该 API 地址
+    ///
+    /// 需要配置文件允许，且只能用于自定义地址：内置提供商的地址始终验证证书。
+    pub fn check_insecure_tls(&self, url: &str, config_file: &ConfigFile) -> Result<()> {
+        if !config_file.allow_insecure_tls {
+            bail!(
+                "--insecure-skip-tls-verify 未被允许\n\n\
+                关闭证书验证会使 API 密钥与译文暴露给中间人。仅在实验环境中使用，\
+                并在配置文件中设置 allow_insecure_tls = true；使用私有根证书时请改用 --ca-cert"
+            );
+        }
+        if self.url.is_none() || config::provider_for_url(url).is_some() {
+            bail!(
+                "--insecure-skip-tls-verify 只能用于自定义的 API 地址\n\n\
+                请通过 --url 或配置档指定内部网关的地址，内置提供商的地址（{}）始终验证证书",
+                url
+            );
+        }
+        Ok(())
+    }
+
+    /// 启用 `--strict-model` 时检查模型是否在内置列表中
+    fn check_strict_model(&self) -> Result<()> {
+        if self.strict_model && !config::is_model_supported(&self.model) {
+            bail!(
+                "已启用 --strict-model，只能使用内置列表中的模型\n\n{}",
+                config::create_model_error_message(&self.model)
+            );
+        }
+        Ok(())
+    }
+
+    /// 是否会为一次运行发送多个翻译请求，此时 `--dump-response` 按顺序编号保存
+    pub fn sends_many_requests(&self) -> bool {
+        self.lines || !self.is_plain_text() || self.to.len() > 1 || self.diff_against.is_some()
+    }
+
+    /// 按 --sort 显示支持的模型，指定了 --no-urls 时不显示 API 地址，--filter 时只显示匹配的模型
+    ///
+    /// `--json` 时输出模型的 JSON 数组，没有匹配的模型时为空数组。
+    pub fn list_all_models(&self) -> Result<()> {
+        if !self.json_output() {
+            config::list_models_with(!self.no_urls, self.sort, self.filter.as_deref());
+            return Ok(());
+        }
+        let models = config::matching_models(self.sort, self.filter.as_deref());
+        let json = if self.pretty_json {
+            serde_json::to_string_pretty(&models)?
+        } else {
+            serde_json::to_string(&models)?
+        };
+        println!("{}", json);
+        Ok(())
     }
 
     /// 验证必填字段配置
@@ -134,10 +1311,10 @@ impl Cli {
     /// - 非列表模式时，文本输入必填
     /// - 文本输入不能为空或仅包含空白字符
     /// - 验证API密钥是否可用（调用 resolve_api_key 方法）
-    /// - 必须支持所选模型（除非提供了自定义URL）
+    /// - 必须支持所选模型（除非提供了自定义URL且未启用 --strict-model），升级模型同样
     pub fn validate(&self) -> Result<()> {
-        // 如果只是列出模型则跳过验证
-        if self.list_models {
+        // 如果只是列出模型或执行子命令则跳过验证
+        if self.list_models || self.validate_keys || self.command.is_some() {
             return Ok(());
         }
 
@@ -160,6 +1337,83 @@ impl Cli {
             }
         }
 
+        // 整体翻译的纯文本没有可以单独保留原文的条目或片段
+        if self.on_error != OnError::Continue
+            && !self.lines
+            && self.is_plain_text()
+            && self.diff_against.is_none()
+        {
+            bail!(
+                "--on-error 仅用于 --lines 批量模式、--format 指定的文档格式与 --diff-against\n\n\
+                示例: transome --format markdown --on-error keep-source --file README.md"
+            );
+        }
+
+        // 验证折行宽度
+        if self.wrap == Some(0) {
+            bail!(
+                "折行宽度必须大于 0\n\n\
+                使用方法: transome --wrap <N> <文本>"
+            );
+        }
+
+        // 多目标语言时每种语言写入各自的文件，JSON 则为单个对象
+        if let Some(output) = &self.output {
+            let has_placeholder = output.to_string_lossy().contains(LANG_PLACEHOLDER);
+            if self.to.len() > 1 && !self.json_output() && !has_placeholder {
+                bail!(
+                    "翻译为多种目标语言时，--output 需要包含 {{lang}} 占位符\n\n\
+                    示例: transome --to en --to ja -o out.{{lang}}.md < README.md"
+                );
+            }
+            if self.json_output() && has_placeholder {
+                bail!("--json 将所有目标语言输出为一个对象，--output 中不能包含 {{lang}} 占位符");
+            }
+        }
+
+        // 验证长度上限
+        if let Some(ratio) = self.max_output_ratio
+            && !(ratio.is_finite() && ratio > 0.0)
+        {
+            bail!(
+                "--max-output-ratio 必须是大于 0 的数值\n\n\
+                使用方法: transome --max-output-ratio 1.3 <文本>"
+            );
+        }
+
+        // 验证费用估算选项
+        if !(self.estimate_output_ratio.is_finite() && self.estimate_output_ratio >= 0.0) {
+            bail!(
+                "--estimate-output-ratio 必须是不小于 0 的数值\n\n\
+                使用方法: transome --estimate --estimate-output-ratio 1.2 --file <文件>"
+            );
+        }
+        if let Some(max_cost) = self.max_cost
+            && !(max_cost.is_finite() && max_cost >= 0.0)
+        {
+            bail!(
+                "--max-cost 必须是不小于 0 的金额（美元）\n\n\
+                使用方法: transome --max-cost 5 --file <文件>"
+            );
+        }
+
+        // 验证 CSV 列选项
+        self.validate_format_options()?;
+
+        // 验证并发数
+        if self.concurrency == 0 {
+            bail!(
+                "并发数必须大于 0\n\n\
+                使用方法: transome --lines --concurrency <N>"
+            );
+        }
+        if self.max_inflight == 0 {
+            bail!(
+                "最大请求数必须大于 0\n\n\
+                使用方法: transome --max-inflight <N> <文本>"
+            );
+        }
+
         // 验证API密钥是否可用
         self.resolve_api_key().map_err(|e| {
             anyhow::anyhow!(
@@ -169,15 +1423,94 @@ impl Cli {
             )
         })?;
 
-        // 验证模型（仅在未提供自定义URL时）
+        // 验证模型（仅在未提供自定义URL时，--strict-model 下始终验证）
+        self.check_strict_model()?;
         if self.url.is_none() && !config::is_model_supported(&self.model) {
             bail!("{}", config::create_model_error_message(&self.model));
         }
 
+        // 升级模型与 -m 的规则相同
+        if let Some(model) = &self.escalate_model
+            && (self.strict_model || self.url.is_none())
+            && !config::is_model_supported(model)
+        {
+            bail!(
+                "--escalate-model 指定了未知模型\n\n{}",
+                config::create_model_error_message(model)
+            );
+        }
+
         Ok(())
     }
 }
 
+/// 读取文本文件
+fn read_text_file(path: &std::path::Path) -> Result<String> {
+    Ok(std::fs::read_to_string(path).map_err(|e| {
+        TransomeError::io_error_with_context(e, format!("无法读取文件 '{}'", path.display()))
+    })?)
+}
+
+/// 按扩展名推断文件的格式，无法识别时检查文件开头的内容
+///
+/// 未启用对应特性的格式按纯文本处理；内容符合多种格式时报错并列出候选格式。
+fn detect_format(path: &Path) -> Result<Format> {
+    let format = match detect::from_path(path) {
+        Some(format) => format,
+        None => {
+            let mut head = Vec::new();
+            File::open(path)
+                .and_then(|file| file.take(detect::SNIFF_LIMIT as u64).read_to_end(&mut head))
+                .map_err(|e| {
+                    TransomeError::io_error_with_context(
+                        e,
+                        format!("无法读取文件 '{}'", path.display()),
+                    )
+                })?;
+            match detect::sniff(&String::from_utf8_lossy(&head)) {
+                Detection::Detected(format) => format,
+                Detection::Unknown => Format::Text,
+                Detection::Ambiguous(candidates) => {
+                    let names: Vec<String> =
+                        candidates.into_iter().map(detect::format_name).collect();
+                    bail!(
+                        "无法确定文件 '{}' 的格式，内容可能是: {}\n\n\
+                        使用 --format 指定格式，如: transome --format {} --file {}",
+                        path.display(),
+                        names.join("、"),
+                        names[0],
+                        path.display()
+                    );
+                }
+            }
+        }
+    };
+    Ok(match format {
+        Format::Html if !cfg!(feature = "html") => Format::Text,
+        Format::Docx if !cfg!(feature = "docx") => Format::Text,
+        format => format,
+    })
+}
+
+/// 读取 docx 文档的正文文字，每个段落一行
+#[cfg(feature = "docx")]
+fn read_docx_text(path: &std::path::Path) -> Result<String> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        TransomeError::io_error_with_context(e, format!("无法读取文件 '{}'", path.display()))
+    })?;
+    use crate::formats::Document;
+
+    Ok(crate::formats::docx::DocxDocument::parse(&bytes)?
+        .segments()
+        .join("\n"))
+}
+
+/// 未启用 docx 特性时无法解析文档
+#[cfg(not(feature = "docx"))]
+fn read_docx_text(_path: &std::path::Path) -> Result<String> {
+    bail!("--format docx 需使用 --features docx 构建")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,26 +1518,21 @@ mod tests {
 
     /// 创建基本 CLI 实例的辅助函数，包含必需字段
     fn create_test_cli(model: &str) -> Cli {
-        Cli {
-            text: Some("test text".to_string()),
-            model: model.to_string(),
-            url: None,
-            key: None,
-            prompt: "test prompt".to_string(),
-            list_models: false,
-        }
+        Cli::parse_from(["transome", "-m", model, "-p", "test prompt", "test text"])
     }
 
     /// 创建带有自定义密钥的 CLI 实例的辅助函数
     fn create_test_cli_with_key(model: &str, key: &str) -> Cli {
-        Cli {
-            text: Some("test text".to_string()),
-            model: model.to_string(),
-            url: None,
-            key: Some(key.to_string()),
-            prompt: "test prompt".to_string(),
-            list_models: false,
-        }
+        Cli::parse_from([
+            "transome",
+            "-m",
+            model,
+            "-k",
+            key,
+            "-p",
+            "test prompt",
+            "test text",
+        ])
     }
 
     /// 临时设置环境变量的辅助函数
@@ -346,7 +1674,10 @@ mod tests {
 
             let error_msg = result.unwrap_err().to_string();
             assert!(error_msg.contains("环境变量 OPENAI_API_KEY 未设置"));
-            assert!(error_msg.contains("export OPENAI_API_KEY=<your_api_key>"));
+            assert!(
+                error_msg
+                    .contains(&Shell::current().set_env_var("OPENAI_API_KEY", "<your_api_key>"))
+            );
             assert!(error_msg.contains("transome -k <your_api_key>"));
             assert!(error_msg.contains("https://platform.openai.com/api-keys"));
         });
@@ -420,6 +1751,46 @@ mod tests {
         assert!(error_msg.contains("找不到模型 'unsupported-model'"));
     }
 
+    #[test]
+    fn test_unknown_escalate_model_is_rejected() {
+        let mut cli = create_test_cli_with_key("gpt-4", "key");
+        cli.escalate_on_low_quality = Some(0.7);
+        cli.escalate_model = Some("gpt-4o".to_string());
+        assert!(cli.validate().is_ok());
+
+        cli.escalate_model = Some("gpt-9-ultra".to_string());
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--escalate-model"));
+        assert!(error_msg.contains("找不到模型 'gpt-9-ultra'"));
+
+        // 与 -m 一样，自定义URL可以搭配任意模型
+        cli.url = Some("http://localhost:8080/v1".to_string());
+        assert!(cli.validate().is_ok());
+    }
+
+    #[test]
+    fn test_strict_model_rejects_custom_url_with_unknown_model() {
+        let mut cli = create_test_cli_with_key("my-local-model", "key");
+        cli.url = Some("http://localhost:8080/v1".to_string());
+
+        // 默认允许自定义URL搭配任意模型
+        assert_eq!(cli.resolve_url().unwrap(), "http://localhost:8080/v1");
+        assert!(cli.validate().is_ok());
+
+        cli.strict_model = true;
+        let error_msg = cli.resolve_url().unwrap_err().to_string();
+        assert!(error_msg.contains("--strict-model"));
+        assert!(error_msg.contains("找不到模型 'my-local-model'"));
+        assert!(cli.validate().is_err());
+
+        // 已知模型仍可使用自定义URL
+        let mut cli = create_test_cli_with_key("gpt-4", "key");
+        cli.url = Some("https://proxy.example.com/v1".to_string());
+        cli.strict_model = true;
+        assert_eq!(cli.resolve_url().unwrap(), "https://proxy.example.com/v1");
+        assert!(cli.validate().is_ok());
+    }
+
     #[test]
     fn test_manual_key_overrides_env_different_providers() {
         // 测试在设置 Google 环境变量时使用手动密钥的 OpenAI 模型
@@ -467,4 +1838,1058 @@ mod tests {
         let result = cli.validate();
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_stats_subcommand() {
+        let cli = Cli::parse_from(["transome", "stats"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Stats {
+                days: stats::DEFAULT_DAYS,
+                json: false
+            })
+        );
+        assert!(!cli.records_history());
+        assert!(cli.validate().is_ok());
+
+        let cli = Cli::parse_from(["transome", "stats", "--days", "7", "--json"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Stats {
+                days: 7,
+                json: true
+            })
+        );
+
+        let cli = Cli::parse_from(["transome", "hello"]);
+        assert_eq!(cli.command, None);
+        assert!(cli.records_history());
+        let cli = Cli::parse_from(["transome", "--no-history", "hello"]);
+        assert!(!cli.records_history());
+    }
+
+    #[test]
+    fn test_features_subcommand() {
+        let cli = Cli::parse_from(["transome", "features"]);
+        assert_eq!(cli.command, Some(Command::Features));
+        assert!(cli.validate().is_ok());
+        assert!(Cli::try_parse_from(["transome", "--features"]).is_err());
+    }
+
+    #[test]
+    fn test_list_models_sort() {
+        let cli = Cli::parse_from(["transome", "--list-models"]);
+        assert_eq!(cli.sort, ModelSort::Provider);
+
+        let cli = Cli::parse_from(["transome", "--list-models", "--sort", "name"]);
+        assert_eq!(cli.sort, ModelSort::Name);
+
+        assert!(Cli::try_parse_from(["transome", "--sort", "url", "hi"]).is_err());
+    }
+
+    #[test]
+    fn test_sampling_flags() {
+        let cli = Cli::parse_from([
+            "transome",
+            "--top-p",
+            "0.8",
+            "--frequency-penalty",
+            "-0.5",
+            "--presence-penalty",
+            "1",
+            "hi",
+        ]);
+        assert_eq!(
+            cli.sampling(),
+            SamplingOptions {
+                top_p: Some(0.8),
+                frequency_penalty: Some(-0.5),
+                presence_penalty: Some(1.0),
+            }
+        );
+        assert!(Cli::parse_from(["transome", "hi"]).sampling().is_empty());
+
+        assert!(Cli::try_parse_from(["transome", "--top-p", "1.5", "hi"]).is_err());
+        assert!(Cli::try_parse_from(["transome", "--frequency-penalty", "-3", "hi"]).is_err());
+        assert!(Cli::try_parse_from(["transome", "--presence-penalty", "2.1", "hi"]).is_err());
+    }
+
+    #[test]
+    fn test_list_models_filter() {
+        let cli = Cli::parse_from(["transome", "--list-models", "--filter", "gpt"]);
+        assert_eq!(cli.filter.as_deref(), Some("gpt"));
+        assert!(Cli::try_parse_from(["transome", "--filter", "gpt", "hi"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_wrap_width() {
+        let mut cli = create_test_cli_with_key("gpt-4", "test-key");
+        cli.wrap = Some(0);
+
+        let result = cli.validate();
+        assert!(result.is_err());
+
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("折行宽度必须大于 0"));
+    }
+
+    #[test]
+    fn test_parse_wrap_flag() {
+        let cli = Cli::parse_from(["transome", "--wrap", "40", "hello"]);
+        assert_eq!(cli.wrap, Some(40));
+
+        let cli = Cli::parse_from(["transome", "hello"]);
+        assert_eq!(cli.wrap, None);
+    }
+
+    #[test]
+    fn test_check_key_format_warns_on_mismatch() {
+        let cli = create_test_cli("gpt-4");
+        let warnings = Warnings::new(true);
+
+        cli.check_key_format("AIza-google-key", &warnings);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings.to_vec()[0].message.contains("sk-"));
+
+        // 启用 --deny-warnings 时警告导致失败
+        assert!(warnings.check_denied(true).is_err());
+    }
+
+    #[test]
+    fn test_check_key_format_matching_or_custom_url() {
+        let warnings = Warnings::new(true);
+
+        let cli = create_test_cli("gemini-2.5-flash");
+        cli.check_key_format("AIzaSy-test", &warnings);
+
+        // 自定义 URL 时不检查密钥格式
+        let mut cli = create_test_cli("gpt-4");
+        cli.url = Some("https://custom.api.com/v1".to_string());
+        cli.check_key_format("gateway-key", &warnings);
+
+        assert!(warnings.is_empty());
+        assert!(warnings.check_denied(true).is_ok());
+    }
+
+    #[test]
+    fn test_deprecation_notice_respects_config() {
+        let cli = create_test_cli("gpt-3.5-turbo-16k");
+        let notice = cli.deprecation_notice(&ConfigFile::default()).unwrap();
+        assert!(notice.contains("gpt-4o-mini"));
+
+        let disabled = ConfigFile {
+            deprecation_notices: false,
+            ..ConfigFile::default()
+        };
+        assert_eq!(cli.deprecation_notice(&disabled), None);
+
+        // 静默模式只是不输出，提示仍作为警告记录
+        let mut quiet_cli = create_test_cli("gpt-3.5-turbo-16k");
+        quiet_cli.quiet = true;
+        assert!(
+            quiet_cli
+                .deprecation_notice(&ConfigFile::default())
+                .is_some()
+        );
+
+        let current = create_test_cli("gpt-4o");
+        assert_eq!(current.deprecation_notice(&ConfigFile::default()), None);
+    }
+
+    #[test]
+    fn test_resolve_api_key_custom_url_of_known_provider() {
+        // 未知模型 + 已知提供商的 URL，使用该提供商的环境变量
+        let mut cli = create_test_cli("gemini-exp-custom");
+        cli.url = Some("https://generativelanguage.googleapis.com/v1beta/openai".to_string());
+
+        with_env_var("GOOGLE_AI_API_KEY", "google-key", || {
+            assert_eq!(cli.resolve_api_key().unwrap(), "google-key");
+        });
+
+        // 未知模型 + 未知 URL 仍然报错
+        let mut cli = create_test_cli("custom-model");
+        cli.url = Some("https://custom.api.com/v1".to_string());
+        assert!(cli.resolve_api_key().is_err());
+    }
+
+    #[test]
+    fn test_parse_batch_flags() {
+        let cli = Cli::parse_from(["transome", "--lines", "--deadline", "10m", "a\nb"]);
+        assert!(cli.lines);
+        assert_eq!(cli.concurrency, 4);
+        assert_eq!(cli.deadline, Some(Duration::from_secs(600)));
+        assert_eq!(cli.abort_after, DEFAULT_ABORT_AFTER);
+
+        let cli = Cli::parse_from(["transome", "--lines", "--abort-after", "0", "a"]);
+        assert_eq!(cli.abort_after, 0);
+        assert!(Cli::try_parse_from(["transome", "--abort-after", "5", "a"]).is_err());
+
+        assert!(!Cli::parse_from(["transome", "--lines", "a"]).unordered);
+        assert!(Cli::parse_from(["transome", "--lines", "--unordered", "a"]).unordered);
+        assert!(Cli::try_parse_from(["transome", "--unordered", "a"]).is_err());
+
+        assert!(Cli::try_parse_from(["transome", "--lines", "--deadline", "soon", "a"]).is_err());
+
+        let cli = Cli::parse_from(["transome", "--lines", "--resume", "job.checkpoint", "a"]);
+        assert_eq!(cli.resume, Some(PathBuf::from("job.checkpoint")));
+        assert!(Cli::try_parse_from(["transome", "--resume", "job.checkpoint", "a"]).is_err());
+
+        assert!(Cli::parse_from(["transome", "--lines", "--reload-config", "a"]).reload_config);
+        assert!(Cli::try_parse_from(["transome", "--reload-config", "a"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_retry_flags() {
+        let cli = Cli::parse_from(["transome", "text"]);
+        assert_eq!(cli.retries, None);
+        assert_eq!(cli.timeout, None);
+
+        // --deadline 同样约束单次翻译的重试
+        let cli = Cli::parse_from([
+            "transome",
+            "--timeout",
+            "20s",
+            "--retries",
+            "3",
+            "--deadline",
+            "1m",
+            "text",
+        ]);
+        assert_eq!(cli.timeout, Some(Duration::from_secs(20)));
+        assert_eq!(cli.retries, Some(3));
+        assert_eq!(cli.retry_budget, DEFAULT_RETRY_BUDGET);
+        assert_eq!(cli.deadline, Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_concurrency() {
+        let mut cli = create_test_cli_with_key("gpt-4", "test-key");
+        cli.concurrency = 0;
+
+        let result = cli.validate();
+        assert!(result.is_err());
+
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("并发数必须大于 0"));
+
+        let mut cli = create_test_cli_with_key("gpt-4", "test-key");
+        cli.max_inflight = 0;
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("最大请求数必须大于 0"));
+    }
+
+    #[test]
+    fn test_validate_csv_options() {
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "csv",
+            "--columns",
+            "2,3",
+            "a,b,c",
+        ]);
+        assert_eq!(cli.columns, vec![2, 3]);
+        assert!(cli.validate().is_ok());
+
+        let cli = Cli::parse_from(["transome", "-k", "key", "--format", "csv", "a,b"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--columns"));
+
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "csv",
+            "--columns",
+            "0",
+            "a,b",
+        ]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("列号从 1 开始"));
+
+        let cli = Cli::parse_from(["transome", "-k", "key", "--columns", "1", "text"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("仅适用于 CSV 格式"));
+    }
+
+    #[test]
+    fn test_validate_markdown_options() {
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "markdown",
+            "--markdown-translate-links",
+            "all",
+            "# Title",
+        ]);
+        assert_eq!(cli.markdown_translate_links, Some(LinkTranslation::All));
+        assert!(cli.validate().is_ok());
+
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--markdown-translate-links",
+            "none",
+            "text",
+        ]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("仅适用于 Markdown 格式"));
+    }
+
+    #[test]
+    fn test_validate_html_options() {
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "html",
+            "--translate-attrs",
+            "alt,title",
+            "<p>Hi</p>",
+        ]);
+        assert_eq!(cli.translate_attrs, ["alt", "title"]);
+        assert_eq!(cli.validate().is_ok(), cfg!(feature = "html"));
+
+        let cli = Cli::parse_from(["transome", "-k", "key", "--translate-attrs", "alt", "text"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("仅适用于 HTML 格式"));
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.txt"), "second\n").unwrap();
+        std::fs::write(dir.path().join("a.txt"), "first\r\n").unwrap();
+        let path = dir.path().to_str().unwrap();
+
+        let mut cli = Cli::parse_from(["transome", "--lines", "--dir", path]);
+        cli.read_file().unwrap();
+        assert_eq!(cli.text.as_deref(), Some("first\nsecond\n"));
+
+        // 目录中的文件按路径排序，排在 --file 之后
+        let other = tempfile::tempdir().unwrap();
+        let extra = other.path().join("c.md");
+        std::fs::write(&extra, "extra\n").unwrap();
+        let args = ["transome", "--lines", "--dir", path, "--exclude", "*.md"];
+        let mut cli = Cli::parse_from(args.iter().copied().chain(["-f", extra.to_str().unwrap()]));
+        cli.read_file().unwrap();
+        let names: Vec<_> = cli
+            .sources
+            .iter()
+            .map(|source| source.name.clone())
+            .collect();
+        assert_eq!(names.len(), 3);
+        assert!(
+            names[0].ends_with("c.md") && names[2].ends_with("b.txt"),
+            "{:?}",
+            names
+        );
+
+        let mut cli = Cli::parse_from(["transome", "--lines", "--dir", path, "--exclude", "*.txt"]);
+        let error = cli.read_file().unwrap_err();
+        assert!(error.to_string().contains("没有要翻译的文件"), "{}", error);
+
+        assert!(Cli::try_parse_from(["transome", "--dir", path]).is_err());
+    }
+
+    #[test]
+    fn test_validate_docx_options() {
+        let cli = Cli::parse_from(["transome", "-k", "key", "--format", "docx", "text"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--file"), "{}", error_msg);
+
+        let args = [
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "docx",
+            "--file",
+            "report.docx",
+            "-o",
+            "report.zh.docx",
+        ];
+        let mut cli = Cli::parse_from(args);
+        cli.text = Some("Quarterly report".to_string());
+        assert_eq!(cli.validate().is_ok(), cfg!(feature = "docx"));
+
+        cli.json = true;
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--json"), "{}", error_msg);
+
+        let mut cli = Cli::parse_from(args.iter().copied().chain(["--range", "1:2"]));
+        let error_msg = cli.read_file().unwrap_err().to_string();
+        assert!(error_msg.contains("不适用于 docx 格式"), "{}", error_msg);
+    }
+
+    #[test]
+    fn test_validate_properties_options() {
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "properties",
+            "--properties-utf8",
+            "greeting=Hello",
+        ]);
+        assert!(cli.validate().is_ok());
+
+        let cli = Cli::parse_from(["transome", "-k", "key", "--format", "kv", "KEY=Hello"]);
+        assert_eq!(cli.format, Format::Kv);
+        assert!(cli.validate().is_ok());
+
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "kv",
+            "--properties-utf8",
+            "KEY=Hello",
+        ]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("仅适用于 properties 格式"));
+    }
+
+    #[test]
+    fn test_validate_json_options() {
+        let cli = Cli::parse_from([
+            "transome",
+            "-k",
+            "key",
+            "--format",
+            "json",
+            "--translate-strings",
+            "{\"a\": \"Hello\"}",
+        ]);
+        assert_eq!(cli.format, Format::Json);
+        assert!(cli.validate().is_ok());
+
+        // 未指定要翻译的内容
+        let cli = Cli::parse_from(["transome", "-k", "key", "--format", "json", "{}"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--translate-strings"));
+
+        let cli = Cli::parse_from(["transome", "-k", "key", "--translate-keys", "Hello"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("仅适用于 JSON 格式"));
+    }
+
+    #[test]
+    fn test_validate_code_options() {
+        let cli = Cli::parse_from(["transome", "-k", "key", "--format", "code", "// hi"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--lang"));
+
+        let cli = Cli::parse_from([
+            "transome", "-k", "key", "--format", "code", "--lang", "rust", "// hi",
+        ]);
+        assert!(cli.validate().is_ok());
+
+        let mut cli = Cli::parse_from(["transome", "--format", "code", "--file", "src/main.py"]);
+        assert_eq!(cli.code_language(), Some(Language::Python));
+        cli.format = Format::Text;
+        assert_eq!(cli.code_language(), Some(Language::Python));
+
+        let cli = Cli::parse_from(["transome", "-k", "key", "--lang", "js", "text"]);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("仅适用于源代码格式"));
+    }
+
+    #[test]
+    fn test_read_stdin_as_text() {
+        let mut cli = Cli::parse_from(["transome"]);
+        cli.read_stdin(false, || Ok("piped text".to_string()))
+            .unwrap();
+        assert_eq!(cli.text.as_deref(), Some("piped text"));
+        assert_eq!(cli.prompt, PROMPT);
+
+        // 提供了文本参数时不读取 stdin
+        let mut cli = Cli::parse_from(["transome", "arg text"]);
+        cli.read_stdin(false, || panic!("stdin should not be read"))
+            .unwrap();
+        assert_eq!(cli.text.as_deref(), Some("arg text"));
+    }
+
+    #[test]
+    fn test_pipe_preset() {
+        let mut cli = Cli::parse_from(["transome", "-k", "key", "--pipe"]);
+        cli.apply_pipe();
+        assert!(cli.quiet);
+        assert!(!cli.show_hints(true));
+        assert!(!cli.show_progress(true));
+        assert_eq!(cli.trailing_newline(), Some(false));
+
+        // 即使 stdin 是终端也读取，如同 cat
+        cli.read_stdin(true, || Ok("Hello\nworld\n".to_string()))
+            .unwrap();
+        assert_eq!(cli.text.as_deref(), Some("Hello\nworld\n"));
+        assert!(cli.validate().is_ok());
+
+        // 译文原样写出，不补充换行
+        let trailing_newline = cli.trailing_newline().unwrap();
+        let mut sink = sink::WriterSink::new(Vec::new()).trailing_newline(trailing_newline);
+        sink.write_result("你好\n世界").unwrap();
+        assert_eq!(sink.into_inner(), "你好\n世界".as_bytes());
+
+        for conflicting in [
+            &["--pipe", "text"][..],
+            &["--pipe", "--json"],
+            &["--pipe", "-o", "out.txt"],
+        ] {
+            let args = ["transome"].iter().chain(conflicting);
+            assert!(Cli::try_parse_from(args).is_err(), "{:?}", conflicting);
+        }
+
+        // 未使用 --pipe 时预设不生效
+        let mut cli = Cli::parse_from(["transome", "text"]);
+        cli.apply_pipe();
+        assert!(!cli.quiet);
+        assert_eq!(cli.trailing_newline(), None);
+    }
+
+    #[test]
+    fn test_read_file_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.txt");
+        std::fs::write(&path, "a\r\nb\r\nc\r\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut cli = Cli::parse_from(["transome", "--file", path, "--range", "2:3"]);
+        assert_eq!(cli.read_file().unwrap(), None);
+        assert_eq!(cli.text.as_deref(), Some("b\r\nc"));
+
+        let mut cli = Cli::parse_from([
+            "transome",
+            "--file",
+            path,
+            "--range",
+            "2:2",
+            "--in-place-range",
+        ]);
+        let slice = cli.read_file().unwrap().unwrap();
+        assert_eq!(slice.splice("B"), "a\r\nB\r\nc\r\n");
+
+        // 超出范围时错误信息包含文件长度
+        let mut cli = Cli::parse_from(["transome", "--file", path, "--range", "2:5"]);
+        let error = cli.read_file().unwrap_err().to_string();
+        assert!(error.contains("共 3 行"), "{}", error);
+
+        // 拼接需要指定范围
+        let mut cli = Cli::parse_from(["transome", "--file", path, "--in-place-range"]);
+        assert!(cli.read_file().is_err());
+        let mut cli = Cli::parse_from(["transome", "--range", "1:2", "text"]);
+        assert!(cli.read_file().is_err());
+    }
+
+    #[test]
+    fn test_read_file_detects_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let notes = write("notes", "# Title\n\nBody.\n");
+        let messages = write("messages", "greeting=Hello\nfarewell=Bye\n");
+
+        let mut cli = Cli::parse_from(["transome", "--file", &notes]);
+        cli.read_file().unwrap();
+        assert_eq!(cli.format, Format::Markdown);
+
+        // 扩展名优先于内容，--format 优先于推断
+        let mut cli = Cli::parse_from(["transome", "--file", &write("notes.txt", "# Title")]);
+        cli.read_file().unwrap();
+        assert_eq!(cli.format, Format::Text);
+        let mut cli = Cli::parse_from(["transome", "--format", "kv", "--file", &messages]);
+        cli.read_file().unwrap();
+        assert_eq!(cli.format, Format::Kv);
+
+        let mut cli = Cli::parse_from(["transome", "--file", &messages]);
+        let error = cli.read_file().unwrap_err().to_string();
+        assert!(error.contains("properties、kv"), "{}", error);
+
+        // 批量模式逐行翻译，不推断格式
+        let mut cli = Cli::parse_from(["transome", "--lines", "--file", &notes]);
+        cli.read_file().unwrap();
+        assert!(cli.is_plain_text());
+    }
+
+    #[test]
+    fn test_batch_sources_stdin_then_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "a 1\n\na 3\n").unwrap();
+        std::fs::write(&b, "b 1").unwrap();
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+        // 命令行中文件的顺序即翻译顺序，stdin 排在最前
+        let mut cli = Cli::parse_from(["transome", "--lines", "--label", "-f", b, "--file", a]);
+        cli.read_file().unwrap();
+        cli.read_stdin(false, || Ok("main 1\nmain 2\n".to_string()))
+            .unwrap();
+        let names: Vec<&str> = cli.sources.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, [source::STDIN_NAME, b, a]);
+        assert_eq!(
+            cli.text.as_deref(),
+            Some("main 1\nmain 2\nb 1\na 1\n\na 3\n")
+        );
+
+        let (lines, origins) = source::split_sources(&cli.batch_sources());
+        assert_eq!(lines, ["main 1", "main 2", "b 1", "a 1", "a 3"]);
+        let labels: Vec<String> = origins.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            labels,
+            [
+                "(standard input):1".to_string(),
+                "(standard input):2".to_string(),
+                format!("{}:1", b),
+                format!("{}:1", a),
+                format!("{}:3", a),
+            ]
+        );
+
+        // stdin 是终端时只读取文件
+        let mut cli = Cli::parse_from(["transome", "--lines", "-f", a, "-f", b]);
+        cli.read_file().unwrap();
+        cli.read_stdin(true, || panic!("stdin should not be read"))
+            .unwrap();
+        assert_eq!(cli.sources.len(), 2);
+
+        // 单个文件指定了范围时，行号从范围的起始行开始
+        let mut cli = Cli::parse_from(["transome", "--lines", "-f", a, "--range", "3:3"]);
+        cli.read_file().unwrap();
+        let (_, origins) = source::split_sources(&cli.batch_sources());
+        assert_eq!(origins[0].line, 3);
+
+        // 参数中的文本
+        let cli = Cli::parse_from(["transome", "--lines", "x\ny"]);
+        let (_, origins) = source::split_sources(&cli.batch_sources());
+        assert_eq!(origins[1].to_string(), "(argument):2");
+    }
+
+    #[test]
+    fn test_multiple_files_require_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        std::fs::write(&a, "a").unwrap();
+        let a = a.to_str().unwrap();
+
+        let mut cli = Cli::parse_from(["transome", "-f", a, "-f", a]);
+        let error = cli.read_file().unwrap_err().to_string();
+        assert!(error.contains("--lines"), "{}", error);
+
+        let mut cli = Cli::parse_from(["transome", "--lines", "-f", a, "-f", a, "--range", "1:1"]);
+        assert!(cli.read_file().is_err());
+
+        // --label 只适用于批量模式，批量模式可以输出 JSON Lines
+        assert!(Cli::try_parse_from(["transome", "--label", "text"]).is_err());
+        assert!(Cli::try_parse_from(["transome", "--lines", "--json", "--label", "text"]).is_ok());
+    }
+
+    #[test]
+    fn test_prompt_from_stdin() {
+        let mut cli = Cli::parse_from(["transome", "--prompt-from-stdin", "hello"]);
+        cli.read_stdin(false, || Ok("翻译成日语\n".to_string()))
+            .unwrap();
+        assert_eq!(cli.prompt, "翻译成日语");
+        assert_eq!(cli.text.as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn test_prompt_from_stdin_conflicts() {
+        // 文本也需要从 stdin 读取
+        let mut cli = Cli::parse_from(["transome", "--prompt-from-stdin"]);
+        let result = cli.read_stdin(false, || Ok("prompt".to_string()));
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("待翻译文本需通过参数提供")
+        );
+
+        // stdin 是终端，没有可读取的提示词
+        let mut cli = Cli::parse_from(["transome", "--prompt-from-stdin", "hello"]);
+        let result = cli.read_stdin(true, || Ok("prompt".to_string()));
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("需要通过管道提供提示词")
+        );
+
+        // 与 --prompt 同时使用
+        let result = Cli::try_parse_from(["transome", "--prompt-from-stdin", "-p", "x", "hello"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_request_id_flag() {
+        let cli = Cli::parse_from(["transome", "--request-id", "trace-1", "--verbose", "text"]);
+        assert_eq!(cli.request_id.as_deref(), Some("trace-1"));
+        assert!(cli.verbose);
+
+        assert!(Cli::try_parse_from(["transome", "--request-id", "a\nb", "text"]).is_err());
+    }
+
+    #[test]
+    fn test_chunk_flags() {
+        let text = "前言\n\n## 一\n甲\n\n## 二\n乙";
+        let cli = Cli::parse_from(["transome", "--show-chunks", "--split-on", "^## ", "x"]);
+        assert!(cli.show_chunks);
+        assert_eq!(cli.chunks(text, 1_000).len(), 3);
+
+        // 未指定 --split-on 时按段落自动分块
+        let cli = Cli::parse_from(["transome", "x"]);
+        assert_eq!(cli.chunks(text, 1_000).len(), 1);
+        assert_eq!(
+            cli.context_window(&ConfigFile::default()),
+            config::get_context_window(config::DEFAULT_MODEL)
+        );
+
+        assert!(Cli::try_parse_from(["transome", "--split-on", "(", "x"]).is_err());
+        assert!(Cli::try_parse_from(["transome", "--split-on", "^#", "--lines", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_json_flags() {
+        let cli = Cli::parse_from(["transome", "--pretty-json", "text"]);
+        assert!(cli.json_output());
+        assert!(!cli.json);
+        assert!(!create_test_cli("gpt-4o").json_output());
+
+        assert!(Cli::try_parse_from(["transome", "--json", "--pretty-json", "text"]).is_err());
+        assert!(Cli::try_parse_from(["transome", "--pretty-json", "--lines", "text"]).is_err());
+    }
+
+    #[test]
+    fn test_show_hints() {
+        let cli = create_test_cli("gpt-4o");
+        assert!(cli.show_hints(true));
+        assert!(!cli.show_hints(false));
+
+        for flag in ["--quiet", "--json", "--pretty-json", "--no-hints"] {
+            let cli = Cli::parse_from(["transome", flag, "text"]);
+            assert!(!cli.show_hints(true), "{} should hide hints", flag);
+        }
+    }
+
+    #[test]
+    fn test_prompt_check_targets() {
+        let langs = |cli: &Cli, text: &str| -> Vec<String> {
+            cli.prompt_check_targets(text)
+                .into_iter()
+                .map(|(lang, _)| lang)
+                .collect()
+        };
+
+        // 默认提示词按原文推断中英互译的方向
+        let cli = create_test_cli("gpt-4o");
+        assert_eq!(langs(&cli, "你好"), ["en"]);
+        assert_eq!(langs(&cli, "Hello"), ["zh"]);
+
+        // --to 的每种语言使用各自的提示词
+        let cli = Cli::parse_from(["transome", "--to", "ja", "--to", "fr", "hello"]);
+        let targets = cli.prompt_check_targets("hello");
+        assert_eq!(targets.len(), 2);
+        assert_eq!(
+            targets[0],
+            ("ja".to_string(), cli.composed_prompt_for("ja"))
+        );
+        assert!(targets[1].1.contains("法文"));
+
+        // 自定义提示词要求的目标语言
+        let cli = Cli::parse_from(["transome", "-p", "翻译成日文", "hello"]);
+        assert_eq!(langs(&cli, "hello"), ["ja"]);
+        let cli = Cli::parse_from(["transome", "-p", "使用正式语气", "hello"]);
+        assert_eq!(langs(&cli, "hello"), ["zh"]);
+    }
+
+    #[test]
+    fn test_composed_prompt() {
+        let mut cli =
+            Cli::parse_from(["transome", "--echo-prompt", "--prompt-from-stdin", "hello"]);
+        assert!(cli.echo_prompt);
+        cli.read_stdin(false, || Ok("  翻译成日语\n".to_string()))
+            .unwrap();
+        assert_eq!(cli.composed_prompt(), "翻译成日语");
+
+        let cli = Cli::parse_from([
+            "transome",
+            "--echo-prompt",
+            "-p",
+            "译为法语",
+            "--creative",
+            "hi",
+        ]);
+        assert_eq!(cli.composed_prompt(), "译为法语");
+    }
+
+    #[test]
+    #[cfg(feature = "bundle")]
+    fn test_config_bundle_subcommands() {
+        let import_mode = |args: &[&str]| {
+            let cli = Cli::parse_from(args);
+            assert!(cli.validate().is_ok());
+            match cli.command {
+                Some(Command::Config { action }) => action.import_mode(),
+                command => panic!("{:?}", command),
+            }
+        };
+        let import = ["transome", "config", "import", "b.tar.gz"];
+        assert_eq!(import_mode(&import), Some(ImportMode::Refuse));
+        assert_eq!(
+            import_mode(&[&import[..], &["--merge"]].concat()),
+            Some(ImportMode::Merge)
+        );
+        assert_eq!(
+            import_mode(&[&import[..], &["--overwrite"]].concat()),
+            Some(ImportMode::Overwrite)
+        );
+        assert_eq!(
+            import_mode(&["transome", "config", "export", "b.tar.gz"]),
+            None
+        );
+
+        for args in [
+            vec!["transome", "--overwrite", "hello"],
+            vec![
+                "transome",
+                "config",
+                "import",
+                "b",
+                "--overwrite",
+                "--merge",
+            ],
+            vec!["transome", "config", "export"],
+            vec!["transome", "--export-config", "b"],
+        ] {
+            assert!(Cli::try_parse_from(&args).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        let cli = Cli::parse_from(["transome", "hi"]);
+        assert_eq!(cli.trailing_newline(), None);
+
+        let cli = Cli::parse_from(["transome", "--trailing-newline", "hi"]);
+        assert_eq!(cli.trailing_newline(), Some(true));
+
+        let cli = Cli::parse_from(["transome", "--no-trailing-newline", "hi"]);
+        assert_eq!(cli.trailing_newline(), Some(false));
+
+        let args = [
+            "transome",
+            "--trailing-newline",
+            "--no-trailing-newline",
+            "hi",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_max_output_ratio() {
+        let mut cli = create_test_cli_with_key("gpt-4", "test-key");
+        cli.max_output_ratio = Some(1.3);
+        assert!(cli.validate().is_ok());
+        assert_eq!(
+            cli.composed_prompt(),
+            format!("test prompt\n{}", length_clause(1.3))
+        );
+
+        for ratio in [0.0, -1.0, f64::NAN] {
+            cli.max_output_ratio = Some(ratio);
+            let error = cli.validate().unwrap_err().to_string();
+            assert!(error.contains("--max-output-ratio"), "{}", ratio);
+        }
+    }
+
+    #[test]
+    fn test_validate_estimate_options() {
+        let mut cli = create_test_cli_with_key("gpt-4o", "test-key");
+        assert_eq!(cli.estimate_output_ratio, DEFAULT_OUTPUT_RATIO);
+        cli.estimate_output_ratio = 0.0;
+        cli.max_cost = Some(0.0);
+        assert!(cli.validate().is_ok());
+
+        cli.estimate_output_ratio = f64::NAN;
+        let error = cli.validate().unwrap_err().to_string();
+        assert!(error.contains("--estimate-output-ratio"), "{}", error);
+
+        cli.estimate_output_ratio = 1.0;
+        cli.max_cost = Some(-1.0);
+        let error = cli.validate().unwrap_err().to_string();
+        assert!(error.contains("--max-cost"), "{}", error);
+    }
+
+    #[test]
+    fn test_multi_target_flags() {
+        let cli = Cli::parse_from(["transome", "--to", "en", "--to", "JA", "text"]);
+        assert_eq!(cli.to, vec!["en", "ja"]);
+        assert!(cli.composed_prompt_for("ja").contains("翻译成日文"));
+        assert!(Cli::try_parse_from(["transome", "--to", "en", "--lines", "a"]).is_err());
+        assert!(Cli::try_parse_from(["transome", "--to", "e n", "a"]).is_err());
+
+        let mut cli = create_test_cli_with_key("gpt-4", "test-key");
+        cli.to = vec!["en".to_string(), "ja".to_string()];
+        cli.output = Some(PathBuf::from("out.md"));
+        let error = cli.validate().unwrap_err().to_string();
+        assert!(error.contains("{lang}"), "{}", error);
+
+        cli.output = Some(PathBuf::from("out.{lang}.md"));
+        assert!(cli.validate().is_ok());
+
+        cli.json = true;
+        assert!(cli.validate().is_err());
+        cli.output = Some(PathBuf::from("out.json"));
+        assert!(cli.validate().is_ok());
+    }
+
+    #[test]
+    fn test_parse_on_error() {
+        let cli = Cli::parse_from(["transome", "--lines", "--on-error", "keep-original"]);
+        assert_eq!(cli.on_error, OnError::KeepOriginal);
+        assert_eq!(create_test_cli("gpt-4o").on_error, OnError::Continue);
+
+        // 文档格式下同样适用，keep-source 与 fail 为 keep-original 与 stop 的别名
+        let cli = Cli::parse_from(["transome", "--format", "csv", "--on-error", "keep-source"]);
+        assert_eq!(cli.on_error, OnError::KeepOriginal);
+        // 提供密钥，验证结果与环境中的 API 密钥无关
+        let cli = Cli::parse_from(["transome", "-k", "key", "--on-error", "fail", "hello"]);
+        assert_eq!(cli.on_error, OnError::Stop);
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--on-error 仅用于"), "{}", error_msg);
+
+        let mut cli = create_test_cli_with_key("gpt-4o", "key");
+        cli.on_error = OnError::Placeholder;
+        let error_msg = cli.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("--on-error 仅用于"), "{}", error_msg);
+        cli.format = Format::Markdown;
+        assert!(cli.validate().is_ok());
+        let options = cli.segment_error_options();
+        assert_eq!(options.on_error, OnError::Placeholder);
+        assert_eq!(options.placeholder, "⟦UNTRANSLATED⟧");
+    }
+
+    #[test]
+    fn test_parse_post_process() {
+        let cli = create_test_cli("gpt-4o");
+        assert!(cli.post_process.is_empty());
+        assert_eq!(cli.locale, None);
+
+        let cli = Cli::parse_from([
+            "transome",
+            "--post-process",
+            "localize-numbers",
+            "--locale",
+            "en",
+            "你好",
+        ]);
+        assert_eq!(cli.post_process, vec![PostProcessor::LocalizeNumbers]);
+        assert_eq!(cli.locale, Some(Locale::En));
+        assert!(Cli::try_parse_from(["transome", "--post-process", "uppercase", "a"]).is_err());
+    }
+
+    #[test]
+    fn test_show_progress() {
+        let cli = create_test_cli("gpt-4o");
+        assert!(cli.show_progress(true));
+        assert!(!cli.show_progress(false));
+
+        for flag in ["--quiet", "--json", "--pretty-json"] {
+            let cli = Cli::parse_from(["transome", flag, "text"]);
+            assert!(!cli.show_progress(true), "{} should hide progress", flag);
+        }
+    }
+
+    #[test]
+    fn test_check_insecure_tls() {
+        let allowed = ConfigFile {
+            allow_insecure_tls: true,
+            ..ConfigFile::default()
+        };
+        let gateway = "https://llm.internal.example/v1";
+        let cli = Cli::parse_from([
+            "transome",
+            "--url",
+            gateway,
+            "--insecure-skip-tls-verify",
+            "text",
+        ]);
+        assert!(cli.check_insecure_tls(gateway, &allowed).is_ok());
+
+        // 配置文件未允许
+        let error = cli
+            .check_insecure_tls(gateway, &ConfigFile::default())
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("allow_insecure_tls"),
+            "{}",
+            error
+        );
+
+        // 内置提供商的地址，无论是否通过 --url 指定
+        let openai = "https://api.openai.com/v1";
+        let cli = Cli::parse_from(["transome", "--url", openai, "text"]);
+        assert!(cli.check_insecure_tls(openai, &allowed).is_err());
+        let cli = Cli::parse_from(["transome", "-m", "gpt-4o", "text"]);
+        assert!(cli.check_insecure_tls(openai, &allowed).is_err());
+
+        let cli = Cli::parse_from(["transome", "--ca-cert", "/tmp/ca.pem", "text"]);
+        assert_eq!(cli.ca_bundle, Some(PathBuf::from("/tmp/ca.pem")));
+    }
+
+    #[test]
+    fn test_temperature_resolution() {
+        let config = ConfigFile::default();
+        let cli = create_test_cli("gpt-4o");
+        assert_eq!(cli.temperature(&config), Some(config.temperature));
+
+        let unpinned = ConfigFile {
+            pin_temperature: false,
+            ..ConfigFile::default()
+        };
+        assert_eq!(cli.temperature(&unpinned), None);
+
+        let creative = Cli::parse_from(["transome", "--creative", "text"]);
+        assert_eq!(creative.temperature(&unpinned), Some(CREATIVE_TEMPERATURE));
+    }
+
+    #[test]
+    fn test_diff_against_requires_previous_translation() {
+        let cli = Cli::parse_from([
+            "transome",
+            "--diff-against",
+            "old.md",
+            "--previous-translation",
+            "old.zh.md",
+            "text",
+        ]);
+        assert_eq!(cli.diff_against, Some(PathBuf::from("old.md")));
+        assert_eq!(cli.previous_translation, Some(PathBuf::from("old.zh.md")));
+
+        assert!(Cli::try_parse_from(["transome", "--diff-against", "old.md", "text"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "transome",
+                "--lines",
+                "--diff-against",
+                "old.md",
+                "--previous-translation",
+                "old.zh.md",
+                "text",
+            ])
+            .is_err()
+        );
+    }
 }
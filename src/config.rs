@@ -2,13 +2,27 @@
 
 use std::collections::HashMap;
 use std::sync::OnceLock;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// 未指定模型时使用的默认模型
+pub const DEFAULT_MODEL: &str = "gemini-2.5-flash-lite";
 
 /// AI 模型配置结构
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
 pub struct ModelConfig {
     pub name: String,
     pub url: String,
     pub provider: String,
+    /// 已弃用模型的建议替代模型
+    pub deprecated: Option<String>,
+    /// 是否为推理模型（见 [`is_reasoning_model`]）
+    pub reasoning: bool,
+    /// 请求中是否必须包含 `max_tokens`（见 [`is_max_tokens_required`]）
+    pub max_tokens_required: bool,
 }
 
 impl ModelConfig {
@@ -22,8 +36,29 @@ impl ModelConfig {
             name: name.into(),
             url: url.into(),
             provider: provider.into(),
+            deprecated: None,
+            reasoning: false,
+            max_tokens_required: false,
         }
     }
+
+    /// 将模型标记为已弃用，并指定建议的替代模型
+    pub fn with_deprecated(mut self, replacement: impl Into<String>) -> Self {
+        self.deprecated = Some(replacement.into());
+        self
+    }
+
+    /// 将模型标记为推理模型
+    pub fn with_reasoning(mut self) -> Self {
+        self.reasoning = true;
+        self
+    }
+
+    /// 将模型标记为请求中必须包含 `max_tokens`
+    pub fn with_max_tokens_required(mut self) -> Self {
+        self.max_tokens_required = true;
+        self
+    }
 }
 
 /// 模型名称到 API 端点的静态映射
@@ -61,33 +96,333 @@ fn get_model_to_url() -> &'static HashMap<&'static str, &'static str> {
             ("gpt-4o-mini", "https://api.openai.com/v1"),
             ("gpt-3.5-turbo", "https://api.openai.com/v1"),
             ("gpt-3.5-turbo-16k", "https://api.openai.com/v1"),
+            ("o1", "https://api.openai.com/v1"),
+            ("o1-mini", "https://api.openai.com/v1"),
+            ("o3-mini", "https://api.openai.com/v1"),
         ])
     })
 }
 
+/// 已弃用的内置模型及其建议的替代模型
+const DEPRECATED_MODELS: &[(&str, &str)] = &[("gpt-3.5-turbo-16k", "gpt-4o-mini")];
+
+/// 获取已弃用模型的建议替代模型
+pub fn get_deprecation(model: &str) -> Option<&'static str> {
+    DEPRECATED_MODELS
+        .iter()
+        .find(|&&(name, _)| name == model)
+        .map(|&(_, replacement)| replacement)
+}
+
+/// 为已弃用模型生成一行提示信息
+pub fn deprecation_notice(model: &str) -> Option<String> {
+    get_deprecation(model)
+        .map(|replacement| format!("模型 '{}' 已弃用，建议改用 '{}'", model, replacement))
+}
+
+/// 推理模型系列的名称前缀
+///
+/// 推理模型（o1 等）不接受采样温度，输出长度上限使用 `max_completion_tokens` 而不是 `max_tokens`。
+const REASONING_MODEL_FAMILIES: &[&str] = &["o1", "o3", "o4"];
+
+/// 判断模型是否为推理模型
+///
+/// 模型名称为推理模型系列本身或以 `系列-` 开头时视为推理模型，如 `o1`、`o3-mini`、`o1-2024-12-17`。
+pub fn is_reasoning_model(model: &str) -> bool {
+    REASONING_MODEL_FAMILIES.iter().any(|&family| {
+        model
+            .strip_prefix(family)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+    })
+}
+
+/// 请求中必须包含 `max_tokens` 的模型系列的名称前缀
+///
+/// Anthropic 的 OpenAI 兼容端点会以 400 拒绝不含 `max_tokens` 的请求。
+const MAX_TOKENS_REQUIRED_FAMILIES: &[&str] = &["claude"];
+
+/// 未指定 `--max-tokens` 时为必须包含该字段的模型填入的上限
+pub const MAX_DEFAULT_MAX_TOKENS: u32 = 8_192;
+
+/// 判断模型的请求中是否必须包含 `max_tokens`
+///
+/// 名称规则同 [`is_reasoning_model`]，如 `claude-sonnet-4`、`claude-3-5-haiku-latest`。
+pub fn is_max_tokens_required(model: &str) -> bool {
+    MAX_TOKENS_REQUIRED_FAMILIES.iter().any(|&family| {
+        model
+            .strip_prefix(family)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+    })
+}
+
+/// 未指定 `--max-tokens` 时必须包含该字段的模型使用的默认值
+///
+/// 取上下文窗口的一半（与分块时为译文留出的空间一致），且不超过 [`MAX_DEFAULT_MAX_TOKENS`]。
+pub fn default_max_tokens(model: &str) -> u32 {
+    let half = get_context_window(model) / 2;
+    u32::try_from(half).map_or(MAX_DEFAULT_MAX_TOKENS, |half| {
+        half.min(MAX_DEFAULT_MAX_TOKENS)
+    })
+}
+
+/// 未知模型的上下文窗口（token），取常见模型中较小的值
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8_192;
+
+/// 内置模型的上下文窗口（token）
+const CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("gemini-2.5-pro", 1_048_576),
+    ("gemini-2.5-flash", 1_048_576),
+    ("gemini-2.5-flash-lite", 1_048_576),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini-1.5-flash", 1_048_576),
+    ("gpt-4", 8_192),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4o", 128_000),
+    ("gpt-4o-mini", 128_000),
+    ("gpt-3.5-turbo", 16_385),
+    ("gpt-3.5-turbo-16k", 16_385),
+    ("o1", 200_000),
+    ("o1-mini", 128_000),
+    ("o3-mini", 200_000),
+];
+
+/// 获取模型的上下文窗口（token），未知模型使用 [`DEFAULT_CONTEXT_WINDOW`]
+pub fn get_context_window(model: &str) -> usize {
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|&&(name, _)| name == model)
+        .map_or(DEFAULT_CONTEXT_WINDOW, |&(_, window)| window)
+}
+
 /// 获取模型的 API URL
 pub fn get_model_url(model: &str) -> Option<String> {
     let model_to_url = get_model_to_url();
     model_to_url.get(model).map(|&url| url.to_string())
 }
 
+/// 已知提供商的主机名、名称、API 密钥环境变量、API 地址及默认模型
+const KNOWN_PROVIDERS: &[(&str, &str, &str, &str, &str)] = &[
+    (
+        "generativelanguage.googleapis.com",
+        "Google Gemini",
+        "GOOGLE_AI_API_KEY",
+        "https://generativelanguage.googleapis.com/v1beta/openai",
+        DEFAULT_MODEL,
+    ),
+    (
+        "api.openai.com",
+        "OpenAI",
+        "OPENAI_API_KEY",
+        "https://api.openai.com/v1",
+        "gpt-4o-mini",
+    ),
+];
+
+/// 已知提供商的基本信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Provider {
+    pub name: &'static str,
+    pub env_var: &'static str,
+    pub url: &'static str,
+    /// 只有该提供商的密钥可用时自动选择的模型
+    pub default_model: &'static str,
+}
+
+/// 获取所有已知提供商
+pub fn known_providers() -> Vec<Provider> {
+    KNOWN_PROVIDERS
+        .iter()
+        .map(|&(_, name, env_var, url, default_model)| Provider {
+            name,
+            env_var,
+            url,
+            default_model,
+        })
+        .collect()
+}
+
+/// 默认模型所属提供商的密钥未设置、且恰好只设置了一个其他提供商的密钥时，
+/// 返回该提供商的默认模型及其环境变量
+///
+/// 同时设置了多个密钥或一个都没有设置时返回 None，仍使用 [`DEFAULT_MODEL`]。
+pub fn fallback_default_model<E>(env: E) -> Option<(&'static str, &'static str)>
+where
+    E: Fn(&str) -> Option<String>,
+{
+    let is_set = |var: &str| env(var).is_some_and(|value| !value.trim().is_empty());
+    if get_env_var_name_for_model(DEFAULT_MODEL).is_some_and(is_set) {
+        return None;
+    }
+
+    let available: Vec<Provider> = known_providers()
+        .into_iter()
+        .filter(|provider| is_set(provider.env_var))
+        .collect();
+    match available.as_slice() {
+        [provider] => Some((provider.default_model, provider.env_var)),
+        _ => None,
+    }
+}
+
+/// 提示词与待翻译文本在请求中的排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PromptLayout {
+    /// system 消息放提示词，user 消息放待翻译文本
+    #[default]
+    System,
+    /// developer 消息放提示词，user 消息放待翻译文本
+    Developer,
+    /// 提示词与待翻译文本合并为一条 user 消息，文本以标签分隔
+    SingleUser,
+}
+
+/// 与标准 OpenAI 接口行为不同的提供商及其应使用的提示词排列方式
+///
+/// Gemini 的 OpenAI 兼容端点有时会合并或打乱连续的多条消息，使用单条 user 消息更稳定。
+const PROVIDER_QUIRKS: &[(&str, PromptLayout)] = &[("Google Gemini", PromptLayout::SingleUser)];
+
+/// 获取 API 地址对应提供商的默认提示词排列方式
+pub fn prompt_layout_for_url(url: &str) -> PromptLayout {
+    let provider = get_provider_name(url);
+    PROVIDER_QUIRKS
+        .iter()
+        .find(|&&(name, _)| name == provider)
+        .map_or(PromptLayout::default(), |&(_, layout)| layout)
+}
+
+/// 不接受 `logit_bias` 字段的提供商
+///
+/// Gemini 的 OpenAI 兼容端点会以 400 拒绝包含该字段的请求。
+const LOGIT_BIAS_UNSUPPORTED: &[&str] = &["Google Gemini"];
+
+/// 判断 API 地址对应的提供商是否接受 `logit_bias`，未知提供商视为接受
+pub fn supports_logit_bias(url: &str) -> bool {
+    !LOGIT_BIAS_UNSUPPORTED.contains(&get_provider_name(url))
+}
+
+/// 不接受 `frequency_penalty` 与 `presence_penalty` 的提供商
+///
+/// Gemini 的 OpenAI 兼容端点不支持这两个字段。
+const PENALTIES_UNSUPPORTED: &[&str] = &["Google Gemini"];
+
+/// 判断 API 地址对应的提供商是否接受惩罚参数，未知提供商视为接受
+pub fn supports_penalties(url: &str) -> bool {
+    !PENALTIES_UNSUPPORTED.contains(&get_provider_name(url))
+}
+
+/// 提供商接受的请求体上限（字节）
+///
+/// 超出时服务只返回不透明的 413，因此在发送前比较序列化后的请求大小。
+/// 网关的上限通常更小，可在配置文件中按模型设置 `max_request_bytes`。
+const PROVIDER_MAX_REQUEST_BYTES: &[(&str, usize)] = &[
+    ("Google Gemini", 20 * 1024 * 1024),
+    ("OpenAI", 32 * 1024 * 1024),
+];
+
+/// 获取 API 地址对应提供商的请求体上限，未知提供商返回 None（不检查）
+pub fn max_request_bytes_for_url(url: &str) -> Option<usize> {
+    let provider = get_provider_name(url);
+    PROVIDER_MAX_REQUEST_BYTES
+        .iter()
+        .find(|&&(name, _)| name == provider)
+        .map(|&(_, limit)| limit)
+}
+
+/// 未通过命令行参数指定时使用的请求超时、重试次数与首次重试前的等待时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestDefaults {
+    pub timeout: Duration,
+    pub retries: u32,
+    pub backoff_base: Duration,
+}
+
+/// 托管服务的请求默认值：响应快，超时短，偶发的限流与 5xx 值得重试
+const PROVIDER_REQUEST_DEFAULTS: &[(&str, RequestDefaults)] = &[
+    (
+        "Google Gemini",
+        RequestDefaults {
+            timeout: Duration::from_secs(30),
+            retries: 2,
+            backoff_base: Duration::from_millis(500),
+        },
+    ),
+    (
+        "OpenAI",
+        RequestDefaults {
+            timeout: Duration::from_secs(60),
+            retries: 2,
+            backoff_base: Duration::from_secs(1),
+        },
+    ),
+];
+
+/// 本机服务的请求默认值：大模型在本地生成较慢，超时长；失败多为服务未启动，少重试
+const LOCAL_REQUEST_DEFAULTS: RequestDefaults = RequestDefaults {
+    timeout: Duration::from_secs(120),
+    retries: 1,
+    backoff_base: Duration::from_secs(2),
+};
+
+/// 本机服务在来源说明中使用的名称
+pub const LOCAL_PROVIDER_NAME: &str = "本机服务";
+
+/// 获取 API 地址对应的请求默认值及其来源（提供商名称或 [`LOCAL_PROVIDER_NAME`]）
+///
+/// 既不是已知提供商也不是本机地址时返回 None，使用内置默认值（不限超时、不重试）。
+pub fn request_defaults_for_url(url: &str) -> Option<(&'static str, RequestDefaults)> {
+    if is_local_host(extract_host(url)) {
+        return Some((LOCAL_PROVIDER_NAME, LOCAL_REQUEST_DEFAULTS));
+    }
+    let provider = get_provider_name(url);
+    PROVIDER_REQUEST_DEFAULTS
+        .iter()
+        .find(|&&(name, _)| name == provider)
+        .copied()
+}
+
+/// 判断主机名是否指向本机
+fn is_local_host(host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.starts_with("127.")
+        || host == "0.0.0.0"
+}
+
+/// 从 URL 中提取主机名（不含协议、端口和路径）
+fn extract_host(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or(without_scheme);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or(host)
+}
+
+/// 根据 URL 的主机名查找已知提供商
+///
+/// 返回 (提供商名称, API 密钥环境变量名)，主机名不属于任何已知提供商时返回 None。
+pub fn provider_for_url(url: &str) -> Option<(&'static str, &'static str)> {
+    let host = extract_host(url).to_ascii_lowercase();
+
+    KNOWN_PROVIDERS
+        .iter()
+        .find(|&&(known_host, ..)| {
+            host == known_host || host.ends_with(&format!(".{}", known_host))
+        })
+        .map(|&(_, provider, env_var, ..)| (provider, env_var))
+}
+
 /// 获取提供商名称
 pub fn get_provider_name(model_or_url: &str) -> &'static str {
-    // 首先尝试从模型名称获取 URL
-    let url = if let Some(model_url) = get_model_url(model_or_url) {
-        model_url
-    } else {
-        // 如果未找到模型名称，则将其视为 URL
-        model_or_url.to_string()
-    };
-
-    if url.contains("generativelanguage.googleapis.com") {
-        "Google Gemini"
-    } else if url.contains("api.openai.com") {
-        "OpenAI"
-    } else {
-        "Other"
-    }
+    // 首先尝试从模型名称获取 URL，如果未找到模型名称，则将其视为 URL
+    let url = get_model_url(model_or_url).unwrap_or_else(|| model_or_url.to_string());
+
+    provider_for_url(&url).map_or("Other", |(provider, _)| provider)
 }
 
 /// 按提供商分组模型
@@ -117,46 +452,158 @@ fn get_sorted_providers_with_models() -> Vec<(&'static str, Vec<(&'static str, &
     sorted_providers
 }
 
+/// `--list-models` 的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ModelSort {
+    /// 按提供商分组，组内按模型名称排序
+    #[default]
+    Provider,
+    /// 不分组，所有模型按名称排序
+    Name,
+    /// 按提供商分组，各组按 API 地址排序
+    Url,
+}
+
 /// 列出所有支持的模型
 pub fn list_models() {
-    println!("\n支持的模型:");
+    list_models_with(true, ModelSort::default(), None);
+}
 
-    let sorted_providers = get_sorted_providers_with_models();
+/// 按 `sort` 列出支持的模型，`show_urls` 为 false 时不显示 API 地址，
+/// 指定了 `filter` 时只列出匹配的模型
+pub fn list_models_with(show_urls: bool, sort: ModelSort, filter: Option<&str>) {
+    print!("{}", render_model_list(show_urls, sort, filter));
+}
 
-    for (provider, models) in sorted_providers {
-        if let Some((_, first_url)) = models.first() {
-            println!("\n{} ({}):", provider, first_url);
-            for (model, _) in models {
-                println!("  - {}", model);
+/// 模型名称或提供商包含 `term` 的模型，不区分大小写
+pub fn filter_models(models: Vec<ModelConfig>, term: &str) -> Vec<ModelConfig> {
+    let term = term.trim().to_lowercase();
+    models
+        .into_iter()
+        .filter(|model| {
+            model.name.to_lowercase().contains(&term)
+                || model.provider.to_lowercase().contains(&term)
+        })
+        .collect()
+}
+
+/// 按 `sort` 排序并按 `filter` 筛选的模型
+pub fn matching_models(sort: ModelSort, filter: Option<&str>) -> Vec<ModelConfig> {
+    let models = get_all_models_sorted(sort);
+    match filter {
+        Some(term) => filter_models(models, term),
+        None => models,
+    }
+}
+
+/// 没有模型匹配 `--filter` 时的提示
+pub fn no_match_message(term: &str) -> String {
+    format!(
+        "没有与 '{}' 匹配的模型\n\n去掉 --filter 查看所有支持的模型: transome --list-models",
+        term
+    )
+}
+
+/// 模型列表中的一行，标注已弃用模型的替代模型
+fn model_line(model: &ModelConfig, annotation: Option<String>) -> String {
+    let mut line = format!("  - {}", model.name);
+    if let Some(annotation) = annotation {
+        line.push_str(&format!(" ({})", annotation));
+    }
+    if let Some(replacement) = &model.deprecated {
+        line.push_str(&format!("（已弃用，建议使用 {}）", replacement));
+    }
+    line
+}
+
+/// 生成支持的模型列表文本，没有模型匹配 `filter` 时为提示信息
+pub fn render_model_list(show_urls: bool, sort: ModelSort, filter: Option<&str>) -> String {
+    let models = matching_models(sort, filter);
+    if let (Some(term), true) = (filter, models.is_empty()) {
+        return format!("{}\n", no_match_message(term));
+    }
+
+    let mut lines = vec![String::new(), "支持的模型:".to_string()];
+
+    if sort == ModelSort::Name {
+        // 不分组时在每个模型后标注提供商
+        lines.push(String::new());
+        for model in &models {
+            let annotation = if show_urls {
+                format!("{}, {}", model.provider, model.url)
+            } else {
+                model.provider.clone()
+            };
+            lines.push(model_line(model, Some(annotation)));
+        }
+    } else {
+        for group in models.chunk_by(|a, b| a.provider == b.provider) {
+            let first = &group[0];
+            lines.push(String::new());
+            if show_urls {
+                lines.push(format!("{} ({}):", first.provider, first.url));
+            } else {
+                lines.push(format!("{}:", first.provider));
             }
+            lines.extend(group.iter().map(|model| model_line(model, None)));
         }
     }
 
-    println!("\n使用方法:");
-    println!("  transome [选项] [文本]");
-    println!("\n选项:");
-    println!("  -m, --model <模型>    使用上述列表中的支持模型");
-    println!("  -u, --url <地址>      使用自定义 API 地址（覆盖模型选择）");
-    println!("\n示例:");
-    println!("  transome -m gpt-4 \"Hello world\"");
-    println!("  transome -u https://custom.api.com/v1 -m custom-model \"Hello world\"");
+    lines.extend(
+        [
+            "",
+            "使用方法:",
+            "  transome [选项] [文本]",
+            "",
+            "选项:",
+            "  -m, --model <模型>    使用上述列表中的支持模型",
+            "  -u, --url <地址>      使用自定义 API 地址（覆盖模型选择）",
+            "",
+            "示例:",
+            "  transome -m gpt-4 \"Hello world\"",
+            "  transome -u https://custom.api.com/v1 -m custom-model \"Hello world\"",
+        ]
+        .map(String::from),
+    );
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
 }
 
-/// 获取所有可用模型
+/// 获取所有可用模型，按提供商、模型名称排序
 pub fn get_all_models() -> Vec<ModelConfig> {
+    get_all_models_sorted(ModelSort::Provider)
+}
+
+/// 获取所有可用模型，按 `sort` 排序
+pub fn get_all_models_sorted(sort: ModelSort) -> Vec<ModelConfig> {
     let model_to_url = get_model_to_url();
     let mut models = Vec::new();
 
     for (&model, &url) in model_to_url.iter() {
         let provider = get_provider_name(model);
-        models.push(ModelConfig::new(model, url, provider));
+        let mut config = ModelConfig::new(model, url, provider);
+        if is_reasoning_model(model) {
+            config = config.with_reasoning();
+        }
+        if is_max_tokens_required(model) {
+            config = config.with_max_tokens_required();
+        }
+        models.push(match get_deprecation(model) {
+            Some(replacement) => config.with_deprecated(replacement),
+            None => config,
+        });
     }
 
-    // 首先按提供商排序，然后按模型名称排序以保持一致的顺序
+    // 最后按模型名称排序以保持一致的顺序
     models.sort_by(|a, b| {
-        a.provider
-            .cmp(&b.provider)
-            .then_with(|| a.name.cmp(&b.name))
+        let key = match sort {
+            ModelSort::Provider => a.provider.cmp(&b.provider),
+            ModelSort::Name => std::cmp::Ordering::Equal,
+            ModelSort::Url => a.url.cmp(&b.url).then_with(|| a.provider.cmp(&b.provider)),
+        };
+        key.then_with(|| a.name.cmp(&b.name))
     });
 
     models
@@ -193,15 +640,30 @@ pub fn get_supported_model_names() -> Vec<String> {
 
 /// 根据模型名称获取对应的环境变量名
 pub fn get_env_var_name_for_model(model: &str) -> Option<&'static str> {
-    let provider = get_provider_name(model);
+    let url = get_model_url(model).unwrap_or_else(|| model.to_string());
 
-    match provider {
-        "OpenAI" => Some("OPENAI_API_KEY"),
-        "Google Gemini" => Some("GOOGLE_AI_API_KEY"),
+    provider_for_url(&url).map(|(_, env_var)| env_var)
+}
+
+/// 根据模型名称获取对应提供商 API 密钥的常见前缀
+pub fn get_key_prefix_for_model(model: &str) -> Option<&'static str> {
+    match get_provider_name(model) {
+        "OpenAI" => Some("sk-"),
+        "Google Gemini" => Some("AIza"),
         _ => None,
     }
 }
 
+/// 判断一段文本是否像某个提供商的 API 密钥
+///
+/// 以已知前缀开头且长度足够，用于避免密钥被写入导出的文件。
+pub fn looks_like_api_key(token: &str) -> bool {
+    const MIN_KEY_LEN: usize = 20;
+    ["sk-", "AIza"]
+        .iter()
+        .any(|prefix| token.starts_with(prefix) && token.len() >= MIN_KEY_LEN)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -260,6 +722,88 @@ mod tests {
         assert_eq!(config.name, "test-model");
         assert_eq!(config.url, "https://test.com");
         assert_eq!(config.provider, "TestProvider");
+        assert_eq!(config.deprecated, None);
+
+        let config = config.with_deprecated("new-model");
+        assert_eq!(config.deprecated, Some("new-model".to_string()));
+        assert!(!config.reasoning);
+        assert!(config.clone().with_reasoning().reasoning);
+        assert!(!config.max_tokens_required);
+        assert!(config.with_max_tokens_required().max_tokens_required);
+    }
+
+    #[test]
+    fn test_context_window() {
+        assert_eq!(get_context_window("gpt-4o"), 128_000);
+        assert_eq!(get_context_window("custom-model"), DEFAULT_CONTEXT_WINDOW);
+
+        // 每个内置模型都有上下文窗口
+        for model in get_supported_model_names() {
+            assert!(
+                CONTEXT_WINDOWS.iter().any(|&(name, _)| name == model),
+                "{}",
+                model
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_tokens_required() {
+        assert!(is_max_tokens_required("claude-sonnet-4"));
+        assert!(is_max_tokens_required("claude-3-5-haiku-latest"));
+        assert!(!is_max_tokens_required("claudette"));
+        assert!(!is_max_tokens_required("gpt-4o"));
+
+        // 未知模型按默认上下文窗口的一半
+        assert_eq!(
+            default_max_tokens("claude-sonnet-4"),
+            (DEFAULT_CONTEXT_WINDOW / 2) as u32
+        );
+        assert_eq!(default_max_tokens("gpt-4o"), MAX_DEFAULT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn test_is_reasoning_model() {
+        assert!(is_reasoning_model("o1"));
+        assert!(is_reasoning_model("o1-mini"));
+        assert!(is_reasoning_model("o3-mini"));
+        assert!(is_reasoning_model("o1-2024-12-17"));
+        assert!(!is_reasoning_model("gpt-4o"));
+        assert!(!is_reasoning_model("o10"));
+        assert!(!is_reasoning_model("gemini-2.5-flash"));
+
+        let o1 = get_all_models()
+            .into_iter()
+            .find(|m| m.name == "o1")
+            .unwrap();
+        assert!(o1.reasoning);
+        assert_eq!(o1.provider, "OpenAI");
+    }
+
+    #[test]
+    fn test_deprecated_models() {
+        assert_eq!(get_deprecation("gpt-3.5-turbo-16k"), Some("gpt-4o-mini"));
+        assert_eq!(get_deprecation("gpt-4o"), None);
+
+        // 替代建议来自配置条目
+        let notice = deprecation_notice("gpt-3.5-turbo-16k").unwrap();
+        assert!(notice.contains("gpt-3.5-turbo-16k"));
+        assert!(notice.contains("gpt-4o-mini"));
+        assert!(!notice.contains('\n'));
+        assert_eq!(deprecation_notice("gpt-4o"), None);
+
+        let models = get_all_models();
+        let legacy = models
+            .iter()
+            .find(|m| m.name == "gpt-3.5-turbo-16k")
+            .unwrap();
+        assert_eq!(legacy.deprecated.as_deref(), Some("gpt-4o-mini"));
+        assert!(
+            models
+                .iter()
+                .filter(|m| m.name != "gpt-3.5-turbo-16k")
+                .all(|m| m.deprecated.is_none())
+        );
     }
 
     #[test]
@@ -288,6 +832,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_render_model_list_without_urls() {
+        let with_urls = render_model_list(true, ModelSort::Provider, None);
+        let without_urls = render_model_list(false, ModelSort::Provider, None);
+
+        for provider in known_providers() {
+            assert!(with_urls.contains(&format!("{} ({}):", provider.name, provider.url)));
+            assert!(without_urls.contains(&format!("\n{}:\n", provider.name)));
+            assert!(!without_urls.contains(provider.url), "{}", provider.url);
+        }
+        assert!(without_urls.contains("  - gpt-4o\n"));
+    }
+
+    fn sorted_by<K: Ord>(models: &[ModelConfig], key: impl Fn(&ModelConfig) -> K) -> bool {
+        models.windows(2).all(|pair| key(&pair[0]) <= key(&pair[1]))
+    }
+
+    #[test]
+    fn test_get_all_models_sorted() {
+        let by_provider = get_all_models_sorted(ModelSort::Provider);
+        assert_eq!(by_provider, get_all_models());
+        assert!(sorted_by(&by_provider, |m| (
+            m.provider.clone(),
+            m.name.clone()
+        )));
+
+        let by_name = get_all_models_sorted(ModelSort::Name);
+        assert!(sorted_by(&by_name, |m| m.name.clone()));
+
+        let by_url = get_all_models_sorted(ModelSort::Url);
+        assert!(sorted_by(&by_url, |m| (
+            m.url.clone(),
+            m.provider.clone(),
+            m.name.clone()
+        )));
+
+        assert_eq!(by_name.len(), by_provider.len());
+        assert_eq!(by_url.len(), by_provider.len());
+    }
+
+    #[test]
+    fn test_render_model_list_sorted_by_name() {
+        let list = render_model_list(true, ModelSort::Name, None);
+        let names: Vec<&str> = list
+            .lines()
+            .filter_map(|line| line.strip_prefix("  - "))
+            .map(|line| line.split(' ').next().unwrap())
+            .collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+        assert_eq!(names.len(), get_all_models().len());
+
+        assert!(list.contains("  - gpt-4o (OpenAI, https://api.openai.com/v1)\n"));
+        let without_urls = render_model_list(false, ModelSort::Name, None);
+        assert!(without_urls.contains("  - gpt-4o (OpenAI)\n"));
+        assert!(!without_urls.contains("OpenAI:"));
+    }
+
+    #[test]
+    fn test_render_model_list_sorted_by_url() {
+        let list = render_model_list(true, ModelSort::Url, None);
+        let headers: Vec<&str> = list
+            .lines()
+            .filter(|line| line.ends_with("):"))
+            .filter_map(|line| line.rsplit_once(" (").map(|(_, url)| url))
+            .collect();
+        let mut sorted = headers.clone();
+        sorted.sort();
+        assert_eq!(headers, sorted);
+        assert!(!headers.is_empty());
+    }
+
+    #[test]
+    fn test_filter_models() {
+        let models = matching_models(ModelSort::Provider, Some("GPT-4o"));
+        assert!(!models.is_empty());
+        assert!(models.iter().all(|model| model.name.contains("gpt-4o")));
+
+        // 提供商名称同样匹配
+        let gemini = matching_models(ModelSort::Provider, Some("gemini"));
+        assert!(gemini.iter().any(|model| model.name == DEFAULT_MODEL));
+
+        let list = render_model_list(true, ModelSort::Provider, Some("gpt"));
+        assert!(list.contains("  - gpt-4o\n"));
+        assert!(!list.contains(DEFAULT_MODEL));
+    }
+
+    #[test]
+    fn test_no_models_match_filter() {
+        assert!(matching_models(ModelSort::Name, Some("zzz-nonsense")).is_empty());
+        let list = render_model_list(true, ModelSort::Provider, Some("zzz-nonsense"));
+        assert!(
+            list.starts_with("没有与 'zzz-nonsense' 匹配的模型"),
+            "{}",
+            list
+        );
+        assert!(list.contains("transome --list-models"));
+        // 不输出模型列表
+        assert!(!list.lines().any(|line| line == "支持的模型:"));
+    }
+
     #[test]
     fn test_create_model_error_message() {
         let error_msg = create_model_error_message("nonexistent-model");
@@ -411,4 +1057,154 @@ mod tests {
         assert_eq!(get_env_var_name_for_model(" gpt-4 "), None);
         assert_eq!(get_env_var_name_for_model("gpt-4\n"), None);
     }
+
+    #[test]
+    fn test_get_key_prefix_for_model() {
+        assert_eq!(get_key_prefix_for_model("gpt-4o"), Some("sk-"));
+        assert_eq!(get_key_prefix_for_model("gemini-2.5-flash"), Some("AIza"));
+    }
+
+    #[test]
+    fn test_known_providers_match_their_urls() {
+        for provider in known_providers() {
+            assert_eq!(
+                provider_for_url(provider.url),
+                Some((provider.name, provider.env_var))
+            );
+        }
+    }
+
+    #[test]
+    fn test_prompt_layout_for_url() {
+        assert_eq!(
+            prompt_layout_for_url("https://generativelanguage.googleapis.com/v1beta/openai"),
+            PromptLayout::SingleUser
+        );
+        assert_eq!(
+            prompt_layout_for_url("https://api.openai.com/v1"),
+            PromptLayout::System
+        );
+        assert_eq!(
+            prompt_layout_for_url("http://localhost:11434/v1"),
+            PromptLayout::System
+        );
+    }
+
+    #[test]
+    fn test_supports_logit_bias() {
+        assert!(supports_logit_bias("https://api.openai.com/v1"));
+        assert!(supports_logit_bias("http://localhost:11434/v1"));
+        assert!(!supports_logit_bias(
+            "https://generativelanguage.googleapis.com/v1beta/openai"
+        ));
+    }
+
+    #[test]
+    fn test_max_request_bytes_for_url() {
+        assert_eq!(
+            max_request_bytes_for_url("https://generativelanguage.googleapis.com/v1beta/openai"),
+            Some(20 * 1024 * 1024)
+        );
+        assert_eq!(max_request_bytes_for_url("http://localhost:11434/v1"), None);
+    }
+
+    #[test]
+    fn test_request_defaults_for_url() {
+        let (name, gemini) =
+            request_defaults_for_url("https://generativelanguage.googleapis.com/v1beta/openai")
+                .unwrap();
+        assert_eq!(name, "Google Gemini");
+        assert_eq!(gemini.timeout, Duration::from_secs(30));
+
+        for url in [
+            "http://localhost:11434/v1",
+            "http://127.0.0.1:8080/v1",
+            "http://ollama.localhost/v1",
+        ] {
+            let (name, local) = request_defaults_for_url(url).unwrap();
+            assert_eq!(name, LOCAL_PROVIDER_NAME, "{}", url);
+            assert!(local.timeout > gemini.timeout);
+        }
+
+        assert_eq!(request_defaults_for_url("https://custom.api.com/v1"), None);
+    }
+
+    #[test]
+    fn test_looks_like_api_key() {
+        assert!(looks_like_api_key("sk-proj-abcdefghijklmnopqrstuvwxyz"));
+        assert!(looks_like_api_key("AIzaSyA1234567890abcdefghijklmnopqrstu"));
+        assert!(!looks_like_api_key("sk-short"));
+        assert!(!looks_like_api_key("gpt-4o-mini-2024-07-18"));
+        assert_eq!(get_key_prefix_for_model("custom-model"), None);
+    }
+
+    #[test]
+    fn test_provider_for_url_known_hosts() {
+        assert_eq!(
+            provider_for_url("https://api.openai.com/v1"),
+            Some(("OpenAI", "OPENAI_API_KEY"))
+        );
+        assert_eq!(
+            provider_for_url("https://generativelanguage.googleapis.com/v1beta/openai"),
+            Some(("Google Gemini", "GOOGLE_AI_API_KEY"))
+        );
+
+        // 端口、大小写和子域名不影响匹配
+        assert_eq!(
+            provider_for_url("HTTPS://API.OPENAI.COM:443/v1"),
+            Some(("OpenAI", "OPENAI_API_KEY"))
+        );
+        assert_eq!(
+            provider_for_url("https://eu.api.openai.com/v1"),
+            Some(("OpenAI", "OPENAI_API_KEY"))
+        );
+    }
+
+    #[test]
+    fn test_provider_for_url_unknown_host() {
+        assert_eq!(provider_for_url("https://custom.api.com/v1"), None);
+        assert_eq!(provider_for_url("http://localhost:8080"), None);
+        assert_eq!(provider_for_url(""), None);
+
+        // 仅匹配主机名，路径或查询参数中的主机名不算
+        assert_eq!(
+            provider_for_url("https://proxy.example.com/api.openai.com/v1"),
+            None
+        );
+        assert_eq!(provider_for_url("https://notapi.openai.com.evil.io"), None);
+    }
+
+    #[test]
+    fn test_extract_host() {
+        assert_eq!(extract_host("https://api.openai.com/v1"), "api.openai.com");
+        assert_eq!(extract_host("http://localhost:8080/v1"), "localhost");
+        assert_eq!(extract_host("https://user@host.com?x=1"), "host.com");
+        assert_eq!(extract_host("api.openai.com"), "api.openai.com");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_model_config_round_trip() {
+        let config = ModelConfig::new("o3", "https://api.openai.com/v1", "OpenAI")
+            .with_reasoning()
+            .with_deprecated("o4");
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": "o3",
+                "url": "https://api.openai.com/v1",
+                "provider": "OpenAI",
+                "deprecated": "o4",
+                "reasoning": true,
+                "max_tokens_required": false,
+            })
+        );
+        assert_eq!(serde_json::from_value::<ModelConfig>(json).unwrap(), config);
+
+        for config in get_all_models() {
+            let json = serde_json::to_string(&config).unwrap();
+            assert_eq!(serde_json::from_str::<ModelConfig>(&json).unwrap(), config);
+        }
+    }
 }
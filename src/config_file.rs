@@ -0,0 +1,463 @@
+//! 用户配置文件的加载与解析
+//!
+//! 配置文件为 TOML 格式，查找顺序：
+//! 1. `--config <PATH>` 参数或 `TRANSOME_CONFIG` 环境变量指定的路径
+//! 2. `$XDG_CONFIG_HOME/transome/config.toml`
+//! 3. `$HOME/.config/transome/config.toml`
+//!
+//! 配置文件不存在时使用默认配置。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::{self, PromptLayout};
+use crate::error::{Result, TransomeError};
+use crate::platform;
+use crate::request_template::RequestTemplate;
+use crate::routing::RoutingRules;
+use crate::translator::DEFAULT_TEMPERATURE;
+
+/// 配置文件内容
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// 未通过 -m 指定模型时使用的模型，优先于按已设置的密钥自动选择
+    pub default_model: Option<String>,
+    /// 使用已弃用模型时是否在 stderr 输出提示
+    pub deprecation_notices: bool,
+    /// 是否在请求中固定采样温度，使相同输入的结果更稳定
+    pub pin_temperature: bool,
+    /// 固定的采样温度（0.0 ~ 2.0）
+    pub temperature: f32,
+    /// 是否允许使用 `--insecure-skip-tls-verify`，默认不允许
+    pub allow_insecure_tls: bool,
+    /// 按模型名称配置的设置
+    pub models: HashMap<String, ModelSettings>,
+    /// 命名的配置档，通过 `--profile` 选择；未指定时使用名为 `default` 的配置档
+    #[serde(rename = "profile")]
+    pub profiles: HashMap<String, Profile>,
+    /// 按语言对选择模型的规则，见 [`crate::routing`]
+    pub routing: RoutingRules,
+}
+
+/// 未指定 `--profile` 时使用的配置档名称
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// 配置档：一组模型、API 地址与密钥来源，优先级低于命令行参数
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub model: Option<String>,
+    pub url: Option<String>,
+    /// 读取 API 密钥的环境变量，未设置时按提供商决定
+    pub key_env: Option<String>,
+}
+
+/// 单个模型的设置
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelSettings {
+    /// 发送前对请求体的调整，用于适配非标准的兼容端点
+    pub request_template: Option<RequestTemplate>,
+    /// 提示词的排列方式，未设置时按提供商决定
+    pub prompt_layout: Option<PromptLayout>,
+    /// 是否为推理模型，未设置时按模型名称判断
+    pub reasoning: Option<bool>,
+    /// 请求中是否必须包含 `max_tokens`，未设置时按模型名称判断
+    pub max_tokens_required: Option<bool>,
+    /// 上下文窗口（token），未设置时按模型名称决定
+    pub context_window: Option<usize>,
+    /// 请求体的上限（字节），未设置时按提供商决定
+    pub max_request_bytes: Option<usize>,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            default_model: None,
+            deprecation_notices: true,
+            pin_temperature: true,
+            temperature: DEFAULT_TEMPERATURE,
+            allow_insecure_tls: false,
+            models: HashMap::new(),
+            profiles: HashMap::new(),
+            routing: RoutingRules::default(),
+        }
+    }
+}
+
+impl ConfigFile {
+    /// 从 TOML 字符串解析配置
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)
+            .map_err(|e| TransomeError::config_error("config", e.to_string()))?;
+
+        if !(0.0..=2.0).contains(&config.temperature) {
+            return Err(TransomeError::config_error(
+                "temperature",
+                format!(
+                    "采样温度必须在 0.0 到 2.0 之间，当前为 {}",
+                    config.temperature
+                ),
+            ));
+        }
+
+        for (model, settings) in &config.models {
+            if let Some(template) = &settings.request_template {
+                template.validate().map_err(|e| match e {
+                    TransomeError::ConfigError { message, .. } => TransomeError::config_error(
+                        format!("models.{}.request_template", model),
+                        format!("模型 '{}' 的请求模板无效：{}", model, message),
+                    ),
+                    other => other,
+                })?;
+            }
+        }
+
+        config.routing.validate(|model| config.knows_model(model))?;
+
+        Ok(config)
+    }
+
+    /// 选择配置档：指定名称时必须存在，否则使用 [`DEFAULT_PROFILE`]（不存在时为 None）
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<&Profile>> {
+        let Some(name) = name else {
+            return Ok(self.profiles.get(DEFAULT_PROFILE));
+        };
+        match self.profiles.get(name) {
+            Some(profile) => Ok(Some(profile)),
+            None => {
+                let mut available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                available.sort_unstable();
+                let available = if available.is_empty() {
+                    "配置文件中没有配置档".to_string()
+                } else {
+                    format!("可用的配置档: {}", available.join(", "))
+                };
+                Err(TransomeError::config_error(
+                    "profile",
+                    format!("配置档 '{}' 不存在（{}）", name, available),
+                ))
+            }
+        }
+    }
+
+    /// 获取指定模型的请求模板
+    pub fn request_template(&self, model: &str) -> Option<&RequestTemplate> {
+        self.models.get(model)?.request_template.as_ref()
+    }
+
+    /// 获取为指定模型配置的提示词排列方式
+    pub fn prompt_layout(&self, model: &str) -> Option<PromptLayout> {
+        self.models.get(model)?.prompt_layout
+    }
+
+    /// 获取为指定模型配置的推理模型标记
+    pub fn reasoning(&self, model: &str) -> Option<bool> {
+        self.models.get(model)?.reasoning
+    }
+
+    /// 获取为指定模型配置的 `max_tokens` 必填标记
+    pub fn max_tokens_required(&self, model: &str) -> Option<bool> {
+        self.models.get(model)?.max_tokens_required
+    }
+
+    /// 获取为指定模型配置的上下文窗口
+    pub fn context_window(&self, model: &str) -> Option<usize> {
+        self.models.get(model)?.context_window
+    }
+
+    /// 获取为指定模型配置的请求体上限
+    pub fn max_request_bytes(&self, model: &str) -> Option<usize> {
+        self.models.get(model)?.max_request_bytes
+    }
+
+    /// 模型是否为内置模型或在 `[models]` 中声明过
+    pub fn knows_model(&self, model: &str) -> bool {
+        config::is_model_supported(model) || self.models.contains_key(model)
+    }
+
+    /// 从指定路径加载配置
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            TransomeError::io_error_with_context(e, format!("读取配置文件 '{}'", path.display()))
+        })?;
+
+        Self::from_toml_str(&content).map_err(|e| match e {
+            TransomeError::ConfigError { message, .. } => {
+                TransomeError::config_error(path.display().to_string(), message)
+            }
+            other => other,
+        })
+    }
+
+    /// 加载配置文件
+    ///
+    /// 显式指定的路径必须存在；默认路径下没有配置文件时返回默认配置。
+    pub fn load(explicit_path: Option<&Path>) -> Result<Self> {
+        if let Some(path) = explicit_path {
+            return Self::load_from(path);
+        }
+
+        match default_path() {
+            Some(path) => match Self::load_from(&path) {
+                Err(TransomeError::IoError { source, .. })
+                    if source.kind() == io::ErrorKind::NotFound =>
+                {
+                    Ok(Self::default())
+                }
+                result => result,
+            },
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+/// 配置目录中的配置文件名
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 获取默认的配置文件路径
+pub fn default_path() -> Option<PathBuf> {
+    Some(default_dir()?.join(CONFIG_FILE_NAME))
+}
+
+/// 获取默认的配置目录
+///
+/// 优先使用 `XDG_CONFIG_HOME`，否则为主目录下的 `.config`（Windows 上同样如此，
+/// 主目录见 [`platform::home_dir`]）。
+pub fn default_dir() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| platform::home_dir().map(|home| home.join(".config")))?;
+
+    Some(config_dir.join("transome"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = ConfigFile::default();
+        assert!(config.deprecation_notices);
+        assert!(config.pin_temperature);
+        assert_eq!(config.temperature, DEFAULT_TEMPERATURE);
+        assert!(!config.allow_insecure_tls);
+        assert_eq!(ConfigFile::from_toml_str("").unwrap(), config);
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = ConfigFile::from_toml_str("deprecation_notices = false").unwrap();
+        assert!(!config.deprecation_notices);
+    }
+
+    #[test]
+    fn test_parse_default_model() {
+        let config = ConfigFile::from_toml_str("default_model = \"gpt-4o\"").unwrap();
+        assert_eq!(config.default_model.as_deref(), Some("gpt-4o"));
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [profile.default]
+            model = "gemini-2.5-flash"
+
+            [profile.work]
+            model = "gpt-4o"
+            url = "https://gateway.example.com/v1"
+            key_env = "WORK_OPENAI_KEY"
+            "#,
+        )
+        .unwrap();
+
+        let work = config.profile(Some("work")).unwrap().unwrap();
+        assert_eq!(work.key_env.as_deref(), Some("WORK_OPENAI_KEY"));
+        let default = config.profile(None).unwrap().unwrap();
+        assert_eq!(default.model.as_deref(), Some("gemini-2.5-flash"));
+
+        let error = config.profile(Some("home")).unwrap_err().to_string();
+        assert!(error.contains("default, work"), "{}", error);
+        assert_eq!(ConfigFile::default().profile(None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_temperature() {
+        let config = ConfigFile::from_toml_str("temperature = 0.7").unwrap();
+        assert_eq!(config.temperature, 0.7);
+
+        let result = ConfigFile::from_toml_str("temperature = 3.0");
+        assert!(result.unwrap_err().is_config_error());
+    }
+
+    #[test]
+    fn test_unknown_field_is_rejected() {
+        let result = ConfigFile::from_toml_str("unknown_option = 1");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().is_config_error());
+    }
+
+    #[test]
+    fn test_parse_request_template() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [models."gateway-model".request_template]
+            omit = ["model"]
+            rename = { messages = "inputs" }
+            "#,
+        )
+        .unwrap();
+
+        let template = config.request_template("gateway-model").unwrap();
+        assert_eq!(template.omit, vec!["model"]);
+        assert_eq!(template.rename["messages"], "inputs");
+        assert!(config.request_template("gpt-4o").is_none());
+    }
+
+    #[test]
+    fn test_parse_reasoning() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [models."my-reasoner"]
+            reasoning = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.reasoning("my-reasoner"), Some(true));
+        assert_eq!(config.reasoning("gpt-4o"), None);
+    }
+
+    #[test]
+    fn test_parse_max_tokens_required() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [models."sonnet-gateway"]
+            max_tokens_required = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.max_tokens_required("sonnet-gateway"), Some(true));
+        assert_eq!(config.max_tokens_required("gpt-4o"), None);
+    }
+
+    #[test]
+    fn test_parse_context_window() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [models."llama-3"]
+            context_window = 32768
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.context_window("llama-3"), Some(32768));
+        assert_eq!(config.context_window("gpt-4o"), None);
+    }
+
+    #[test]
+    fn test_parse_max_request_bytes() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [models."gpt-4o"]
+            max_request_bytes = 1000000
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.max_request_bytes("gpt-4o"), Some(1_000_000));
+        assert_eq!(config.max_request_bytes("llama-3"), None);
+    }
+
+    #[test]
+    fn test_parse_prompt_layout() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [models."o3-mini"]
+            prompt_layout = "developer"
+
+            [models."gemma-3"]
+            prompt_layout = "single-user"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.prompt_layout("o3-mini"),
+            Some(PromptLayout::Developer)
+        );
+        assert_eq!(
+            config.prompt_layout("gemma-3"),
+            Some(PromptLayout::SingleUser)
+        );
+        assert_eq!(config.prompt_layout("gpt-4o"), None);
+        assert!(ConfigFile::from_toml_str("[models.x]\nprompt_layout = \"assistant\"").is_err());
+    }
+
+    #[test]
+    fn test_conflicting_request_template_is_rejected() {
+        let result = ConfigFile::from_toml_str(
+            r#"
+            [models."gateway-model".request_template]
+            omit = ["model"]
+            set = { model = "other" }
+            "#,
+        );
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("gateway-model"));
+        assert!(error_msg.contains("omit 和 set"));
+    }
+
+    #[test]
+    fn test_parse_routing() {
+        let config = ConfigFile::from_toml_str(
+            r#"
+            [models."my-llama"]
+            context_window = 32768
+
+            [routing]
+            "*:ja" = "gemini-2.5-pro"
+            "zh:en" = "my-llama"
+            default = "gpt-4o-mini"
+            "#,
+        )
+        .unwrap();
+        assert!(!config.routing.is_empty());
+
+        // 未知模型在加载时被拒绝
+        let result = ConfigFile::from_toml_str(
+            "[routing]
+\"de:en\" = \"deepl\"
+",
+        );
+        let error = result.unwrap_err();
+        assert!(error.is_config_error());
+        assert!(error.to_string().contains("deepl"), "{}", error);
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "deprecation_notices = false\n").unwrap();
+
+        let config = ConfigFile::load(Some(&path)).unwrap();
+        assert!(!config.deprecation_notices);
+    }
+
+    #[test]
+    fn test_load_missing_explicit_path_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = ConfigFile::load(Some(&dir.path().join("missing.toml")));
+        assert!(matches!(result, Err(TransomeError::IoError { .. })));
+    }
+}
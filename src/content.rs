@@ -0,0 +1,53 @@
+//! 用户消息的内容片段
+//!
+//! 用户消息以内容片段列表（OpenAI 的 content parts）而不是单个字符串构建。
+//! 目前只有文字片段；以后支持图片翻译（如 `--image`）时在 [`ContentPart`] 中增加
+//! 图片片段即可，消息的组装方式不变。
+
+use async_openai::types::{
+    ChatCompletionRequestMessageContentPartText, ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestUserMessageContentPart,
+};
+
+/// 用户消息中的一个内容片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentPart {
+    /// 文字
+    Text(String),
+}
+
+impl ContentPart {
+    /// 文字片段
+    pub fn text(text: impl Into<String>) -> Self {
+        ContentPart::Text(text.into())
+    }
+}
+
+impl From<ContentPart> for ChatCompletionRequestUserMessageContentPart {
+    fn from(part: ContentPart) -> Self {
+        match part {
+            ContentPart::Text(text) => ChatCompletionRequestUserMessageContentPart::Text(
+                ChatCompletionRequestMessageContentPartText { text },
+            ),
+        }
+    }
+}
+
+/// 由内容片段组成用户消息的内容
+pub fn user_content(parts: Vec<ContentPart>) -> ChatCompletionRequestUserMessageContent {
+    ChatCompletionRequestUserMessageContent::Array(parts.into_iter().map(Into::into).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_part_serializes_as_typed_part() {
+        let content = user_content(vec![ContentPart::text("hello")]);
+        assert_eq!(
+            serde_json::to_value(&content).unwrap(),
+            serde_json::json!([{ "type": "text", "text": "hello" }])
+        );
+    }
+}
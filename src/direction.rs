@@ -0,0 +1,119 @@
+//! 固定一次会话的翻译方向
+//!
+//! 默认提示词为中英互译，每条输入由模型各自判断方向；混有中英文的批量输入中，个别条目可能被
+//! 译反。`--lock-direction` 时按第一条能判断语言的输入固定方向（如 `zh→en`），之后的条目都
+//! 翻译成该方向的目标语言，直到解除固定。
+
+use std::fmt;
+
+use crate::history;
+use crate::multi_target::target_prompt;
+
+/// 翻译方向
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Direction {
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}→{}", self.from, self.to)
+    }
+}
+
+/// 一次会话的方向状态
+#[derive(Debug, Clone, Default)]
+pub struct DirectionSession {
+    locked: bool,
+    direction: Option<Direction>,
+}
+
+impl DirectionSession {
+    /// 创建会话，`locked` 为 true 时固定之后检测到的第一个方向
+    pub fn new(locked: bool) -> Self {
+        Self {
+            locked,
+            direction: None,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// 固定方向：已检测到方向时立即生效，否则固定之后检测到的第一个方向
+    pub fn lock(&mut self) {
+        self.locked = true;
+    }
+
+    /// 解除固定，之后的输入重新由模型判断方向
+    pub fn unlock(&mut self) {
+        self.locked = false;
+        self.direction = None;
+    }
+
+    /// 已固定的方向，未固定或尚未检测到时为 None
+    pub fn direction(&self) -> Option<&Direction> {
+        self.direction.as_ref().filter(|_| self.locked)
+    }
+
+    /// 检测一条输入的方向；固定后且尚未确定方向时记录下来，返回当前固定的方向
+    pub fn observe(&mut self, text: &str) -> Option<&Direction> {
+        if self.locked
+            && self.direction.is_none()
+            && let (Some(from), to) = history::language_pair(text, &[])
+            && let Some(to) = to.into_iter().next()
+        {
+            self.direction = Some(Direction { from, to });
+        }
+        self.direction()
+    }
+
+    /// 按固定的方向调整提示词，未固定时原样返回
+    pub fn prompt(&self, prompt: &str) -> String {
+        match self.direction() {
+            Some(direction) => target_prompt(prompt, &direction.to),
+            None => prompt.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translator::PROMPT;
+
+    #[test]
+    fn test_lock_pins_first_detected_direction() {
+        let mut session = DirectionSession::new(true);
+        assert_eq!(session.observe("12345"), None);
+        assert_eq!(session.observe("你好").unwrap().to_string(), "zh→en");
+        // 之后的英文输入不改变已固定的方向
+        assert_eq!(session.observe("Hello").unwrap().to_string(), "zh→en");
+
+        let prompt = session.prompt(PROMPT);
+        assert_ne!(prompt, PROMPT);
+        assert!(prompt.contains("翻译成英文"), "{}", prompt);
+    }
+
+    #[test]
+    fn test_toggle_lock() {
+        let mut session = DirectionSession::default();
+        assert!(!session.is_locked());
+        assert_eq!(session.observe("你好"), None);
+        assert_eq!(session.prompt(PROMPT), PROMPT);
+
+        session.lock();
+        assert_eq!(session.observe("Hello").unwrap().to_string(), "en→zh");
+        assert!(session.prompt("翻译").ends_with("请翻译成简体中文。"));
+
+        session.unlock();
+        assert_eq!(session.direction(), None);
+        assert_eq!(session.prompt(PROMPT), PROMPT);
+
+        // 重新固定时按新的输入检测方向
+        session.lock();
+        assert_eq!(session.observe("你好").unwrap().to_string(), "zh→en");
+    }
+}
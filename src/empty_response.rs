@@ -0,0 +1,224 @@
+//! 没有译文的响应的诊断
+//!
+//! 服务返回成功状态，但 `choices` 为空、缺失或其中没有任何内容时，仅报告“没有翻译结果”
+//! 无从排查。这里检查原始响应，区分三种情况（见 [`EmptyResponseKind`]）：
+//!
+//! - 被内容安全策略拦截：Gemini 的 `promptFeedback.blockReason`，或 `finish_reason`
+//!   为 `content_filter`、`SAFETY` 等；
+//! - 响应格式异常：缺少 `choices`、选项中没有 `message`，或带有顶层的 `error`；
+//! - 结构正常但确实为空。
+//!
+//! 错误中附带状态码、模型、finish_reason、顶层的 `error` 与 `promptFeedback` 字段，
+//! 以及隐去 API 密钥后的响应摘录。重试只对确实为空的响应有意义（见 [`crate::retry`]）。
+
+use serde_json::Value;
+
+use crate::error::{EmptyResponseKind, TransomeError};
+use crate::response_dump::redact;
+use crate::util::body_excerpt;
+
+/// 表示被内容安全策略拦截的 finish_reason（小写）
+const FILTERED_FINISH_REASONS: &[&str] = &[
+    "content_filter",
+    "safety",
+    "prohibited_content",
+    "blocklist",
+    "spii",
+    "recitation",
+];
+
+/// 检查响应中是否有译文，没有时返回说明原因的错误
+///
+/// 任一选项的 `message.content` 为字符串即视为有译文，内容是否为空白由调用方判断。
+pub fn diagnose(raw: &Value, status_code: u16, model: &str, secret: &str) -> Option<TransomeError> {
+    let choices = raw.get("choices").and_then(Value::as_array);
+    let has_content = choices.is_some_and(|choices| {
+        choices.iter().any(|choice| {
+            choice
+                .pointer("/message/content")
+                .is_some_and(Value::is_string)
+        })
+    });
+    if has_content {
+        return None;
+    }
+
+    let mut raw = raw.clone();
+    redact(&mut raw, secret);
+
+    let finish_reasons = finish_reasons(&raw);
+    let error = raw.get("error");
+    let prompt_feedback = raw.get("promptFeedback");
+
+    let kind = if prompt_feedback.is_some_and(|feedback| feedback.get("blockReason").is_some())
+        || finish_reasons
+            .iter()
+            .any(|reason| FILTERED_FINISH_REASONS.contains(&reason.to_lowercase().as_str()))
+    {
+        EmptyResponseKind::Filtered
+    } else if error.is_some()
+        || choices.is_none_or(|choices| {
+            choices
+                .iter()
+                .any(|choice| !choice.get("message").is_some_and(Value::is_object))
+        })
+    {
+        EmptyResponseKind::Malformed
+    } else {
+        EmptyResponseKind::Empty
+    };
+
+    let mut detail = Vec::new();
+    if !finish_reasons.is_empty() {
+        detail.push(format!("finish_reason: {}", finish_reasons.join(", ")));
+    }
+    if let Some(error) = error {
+        detail.push(format!("error: {}", error));
+    }
+    if let Some(feedback) = prompt_feedback {
+        detail.push(format!("promptFeedback: {}", feedback));
+    }
+
+    let excerpt = body_excerpt(raw.to_string().as_bytes());
+    Some(TransomeError::empty_response(
+        model,
+        status_code,
+        kind,
+        detail.join("；"),
+        excerpt,
+    ))
+}
+
+/// 各选项的 finish_reason，也包括 Gemini 原生格式中候选项的 finishReason
+fn finish_reasons(raw: &Value) -> Vec<String> {
+    ["choices", "candidates"]
+        .iter()
+        .filter_map(|key| raw.get(key).and_then(Value::as_array))
+        .flatten()
+        .filter_map(|choice| {
+            choice
+                .get("finish_reason")
+                .or_else(|| choice.get("finishReason"))
+                .and_then(Value::as_str)
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kind_of(raw: &Value) -> Option<EmptyResponseKind> {
+        match diagnose(raw, 200, "gemini-2.5-flash", "sk-secret")? {
+            TransomeError::EmptyResponse { kind, .. } => Some(kind),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_response_with_content_passes() {
+        let raw = json!({
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello"}}]
+        });
+        assert_eq!(kind_of(&raw), None);
+    }
+
+    #[test]
+    fn test_gemini_safety_block() {
+        // Gemini 原生格式：没有 candidates，只有 promptFeedback
+        let raw = json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY",
+                "safetyRatings": [
+                    {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH"}
+                ]
+            },
+            "modelVersion": "gemini-2.5-flash"
+        });
+        let error = diagnose(&raw, 200, "gemini-2.5-flash", "sk-secret").unwrap();
+        assert!(matches!(
+            error,
+            TransomeError::EmptyResponse {
+                kind: EmptyResponseKind::Filtered,
+                status_code: 200,
+                ..
+            }
+        ));
+        let message = error.to_string();
+        assert!(message.contains("gemini-2.5-flash"), "{}", message);
+        assert!(message.contains("被内容安全策略拦截"), "{}", message);
+        assert!(
+            message.contains("\"blockReason\":\"SAFETY\""),
+            "{}",
+            message
+        );
+
+        // OpenAI 兼容端点：选项没有内容，finish_reason 表明被拦截
+        let raw = json!({
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant"},
+                "finish_reason": "content_filter"
+            }]
+        });
+        assert_eq!(kind_of(&raw), Some(EmptyResponseKind::Filtered));
+    }
+
+    #[test]
+    fn test_malformed_openai_compat_body() {
+        // 旧版补全接口的格式：选项中是 text 而不是 message
+        let raw = json!({
+            "id": "cmpl-1",
+            "object": "text_completion",
+            "choices": [{"index": 0, "text": "Hello", "finish_reason": "stop"}]
+        });
+        let error = diagnose(&raw, 200, "llama-3", "").unwrap();
+        let message = error.to_string();
+        assert!(matches!(
+            error,
+            TransomeError::EmptyResponse {
+                kind: EmptyResponseKind::Malformed,
+                ..
+            }
+        ));
+        assert!(message.contains("finish_reason: stop"), "{}", message);
+
+        // 以成功状态返回的错误
+        let raw = json!({"error": {"message": "upstream timeout", "code": 502}});
+        assert_eq!(kind_of(&raw), Some(EmptyResponseKind::Malformed));
+        assert_eq!(
+            kind_of(&json!({"id": "x"})),
+            Some(EmptyResponseKind::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_truly_empty() {
+        let raw = json!({"object": "chat.completion", "choices": []});
+        assert_eq!(kind_of(&raw), Some(EmptyResponseKind::Empty));
+
+        let raw = json!({
+            "choices": [{"message": {"role": "assistant", "content": null}, "finish_reason": "length"}]
+        });
+        assert_eq!(kind_of(&raw), Some(EmptyResponseKind::Empty));
+    }
+
+    #[test]
+    fn test_excerpt_is_redacted_and_truncated() {
+        let raw = json!({
+            "choices": [],
+            "echo": format!("Bearer sk-secret {}", "x".repeat(500))
+        });
+        let Some(TransomeError::EmptyResponse { excerpt, .. }) =
+            diagnose(&raw, 200, "gpt-4o", "sk-secret")
+        else {
+            panic!("expected an empty response error");
+        };
+        assert!(!excerpt.contains("sk-secret"), "{}", excerpt);
+        assert!(excerpt.contains("[REDACTED]"), "{}", excerpt);
+        assert_eq!(excerpt.chars().count(), 201);
+    }
+}
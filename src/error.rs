@@ -51,10 +51,58 @@ pub enum TransomeError {
     /// 翻译服务错误
     TranslationServiceError { service: String, message: String },
 
+    /// 文档格式解析或生成错误
+    FormatError { format: String, message: String },
+
+    /// 服务不接受 system 角色的提示消息
+    SystemRoleRejected { model: String, message: String },
+
+    /// 请求超过时限
+    Timeout { message: String },
+
+    /// 获取网页失败（服务返回错误状态）
+    FetchFailed { url: String, status_code: u16 },
+
+    /// 请求体超过提供商接受的上限（字节）
+    RequestTooLarge { size: usize, limit: usize },
+
+    /// 服务返回成功状态，但响应中没有译文
+    EmptyResponse {
+        model: String,
+        status_code: u16,
+        kind: EmptyResponseKind,
+        /// finish_reason 与顶层的 error、promptFeedback 字段，没有时为空
+        detail: String,
+        /// 隐去 API 密钥后的响应摘录
+        excerpt: String,
+    },
+
     /// 通用错误
     General { message: String },
 }
 
+/// 响应中没有译文的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyResponseKind {
+    /// 被服务的内容安全策略拦截
+    Filtered,
+    /// 响应结构不符合聊天完成接口
+    Malformed,
+    /// 结构正常但没有内容
+    Empty,
+}
+
+impl fmt::Display for EmptyResponseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            EmptyResponseKind::Filtered => "被内容安全策略拦截",
+            EmptyResponseKind::Malformed => "响应格式异常",
+            EmptyResponseKind::Empty => "响应为空",
+        };
+        write!(f, "{}", text)
+    }
+}
+
 impl fmt::Display for TransomeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -123,6 +171,48 @@ impl fmt::Display for TransomeError {
                 write!(f, "翻译服务 '{}' 错误：{}", service, message)
             }
 
+            TransomeError::FormatError { format, message } => {
+                write!(f, "处理 {} 格式失败：{}", format, message)
+            }
+
+            TransomeError::SystemRoleRejected { model, message } => {
+                write!(f, "模型 '{}' 不接受 system 角色消息：{}", model, message)
+            }
+
+            TransomeError::Timeout { message } => {
+                write!(f, "{}，已超时", message)
+            }
+
+            TransomeError::FetchFailed { url, status_code } => {
+                write!(f, "获取网页 '{}' 失败，状态码 {}", url, status_code)
+            }
+
+            TransomeError::RequestTooLarge { size, limit } => {
+                write!(
+                    f,
+                    "请求体大小为 {} 字节，超过提供商接受的上限 {} 字节",
+                    size, limit
+                )
+            }
+
+            TransomeError::EmptyResponse {
+                model,
+                status_code,
+                kind,
+                detail,
+                excerpt,
+            } => {
+                write!(
+                    f,
+                    "模型 '{}' 的响应中没有译文（{}，状态码 {}）",
+                    model, kind, status_code
+                )?;
+                if !detail.is_empty() {
+                    write!(f, "：{}", detail)?;
+                }
+                write!(f, "\n响应摘录：{}", excerpt)
+            }
+
             TransomeError::General { message } => {
                 write!(f, "{}", message)
             }
@@ -257,6 +347,53 @@ impl TransomeError {
         }
     }
 
+    pub fn format_error(format: impl Into<String>, message: impl Into<String>) -> Self {
+        TransomeError::FormatError {
+            format: format.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn system_role_rejected(model: impl Into<String>, message: impl Into<String>) -> Self {
+        TransomeError::SystemRoleRejected {
+            model: model.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn fetch_failed(url: impl Into<String>, status_code: u16) -> Self {
+        TransomeError::FetchFailed {
+            url: url.into(),
+            status_code,
+        }
+    }
+
+    pub fn request_too_large(size: usize, limit: usize) -> Self {
+        TransomeError::RequestTooLarge { size, limit }
+    }
+
+    pub fn empty_response(
+        model: impl Into<String>,
+        status_code: u16,
+        kind: EmptyResponseKind,
+        detail: impl Into<String>,
+        excerpt: impl Into<String>,
+    ) -> Self {
+        TransomeError::EmptyResponse {
+            model: model.into(),
+            status_code,
+            kind,
+            detail: detail.into(),
+            excerpt: excerpt.into(),
+        }
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        TransomeError::Timeout {
+            message: message.into(),
+        }
+    }
+
     pub fn is_network_error(&self) -> bool {
         matches!(self, TransomeError::NetworkError { .. })
     }
@@ -269,6 +406,136 @@ impl TransomeError {
         matches!(self, TransomeError::ConfigError { .. })
     }
 
+    /// 是否因只读文件系统（EROFS）或没有权限（EACCES）而无法读写本地文件
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            TransomeError::IoError { source, .. } if matches!(
+                source.kind(),
+                io::ErrorKind::ReadOnlyFilesystem | io::ErrorKind::PermissionDenied
+            )
+        )
+    }
+
+    /// 错误类别的稳定名称，用于汇总与 JSON 输出
+    ///
+    /// 429 归为 `RateLimited`，被内容安全策略拦截的空响应归为 `ContentFiltered`。
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            TransomeError::ModelNotFound { .. } => "ModelNotFound",
+            TransomeError::ApiCallFailed {
+                status_code: Some(429),
+                ..
+            } => "RateLimited",
+            TransomeError::ApiCallFailed { .. } => "ApiCallFailed",
+            TransomeError::NetworkError { .. } => "NetworkError",
+            TransomeError::JsonError { .. } => "JsonError",
+            TransomeError::IoError { .. } => "IoError",
+            TransomeError::AuthenticationError { .. } => "AuthenticationError",
+            TransomeError::ConfigError { .. } => "ConfigError",
+            TransomeError::ValidationError { .. } => "ValidationError",
+            TransomeError::ModelLoadError { .. } => "ModelLoadError",
+            TransomeError::TranslationServiceError { .. } => "TranslationServiceError",
+            TransomeError::FormatError { .. } => "FormatError",
+            TransomeError::SystemRoleRejected { .. } => "SystemRoleRejected",
+            TransomeError::Timeout { .. } => "Timeout",
+            TransomeError::FetchFailed { .. } => "FetchFailed",
+            TransomeError::RequestTooLarge { .. } => "RequestTooLarge",
+            TransomeError::EmptyResponse { kind, .. } => match kind {
+                EmptyResponseKind::Filtered => "ContentFiltered",
+                EmptyResponseKind::Malformed => "MalformedResponse",
+                EmptyResponseKind::Empty => "EmptyResponse",
+            },
+            TransomeError::General { .. } => "General",
+        }
+    }
+
+    /// 针对错误类型的故障排除建议
+    pub fn hints(&self) -> Vec<&'static str> {
+        match self {
+            TransomeError::AuthenticationError { .. } => vec![
+                "验证您的 API 密钥是否正确，并具有访问该模型的权限",
+                "OpenAI 密钥通常以 'sk-' 开头，Google AI 密钥通常以 'AIza' 开头",
+                "使用 -k <KEY> 直接提供密钥，或检查对应的环境变量",
+            ],
+
+            TransomeError::NetworkError { .. } => vec![
+                "检查您的网络连接",
+                "如果通过代理访问，确认 HTTPS_PROXY / HTTP_PROXY 环境变量设置正确",
+                "如果使用了 --url，确认该端点可以访问",
+            ],
+
+            TransomeError::ModelNotFound { .. } => vec![
+                "使用 --list-models 查看可用选项",
+                "使用 --model <MODEL> 尝试不同的模型",
+            ],
+
+            TransomeError::ApiCallFailed { status_code, .. } => match status_code {
+                Some(404) => vec![
+                    "确认模型名称正确，且您有权使用该模型",
+                    "使用 --list-models 查看可用选项",
+                ],
+                Some(429) => vec![
+                    "稍后再试，或使用 --concurrency 降低并发数",
+                    "如果频繁出现，请考虑升级您的 API 套餐",
+                ],
+                _ => vec![
+                    "服务可能暂时不可用，请稍后重试",
+                    "使用 --model <MODEL> 尝试不同的模型",
+                ],
+            },
+
+            TransomeError::JsonError { .. } => {
+                vec!["API 返回了无法解析的响应，确认端点兼容 OpenAI 接口"]
+            }
+
+            TransomeError::ConfigError { .. } => {
+                vec!["检查配置文件内容，或使用 --config 指定其他配置文件"]
+            }
+
+            TransomeError::TranslationServiceError { .. } => {
+                vec!["服务可能暂时不可用，请稍后重试"]
+            }
+
+            TransomeError::Timeout { .. } => vec![
+                "检查您的网络连接，或稍后重试",
+                "使用 --timeout 或 --deadline 放宽时限，或使用 --retries 允许重试",
+            ],
+
+            TransomeError::SystemRoleRejected { .. } => vec![
+                "该服务或模型不支持 system 角色，提示词需要以 user 消息发送",
+                "去掉 --no-system-fallback 即可自动改用 user 消息重试",
+            ],
+
+            TransomeError::FetchFailed { .. } => vec![
+                "确认网址正确，且无需登录即可访问",
+                "也可以在浏览器中保存网页后使用 --file 与 --format html 翻译",
+            ],
+
+            TransomeError::RequestTooLarge { .. } => vec![
+                "将文本保存为文件后使用 --file 翻译，超出上限的文本会自动分块",
+                "网关的上限不同时，可在配置文件中为模型设置 max_request_bytes",
+            ],
+
+            TransomeError::EmptyResponse { kind, .. } => match kind {
+                EmptyResponseKind::Filtered => vec![
+                    "原文可能触发了服务的内容安全策略，可以改写相关内容",
+                    "也可以使用 -m 换用其他模型",
+                ],
+                EmptyResponseKind::Malformed => vec![
+                    "确认 --url 指向 OpenAI 兼容的接口（通常以 /v1 结尾）",
+                    "使用 --dump-response 保存完整响应以便排查",
+                ],
+                EmptyResponseKind::Empty => vec![
+                    "服务偶尔会返回空响应，可以使用 --retries 允许重试",
+                    "推理模型可能用尽了 token，可以调大 --max-tokens",
+                ],
+            },
+
+            _ => Vec::new(),
+        }
+    }
+
     pub fn user_friendly_message(&self) -> String {
         match self {
             TransomeError::ModelNotFound {
@@ -332,11 +599,146 @@ impl TransomeError {
                 format!("翻译服务 '{}' 暂时不可用，请稍后重试", service)
             }
 
+            TransomeError::FormatError { format, .. } => {
+                format!("文档格式错误：请检查输入是否为有效的 {}", format)
+            }
+
+            TransomeError::Timeout { .. } => "请求超时，请稍后重试".to_string(),
+
+            TransomeError::SystemRoleRejected { model, .. } => {
+                format!("模型 '{}' 不支持 system 角色的提示消息", model)
+            }
+
+            TransomeError::FetchFailed { status_code, .. } => {
+                format!("获取网页失败 ({}): 请检查网址是否可以访问", status_code)
+            }
+
+            TransomeError::EmptyResponse { model, kind, .. } => {
+                format!("模型 '{}' 没有返回译文：{}", model, kind)
+            }
+
+            TransomeError::RequestTooLarge { limit, .. } => {
+                format!("请求过大：提供商只接受不超过 {} 字节的请求", limit)
+            }
+
             _ => "操作失败，请重试".to_string(),
         }
     }
 }
 
+/// [`TransomeError`] 的可序列化形式，用于保存或传输错误
+///
+/// 底层错误（如 `reqwest::Error`、`io::Error`）无法序列化，只保留其文字；变体的结构化字段
+/// 以字符串记录在 `metadata` 中。字段名是 JSON 输出的兼容性约定，不能随意修改。
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ErrorInfo {
+    /// 错误类别，见 [`TransomeError::kind_name`]；不是 [`TransomeError`] 的错误为 `Other`
+    pub kind: String,
+    /// 错误信息，来自 anyhow 错误时包含完整的上下文
+    pub message: String,
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub metadata: std::collections::BTreeMap<String, String>,
+}
+
+#[cfg(feature = "serde")]
+impl ErrorInfo {
+    /// 从 anyhow 错误创建，错误链中的 [`TransomeError`] 提供类别与字段
+    pub fn from_anyhow(error: &anyhow::Error) -> Self {
+        let mut info = match error.downcast_ref::<TransomeError>() {
+            Some(e) => Self::from(e),
+            None => Self {
+                kind: "Other".to_string(),
+                message: String::new(),
+                metadata: Default::default(),
+            },
+        };
+        info.message = format!("{:#}", error);
+        info
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<&TransomeError> for ErrorInfo {
+    fn from(error: &TransomeError) -> Self {
+        let fields: Vec<(&str, String)> = match error {
+            TransomeError::ModelNotFound {
+                model_name,
+                available_models,
+            } => vec![
+                ("model_name", model_name.clone()),
+                ("available_models", available_models.join(", ")),
+            ],
+            TransomeError::ApiCallFailed {
+                endpoint,
+                status_code,
+                ..
+            } => {
+                let mut fields = vec![("endpoint", endpoint.clone())];
+                fields.extend(status_code.map(|code| ("status_code", code.to_string())));
+                fields
+            }
+            TransomeError::JsonError { context, .. } => vec![("context", context.clone())],
+            TransomeError::IoError { source, context } => vec![
+                ("context", context.clone()),
+                ("io_kind", format!("{:?}", source.kind())),
+            ],
+            TransomeError::ConfigError { field, .. } => vec![("field", field.clone())],
+            TransomeError::ValidationError {
+                field,
+                expected,
+                actual,
+            } => vec![
+                ("field", field.clone()),
+                ("expected", expected.clone()),
+                ("actual", actual.clone()),
+            ],
+            TransomeError::ModelLoadError { model_path, .. } => {
+                vec![("model_path", model_path.clone())]
+            }
+            TransomeError::TranslationServiceError { service, .. } => {
+                vec![("service", service.clone())]
+            }
+            TransomeError::FormatError { format, .. } => vec![("format", format.clone())],
+            TransomeError::SystemRoleRejected { model, .. } => vec![("model", model.clone())],
+            TransomeError::FetchFailed { url, status_code } => vec![
+                ("url", url.clone()),
+                ("status_code", status_code.to_string()),
+            ],
+            TransomeError::RequestTooLarge { size, limit } => {
+                vec![("size", size.to_string()), ("limit", limit.to_string())]
+            }
+            TransomeError::EmptyResponse {
+                model,
+                status_code,
+                detail,
+                ..
+            } => {
+                let mut fields = vec![
+                    ("model", model.clone()),
+                    ("status_code", status_code.to_string()),
+                ];
+                if !detail.is_empty() {
+                    fields.push(("detail", detail.clone()));
+                }
+                fields
+            }
+            TransomeError::NetworkError { .. }
+            | TransomeError::AuthenticationError { .. }
+            | TransomeError::Timeout { .. }
+            | TransomeError::General { .. } => Vec::new(),
+        };
+        Self {
+            kind: error.kind_name().to_string(),
+            message: error.to_string(),
+            metadata: fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -379,6 +781,17 @@ mod tests {
             message: "test".to_string(),
         };
         assert!(!general_error.is_network_error());
+        assert!(!general_error.is_read_only());
+
+        for kind in [
+            io::ErrorKind::ReadOnlyFilesystem,
+            io::ErrorKind::PermissionDenied,
+        ] {
+            let error = TransomeError::io_error_with_context(io::Error::from(kind), "创建目录");
+            assert!(error.is_read_only(), "{:?}", kind);
+        }
+        let not_found = io::Error::from(io::ErrorKind::NotFound);
+        assert!(!TransomeError::io_error_with_context(not_found, "读取").is_read_only());
     }
 
     #[test]
@@ -390,4 +803,47 @@ mod tests {
         let owned_string_error: TransomeError = "test error".to_string().into();
         assert!(matches!(owned_string_error, TransomeError::General { .. }));
     }
+
+    #[test]
+    fn test_format_error() {
+        let error = TransomeError::format_error("CSV", "bad row");
+        assert!(error.to_string().contains("CSV"));
+        assert!(error.to_string().contains("bad row"));
+        assert!(error.user_friendly_message().contains("CSV"));
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_error_info_round_trip() {
+        let error =
+            TransomeError::api_call_failed("https://api.example.com", Some(429), "slow down");
+        let info = ErrorInfo::from(&error);
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "RateLimited",
+                "message": error.to_string(),
+                "metadata": {
+                    "endpoint": "https://api.example.com",
+                    "status_code": "429",
+                },
+            })
+        );
+        assert_eq!(serde_json::from_value::<ErrorInfo>(json).unwrap(), info);
+
+        // 没有结构化字段时省略 metadata，上下文保留在 message 中
+        let error =
+            anyhow::Error::new(TransomeError::authentication_error("bad key")).context("翻译失败");
+        let info = ErrorInfo::from_anyhow(&error);
+        assert_eq!(info.kind, "AuthenticationError");
+        assert!(info.message.starts_with("翻译失败: "), "{}", info.message);
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(!json.contains("metadata"), "{}", json);
+        assert_eq!(serde_json::from_str::<ErrorInfo>(&json).unwrap(), info);
+
+        assert_eq!(
+            ErrorInfo::from_anyhow(&anyhow::anyhow!("boom")).kind,
+            "Other"
+        );
+    }
 }
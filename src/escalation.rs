@@ -0,0 +1,173 @@
+//! 低质量译文升级到更强的模型
+//!
+//! `--escalate-on-low-quality <THRESHOLD>` 对同一原文独立翻译两次，以两次译文的相似度
+//! 作为自一致性得分（0 到 1）：模型对译法没有把握时，两次结果往往差别较大。得分低于
+//! 阈值时改用 `--escalate-model` 指定的更强模型重新翻译，并使用其结果。
+//!
+//! 相似度为两次译文字符二元组的 Dice 系数，忽略首尾空白。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+
+use anyhow::Result;
+
+/// 解析 `--escalate-on-low-quality`：0 到 1 之间的得分阈值
+pub fn parse_threshold(s: &str) -> Result<f64, String> {
+    let threshold: f64 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("无效的阈值 '{}'，应为 0 到 1 之间的数", s))?;
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("阈值 {} 超出范围，应为 0 到 1 之间的数", threshold));
+    }
+    Ok(threshold)
+}
+
+/// 文本的字符二元组及其出现次数
+fn bigrams(text: &str) -> HashMap<(char, char), usize> {
+    let chars: Vec<char> = text.trim().chars().collect();
+    let mut counts = HashMap::new();
+    for pair in chars.windows(2) {
+        *counts.entry((pair[0], pair[1])).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// 两次译文的自一致性得分：字符二元组的 Dice 系数，完全相同为 1
+pub fn consistency_score(a: &str, b: &str) -> f64 {
+    let (a, b) = (a.trim(), b.trim());
+    if a == b {
+        return 1.0;
+    }
+    let (left, right) = (bigrams(a), bigrams(b));
+    let total: usize = left.values().sum::<usize>() + right.values().sum::<usize>();
+    if total == 0 {
+        // 都不足两个字符且不相同
+        return 0.0;
+    }
+    let shared: usize = left
+        .iter()
+        .map(|(pair, &count)| count.min(right.get(pair).copied().unwrap_or(0)))
+        .sum();
+    2.0 * shared as f64 / total as f64
+}
+
+/// 一次升级：自一致性得分低于阈值，改用更强的模型重新翻译
+#[derive(Debug, Clone, PartialEq)]
+pub struct Escalation {
+    /// 两次译文的自一致性得分
+    pub score: f64,
+    /// 得分阈值
+    pub threshold: f64,
+    /// 重新翻译使用的模型
+    pub model: String,
+}
+
+impl fmt::Display for Escalation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "自一致性得分 {:.2} 低于阈值 {}，已改用模型 '{}' 重新翻译",
+            self.score, self.threshold, self.model
+        )
+    }
+}
+
+/// 翻译两次并计算自一致性得分，低于阈值时改用更强的模型重新翻译
+///
+/// `translate` 使用当前模型翻译一次，`escalate` 使用 `model` 翻译一次。
+/// 未升级时使用第一次的译文。返回最终译文及发生的升级。
+pub async fn translate_escalating<T, F, Fut, G, GFut>(
+    threshold: f64,
+    model: &str,
+    translate: F,
+    escalate: G,
+) -> Result<(T, Option<Escalation>)>
+where
+    T: AsRef<str>,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+    G: FnOnce() -> GFut,
+    GFut: Future<Output = Result<T>>,
+{
+    let first = translate().await?;
+    let second = translate().await?;
+    let score = consistency_score(first.as_ref(), second.as_ref());
+    if score >= threshold {
+        return Ok((first, None));
+    }
+
+    let output = escalate().await?;
+    Ok((
+        output,
+        Some(Escalation {
+            score,
+            threshold,
+            model: model.to_string(),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_parse_threshold() {
+        assert_eq!(parse_threshold("0.7").unwrap(), 0.7);
+        assert_eq!(parse_threshold("1").unwrap(), 1.0);
+        assert!(parse_threshold("1.5").is_err());
+        assert!(parse_threshold("-0.1").is_err());
+        assert!(parse_threshold("high").is_err());
+    }
+
+    #[test]
+    fn test_consistency_score() {
+        assert_eq!(consistency_score("你好，世界", " 你好，世界\n"), 1.0);
+        assert_eq!(consistency_score("好", "坏"), 0.0);
+
+        let close = consistency_score("打开设置页面", "打开设置界面");
+        let far = consistency_score("打开设置页面", "启动配置窗口");
+        assert!(close > 0.5, "{}", close);
+        assert!(far < 0.2, "{}", far);
+    }
+
+    #[tokio::test]
+    async fn test_low_quality_escalates_to_stronger_model() {
+        let samples = Mutex::new(vec!["打开设置页面", "启动配置窗口"]);
+        let (output, escalation) = translate_escalating(
+            0.7,
+            "strong-model",
+            || {
+                let sample = samples.lock().unwrap().remove(0);
+                async move { Ok(sample.to_string()) }
+            },
+            || async { Ok("打开设置".to_string()) },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output, "打开设置");
+        let escalation = escalation.unwrap();
+        assert_eq!(escalation.model, "strong-model");
+        assert!(escalation.score < 0.7);
+        assert!(escalation.to_string().contains("strong-model"));
+    }
+
+    #[tokio::test]
+    async fn test_consistent_output_is_kept() {
+        let (output, escalation) = translate_escalating(
+            0.7,
+            "strong-model",
+            || async { Ok("打开设置".to_string()) },
+            || async { unreachable!("得分足够高时不应升级") },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output, "打开设置");
+        assert_eq!(escalation, None);
+    }
+}
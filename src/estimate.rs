@@ -0,0 +1,200 @@
+//! 翻译前的用量与费用估算
+//!
+//! `--estimate` 按实际运行时的解析与分块列出将要发送的请求，不发送请求，
+//! 以 [`chunk::estimate_tokens`] 估算每个请求的输入 token（含提示词），按输出比例
+//! （`--estimate-output-ratio`）推算输出 token，再按模型的参考价格计算费用。
+//! `--max-cost` 在正式运行前做同样的估算，超出上限时拒绝运行。
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::chunk;
+use crate::stats::{price_per_million, table};
+
+/// 默认的输出与输入 token 之比
+pub const DEFAULT_OUTPUT_RATIO: f64 = 1.0;
+
+/// 单个文件的估算
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileEstimate {
+    /// 文件名，从 stdin 或参数读取时为说明文字
+    pub name: String,
+    /// 请求数
+    pub requests: usize,
+    /// 输入 token（含每个请求携带的提示词）
+    pub input_tokens: usize,
+    /// 按输出比例推算的输出 token
+    pub output_tokens: usize,
+}
+
+impl FileEstimate {
+    /// 按将要发送的各请求的文本估算，每个请求都携带 `prompt`
+    pub fn from_requests(
+        name: impl Into<String>,
+        requests: &[String],
+        prompt: &str,
+        output_ratio: f64,
+    ) -> Self {
+        let prompt_tokens = chunk::estimate_tokens(prompt);
+        let (input_tokens, output_tokens) =
+            requests.iter().fold((0, 0), |(input, output), request| {
+                let tokens = chunk::estimate_tokens(request);
+                let projected = (tokens as f64 * output_ratio).ceil() as usize;
+                (input + prompt_tokens + tokens, output + projected)
+            });
+        Self {
+            name: name.into(),
+            requests: requests.len(),
+            input_tokens,
+            output_tokens,
+        }
+    }
+}
+
+/// 一次运行的估算
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Estimate {
+    pub model: String,
+    pub files: Vec<FileEstimate>,
+    /// 估算的总费用（美元），模型价格未知时为 None
+    pub cost: Option<f64>,
+}
+
+impl Estimate {
+    /// 汇总各文件的估算，按模型的参考价格计算费用
+    pub fn new(model: impl Into<String>, files: Vec<FileEstimate>) -> Self {
+        let model = model.into();
+        let cost = price_per_million(&model)
+            .map(|price| files.iter().map(|file| file_cost(file, price)).sum::<f64>());
+        Self { model, files, cost }
+    }
+
+    /// 合计的请求数、输入 token 与输出 token
+    pub fn totals(&self) -> (usize, usize, usize) {
+        self.files.iter().fold((0, 0, 0), |(r, i, o), file| {
+            (
+                r + file.requests,
+                i + file.input_tokens,
+                o + file.output_tokens,
+            )
+        })
+    }
+
+    /// 检查估算费用是否超出 `--max-cost`；模型价格未知时无法确认，同样视为超出
+    pub fn check_max_cost(&self, max_cost: f64) -> std::result::Result<(), String> {
+        match self.cost {
+            Some(cost) if cost <= max_cost => Ok(()),
+            Some(cost) => Err(format!(
+                "估算费用 ${:.4} 超出 --max-cost 上限 ${:.4}",
+                cost, max_cost
+            )),
+            None => Err(format!(
+                "模型 {} 的价格未知，无法确认费用是否在 --max-cost 上限 ${:.4} 之内",
+                self.model, max_cost
+            )),
+        }
+    }
+}
+
+/// 单个文件的费用
+fn file_cost(file: &FileEstimate, (input, output): (f64, f64)) -> f64 {
+    (file.input_tokens as f64 * input + file.output_tokens as f64 * output) / 1e6
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let price = price_per_million(&self.model);
+        match price {
+            Some((input, output)) => writeln!(
+                f,
+                "模型: {}（每百万 token 输入 ${}、输出 ${}，仅为参考价格）",
+                self.model, input, output
+            )?,
+            None => writeln!(f, "模型: {}（价格未知，不估算费用）", self.model)?,
+        }
+
+        let cost = |file: &FileEstimate| {
+            price.map_or("-".to_string(), |price| {
+                format!("${:.4}", file_cost(file, price))
+            })
+        };
+        let mut rows = vec![vec![
+            "文件".to_string(),
+            "请求".to_string(),
+            "输入 token".to_string(),
+            "预计输出 token".to_string(),
+            "估算费用".to_string(),
+        ]];
+        rows.extend(self.files.iter().map(|file| {
+            vec![
+                file.name.clone(),
+                file.requests.to_string(),
+                file.input_tokens.to_string(),
+                file.output_tokens.to_string(),
+                cost(file),
+            ]
+        }));
+        let (requests, input, output) = self.totals();
+        rows.push(vec![
+            "合计".to_string(),
+            requests.to_string(),
+            input.to_string(),
+            output.to_string(),
+            self.cost
+                .map_or("-".to_string(), |cost| format!("${:.4}", cost)),
+        ]);
+        write!(f, "{}", table(&rows).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_estimate_counts_prompt_per_request() {
+        let requests = vec!["abcdefgh".to_string(), "你好".to_string()];
+        let estimate = FileEstimate::from_requests("a.txt", &requests, "翻译", 1.5);
+
+        // 每个请求携带 2 个 token 的提示词：(2 + 2) + (2 + 2)
+        assert_eq!(estimate.requests, 2);
+        assert_eq!(estimate.input_tokens, 8);
+        // 2 × 1.5 = 3，每个请求分别计算
+        assert_eq!(estimate.output_tokens, 6);
+    }
+
+    #[test]
+    fn test_cost_and_max_cost() {
+        let file = FileEstimate {
+            name: "a.txt".to_string(),
+            requests: 1,
+            input_tokens: 1_000_000,
+            output_tokens: 500_000,
+        };
+        let estimate = Estimate::new("gpt-4o", vec![file.clone(), file.clone()]);
+        // (1M × 2.5 + 0.5M × 10) / 1M = 7.5，两个文件
+        assert_eq!(estimate.cost, Some(15.0));
+        assert_eq!(estimate.totals(), (2, 2_000_000, 1_000_000));
+        assert!(estimate.check_max_cost(20.0).is_ok());
+        assert!(
+            estimate
+                .check_max_cost(10.0)
+                .unwrap_err()
+                .contains("$15.0000")
+        );
+
+        let rendered = estimate.to_string();
+        assert!(
+            rendered.contains("合计   2     2000000     1000000"),
+            "{}",
+            rendered
+        );
+        assert!(rendered.ends_with("$15.0000"), "{}", rendered);
+
+        // 价格未知时无法确认
+        let estimate = Estimate::new("llama-3", vec![file]);
+        assert_eq!(estimate.cost, None);
+        assert!(estimate.check_max_cost(100.0).is_err());
+    }
+}
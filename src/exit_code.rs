@@ -0,0 +1,36 @@
+//! 进程退出码
+
+use crate::batch::BatchOutcome;
+
+/// 全部成功
+pub const SUCCESS: i32 = 0;
+
+/// 失败
+pub const FAILURE: i32 = 1;
+
+/// 批量任务因截止时间停止，部分条目未处理
+pub const DEADLINE_REACHED: i32 = 5;
+
+/// 批量任务部分条目成功、部分条目失败
+pub const PARTIAL_SUCCESS: i32 = 6;
+
+/// 批量任务整体结果对应的退出码
+pub fn for_outcome(outcome: BatchOutcome) -> i32 {
+    match outcome {
+        BatchOutcome::AllSucceeded => SUCCESS,
+        BatchOutcome::PartialSuccess => PARTIAL_SUCCESS,
+        BatchOutcome::AllFailed => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_for_outcome() {
+        assert_eq!(for_outcome(BatchOutcome::AllSucceeded), SUCCESS);
+        assert_eq!(for_outcome(BatchOutcome::PartialSuccess), PARTIAL_SUCCESS);
+        assert_eq!(for_outcome(BatchOutcome::AllFailed), FAILURE);
+    }
+}
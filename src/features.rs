@@ -0,0 +1,244 @@
+//! 编译特性与功能支持情况报告
+//!
+//! 用于排查“某个参数为什么不可用”：列出编译时启用的 cargo 特性，
+//! 以及各项功能是否可用、依赖哪个特性。
+//!
+//! 默认启用 `clipboard` 与 `bundle`。资源受限的设备上可以使用
+//! `--no-default-features` 构建，按需逐个启用；未启用的特性对应的参数不会出现在 `--help` 中。
+
+use once_cell::sync::Lazy;
+
+/// 所有可选的 cargo 特性及其是否在编译时启用
+pub fn compiled_features() -> Vec<(&'static str, bool)> {
+    vec![
+        ("clipboard", cfg!(feature = "clipboard")),
+        ("bundle", cfg!(feature = "bundle")),
+        ("logging", cfg!(feature = "logging")),
+        ("html", cfg!(feature = "html")),
+        ("from-url", cfg!(feature = "from-url")),
+        ("docx", cfg!(feature = "docx")),
+        ("serde", cfg!(feature = "serde")),
+        ("strip-markdown", cfg!(feature = "strip-markdown")),
+    ]
+}
+
+/// `--version` 的输出：版本号与编译时启用的特性，便于在问题报告中附上
+pub fn long_version() -> &'static str {
+    static LONG_VERSION: Lazy<String> = Lazy::new(|| {
+        let enabled: Vec<&str> = compiled_features()
+            .into_iter()
+            .filter_map(|(feature, enabled)| enabled.then_some(feature))
+            .collect();
+        let enabled = if enabled.is_empty() {
+            "无".to_string()
+        } else {
+            enabled.join(", ")
+        };
+        format!("v{}\n编译特性: {}", env!("CARGO_PKG_VERSION"), enabled)
+    });
+    &LONG_VERSION
+}
+
+/// 单项功能
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capability {
+    /// 功能名称
+    pub name: &'static str,
+    /// 功能说明
+    pub description: &'static str,
+    /// 依赖的 cargo 特性，None 表示始终可用
+    pub feature: Option<&'static str>,
+    /// 当前构建中是否可用
+    pub available: bool,
+}
+
+impl Capability {
+    const fn builtin(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            feature: None,
+            available: true,
+        }
+    }
+
+    const fn gated(
+        name: &'static str,
+        description: &'static str,
+        feature: &'static str,
+        available: bool,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            feature: Some(feature),
+            available,
+        }
+    }
+}
+
+/// 当前构建支持的功能列表
+pub fn capabilities() -> Vec<Capability> {
+    vec![
+        Capability::builtin("stdin", "从标准输入读取待翻译文本"),
+        Capability::builtin("json", "--json 结构化输出"),
+        Capability::builtin("wrap", "--wrap 按显示宽度折行"),
+        Capability::builtin("batch", "--lines 逐行批量翻译"),
+        Capability::builtin("csv", "--format csv 按列翻译"),
+        Capability::builtin("markdown", "--format markdown 翻译 Markdown 文字"),
+        Capability::gated(
+            "html",
+            "--format html 翻译 HTML 文本节点",
+            "html",
+            cfg!(feature = "html"),
+        ),
+        Capability::gated(
+            "from-url",
+            "--from-url 翻译网页正文",
+            "from-url",
+            cfg!(feature = "from-url"),
+        ),
+        Capability::builtin("code", "--format code 翻译源代码注释"),
+        Capability::builtin(
+            "properties",
+            "--format properties / kv 翻译 key=value 文件的值",
+        ),
+        Capability::builtin("json", "--format json 翻译 JSON 中的字符串值"),
+        Capability::gated(
+            "docx",
+            "--format docx 翻译 Word 文档正文",
+            "docx",
+            cfg!(feature = "docx"),
+        ),
+        Capability::builtin("diff", "--diff-against 差量翻译"),
+        Capability::gated(
+            "clipboard",
+            "--clipboard 输出到系统剪贴板",
+            "clipboard",
+            cfg!(feature = "clipboard"),
+        ),
+        Capability::gated(
+            "config-bundle",
+            "config export / config import 配置包",
+            "bundle",
+            cfg!(feature = "bundle"),
+        ),
+        Capability::gated(
+            "strip-markdown",
+            "--strip-markdown 去除译文中的 Markdown 标记",
+            "strip-markdown",
+            cfg!(feature = "strip-markdown"),
+        ),
+        Capability::builtin("request-template", "配置文件中的请求模板"),
+        Capability::gated(
+            "log-level",
+            "--log-level 日志输出",
+            "logging",
+            cfg!(feature = "logging"),
+        ),
+    ]
+}
+
+/// 生成功能报告文本
+pub fn report() -> String {
+    let mut lines = vec![format!("transome {}", env!("CARGO_PKG_VERSION"))];
+
+    lines.push(String::new());
+    lines.push("编译特性:".to_string());
+    for (feature, enabled) in compiled_features() {
+        let state = if enabled { "已启用" } else { "未启用" };
+        lines.push(format!("  - {}: {}", feature, state));
+    }
+
+    lines.push(String::new());
+    lines.push("功能:".to_string());
+    for capability in capabilities() {
+        let mark = if capability.available { "✓" } else { "✗" };
+        let mut line = format!(
+            "  {} {} — {}",
+            mark, capability.name, capability.description
+        );
+        if let Some(feature) = capability.feature.filter(|_| !capability.available) {
+            line.push_str(&format!("（需使用 --features {} 构建）", feature));
+        }
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capability(name: &str) -> Capability {
+        capabilities()
+            .into_iter()
+            .find(|capability| capability.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_builtin_capabilities_are_available() {
+        for name in ["stdin", "json", "batch", "csv"] {
+            assert!(capability(name).available, "{} should be available", name);
+        }
+    }
+
+    #[test]
+    fn test_gated_capabilities_follow_features() {
+        let log_level = capability("log-level");
+        assert_eq!(log_level.feature, Some("logging"));
+        assert_eq!(log_level.available, cfg!(feature = "logging"));
+
+        let report = report();
+        if cfg!(feature = "logging") {
+            assert!(report.contains("logging: 已启用"));
+            assert!(report.contains("✓ log-level"));
+        } else {
+            assert!(report.contains("logging: 未启用"));
+            assert!(report.contains("需使用 --features logging 构建"));
+        }
+    }
+
+    #[test]
+    fn test_long_version_lists_compiled_features() {
+        let version = long_version();
+        assert!(version.starts_with(&format!("v{}", env!("CARGO_PKG_VERSION"))));
+        for (feature, enabled) in compiled_features() {
+            assert_eq!(version.contains(feature), enabled, "{}", version);
+        }
+    }
+
+    /// 逐个只启用一个特性构建，检查特性之间没有遗漏的条件编译
+    ///
+    /// 需要多次完整构建，默认不运行：`cargo test -- --ignored each_feature_builds_alone`
+    #[test]
+    #[ignore]
+    fn each_feature_builds_alone() {
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let target_dir = std::path::Path::new(manifest_dir).join("target/feature-check");
+        let mut sets: Vec<String> = compiled_features()
+            .into_iter()
+            .map(|(feature, _)| feature.to_string())
+            .collect();
+        sets.push(String::new());
+
+        for features in sets {
+            let status = std::process::Command::new(env!("CARGO"))
+                .args([
+                    "check",
+                    "--all-targets",
+                    "--no-default-features",
+                    "--features",
+                ])
+                .arg(&features)
+                .arg("--target-dir")
+                .arg(&target_dir)
+                .current_dir(manifest_dir)
+                .status()
+                .unwrap();
+            assert!(status.success(), "仅启用特性 '{}' 时构建失败", features);
+        }
+    }
+}
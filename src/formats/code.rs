@@ -0,0 +1,476 @@
+//! 源代码格式处理：只翻译注释，代码与字符串字面量原样保留
+//!
+//! 使用轻量的词法扫描找出注释，不做完整的语法分析：跳过字符串字面量（含 Rust 原始字符串、
+//! Python 三引号字符串与 JavaScript 模板字符串），其余位置的行注释与块注释参与翻译。
+//! 行注释的注释符号（含 Rust 文档注释的 `///`、`//!`）保留，只翻译其后的文字；
+//! 块注释按行翻译，每行开头的 `*` 保留。不含字母或数字的注释（如分隔线）不翻译。
+//!
+//! 注释只能占一行时，译文中的换行替换为空格；块注释的译文中出现结束符号时插入空格，
+//! 避免提前结束注释。JavaScript 的正则表达式字面量不做识别。
+
+use std::ops::Range;
+
+use clap::ValueEnum;
+
+use super::Document;
+use crate::error::Result;
+
+/// 源代码的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    Rust,
+    Python,
+    /// JavaScript 与 TypeScript
+    Js,
+}
+
+impl Language {
+    /// 按文件扩展名推断语言
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" => Some(Language::Python),
+            "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" => Some(Language::Js),
+            _ => None,
+        }
+    }
+
+    fn syntax(self) -> Syntax {
+        match self {
+            Language::Rust => Syntax {
+                line_comment: "//",
+                block_comment: Some(("/*", "*/")),
+                quotes: &["\""],
+                rust: true,
+            },
+            Language::Python => Syntax {
+                line_comment: "#",
+                block_comment: None,
+                quotes: &["\"\"\"", "'''", "\"", "'"],
+                rust: false,
+            },
+            Language::Js => Syntax {
+                line_comment: "//",
+                block_comment: Some(("/*", "*/")),
+                quotes: &["\"", "'", "`"],
+                rust: false,
+            },
+        }
+    }
+}
+
+/// 一种语言的注释与字符串写法
+struct Syntax {
+    line_comment: &'static str,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// 字符串的引号，较长的在前
+    quotes: &'static [&'static str],
+    /// 是否识别 Rust 的原始字符串、字符字面量与嵌套块注释
+    rust: bool,
+}
+
+/// 待翻译的一段注释文字
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Span {
+    range: Range<usize>,
+    /// 所在的块注释的结束符号；行注释为 None
+    block_close: Option<&'static str>,
+}
+
+/// 解析后的源代码
+#[derive(Debug, Clone)]
+pub struct CodeDocument {
+    source: String,
+    /// 按位置排序、互不重叠，与片段一一对应
+    spans: Vec<Span>,
+}
+
+impl CodeDocument {
+    /// 解析源代码
+    pub fn parse(input: &str, language: Language) -> Self {
+        let syntax = language.syntax();
+        let bytes = input.as_bytes();
+        let mut spans = Vec::new();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let rest = &bytes[i..];
+
+            if rest.starts_with(syntax.line_comment.as_bytes()) {
+                let end = line_end(input, i);
+                let mut start = i + syntax.line_comment.len();
+                // 文档注释的标记（`///`、`//!`）保留
+                if syntax.rust && matches!(bytes.get(start), Some(b'/' | b'!')) {
+                    start += 1;
+                }
+                spans.extend(text_span(input, start..end, None));
+                i = end;
+                continue;
+            }
+
+            if let Some((open, close)) = syntax.block_comment
+                && rest.starts_with(open.as_bytes())
+            {
+                let (body_end, end) = block_end(input, i + open.len(), open, close, syntax.rust);
+                spans.extend(block_spans(input, i + open.len()..body_end, close));
+                i = end;
+                continue;
+            }
+
+            if syntax.rust {
+                if let Some(end) = raw_string_end(input, i) {
+                    i = end;
+                    continue;
+                }
+                if bytes[i] == b'\'' {
+                    i = char_literal_end(input, i);
+                    continue;
+                }
+            }
+
+            if let Some(quote) = syntax
+                .quotes
+                .iter()
+                .find(|quote| rest.starts_with(quote.as_bytes()))
+            {
+                let multiline = syntax.rust || quote.len() > 1 || *quote == "`";
+                i = string_end(input, i + quote.len(), quote, multiline);
+                continue;
+            }
+
+            i += 1;
+        }
+
+        Self {
+            source: input.to_string(),
+            spans,
+        }
+    }
+}
+
+impl Document for CodeDocument {
+    type Output = String;
+
+    /// 待翻译的注释文字（按在原文中的顺序）
+    fn segments(&self) -> Vec<String> {
+        self.spans
+            .iter()
+            .map(|span| self.source[span.range.clone()].to_string())
+            .collect()
+    }
+
+    /// 使用翻译结果重新生成源代码
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    fn render(&self, translations: &[Option<String>]) -> Result<String> {
+        let mut output = String::with_capacity(self.source.len());
+        let mut last = 0;
+        for (span, translation) in self.spans.iter().zip(translations) {
+            let Some(translation) = translation else {
+                continue;
+            };
+            output.push_str(&self.source[last..span.range.start]);
+            output.push_str(&single_line(translation, span.block_close));
+            last = span.range.end;
+        }
+        output.push_str(&self.source[last..]);
+        Ok(output)
+    }
+}
+
+/// 去掉首尾空白后的注释文字，不含字母或数字时不翻译
+fn text_span(input: &str, range: Range<usize>, block_close: Option<&'static str>) -> Option<Span> {
+    let text = &input[range.clone()];
+    // 结束位置由去掉开头空白后的文字计算，只含空白（如 CRLF 的 `\r`）时为空区间
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = start + text.trim().len();
+    input[start..end]
+        .chars()
+        .any(char::is_alphanumeric)
+        .then_some(Span {
+            range: start..end,
+            block_close,
+        })
+}
+
+/// 块注释按行拆分，每行开头的 `*` 不翻译
+fn block_spans(input: &str, body: Range<usize>, close: &'static str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut start = body.start;
+    while start < body.end {
+        let end = input[start..body.end]
+            .find('\n')
+            .map_or(body.end, |offset| start + offset);
+        let line = &input[start..end];
+        let content = line.trim_start();
+        let content_start = end - content.len();
+        let content_start = if content.starts_with('*') {
+            content_start + 1
+        } else {
+            content_start
+        };
+        spans.extend(text_span(input, content_start..end, Some(close)));
+        start = end + 1;
+    }
+    spans
+}
+
+/// 注释只能占一行：换行替换为空格，块注释中的结束符号插入空格
+fn single_line(translation: &str, block_close: Option<&str>) -> String {
+    let line = translation
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    match block_close {
+        Some(close) => {
+            let (head, tail) = close.split_at(1);
+            line.replace(close, &format!("{} {}", head, tail))
+        }
+        None => line,
+    }
+}
+
+/// 行尾位置（不含换行符）
+fn line_end(input: &str, from: usize) -> usize {
+    input[from..]
+        .find('\n')
+        .map_or(input.len(), |offset| from + offset)
+}
+
+/// 块注释的正文结束位置与整个注释的结束位置；Rust 的块注释可以嵌套
+fn block_end(input: &str, from: usize, open: &str, close: &str, nested: bool) -> (usize, usize) {
+    let bytes = input.as_bytes();
+    let mut depth = 1;
+    let mut i = from;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        if rest.starts_with(close.as_bytes()) {
+            depth -= 1;
+            if depth == 0 {
+                return (i, i + close.len());
+            }
+            i += close.len();
+        } else if nested && rest.starts_with(open.as_bytes()) {
+            depth += 1;
+            i += open.len();
+        } else {
+            i += 1;
+        }
+    }
+    (input.len(), input.len())
+}
+
+/// 跳过字符串字面量，返回结束引号之后的位置
+///
+/// `multiline` 为 false 时字符串在行尾结束；反斜杠转义下一个字符。
+fn string_end(input: &str, from: usize, quote: &str, multiline: bool) -> usize {
+    let bytes = input.as_bytes();
+    let mut i = from;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i..].starts_with(quote.as_bytes()) {
+            return i + quote.len();
+        }
+        if bytes[i] == b'\n' && !multiline {
+            return i;
+        }
+        i += 1;
+    }
+    input.len()
+}
+
+/// Rust 原始字符串（`r"..."`、`r#"..."#`、`br"..."`）的结束位置，不是原始字符串时为 None
+fn raw_string_end(input: &str, at: usize) -> Option<usize> {
+    let bytes = input.as_bytes();
+    if at > 0 && (bytes[at - 1].is_ascii_alphanumeric() || bytes[at - 1] == b'_') {
+        return None;
+    }
+    let mut i = at;
+    if bytes.get(i) == Some(&b'b') {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'r') {
+        return None;
+    }
+    i += 1;
+    let hashes = bytes[i..].iter().take_while(|&&b| b == b'#').count();
+    i += hashes;
+    if bytes.get(i) != Some(&b'"') {
+        return None;
+    }
+    let close = format!("\"{}", "#".repeat(hashes));
+    Some(
+        input[i + 1..]
+            .find(&close)
+            .map_or(input.len(), |offset| i + 1 + offset + close.len()),
+    )
+}
+
+/// Rust 字符字面量（`'a'`、`'\n'`）的结束位置；生命周期标注只跳过单引号
+fn char_literal_end(input: &str, at: usize) -> usize {
+    let rest = &input[at + 1..];
+    if let Some(escaped) = rest.strip_prefix('\\') {
+        // 跳过被转义的字符（可能就是单引号）后再找结束引号
+        let skip = escaped.chars().next().map_or(0, char::len_utf8);
+        return escaped[skip..]
+            .find('\'')
+            .map_or(input.len(), |offset| at + 2 + skip + offset + 1);
+    }
+    let mut chars = rest.char_indices();
+    match (chars.next(), chars.next()) {
+        (Some(_), Some((offset, '\''))) => at + 1 + offset + 1,
+        _ => at + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_translates_only_comments() {
+        let input = r##"//! 模块说明
+/// 计算总和
+fn sum<'a>(items: &'a [i32]) -> i32 {
+    let url = "https://example.com // 不是注释";
+    let quote = ['"', '\''];
+    let raw = r#"/* 也不是注释 */"#;
+    // 累加所有元素
+    items.iter().sum() // 返回结果
+}
+// ----------
+"##;
+        let document = CodeDocument::parse(input, Language::Rust);
+        assert_eq!(
+            document.segments(),
+            ["模块说明", "计算总和", "累加所有元素", "返回结果"]
+        );
+
+        let translations: Vec<Option<String>> = [
+            "Module docs",
+            "Computes the sum",
+            "Add up\nall items",
+            "Return the result",
+        ]
+        .iter()
+        .map(|s| Some(s.to_string()))
+        .collect();
+        assert_eq!(
+            document.render(&translations).unwrap(),
+            r##"//! Module docs
+/// Computes the sum
+fn sum<'a>(items: &'a [i32]) -> i32 {
+    let url = "https://example.com // 不是注释";
+    let quote = ['"', '\''];
+    let raw = r#"/* 也不是注释 */"#;
+    // Add up all items
+    items.iter().sum() // Return the result
+}
+// ----------
+"##
+        );
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let input = "/*\n * 第一行\n * 第二行\n */\nlet x = 1; /* 行内 */\n";
+        let document = CodeDocument::parse(input, Language::Js);
+        assert_eq!(document.segments(), ["第一行", "第二行", "行内"]);
+
+        let rendered = document
+            .render(&[
+                Some("first".to_string()),
+                None,
+                Some("ends */ early".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "/*\n * first\n * 第二行\n */\nlet x = 1; /* ends * / early */\n"
+        );
+    }
+
+    #[test]
+    fn test_python_skips_strings() {
+        let input = "s = '# 不是注释'\n\"\"\"文档字符串\n# 也不是\"\"\"\nx = 1  # 设置 x\n";
+        let document = CodeDocument::parse(input, Language::Python);
+        assert_eq!(document.segments(), ["设置 x"]);
+    }
+
+    #[test]
+    fn test_empty_comments_and_line_endings() {
+        // 只含 CRLF 的 `\r` 的空注释不翻译
+        for (input, language) in [
+            ("//\r\n", Language::Rust),
+            ("//\r\n", Language::Js),
+            ("#\r\n", Language::Python),
+            ("/*\r\n */\r\n", Language::Js),
+            ("//", Language::Rust),
+        ] {
+            let document = CodeDocument::parse(input, language);
+            assert!(document.segments().is_empty(), "{:?}", input);
+            assert_eq!(document.render(&[]).unwrap(), input);
+        }
+
+        // CRLF 换行保留在译文之后
+        let input = "# 设置 x\r\nx = 1  #  说明 \r\n";
+        let document = CodeDocument::parse(input, Language::Python);
+        assert_eq!(document.segments(), ["设置 x", "说明"]);
+        let rendered = document
+            .render(&[Some("set x".to_string()), Some("note".to_string())])
+            .unwrap();
+        assert_eq!(rendered, "# set x\r\nx = 1  #  note \r\n");
+
+        // 文件开头的 BOM 原样保留
+        for language in [Language::Rust, Language::Js] {
+            let input = "\u{feff}// 注释\r\nlet x = 1;\r\n";
+            let document = CodeDocument::parse(input, language);
+            assert_eq!(document.segments(), ["注释"]);
+            let rendered = document.render(&[Some("note".to_string())]).unwrap();
+            assert_eq!(rendered, "\u{feff}// note\r\nlet x = 1;\r\n");
+        }
+        let document = CodeDocument::parse("\u{feff}#\r\n", Language::Python);
+        assert!(document.segments().is_empty());
+    }
+
+    #[test]
+    fn test_language_from_extension() {
+        assert_eq!(Language::from_extension("rs"), Some(Language::Rust));
+        assert_eq!(Language::from_extension("TSX"), Some(Language::Js));
+        assert_eq!(Language::from_extension("md"), None);
+    }
+
+    #[tokio::test]
+    async fn test_refused_comment_keeps_source() {
+        use crate::batch::OnError;
+        use crate::formats::{SegmentOptions, uppercase_refusing};
+        use crate::warning::Warnings;
+
+        let input = "// first note\nlet x = 1; // refused\n/* last note */\n";
+        let document = CodeDocument::parse(input, Language::Rust);
+        let options = SegmentOptions {
+            on_error: OnError::KeepOriginal,
+            ..SegmentOptions::default()
+        };
+        let warnings = Warnings::new(true);
+        let translations = uppercase_refusing(document.segments(), "refused", &options, &warnings)
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        let rendered = document.render(&translations).unwrap();
+        assert_eq!(
+            rendered,
+            "// FIRST NOTE\nlet x = 1; // refused\n/* LAST NOTE */\n"
+        );
+        assert_eq!(
+            CodeDocument::parse(&rendered, Language::Rust).segments(),
+            ["FIRST NOTE", "refused", "LAST NOTE"]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! CSV 格式处理：仅翻译指定列的单元格
+
+use ::csv::{ReaderBuilder, StringRecord, Terminator, WriterBuilder};
+
+use super::Document;
+use crate::error::{Result, TransomeError};
+
+/// 解析后的 CSV 文档
+#[derive(Debug, Clone)]
+pub struct CsvDocument {
+    records: Vec<StringRecord>,
+    /// 待翻译单元格的位置 (行, 列)，与片段一一对应
+    cells: Vec<(usize, usize)>,
+    /// 译者注所在的列（从 1 开始）
+    note_column: Option<usize>,
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+impl CsvDocument {
+    /// 解析 CSV 文本
+    ///
+    /// `columns` 为从 1 开始的列号；`has_header` 为 true 时首行作为表头原样保留。
+    /// 空白单元格和超出行长度的列不参与翻译。
+    pub fn parse(input: &str, columns: &[usize], has_header: bool) -> Result<Self> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(input.as_bytes());
+
+        let records = reader
+            .records()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| TransomeError::format_error("CSV", e.to_string()))?;
+
+        let first_row = usize::from(has_header);
+        let mut cells = Vec::new();
+        for (row, record) in records.iter().enumerate().skip(first_row) {
+            for &column in columns {
+                let col = column - 1;
+                if record.get(col).is_some_and(|cell| !cell.trim().is_empty()) {
+                    cells.push((row, col));
+                }
+            }
+        }
+
+        Ok(Self {
+            records,
+            cells,
+            note_column: None,
+            crlf: input.contains("\r\n"),
+            trailing_newline: input.ends_with('\n'),
+        })
+    }
+
+    /// 设置译者注所在的列（从 1 开始），None 表示没有译者注
+    pub fn with_note_column(mut self, note_column: Option<usize>) -> Self {
+        self.note_column = note_column;
+        self
+    }
+}
+
+impl Document for CsvDocument {
+    type Output = String;
+
+    /// 待翻译的片段（按行优先顺序）
+    fn segments(&self) -> Vec<String> {
+        self.cells
+            .iter()
+            .map(|&(row, col)| self.records[row][col].to_string())
+            .collect()
+    }
+
+    /// 译者注取自同一行的 `with_note_column` 指定的列
+    ///
+    /// 该列为空白或超出行长度时为 None；未指定列时为空。
+    fn notes(&self) -> Vec<Option<String>> {
+        let Some(note_column) = self.note_column else {
+            return Vec::new();
+        };
+        self.cells
+            .iter()
+            .map(|&(row, _)| {
+                self.records[row]
+                    .get(note_column - 1)
+                    .map(str::trim)
+                    .filter(|note| !note.is_empty())
+                    .map(str::to_string)
+            })
+            .collect()
+    }
+
+    /// 使用翻译结果重新生成 CSV
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    fn render(&self, translations: &[Option<String>]) -> Result<String> {
+        let mut records = self.records.clone();
+        for (&(row, col), translation) in self.cells.iter().zip(translations) {
+            if let Some(text) = translation {
+                let mut fields: Vec<&str> = records[row].iter().collect();
+                fields[col] = text;
+                records[row] = StringRecord::from(fields);
+            }
+        }
+
+        let terminator = if self.crlf {
+            Terminator::CRLF
+        } else {
+            Terminator::Any(b'\n')
+        };
+        let mut writer = WriterBuilder::new()
+            .flexible(true)
+            .terminator(terminator)
+            .from_writer(Vec::new());
+
+        for record in &records {
+            writer
+                .write_record(record)
+                .map_err(|e| TransomeError::format_error("CSV", e.to_string()))?;
+        }
+
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| TransomeError::format_error("CSV", e.to_string()))?;
+        let mut output = String::from_utf8(bytes)
+            .map_err(|e| TransomeError::format_error("CSV", e.to_string()))?;
+
+        if !self.trailing_newline {
+            let trimmed_len = output.trim_end_matches(['\r', '\n']).len();
+            output.truncate(trimmed_len);
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uppercase_all(doc: &CsvDocument) -> Vec<Option<String>> {
+        doc.segments()
+            .into_iter()
+            .map(|s| Some(s.to_uppercase()))
+            .collect()
+    }
+
+    #[test]
+    fn test_translate_one_column_with_header() {
+        let input = "id,name,note\n1,apple,\"red, round\"\n2,banana,yellow\n";
+        let doc = CsvDocument::parse(input, &[3], true).unwrap();
+
+        assert_eq!(doc.segments(), vec!["red, round", "yellow"]);
+
+        let output = doc.render(&uppercase_all(&doc)).unwrap();
+        assert_eq!(
+            output,
+            "id,name,note\n1,apple,\"RED, ROUND\"\n2,banana,YELLOW\n"
+        );
+    }
+
+    #[test]
+    fn test_notes_from_column() {
+        let input = "key,text,comment\nsave,保存,\"button label, max 12 chars\"\nquit,退出,\n";
+        let doc = CsvDocument::parse(input, &[2], true).unwrap();
+
+        assert_eq!(doc.segments(), vec!["保存", "退出"]);
+        assert!(doc.notes().is_empty());
+        assert_eq!(
+            doc.clone().with_note_column(Some(3)).notes(),
+            vec![Some("button label, max 12 chars".to_string()), None]
+        );
+        assert_eq!(doc.with_note_column(Some(9)).notes(), vec![None, None]);
+    }
+
+    #[test]
+    fn test_without_header_first_row_is_translated() {
+        let doc = CsvDocument::parse("hello,1\nworld,2", &[1], false).unwrap();
+        assert_eq!(doc.segments(), vec!["hello", "world"]);
+
+        let output = doc.render(&uppercase_all(&doc)).unwrap();
+        assert_eq!(output, "HELLO,1\nWORLD,2");
+    }
+
+    #[test]
+    fn test_failed_cells_keep_original() {
+        let doc = CsvDocument::parse("a,b\nc,d\n", &[1, 2], false).unwrap();
+        assert_eq!(doc.segments(), vec!["a", "b", "c", "d"]);
+
+        let translations = vec![Some("A".to_string()), None, None, Some("D".to_string())];
+        let output = doc.render(&translations).unwrap();
+        assert_eq!(output, "A,b\nc,D\n");
+    }
+
+    #[test]
+    fn test_preserves_crlf_and_quotes_when_needed() {
+        let input = "name,desc\r\nx,\"say \"\"hi\"\"\"\r\n";
+        let doc = CsvDocument::parse(input, &[2], true).unwrap();
+        assert_eq!(doc.segments(), vec!["say \"hi\""]);
+
+        let output = doc.render(&[Some("说 \"你好\"".to_string())]).unwrap();
+        assert_eq!(output, "name,desc\r\nx,\"说 \"\"你好\"\"\"\r\n");
+    }
+
+    #[test]
+    fn test_skips_empty_and_missing_cells() {
+        let doc = CsvDocument::parse("a,,c\nd\n", &[2, 3], false).unwrap();
+        assert_eq!(doc.segments(), vec!["c"]);
+
+        let output = doc.render(&[Some("C".to_string())]).unwrap();
+        assert_eq!(output, "a,,C\nd\n");
+    }
+
+    #[tokio::test]
+    async fn test_refused_cell_keeps_source() {
+        use crate::batch::OnError;
+        use crate::formats::{SegmentOptions, uppercase_refusing};
+        use crate::warning::Warnings;
+
+        let input = "id,text\n1,\"Hello, world\"\n2,refused\n3,bye\n";
+        let doc = CsvDocument::parse(input, &[2], true).unwrap();
+        let options = SegmentOptions {
+            on_error: OnError::KeepOriginal,
+            ..SegmentOptions::default()
+        };
+        let warnings = Warnings::new(true);
+        let translations = uppercase_refusing(doc.segments(), "refused", &options, &warnings)
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        let output = doc.render(&translations).unwrap();
+        assert_eq!(output, "id,text\n1,\"HELLO, WORLD\"\n2,refused\n3,BYE\n");
+        let reparsed = CsvDocument::parse(&output, &[2], true).unwrap();
+        assert_eq!(reparsed.segments(), vec!["HELLO, WORLD", "refused", "BYE"]);
+    }
+}
@@ -0,0 +1,238 @@
+//! 按文件名与内容推断输入格式
+//!
+//! 未指定 `--format`（或指定 `--format auto`）时，`--file` 的格式先按扩展名判断，扩展名缺失
+//! 或无法识别时再检查文件开头 [`SNIFF_LIMIT`] 字节的内容：
+//!
+//! - 以 `{`，或以 `[` 后接 `{`、`[`、`"`、数字时为 JSON；
+//! - 以 `<!DOCTYPE html`、`<html` 开头，或 `<?xml` 声明后出现 `<html` 时为 HTML；
+//! - 以 front matter 分隔线 `---`、标题（`# `）开头，或含有代码块围栏时为 Markdown；
+//! - 除注释外每行都是 `键=值` 时为 kv（全部为 `大写_下划线` 形式的键）或 properties
+//!   （键中含 `.`），两者都不符合时无法区分，报错并列出候选格式。
+//!
+//! 其余内容按纯文本处理。内容检查只看开头，是不读取文件的纯函数（见 [`sniff`]）。
+
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use super::Format;
+use super::code::Language;
+
+/// 内容检查读取的字节数
+pub const SNIFF_LIMIT: usize = 8 * 1024;
+
+/// 内容检查的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Detection {
+    Detected(Format),
+    /// 内容符合多种格式，需要用 `--format` 指定
+    Ambiguous(Vec<Format>),
+    /// 没有可识别的特征，按纯文本处理
+    Unknown,
+}
+
+/// 按扩展名推断格式，`.env` 文件按文件名识别
+pub fn from_path(path: &Path) -> Option<Format> {
+    let name = path.file_name()?.to_str()?;
+    if name == ".env" || name.starts_with(".env.") {
+        return Some(Format::Kv);
+    }
+    let extension = path.extension()?.to_str()?;
+    let format = match extension.to_ascii_lowercase().as_str() {
+        "txt" | "text" => Format::Text,
+        "csv" => Format::Csv,
+        "md" | "markdown" => Format::Markdown,
+        "html" | "htm" | "xhtml" => Format::Html,
+        "docx" => Format::Docx,
+        "properties" => Format::Properties,
+        "env" => Format::Kv,
+        "json" => Format::Json,
+        _ if Language::from_extension(extension).is_some() => Format::Code,
+        _ => return None,
+    };
+    Some(format)
+}
+
+/// 按开头 [`SNIFF_LIMIT`] 字节的内容推断格式
+pub fn sniff(text: &str) -> Detection {
+    let mut end = text.len().min(SNIFF_LIMIT);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = text[..end].trim_start_matches('\u{feff}').trim_start();
+
+    if head.starts_with('{') || is_json_array(head) {
+        return Detection::Detected(Format::Json);
+    }
+
+    let lower = head.to_ascii_lowercase();
+    if lower.starts_with("<!doctype html")
+        || lower.starts_with("<html")
+        || (lower.starts_with("<?xml") && lower.contains("<html"))
+    {
+        return Detection::Detected(Format::Html);
+    }
+
+    if let Some(detection) = sniff_key_values(head) {
+        return detection;
+    }
+
+    let first_line = head.lines().next().unwrap_or_default().trim_end();
+    if first_line == "---"
+        || is_heading(first_line)
+        || head
+            .lines()
+            .any(|line| line.trim_start().starts_with("```"))
+    {
+        return Detection::Detected(Format::Markdown);
+    }
+
+    Detection::Unknown
+}
+
+/// `--format` 中格式的名称
+pub fn format_name(format: Format) -> String {
+    format.to_possible_value().map_or_else(
+        || format!("{:?}", format),
+        |value| value.get_name().to_string(),
+    )
+}
+
+/// 以 `[` 开头且第一个元素像 JSON 值；`[文字](链接)` 之类的 Markdown 不算
+fn is_json_array(head: &str) -> bool {
+    let Some(rest) = head.strip_prefix('[') else {
+        return false;
+    };
+    rest.trim_start()
+        .starts_with(|c: char| matches!(c, '{' | '[' | '"' | ']' | '-') || c.is_ascii_digit())
+}
+
+/// Markdown 的 ATX 标题：1 到 6 个 `#` 后接空格
+fn is_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    (1..=6).contains(&level) && line[level..].starts_with(' ')
+}
+
+/// 除空行与注释外每行都是 `键=值` 时判断为 kv 或 properties
+fn sniff_key_values(head: &str) -> Option<Detection> {
+    let keys: Vec<&str> = head
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '!']))
+        .map(|line| {
+            let (key, _) = line.split_once('=')?;
+            let key = key.trim_end();
+            let key = key.strip_prefix("export ").unwrap_or(key);
+            let valid = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+            valid.then_some(key)
+        })
+        .collect::<Option<_>>()?;
+    if keys.is_empty() {
+        return None;
+    }
+
+    let env_style = keys.iter().all(|key| {
+        key.starts_with(|c: char| c.is_ascii_uppercase() || c == '_')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+    });
+    let detection = if env_style {
+        Detection::Detected(Format::Kv)
+    } else if keys.iter().any(|key| key.contains('.')) {
+        Detection::Detected(Format::Properties)
+    } else {
+        Detection::Ambiguous(vec![Format::Properties, Format::Kv])
+    };
+    Some(detection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_fixture_heads() {
+        use Detection::*;
+        let cases: &[(&str, Detection)] = &[
+            ("{\"greeting\": \"Hello\"}", Detected(Format::Json)),
+            ("\u{feff}  [\n  {\"a\": 1}\n]", Detected(Format::Json)),
+            ("[1, 2, 3]", Detected(Format::Json)),
+            ("[链接](https://example.com) 开头的段落", Unknown),
+            (
+                "<!DOCTYPE html>\n<html><body>Hi</body></html>",
+                Detected(Format::Html),
+            ),
+            ("<html lang=\"en\">", Detected(Format::Html)),
+            (
+                "<?xml version=\"1.0\"?>\n<html xmlns=\"http://www.w3.org/1999/xhtml\">",
+                Detected(Format::Html),
+            ),
+            ("<?xml version=\"1.0\"?>\n<note>Hi</note>", Unknown),
+            ("---\ntitle: Hello\n---\n\nBody", Detected(Format::Markdown)),
+            ("# Title\n\nSome text.", Detected(Format::Markdown)),
+            (
+                "Intro\n\n```rust\nfn main() {}\n```",
+                Detected(Format::Markdown),
+            ),
+            ("#hashtag 不是标题", Unknown),
+            (
+                "# 数据库配置\nDATABASE_URL=postgres://localhost\nexport API_KEY=abc",
+                Detected(Format::Kv),
+            ),
+            (
+                "! 欢迎页\nwelcome.title=Hello\nwelcome.body = World",
+                Detected(Format::Properties),
+            ),
+            (
+                "greeting=Hello\nfarewell=Bye",
+                Ambiguous(vec![Format::Properties, Format::Kv]),
+            ),
+            // 没有对应的字幕格式，按纯文本处理
+            ("WEBVTT\n\n00:00.000 --> 00:01.000\nHello", Unknown),
+            ("1\n00:00:01,000 --> 00:00:02,000\nHello", Unknown),
+            ("Hello, world.\nx = 1 在正文中", Unknown),
+            ("   \n\n", Unknown),
+        ];
+        for (head, expected) in cases {
+            assert_eq!(&sniff(head), expected, "{:?}", head);
+        }
+    }
+
+    #[test]
+    fn test_sniff_reads_only_the_head() {
+        let mut text = "a".repeat(SNIFF_LIMIT - 1);
+        text.push('中');
+        text.push_str("\n# Title");
+        // 截断位置落在多字节字符中间时向前取整，开头之后的内容不参与判断
+        assert_eq!(sniff(&text), Detection::Unknown);
+        assert_eq!(
+            sniff(&format!("# Title\n{}", "a".repeat(SNIFF_LIMIT))),
+            Detection::Detected(Format::Markdown)
+        );
+    }
+
+    #[test]
+    fn test_from_path() {
+        let cases = [
+            ("notes.md", Some(Format::Markdown)),
+            ("DATA.JSON", Some(Format::Json)),
+            ("page.htm", Some(Format::Html)),
+            ("messages_zh.properties", Some(Format::Properties)),
+            (".env", Some(Format::Kv)),
+            (".env.production", Some(Format::Kv)),
+            ("main.rs", Some(Format::Code)),
+            ("report.docx", Some(Format::Docx)),
+            ("readme.txt", Some(Format::Text)),
+            ("README", None),
+            ("archive.tar.gz", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(from_path(Path::new(path)), expected, "{}", path);
+        }
+        assert_eq!(format_name(Format::Properties), "properties");
+    }
+}
@@ -0,0 +1,179 @@
+//! 差量翻译：源文档修改后仅重新翻译变化的段落
+//!
+//! 文档按空行拆分为段落。旧原文与旧译文的段落一一对应，
+//! 以段落原文的哈希为键查找旧译文；新原文中未变化的段落直接复用，
+//! 新增或修改的段落作为待翻译片段。
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::error::{Result, TransomeError};
+
+/// 新文档中的单个段落
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Paragraph {
+    /// 原文未变化，复用旧译文
+    Reused(String),
+    /// 需要重新翻译的原文
+    Pending(String),
+}
+
+/// 与旧版本对齐后的文档
+#[derive(Debug, Clone)]
+pub struct DiffDocument {
+    paragraphs: Vec<Paragraph>,
+}
+
+/// 将文本按空行拆分为段落，去除首尾空白并忽略空段落
+pub fn split_paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n").trim().to_string());
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n").trim().to_string());
+    }
+
+    paragraphs
+}
+
+/// 段落原文的哈希，忽略首尾空白
+fn paragraph_hash(paragraph: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    paragraph.trim().hash(&mut hasher);
+    hasher.finish()
+}
+
+impl DiffDocument {
+    /// 将新原文与旧原文、旧译文对齐
+    ///
+    /// 旧原文与旧译文的段落数必须一致，否则无法确定对应关系。
+    pub fn align(old_source: &str, old_translation: &str, new_source: &str) -> Result<Self> {
+        let old_paragraphs = split_paragraphs(old_source);
+        let old_translations = split_paragraphs(old_translation);
+
+        if old_paragraphs.len() != old_translations.len() {
+            return Err(TransomeError::format_error(
+                "差量翻译",
+                format!(
+                    "旧原文有 {} 个段落，旧译文有 {} 个段落，无法对应",
+                    old_paragraphs.len(),
+                    old_translations.len()
+                ),
+            ));
+        }
+
+        // 重复段落以首次出现的译文为准
+        let mut known: HashMap<u64, String> = HashMap::new();
+        for (source, translation) in old_paragraphs.iter().zip(old_translations) {
+            known.entry(paragraph_hash(source)).or_insert(translation);
+        }
+
+        let paragraphs = split_paragraphs(new_source)
+            .into_iter()
+            .map(|paragraph| match known.get(&paragraph_hash(&paragraph)) {
+                Some(translation) => Paragraph::Reused(translation.clone()),
+                None => Paragraph::Pending(paragraph),
+            })
+            .collect();
+
+        Ok(Self { paragraphs })
+    }
+
+    /// 需要重新翻译的片段（按文档顺序）
+    pub fn segments(&self) -> Vec<String> {
+        self.paragraphs
+            .iter()
+            .filter_map(|paragraph| match paragraph {
+                Paragraph::Pending(source) => Some(source.clone()),
+                Paragraph::Reused(_) => None,
+            })
+            .collect()
+    }
+
+    /// 复用旧译文的段落数
+    pub fn reused(&self) -> usize {
+        self.paragraphs
+            .iter()
+            .filter(|paragraph| matches!(paragraph, Paragraph::Reused(_)))
+            .count()
+    }
+
+    /// 将复用的译文与新翻译结果按原顺序拼接，段落之间以空行分隔
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    pub fn render(&self, translations: &[Option<String>]) -> String {
+        let mut translations = translations.iter();
+
+        self.paragraphs
+            .iter()
+            .map(|paragraph| match paragraph {
+                Paragraph::Reused(translation) => translation.clone(),
+                Paragraph::Pending(source) => translations
+                    .next()
+                    .and_then(Option::clone)
+                    .unwrap_or_else(|| source.clone()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_SOURCE: &str =
+        "# Title\n\nFirst paragraph.\n\nSecond paragraph.\n\nThird paragraph.\n";
+    const OLD_TRANSLATION: &str = "# 标题\n\n第一段。\n\n第二段。\n\n第三段。\n";
+
+    #[test]
+    fn test_split_paragraphs() {
+        assert_eq!(
+            split_paragraphs("a\nb\n\n\n  c  \n\n"),
+            vec!["a\nb".to_string(), "c".to_string()]
+        );
+        assert!(split_paragraphs("\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_only_changed_paragraph_is_pending() {
+        let new_source =
+            "# Title\n\nFirst paragraph.\n\nSecond paragraph, edited.\n\nThird paragraph.\n";
+        let document = DiffDocument::align(OLD_SOURCE, OLD_TRANSLATION, new_source).unwrap();
+
+        assert_eq!(document.segments(), vec!["Second paragraph, edited."]);
+        assert_eq!(document.reused(), 3);
+        assert_eq!(
+            document.render(&[Some("第二段（已修改）。".to_string())]),
+            "# 标题\n\n第一段。\n\n第二段（已修改）。\n\n第三段。"
+        );
+    }
+
+    #[test]
+    fn test_added_paragraph_and_failed_translation() {
+        let new_source = "# Title\n\nNew intro.\n\nFirst paragraph.";
+        let document = DiffDocument::align(OLD_SOURCE, OLD_TRANSLATION, new_source).unwrap();
+
+        assert_eq!(document.segments(), vec!["New intro."]);
+        assert_eq!(document.reused(), 2);
+        assert_eq!(document.render(&[None]), "# 标题\n\nNew intro.\n\n第一段。");
+    }
+
+    #[test]
+    fn test_mismatched_paragraph_counts() {
+        let result = DiffDocument::align(OLD_SOURCE, "# 标题\n\n第一段。", OLD_SOURCE);
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("无法对应"));
+    }
+}
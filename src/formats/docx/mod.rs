@@ -0,0 +1,490 @@
+//! Word 文档（docx）格式处理：只翻译正文中的文字，保留格式、表格与超链接
+//!
+//! docx 是一个 zip 归档，正文位于 `word/document.xml`。每个段落（`<w:p>`）作为一个片段，
+//! 段落中因格式不同被拆成多个文字块（`<w:t>`）的内容合并后翻译；译文整体写入第一个
+//! 文字块，其余文字块清空。这样各文字块的格式属性、表格结构与超链接关系都原样保留，
+//! 只是段落内局部的格式（如加粗的单词）会沿用第一个文字块的格式。
+//!
+//! 只替换文字块的内容，文档的其余部分逐字节保留；其他条目（样式、图片、关系等）原样复制。
+//! 内容控件（`<w:sdt>`）与域（页码、目录、`<w:fldSimple>` 等）的显示内容从不翻译。
+
+mod zip;
+
+use std::ops::Range;
+
+use self::zip::Archive;
+use super::{Document, has_words, with_surrounding_whitespace};
+use crate::error::{Result, TransomeError};
+
+/// 正文在归档中的位置
+const DOCUMENT_PART: &str = "word/document.xml";
+
+/// 段落中的一个文字块（`<w:t>`）
+#[derive(Debug, Clone)]
+struct TextRun {
+    /// 开始标签在 XML 中的范围
+    open_tag: Range<usize>,
+    /// 内容在 XML 中的范围
+    content: Range<usize>,
+}
+
+/// 待翻译的段落
+#[derive(Debug, Clone)]
+struct Paragraph {
+    runs: Vec<TextRun>,
+    /// 各文字块合并后的原文
+    text: String,
+}
+
+/// 解析后的 docx 文档
+#[derive(Debug, Clone)]
+pub struct DocxDocument {
+    archive: Archive,
+    xml: String,
+    /// 与片段一一对应
+    paragraphs: Vec<Paragraph>,
+}
+
+impl DocxDocument {
+    /// 解析 docx 文件的内容
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let archive = Archive::read(bytes)?;
+        let xml = archive
+            .get(DOCUMENT_PART)?
+            .ok_or_else(|| invalid(format!("缺少 {}，文件可能不是 Word 文档", DOCUMENT_PART)))?;
+        let xml = String::from_utf8(xml)
+            .map_err(|_| invalid(format!("{} 不是 UTF-8 编码", DOCUMENT_PART)))?;
+
+        let paragraphs = scan(&xml)?
+            .into_iter()
+            .filter_map(|runs| {
+                let text: String = runs
+                    .iter()
+                    .map(|run| unescape(&xml[run.content.clone()]))
+                    .collect();
+                has_words(&text).then_some(Paragraph { runs, text })
+            })
+            .collect();
+
+        Ok(Self {
+            archive,
+            xml,
+            paragraphs,
+        })
+    }
+}
+
+impl Document for DocxDocument {
+    type Output = Vec<u8>;
+
+    /// 待翻译的片段（按文档顺序，去掉首尾空白）
+    fn segments(&self) -> Vec<String> {
+        self.paragraphs
+            .iter()
+            .map(|paragraph| paragraph.text.trim().to_string())
+            .collect()
+    }
+
+    /// 使用翻译结果重新打包 docx
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    fn render(&self, translations: &[Option<String>]) -> Result<Vec<u8>> {
+        let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+        for (paragraph, translation) in self.paragraphs.iter().zip(translations) {
+            let Some(translation) = translation else {
+                continue;
+            };
+            let text = with_surrounding_whitespace(&paragraph.text, translation);
+
+            for (index, run) in paragraph.runs.iter().enumerate() {
+                if index > 0 {
+                    replacements.push((run.content.clone(), String::new()));
+                    continue;
+                }
+                // 译文首尾可能有空白，需要声明保留，否则 Word 会将其丢弃
+                let open_tag = &self.xml[run.open_tag.clone()];
+                if !open_tag.contains("xml:space") {
+                    let name_end = open_tag.len() - 1;
+                    replacements.push((
+                        run.open_tag.clone(),
+                        format!("{} xml:space=\"preserve\">", &open_tag[..name_end]),
+                    ));
+                }
+                replacements.push((run.content.clone(), escape(&text)));
+            }
+        }
+        replacements.sort_by_key(|(range, _)| range.start);
+
+        let mut xml = String::with_capacity(self.xml.len());
+        let mut last = 0;
+        for (range, replacement) in replacements {
+            xml.push_str(&self.xml[last..range.start]);
+            xml.push_str(&replacement);
+            last = range.end;
+        }
+        xml.push_str(&self.xml[last..]);
+
+        let mut archive = self.archive.clone();
+        archive.replace(DOCUMENT_PART, xml.as_bytes())?;
+        archive.write()
+    }
+}
+
+/// 扫描正文，返回各段落中需要翻译的文字块（按段落在文档中的顺序）
+///
+/// 文本框中的段落嵌套在外层段落内，单独作为段落处理。
+fn scan(xml: &str) -> Result<Vec<Vec<TextRun>>> {
+    let mut paragraphs = Vec::new();
+    // 尚未结束的段落，内层在后
+    let mut open: Vec<Vec<TextRun>> = Vec::new();
+    let mut content_controls = 0usize;
+    let mut simple_fields = 0usize;
+    let mut complex_fields = 0usize;
+
+    let mut pos = 0;
+    while let Some(offset) = xml[pos..].find('<') {
+        let start = pos + offset;
+        let end = tag_end(xml, start)?;
+        let tag = &xml[start..end];
+        pos = end;
+
+        let closing = tag.starts_with("</");
+        let self_closing = tag.ends_with("/>");
+        let depth_change = |depth: &mut usize| {
+            if closing {
+                *depth = depth.saturating_sub(1);
+            } else if !self_closing {
+                *depth += 1;
+            }
+        };
+
+        match tag_name(tag) {
+            "w:p" if closing => {
+                if let Some(runs) = open.pop()
+                    && !runs.is_empty()
+                {
+                    paragraphs.push(runs);
+                }
+            }
+            "w:p" if !self_closing => open.push(Vec::new()),
+            "w:sdt" => depth_change(&mut content_controls),
+            "w:fldSimple" => depth_change(&mut simple_fields),
+            "w:fldChar" => match attr(tag, "w:fldCharType") {
+                Some("begin") => complex_fields += 1,
+                Some("end") => complex_fields = complex_fields.saturating_sub(1),
+                _ => {}
+            },
+            "w:t" if !closing && !self_closing => {
+                let length = xml[end..]
+                    .find("</w:t>")
+                    .ok_or_else(|| invalid("<w:t> 没有结束标签"))?;
+                let content = end..end + length;
+                pos = content.end + "</w:t>".len();
+
+                let protected = content_controls > 0 || simple_fields > 0 || complex_fields > 0;
+                if !protected && let Some(runs) = open.last_mut() {
+                    runs.push(TextRun {
+                        open_tag: start..end,
+                        content,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    paragraphs.sort_by_key(|runs| runs[0].content.start);
+    Ok(paragraphs)
+}
+
+/// 从 `start` 处的 `<` 开始的标记（标签、注释、处理指令或 CDATA）的结束位置
+fn tag_end(xml: &str, start: usize) -> Result<usize> {
+    let rest = &xml[start..];
+    for (open, close) in [("<!--", "-->"), ("<![CDATA[", "]]>"), ("<?", "?>")] {
+        if rest.starts_with(open) {
+            return rest
+                .find(close)
+                .map(|end| start + end + close.len())
+                .ok_or_else(|| invalid(format!("{} 没有结束", open)));
+        }
+    }
+
+    // 属性值中可能出现 `>`，跳过引号内的内容
+    let mut quote = None;
+    for (index, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Ok(start + index + 1),
+            _ => {}
+        }
+    }
+    Err(invalid(format!("{} 中有未结束的标签", DOCUMENT_PART)))
+}
+
+/// 标签名（含命名空间前缀）
+fn tag_name(tag: &str) -> &str {
+    let tag = tag.trim_start_matches('<').trim_start_matches('/');
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+/// 标签中指定属性的值
+fn attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let after = &rest[index + name.len()..];
+        let preceded_by_space = rest[..index].ends_with(char::is_whitespace);
+        if preceded_by_space && let Some(value) = after.trim_start().strip_prefix('=') {
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            let value = &value[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        rest = after;
+    }
+    None
+}
+
+/// 还原 XML 转义的字符
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 转义文字块内容中的 XML 特殊字符
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn invalid(message: impl Into<String>) -> TransomeError {
+    TransomeError::format_error("DOCX", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+
+    const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+    const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId5" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/?a=1&amp;b=2" TargetMode="External"/></Relationships>"#;
+
+    fn document(body: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+            <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" \
+            xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+            <w:body>{}<w:sectPr/></w:body></w:document>",
+            body
+        )
+    }
+
+    fn docx(body: &str) -> Vec<u8> {
+        let document = document(body);
+        Archive::from_files(&[
+            ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+            ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+            ("word/document.xml", document.as_bytes()),
+            ("word/_rels/document.xml.rels", DOCUMENT_RELS.as_bytes()),
+        ])
+        .write()
+        .unwrap()
+    }
+
+    fn uppercase_all(doc: &DocxDocument) -> Vec<u8> {
+        let translations: Vec<Option<String>> = doc
+            .segments()
+            .into_iter()
+            .map(|s| Some(s.to_uppercase()))
+            .collect();
+        doc.render(&translations).unwrap()
+    }
+
+    fn document_xml(bytes: &[u8]) -> String {
+        let archive = Archive::read(bytes).unwrap();
+        String::from_utf8(archive.get(DOCUMENT_PART).unwrap().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_keeps_formatting_tables_and_links() {
+        let body = "<w:p><w:pPr><w:pStyle w:val=\"Heading1\"/></w:pPr>\
+            <w:r><w:t xml:space=\"preserve\">Hello </w:t></w:r>\
+            <w:r><w:rPr><w:b/></w:rPr><w:t>wor</w:t></w:r>\
+            <w:r><w:rPr><w:i/></w:rPr><w:t>ld &amp; more.</w:t></w:r></w:p>\
+            <w:tbl><w:tblPr><w:tblW w:w=\"0\" w:type=\"auto\"/></w:tblPr>\
+            <w:tr><w:tc><w:p><w:r><w:t>Cell</w:t></w:r></w:p></w:tc>\
+            <w:tc><w:p><w:r><w:t>42</w:t></w:r></w:p></w:tc></w:tr></w:tbl>\
+            <w:p><w:r><w:t xml:space=\"preserve\">See </w:t></w:r>\
+            <w:hyperlink r:id=\"rId5\"><w:r><w:rPr><w:rStyle w:val=\"Hyperlink\"/></w:rPr>\
+            <w:t>the site</w:t></w:r></w:hyperlink></w:p>";
+        let bytes = docx(body);
+        let doc = DocxDocument::parse(&bytes).unwrap();
+
+        assert_eq!(
+            doc.segments(),
+            ["Hello world & more.", "Cell", "42", "See the site"]
+        );
+
+        let output = uppercase_all(&doc);
+        let expected = document(
+            "<w:p><w:pPr><w:pStyle w:val=\"Heading1\"/></w:pPr>\
+            <w:r><w:t xml:space=\"preserve\">HELLO WORLD &amp; MORE.</w:t></w:r>\
+            <w:r><w:rPr><w:b/></w:rPr><w:t></w:t></w:r>\
+            <w:r><w:rPr><w:i/></w:rPr><w:t></w:t></w:r></w:p>\
+            <w:tbl><w:tblPr><w:tblW w:w=\"0\" w:type=\"auto\"/></w:tblPr>\
+            <w:tr><w:tc><w:p><w:r><w:t xml:space=\"preserve\">CELL</w:t></w:r></w:p></w:tc>\
+            <w:tc><w:p><w:r><w:t xml:space=\"preserve\">42</w:t></w:r></w:p></w:tc></w:tr></w:tbl>\
+            <w:p><w:r><w:t xml:space=\"preserve\">SEE THE SITE</w:t></w:r>\
+            <w:hyperlink r:id=\"rId5\"><w:r><w:rPr><w:rStyle w:val=\"Hyperlink\"/></w:rPr>\
+            <w:t></w:t></w:r></w:hyperlink></w:p>",
+        );
+        assert_eq!(document_xml(&output), expected);
+
+        // 重新打包后仍是有效的 docx，其他条目原样保留
+        let archive = Archive::read(&output).unwrap();
+        assert_eq!(
+            archive.names(),
+            [
+                "[Content_Types].xml",
+                "_rels/.rels",
+                "word/document.xml",
+                "word/_rels/document.xml.rels"
+            ]
+        );
+        assert_eq!(
+            archive
+                .get("word/_rels/document.xml.rels")
+                .unwrap()
+                .unwrap(),
+            DOCUMENT_RELS.as_bytes()
+        );
+        assert_eq!(
+            DocxDocument::parse(&output).unwrap().segments(),
+            ["HELLO WORLD & MORE.", "CELL", "42", "SEE THE SITE"]
+        );
+    }
+
+    #[test]
+    fn test_fields_and_content_controls_are_skipped() {
+        let body = "<w:p><w:r><w:t xml:space=\"preserve\">Page </w:t></w:r>\
+            <w:r><w:fldChar w:fldCharType=\"begin\"/></w:r>\
+            <w:r><w:instrText xml:space=\"preserve\"> PAGE </w:instrText></w:r>\
+            <w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>\
+            <w:r><w:t>one</w:t></w:r>\
+            <w:r><w:fldChar w:fldCharType=\"end\"/></w:r></w:p>\
+            <w:p><w:fldSimple w:instr=\" DATE \"><w:r><w:t>Today</w:t></w:r></w:fldSimple></w:p>\
+            <w:sdt><w:sdtPr><w:alias w:val=\"Name\"/></w:sdtPr><w:sdtContent>\
+            <w:p><w:r><w:t>Client name</w:t></w:r></w:p></w:sdtContent></w:sdt>\
+            <w:p><w:r><w:t>Signed</w:t></w:r></w:p>";
+        let doc = DocxDocument::parse(&docx(body)).unwrap();
+        assert_eq!(doc.segments(), ["Page", "Signed"]);
+
+        let xml = document_xml(&uppercase_all(&doc));
+        assert!(
+            xml.contains("<w:t xml:space=\"preserve\">PAGE </w:t>"),
+            "{}",
+            xml
+        );
+        assert!(xml.contains("<w:t>one</w:t>"), "{}", xml);
+        assert!(xml.contains("<w:t>Today</w:t>"), "{}", xml);
+        assert!(xml.contains("<w:t>Client name</w:t>"), "{}", xml);
+        assert!(xml.contains(">SIGNED</w:t>"), "{}", xml);
+    }
+
+    #[test]
+    fn test_failed_segments_keep_original() {
+        let body = "<w:p><w:r><w:t>First</w:t></w:r></w:p>\
+            <w:p><w:r><w:t>Second</w:t></w:r><w:r><w:t>part</w:t></w:r></w:p>\
+            <w:p><w:r><w:t>   </w:t></w:r></w:p>";
+        let bytes = docx(body);
+        let doc = DocxDocument::parse(&bytes).unwrap();
+        assert_eq!(doc.segments(), ["First", "Secondpart"]);
+
+        let output = doc.render(&[Some("Erste".to_string()), None]).unwrap();
+        assert_eq!(
+            document_xml(&output),
+            document(
+                "<w:p><w:r><w:t xml:space=\"preserve\">Erste</w:t></w:r></w:p>\
+                <w:p><w:r><w:t>Second</w:t></w:r><w:r><w:t>part</w:t></w:r></w:p>\
+                <w:p><w:r><w:t>   </w:t></w:r></w:p>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_text_box_paragraphs_are_separate() {
+        let body = "<w:p><w:r><w:t xml:space=\"preserve\">Before </w:t></w:r>\
+            <w:r><w:pict><v:shape><v:textbox><w:txbxContent>\
+            <w:p><w:r><w:t>Inside</w:t></w:r></w:p>\
+            </w:txbxContent></v:textbox></v:shape></w:pict></w:r>\
+            <w:r><w:t>after</w:t></w:r></w:p>";
+        let doc = DocxDocument::parse(&docx(body)).unwrap();
+        assert_eq!(doc.segments(), ["Before after", "Inside"]);
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(
+            unescape("a &lt;b&gt; &amp; &#39;c&#x27; &unknown; &"),
+            "a <b> & 'c' &unknown; &"
+        );
+        assert_eq!(escape("<a> & b"), "&lt;a&gt; &amp; b");
+        assert_eq!(
+            attr("<w:fldChar w:fldCharType='end'/>", "w:fldCharType"),
+            Some("end")
+        );
+        assert_eq!(tag_name("</w:p>"), "w:p");
+        assert_eq!(tag_name("<w:pPr>"), "w:pPr");
+    }
+
+    #[test]
+    fn test_rejects_archive_without_document() {
+        let bytes = Archive::from_files(&[("content.xml", b"<office/>")])
+            .write()
+            .unwrap();
+        let error = DocxDocument::parse(&bytes).unwrap_err();
+        assert!(error.to_string().contains("word/document.xml"), "{}", error);
+    }
+}
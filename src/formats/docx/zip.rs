@@ -0,0 +1,495 @@
+//! 读写 docx 所需的最小 zip 实现
+//!
+//! 只支持单卷、非 zip64 的归档，条目以存储（0）或 deflate（8）方式压缩，足以处理
+//! Word 与 LibreOffice 生成的文档。重新打包时未修改的条目直接复制压缩后的数据，
+//! 保持原有的顺序、压缩方式、时间戳、扩展字段、属性与注释。
+//!
+//! 条目头中的大小来自不可信的输入，只用于校验：解压时最多读取 [`MAX_ENTRY_SIZE`] 字节。
+
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use crate::error::{Result, TransomeError};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const END_OF_CENTRAL_DIRECTORY_LEN: usize = 22;
+const LOCAL_HEADER_LEN: usize = 30;
+const CENTRAL_HEADER_LEN: usize = 46;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+/// 通用标志中表示文件名为 UTF-8 的位，重新打包时只保留这一位
+const FLAG_UTF8: u16 = 0x0800;
+/// 写出的条目所需的版本（2.0，支持 deflate）
+const VERSION: u16 = 20;
+/// 解压后单个条目的大小上限，防止压缩炸弹耗尽内存
+pub const MAX_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 归档中的一个条目
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    time: u16,
+    date: u16,
+    crc: u32,
+    size: u32,
+    /// 创建条目的系统与版本
+    version_made_by: u16,
+    internal_attributes: u16,
+    /// 文件权限等外部属性
+    external_attributes: u32,
+    /// 本地文件头中的扩展字段
+    local_extra: Vec<u8>,
+    /// 中央目录中的扩展字段
+    central_extra: Vec<u8>,
+    comment: Vec<u8>,
+    /// 压缩后的数据
+    data: Vec<u8>,
+}
+
+/// 解析后的 zip 归档
+#[derive(Debug, Clone)]
+pub struct Archive {
+    entries: Vec<Entry>,
+}
+
+impl Archive {
+    /// 解析 zip 归档
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        let end = find_end_of_central_directory(bytes)
+            .ok_or_else(|| invalid("找不到 zip 目录，文件可能不是 docx 或已损坏"))?;
+        let count = u16_at(bytes, end + 10)?;
+        let mut pos = u32_at(bytes, end + 16)? as usize;
+
+        let mut entries = Vec::with_capacity(count.into());
+        for _ in 0..count {
+            if u32_at(bytes, pos)? != CENTRAL_HEADER_SIGNATURE {
+                return Err(invalid("zip 目录项损坏"));
+            }
+            let version_made_by = u16_at(bytes, pos + 4)?;
+            let flags = u16_at(bytes, pos + 8)?;
+            let method = u16_at(bytes, pos + 10)?;
+            let time = u16_at(bytes, pos + 12)?;
+            let date = u16_at(bytes, pos + 14)?;
+            let crc = u32_at(bytes, pos + 16)?;
+            let compressed_size = u32_at(bytes, pos + 20)?;
+            let size = u32_at(bytes, pos + 24)?;
+            let name_len = usize::from(u16_at(bytes, pos + 28)?);
+            let extra_len = usize::from(u16_at(bytes, pos + 30)?);
+            let comment_len = usize::from(u16_at(bytes, pos + 32)?);
+            let internal_attributes = u16_at(bytes, pos + 36)?;
+            let external_attributes = u32_at(bytes, pos + 38)?;
+            let offset = u32_at(bytes, pos + 42)?;
+
+            if [compressed_size, size, offset].contains(&u32::MAX) {
+                return Err(invalid("不支持 zip64 格式的文档"));
+            }
+            if flags & 0x0001 != 0 {
+                return Err(invalid("不支持加密的文档"));
+            }
+
+            let name = slice(bytes, pos + CENTRAL_HEADER_LEN, name_len)?;
+            let name = String::from_utf8_lossy(name).into_owned();
+            let central_extra = slice(bytes, pos + CENTRAL_HEADER_LEN + name_len, extra_len)?;
+            let comment = slice(
+                bytes,
+                pos + CENTRAL_HEADER_LEN + name_len + extra_len,
+                comment_len,
+            )?;
+
+            let local = offset as usize;
+            if u32_at(bytes, local)? != LOCAL_HEADER_SIGNATURE {
+                return Err(invalid(format!("条目 '{}' 的文件头损坏", name)));
+            }
+            let local_name_len = usize::from(u16_at(bytes, local + 26)?);
+            let local_extra_len = usize::from(u16_at(bytes, local + 28)?);
+            let local_extra = slice(
+                bytes,
+                local + LOCAL_HEADER_LEN + local_name_len,
+                local_extra_len,
+            )?;
+            let data_start = local + LOCAL_HEADER_LEN + local_name_len + local_extra_len;
+            let data = slice(bytes, data_start, compressed_size as usize)?.to_vec();
+
+            entries.push(Entry {
+                name,
+                flags,
+                method,
+                time,
+                date,
+                crc,
+                size,
+                version_made_by,
+                internal_attributes,
+                external_attributes,
+                local_extra: local_extra.to_vec(),
+                central_extra: central_extra.to_vec(),
+                comment: comment.to_vec(),
+                data,
+            });
+            pos += CENTRAL_HEADER_LEN + name_len + extra_len + comment_len;
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// 读取并解压指定条目，条目不存在时返回 None
+    ///
+    /// 解压后超过 [`MAX_ENTRY_SIZE`] 或与条目头中记录的大小不符时返回错误。
+    pub fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let Some(entry) = self.entries.iter().find(|entry| entry.name == name) else {
+            return Ok(None);
+        };
+
+        let content = match entry.method {
+            METHOD_STORED => entry.data.clone(),
+            METHOD_DEFLATE => inflate(name, &entry.data, MAX_ENTRY_SIZE)?,
+            method => {
+                return Err(invalid(format!(
+                    "条目 '{}' 使用了不支持的压缩方式 {}，只支持存储（0）与 deflate（8）",
+                    name, method
+                )));
+            }
+        };
+
+        if content.len() as u64 != u64::from(entry.size) {
+            return Err(invalid(format!("条目 '{}' 的大小与记录不符", name)));
+        }
+        if crc32(&content) != entry.crc {
+            return Err(invalid(format!("条目 '{}' 校验失败", name)));
+        }
+        Ok(Some(content))
+    }
+
+    /// 替换指定条目的内容，以 deflate 方式重新压缩
+    pub fn replace(&mut self, name: &str, content: &[u8]) -> Result<()> {
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.name == name) else {
+            return Err(invalid(format!("找不到条目 '{}'", name)));
+        };
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(content)
+            .map_err(|e| invalid(format!("无法压缩 '{}'：{}", name, e)))?;
+        entry.data = encoder
+            .finish()
+            .map_err(|e| invalid(format!("无法压缩 '{}'：{}", name, e)))?;
+        entry.method = METHOD_DEFLATE;
+        entry.crc = crc32(content);
+        entry.size = u32::try_from(content.len()).map_err(|_| invalid("文档过大"))?;
+        Ok(())
+    }
+
+    /// 重新打包为 zip 归档
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+
+        for entry in &self.entries {
+            let offset = u32::try_from(out.len()).map_err(|_| invalid("文档过大"))?;
+            let compressed_size =
+                u32::try_from(entry.data.len()).map_err(|_| invalid("文档过大"))?;
+            let name = entry.name.as_bytes();
+            let name_len = u16::try_from(name.len()).map_err(|_| invalid("条目名称过长"))?;
+            // 读取时长度来自两字节的字段，不会超出
+            let local_extra_len = entry.local_extra.len() as u16;
+            let central_extra_len = entry.central_extra.len() as u16;
+            let comment_len = entry.comment.len() as u16;
+            // 写出时大小已知，不需要数据描述符
+            let flags = entry.flags & FLAG_UTF8;
+
+            put_u32(&mut out, LOCAL_HEADER_SIGNATURE);
+            put_u16(&mut out, VERSION);
+            put_u16(&mut out, flags);
+            put_u16(&mut out, entry.method);
+            put_u16(&mut out, entry.time);
+            put_u16(&mut out, entry.date);
+            put_u32(&mut out, entry.crc);
+            put_u32(&mut out, compressed_size);
+            put_u32(&mut out, entry.size);
+            put_u16(&mut out, name_len);
+            put_u16(&mut out, local_extra_len);
+            out.extend_from_slice(name);
+            out.extend_from_slice(&entry.local_extra);
+            out.extend_from_slice(&entry.data);
+
+            put_u32(&mut central, CENTRAL_HEADER_SIGNATURE);
+            put_u16(&mut central, entry.version_made_by);
+            put_u16(&mut central, VERSION);
+            put_u16(&mut central, flags);
+            put_u16(&mut central, entry.method);
+            put_u16(&mut central, entry.time);
+            put_u16(&mut central, entry.date);
+            put_u32(&mut central, entry.crc);
+            put_u32(&mut central, compressed_size);
+            put_u32(&mut central, entry.size);
+            put_u16(&mut central, name_len);
+            put_u16(&mut central, central_extra_len);
+            put_u16(&mut central, comment_len);
+            // 起始磁盘号
+            put_u16(&mut central, 0);
+            put_u16(&mut central, entry.internal_attributes);
+            put_u32(&mut central, entry.external_attributes);
+            put_u32(&mut central, offset);
+            central.extend_from_slice(name);
+            central.extend_from_slice(&entry.central_extra);
+            central.extend_from_slice(&entry.comment);
+        }
+
+        let count = u16::try_from(self.entries.len()).map_err(|_| invalid("条目过多"))?;
+        let central_offset = u32::try_from(out.len()).map_err(|_| invalid("文档过大"))?;
+        let central_size = u32::try_from(central.len()).map_err(|_| invalid("文档过大"))?;
+        out.extend_from_slice(&central);
+
+        put_u32(&mut out, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        put_u16(&mut out, 0);
+        put_u16(&mut out, 0);
+        put_u16(&mut out, count);
+        put_u16(&mut out, count);
+        put_u32(&mut out, central_size);
+        put_u32(&mut out, central_offset);
+        put_u16(&mut out, 0);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+impl Archive {
+    /// 由条目名称与内容构造归档，用于测试
+    pub fn from_files(files: &[(&str, &[u8])]) -> Self {
+        let mut archive = Self {
+            entries: files
+                .iter()
+                .map(|(name, _)| Entry {
+                    name: name.to_string(),
+                    flags: FLAG_UTF8,
+                    method: METHOD_STORED,
+                    time: 0,
+                    date: 0x21,
+                    crc: 0,
+                    size: 0,
+                    version_made_by: VERSION,
+                    internal_attributes: 0,
+                    external_attributes: 0,
+                    local_extra: Vec::new(),
+                    central_extra: Vec::new(),
+                    comment: Vec::new(),
+                    data: Vec::new(),
+                })
+                .collect(),
+        };
+        for (name, content) in files {
+            archive.replace(name, content).unwrap();
+        }
+        archive
+    }
+
+    /// 所有条目的名称
+    pub fn names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+}
+
+/// 解压 deflate 数据，解压后超过 `limit` 字节时返回错误
+fn inflate(name: &str, data: &[u8], limit: u64) -> Result<Vec<u8>> {
+    // 多读一个字节以判断是否超出上限
+    let mut content = Vec::new();
+    DeflateDecoder::new(data)
+        .take(limit + 1)
+        .read_to_end(&mut content)
+        .map_err(|e| invalid(format!("无法解压 '{}'：{}", name, e)))?;
+    if content.len() as u64 > limit {
+        return Err(invalid(format!(
+            "条目 '{}' 解压后超过 {} 字节，文档可能已损坏",
+            name, limit
+        )));
+    }
+    Ok(content)
+}
+
+/// 从文件末尾向前查找中央目录结束记录（其后最多有 65535 字节的注释）
+fn find_end_of_central_directory(bytes: &[u8]) -> Option<usize> {
+    let last = bytes.len().checked_sub(END_OF_CENTRAL_DIRECTORY_LEN)?;
+    let first = last.saturating_sub(usize::from(u16::MAX));
+    (first..=last)
+        .rev()
+        .find(|&pos| u32_at(bytes, pos).ok() == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+}
+
+fn crc32(content: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(content);
+    crc.sum()
+}
+
+fn invalid(message: impl Into<String>) -> TransomeError {
+    TransomeError::format_error("DOCX", message)
+}
+
+fn slice(bytes: &[u8], start: usize, len: usize) -> Result<&[u8]> {
+    start
+        .checked_add(len)
+        .and_then(|end| bytes.get(start..end))
+        .ok_or_else(|| invalid("文件被截断"))
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> Result<u16> {
+    let bytes = slice(bytes, pos, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> Result<u32> {
+    let bytes = slice(bytes, pos, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_preserves_entries() {
+        let archive = Archive::from_files(&[
+            ("[Content_Types].xml", b"<Types/>"),
+            (
+                "word/document.xml",
+                "<w:document>你好</w:document>".as_bytes(),
+            ),
+        ]);
+        let bytes = archive.write().unwrap();
+        assert_eq!(&bytes[..4], b"PK\x03\x04");
+
+        let archive = Archive::read(&bytes).unwrap();
+        assert_eq!(
+            archive.names(),
+            ["[Content_Types].xml", "word/document.xml"]
+        );
+        assert_eq!(
+            archive.get("word/document.xml").unwrap().unwrap(),
+            "<w:document>你好</w:document>".as_bytes()
+        );
+        assert_eq!(archive.get("missing.xml").unwrap(), None);
+    }
+
+    #[test]
+    fn test_stored_entries_and_comment() {
+        // 手工构造一个以存储方式保存、带归档注释的 zip
+        let content = b"hello";
+        let mut bytes = Vec::new();
+        put_u32(&mut bytes, LOCAL_HEADER_SIGNATURE);
+        for value in [10, 0, METHOD_STORED, 0, 0] {
+            put_u16(&mut bytes, value);
+        }
+        put_u32(&mut bytes, crc32(content));
+        put_u32(&mut bytes, 5);
+        put_u32(&mut bytes, 5);
+        put_u16(&mut bytes, 5);
+        put_u16(&mut bytes, 0);
+        bytes.extend_from_slice(b"a.txt");
+        bytes.extend_from_slice(content);
+
+        let central_offset = bytes.len() as u32;
+        put_u32(&mut bytes, CENTRAL_HEADER_SIGNATURE);
+        for value in [10, 10, 0, METHOD_STORED, 0, 0] {
+            put_u16(&mut bytes, value);
+        }
+        put_u32(&mut bytes, crc32(content));
+        put_u32(&mut bytes, 5);
+        put_u32(&mut bytes, 5);
+        for value in [5, 0, 0, 0, 0] {
+            put_u16(&mut bytes, value);
+        }
+        put_u32(&mut bytes, 0);
+        put_u32(&mut bytes, 0);
+        bytes.extend_from_slice(b"a.txt");
+        let central_size = bytes.len() as u32 - central_offset;
+
+        put_u32(&mut bytes, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        for value in [0, 0, 1, 1] {
+            put_u16(&mut bytes, value);
+        }
+        put_u32(&mut bytes, central_size);
+        put_u32(&mut bytes, central_offset);
+        put_u16(&mut bytes, 7);
+        bytes.extend_from_slice(b"comment");
+
+        let archive = Archive::read(&bytes).unwrap();
+        assert_eq!(archive.get("a.txt").unwrap().unwrap(), content);
+
+        // 未修改的条目原样复制
+        let archive = Archive::read(&archive.write().unwrap()).unwrap();
+        assert_eq!(archive.get("a.txt").unwrap().unwrap(), content);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_extra_fields_and_attributes() {
+        let mut archive = Archive::from_files(&[("a.sh", b"echo hi"), ("b.txt", b"b")]);
+        let entry = &mut archive.entries[0];
+        entry.version_made_by = 0x031e;
+        entry.external_attributes = 0o100755 << 16;
+        entry.local_extra = b"UT\x05\x00\x01\x00\x00\x00\x00".to_vec();
+        entry.central_extra = b"UT\x01\x00\x01".to_vec();
+        entry.comment = b"script".to_vec();
+
+        let read = Archive::read(&archive.write().unwrap()).unwrap();
+        let (before, after) = (&archive.entries[0], &read.entries[0]);
+        assert_eq!(after.version_made_by, before.version_made_by);
+        assert_eq!(after.external_attributes, before.external_attributes);
+        assert_eq!(after.local_extra, before.local_extra);
+        assert_eq!(after.central_extra, before.central_extra);
+        assert_eq!(after.comment, before.comment);
+        assert_eq!(read.get("a.sh").unwrap().unwrap(), b"echo hi");
+        assert_eq!(read.get("b.txt").unwrap().unwrap(), b"b");
+    }
+
+    #[test]
+    fn test_rejects_unsupported_compression_and_oversized_entries() {
+        let mut archive = Archive::from_files(&[("a.txt", b"hello")]);
+        archive.entries[0].method = 12;
+        let error = archive.get("a.txt").unwrap_err();
+        assert!(
+            error.to_string().contains("不支持的压缩方式 12"),
+            "{}",
+            error
+        );
+        // 未读取的条目仍可原样写出
+        assert!(Archive::read(&archive.write().unwrap()).is_ok());
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[0; 1000]).unwrap();
+        let data = encoder.finish().unwrap();
+        assert_eq!(inflate("zeros", &data, 1000).unwrap().len(), 1000);
+        let error = inflate("zeros", &data, 999).unwrap_err();
+        assert!(error.to_string().contains("超过 999 字节"), "{}", error);
+
+        // 条目头中的大小与实际不符
+        let mut archive = Archive::from_files(&[("a.txt", b"hello")]);
+        archive.entries[0].size = u32::MAX - 1;
+        assert!(archive.get("a.txt").is_err());
+    }
+
+    #[test]
+    fn test_rejects_non_zip_input() {
+        let error = Archive::read(b"plain text, not a document").unwrap_err();
+        assert!(error.to_string().contains("找不到 zip 目录"), "{}", error);
+        assert!(Archive::read(b"").is_err());
+    }
+}
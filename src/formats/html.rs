@@ -0,0 +1,226 @@
+//! HTML 格式处理：只翻译文本节点，保留标签与属性
+//!
+//! 使用 scraper（html5ever）解析，替换文本节点后重新序列化。输入是完整文档
+//! （以 `<!DOCTYPE` 或 `<html` 开头）时按文档解析，否则按片段解析，输出时不补全外层标签。
+//! `<script>`、`<style>`、`<code>` 等元素中的内容从不翻译；`alt`、`title` 等属性
+//! 只在通过 `--translate-attrs` 指定时翻译。
+
+use ego_tree::NodeId;
+use scraper::{Html, Node};
+
+use super::{Document, has_words, with_surrounding_whitespace};
+use crate::error::Result;
+
+/// 其中内容从不翻译的元素
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "code", "pre", "kbd", "samp", "textarea", "noscript", "template",
+];
+
+/// 待翻译的内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    /// 文本节点
+    Text(NodeId),
+    /// 元素的属性，保存属性名
+    Attr(NodeId, String),
+}
+
+/// 解析后的 HTML 文档
+#[derive(Debug, Clone)]
+pub struct HtmlDocument {
+    html: Html,
+    fragment: bool,
+    /// 与片段一一对应
+    targets: Vec<Target>,
+}
+
+impl HtmlDocument {
+    /// 解析 HTML 文本
+    ///
+    /// `attrs` 为需要翻译的属性名（如 `alt`、`title`），不区分大小写。
+    pub fn parse(input: &str, attrs: &[String]) -> Self {
+        let head = input.trim_start().get(..9).unwrap_or_default();
+        let fragment = !(head.eq_ignore_ascii_case("<!doctype")
+            || head.to_ascii_lowercase().starts_with("<html"));
+        let html = if fragment {
+            Html::parse_fragment(input)
+        } else {
+            Html::parse_document(input)
+        };
+
+        let mut targets = Vec::new();
+        for node in html.tree.nodes() {
+            let skipped = node.ancestors().any(|ancestor| {
+                ancestor
+                    .value()
+                    .as_element()
+                    .is_some_and(|element| SKIPPED_ELEMENTS.contains(&element.name()))
+            });
+            if skipped {
+                continue;
+            }
+
+            match node.value() {
+                Node::Text(text) if has_words(text) => targets.push(Target::Text(node.id())),
+                Node::Element(element) if !SKIPPED_ELEMENTS.contains(&element.name()) => {
+                    for (name, value) in element.attrs() {
+                        if has_words(value) && attrs.iter().any(|a| a.eq_ignore_ascii_case(name)) {
+                            targets.push(Target::Attr(node.id(), name.to_string()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            html,
+            fragment,
+            targets,
+        }
+    }
+
+    /// 片段在原文档中的内容
+    fn original(&self, target: &Target) -> &str {
+        let id = match target {
+            Target::Text(id) | Target::Attr(id, _) => *id,
+        };
+        match (target, self.html.tree.get(id).map(|node| node.value())) {
+            (Target::Text(_), Some(Node::Text(text))) => text,
+            (Target::Attr(_, name), Some(Node::Element(element))) => {
+                element.attr(name).unwrap_or_default()
+            }
+            _ => "",
+        }
+    }
+}
+
+impl Document for HtmlDocument {
+    type Output = String;
+
+    /// 待翻译的片段（按文档顺序，去掉首尾空白）
+    fn segments(&self) -> Vec<String> {
+        self.targets
+            .iter()
+            .map(|target| self.original(target).trim().to_string())
+            .collect()
+    }
+
+    /// 使用翻译结果重新生成 HTML
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    /// 原文首尾的空白保留在译文两侧。
+    fn render(&self, translations: &[Option<String>]) -> Result<String> {
+        let mut html = self.html.clone();
+        for (target, translation) in self.targets.iter().zip(translations) {
+            let Some(translation) = translation else {
+                continue;
+            };
+            let replacement = with_surrounding_whitespace(self.original(target), translation);
+
+            match target {
+                Target::Text(id) => {
+                    if let Some(mut node) = html.tree.get_mut(*id)
+                        && let Node::Text(text) = node.value()
+                    {
+                        text.text = replacement.as_str().into();
+                    }
+                }
+                Target::Attr(id, name) => {
+                    if let Some(mut node) = html.tree.get_mut(*id)
+                        && let Node::Element(element) = node.value()
+                        && let Some((_, value)) = element
+                            .attrs
+                            .iter_mut()
+                            .find(|(key, _)| &*key.local == name)
+                    {
+                        *value = replacement.as_str().into();
+                    }
+                }
+            }
+        }
+
+        if self.fragment {
+            Ok(html.root_element().inner_html())
+        } else {
+            Ok(html.html())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uppercase_all(doc: &HtmlDocument) -> String {
+        let translations: Vec<Option<String>> = doc
+            .segments()
+            .into_iter()
+            .map(|s| Some(s.to_uppercase()))
+            .collect();
+        doc.render(&translations).unwrap()
+    }
+
+    #[test]
+    fn test_nested_tags_survive() {
+        let input = "<div class=\"card\"><h2 id=\"t\">Hello <em>big</em> world</h2>\n\
+            <p>Click <a href=\"/go?a=1&amp;b=2\" title=\"Go there\">here</a>.</p>\n\
+            <img src=\"cat.png\" alt=\"A cat\"></div>";
+        let doc = HtmlDocument::parse(input, &[]);
+
+        assert_eq!(doc.segments(), ["Hello", "big", "world", "Click", "here"]);
+        assert_eq!(
+            uppercase_all(&doc),
+            "<div class=\"card\"><h2 id=\"t\">HELLO <em>BIG</em> WORLD</h2>\n\
+            <p>CLICK <a href=\"/go?a=1&amp;b=2\" title=\"Go there\">HERE</a>.</p>\n\
+            <img src=\"cat.png\" alt=\"A cat\"></div>"
+        );
+    }
+
+    #[test]
+    fn test_script_and_style_are_never_translated() {
+        let input = "<style>p { color: red; }</style><p>Text</p>\
+            <script>var message = \"hello\";</script><code>let x = 1;</code>";
+        let doc = HtmlDocument::parse(input, &["title".to_string()]);
+
+        assert_eq!(doc.segments(), ["Text"]);
+        assert_eq!(
+            uppercase_all(&doc),
+            "<style>p { color: red; }</style><p>TEXT</p>\
+            <script>var message = \"hello\";</script><code>let x = 1;</code>"
+        );
+    }
+
+    #[test]
+    fn test_translate_attrs() {
+        let input = "<img src=\"cat.png\" alt=\"A cat\" title=\"Cute\"><a href=\"/x\" title=\"Home\">Go</a>";
+        let doc = HtmlDocument::parse(input, &["alt".to_string(), "TITLE".to_string()]);
+
+        assert_eq!(doc.segments(), ["A cat", "Cute", "Home", "Go"]);
+        let rendered = doc
+            .render(&[
+                Some("一只猫".to_string()),
+                None,
+                Some("首页 \"主\"".to_string()),
+                Some("前往".to_string()),
+            ])
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "<img src=\"cat.png\" alt=\"一只猫\" title=\"Cute\"><a href=\"/x\" title=\"首页 &quot;主&quot;\">前往</a>"
+        );
+    }
+
+    #[test]
+    fn test_full_document_keeps_doctype() {
+        let input =
+            "<!DOCTYPE html><html><head><title>Page</title></head><body><p>Hi</p></body></html>";
+        let doc = HtmlDocument::parse(input, &[]);
+
+        assert_eq!(doc.segments(), ["Page", "Hi"]);
+        assert_eq!(
+            uppercase_all(&doc),
+            "<!DOCTYPE html><html><head><title>PAGE</title></head><body><p>HI</p></body></html>"
+        );
+    }
+}
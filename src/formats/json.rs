@@ -0,0 +1,516 @@
+//! JSON 格式：翻译字符串值，可选翻译键
+//!
+//! 按原文扫描出每个字符串的位置，只替换被翻译的字符串，缩进、键的顺序与数字的写法都保持原样；
+//! 数字、布尔值与 null 不参与翻译。键默认不翻译（`--translate-keys` 时翻译），
+//! 同一对象中译文重复的键保留原文（见 [`JsonDocument::check_keys`]）。
+//!
+//! 短字符串很多时逐个请求效率很低，[`batch_segments`] 把相邻的片段以编号标记合并为一个请求，
+//! [`split_batches`] 再按标记拆回；标记缺失或错位的批次由调用方逐个重新翻译。
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{Document, has_words, with_surrounding_whitespace};
+use crate::error::{Result, TransomeError};
+use crate::warning::{WarningKind, Warnings};
+
+/// 一个批次最多包含的片段数
+pub const MAX_BATCH_SEGMENTS: usize = 20;
+
+/// 一个批次的原文最多包含的字符数，单个更长的片段单独成批
+pub const MAX_BATCH_CHARS: usize = 2000;
+
+/// 随合并的批次一起发送的译者注
+pub const BATCH_NOTE: &str = "文本由多段组成，每段前有一行 <<<编号>>> 标记。\
+    逐段翻译，原样保留每个标记及其所在的行，不要增删、合并或调换段落。";
+
+/// 批次中各段之前的编号标记
+static MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^[ \t]*<<<(\d+)>>>[ \t]*$").unwrap());
+
+/// 要翻译的字符串
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Targets {
+    /// 翻译字符串值（`--translate-strings`）
+    pub strings: bool,
+    /// 翻译对象的键（`--translate-keys`）
+    pub keys: bool,
+}
+
+/// 原文中的一个待翻译字符串
+#[derive(Debug, Clone)]
+struct Entry {
+    /// 字符串在原文中的范围，包含引号
+    range: Range<usize>,
+    /// 解码后的内容
+    value: String,
+    /// 是键时为所在对象的序号
+    object: Option<usize>,
+}
+
+/// 扫描时所在的容器
+enum Container {
+    /// 对象及其序号，`expect_key` 表示下一个字符串是键
+    Object {
+        id: usize,
+        expect_key: bool,
+    },
+    Array,
+}
+
+/// 解析后的 JSON 文档
+#[derive(Debug, Clone)]
+pub struct JsonDocument {
+    source: String,
+    /// 与片段一一对应
+    entries: Vec<Entry>,
+}
+
+impl JsonDocument {
+    /// 解析 JSON 文本，只收集 `targets` 指定的、含文字的字符串
+    pub fn parse(input: &str, targets: Targets) -> Result<Self> {
+        serde_json::from_str::<serde_json::Value>(input)
+            .map_err(|e| TransomeError::format_error("json", format!("不是有效的 JSON：{}", e)))?;
+
+        let mut entries = Vec::new();
+        let mut stack: Vec<Container> = Vec::new();
+        let mut objects = 0;
+        let mut chars = input.char_indices();
+        while let Some((start, c)) = chars.next() {
+            match c {
+                '{' => {
+                    stack.push(Container::Object {
+                        id: objects,
+                        expect_key: true,
+                    });
+                    objects += 1;
+                }
+                '[' => stack.push(Container::Array),
+                '}' | ']' => {
+                    stack.pop();
+                }
+                ',' | ':' => {
+                    if let Some(Container::Object { expect_key, .. }) = stack.last_mut() {
+                        *expect_key = c == ',';
+                    }
+                }
+                '"' => {
+                    // 已验证是有效的 JSON，字符串一定以未转义的引号结束
+                    let mut end = input.len();
+                    while let Some((i, c)) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => {
+                                end = i + 1;
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                    let object = match stack.last() {
+                        Some(Container::Object {
+                            id,
+                            expect_key: true,
+                        }) => Some(*id),
+                        _ => None,
+                    };
+                    let wanted = if object.is_some() {
+                        targets.keys
+                    } else {
+                        targets.strings
+                    };
+                    if !wanted {
+                        continue;
+                    }
+                    let value: String = serde_json::from_str(&input[start..end])
+                        .map_err(|e| TransomeError::format_error("json", e.to_string()))?;
+                    if has_words(&value) {
+                        entries.push(Entry {
+                            range: start..end,
+                            value,
+                            object,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            source: input.to_string(),
+            entries,
+        })
+    }
+
+    /// 检查键的译文，同一对象中与其他键重复的译文视为失败，记录警告并保留原文
+    pub fn check_keys(
+        &self,
+        translations: Vec<Option<String>>,
+        warnings: &Warnings,
+    ) -> Vec<Option<String>> {
+        // 每个对象中各键最终的名称，保留原文的键也占用名称
+        let mut names: HashMap<(usize, String), usize> = HashMap::new();
+        for (entry, translation) in self.entries.iter().zip(&translations) {
+            if let Some(object) = entry.object {
+                let name = match translation {
+                    Some(translation) => with_surrounding_whitespace(&entry.value, translation),
+                    None => entry.value.clone(),
+                };
+                *names.entry((object, name)).or_default() += 1;
+            }
+        }
+
+        self.entries
+            .iter()
+            .zip(translations)
+            .map(|(entry, translation)| {
+                let translation = translation?;
+                let Some(object) = entry.object else {
+                    return Some(translation);
+                };
+                let name = with_surrounding_whitespace(&entry.value, &translation);
+                if names.get(&(object, name)).copied().unwrap_or_default() <= 1 {
+                    return Some(translation);
+                }
+                warnings.warn(
+                    WarningKind::DuplicateKey,
+                    format!(
+                        "键 '{}' 的译文与同一对象中的其他键重复，已保留原文：{}",
+                        entry.value, translation
+                    ),
+                );
+                None
+            })
+            .collect()
+    }
+}
+
+impl Document for JsonDocument {
+    type Output = String;
+
+    /// 待翻译的片段（按文档中的顺序，去掉首尾空白）
+    fn segments(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| entry.value.trim().to_string())
+            .collect()
+    }
+
+    fn check(&self, translations: Vec<Option<String>>, warnings: &Warnings) -> Vec<Option<String>> {
+        self.check_keys(translations, warnings)
+    }
+
+    /// 以译文替换对应的字符串，译文为 None 的保留原文
+    fn render(&self, translations: &[Option<String>]) -> Result<String> {
+        let mut output = String::with_capacity(self.source.len());
+        let mut last = 0;
+        for (entry, translation) in self.entries.iter().zip(translations) {
+            let Some(translation) = translation else {
+                continue;
+            };
+            output.push_str(&self.source[last..entry.range.start]);
+            let text = with_surrounding_whitespace(&entry.value, translation);
+            // 字符串的序列化不会失败
+            output.push_str(&serde_json::to_string(&text).unwrap_or_default());
+            last = entry.range.end;
+        }
+        output.push_str(&self.source[last..]);
+        Ok(output)
+    }
+}
+
+/// 合并翻译的一批片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    /// 片段的序号
+    pub indices: Vec<usize>,
+    /// 发送的文本；多于一个片段时每段前有编号标记
+    pub text: String,
+}
+
+impl Batch {
+    /// 是否合并了多个片段，需要附带 [`BATCH_NOTE`] 并按标记拆分
+    pub fn is_merged(&self) -> bool {
+        self.indices.len() > 1
+    }
+}
+
+/// 将相邻的片段合并为批次，每批不超过 [`MAX_BATCH_SEGMENTS`] 个片段与 [`MAX_BATCH_CHARS`] 个字符
+pub fn batch_segments(segments: &[String]) -> Vec<Batch> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut chars = 0;
+    for (index, segment) in segments.iter().enumerate() {
+        let len = segment.chars().count();
+        match groups.last_mut() {
+            Some(group) if group.len() < MAX_BATCH_SEGMENTS && chars + len <= MAX_BATCH_CHARS => {
+                group.push(index);
+                chars += len;
+            }
+            _ => {
+                groups.push(vec![index]);
+                chars = len;
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|indices| {
+            let text = if let [index] = indices[..] {
+                segments[index].clone()
+            } else {
+                let mut text = String::new();
+                for (n, &index) in indices.iter().enumerate() {
+                    let _ = write!(text, "<<<{}>>>\n{}\n", n + 1, segments[index]);
+                }
+                text
+            };
+            Batch { indices, text }
+        })
+        .collect()
+}
+
+/// 按编号标记把批次的译文拆回各片段，标记缺失、重复或顺序不对时返回 None
+fn split_batch(batch: &Batch, translation: &str) -> Option<Vec<String>> {
+    if !batch.is_merged() {
+        return Some(vec![translation.to_string()]);
+    }
+
+    let markers: Vec<_> = MARKER.captures_iter(translation).collect();
+    if markers.len() != batch.indices.len() {
+        return None;
+    }
+    let mut parts = Vec::with_capacity(markers.len());
+    for (n, marker) in markers.iter().enumerate() {
+        if marker[1].parse::<usize>().ok()? != n + 1 {
+            return None;
+        }
+        let start = marker.get(0)?.end();
+        let end = markers.get(n + 1).map_or(translation.len(), |next| {
+            next.get(0).map_or(0, |m| m.start())
+        });
+        let part = translation[start..end].trim();
+        if part.is_empty() {
+            return None;
+        }
+        parts.push(part.to_string());
+    }
+    Some(parts)
+}
+
+/// 把各批次的译文拆回片段
+///
+/// 返回与片段一一对应的译文，以及译文无法按标记拆分、需要逐个重新翻译的片段序号。
+/// 翻译失败的批次中的片段为 None，不重新翻译。
+pub fn split_batches(
+    batches: &[Batch],
+    translations: Vec<Option<String>>,
+    segment_count: usize,
+) -> (Vec<Option<String>>, Vec<usize>) {
+    let mut results = vec![None; segment_count];
+    let mut retry = Vec::new();
+    for (batch, translation) in batches.iter().zip(translations) {
+        let Some(translation) = translation else {
+            continue;
+        };
+        match split_batch(batch, &translation) {
+            Some(parts) => {
+                for (&index, part) in batch.indices.iter().zip(parts) {
+                    results[index] = Some(part);
+                }
+            }
+            None => retry.extend(&batch.indices),
+        }
+    }
+    (results, retry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NESTED: &str = r#"{
+  "title": "Hello world",
+  "count": 3,
+  "ratio": 1.50,
+  "enabled": true,
+  "missing": null,
+  "menu": {
+    "items": ["Open file", "Save", 42, false, "  Quit  "],
+    "shortcut": "Ctrl+S"
+  },
+  "escaped": "Say \"hi\"\nthen leave"
+}"#;
+
+    fn strings() -> Targets {
+        Targets {
+            strings: true,
+            keys: false,
+        }
+    }
+
+    #[test]
+    fn test_only_string_values_change() {
+        let document = JsonDocument::parse(NESTED, strings()).unwrap();
+        let segments = document.segments();
+        assert_eq!(
+            segments,
+            vec![
+                "Hello world",
+                "Open file",
+                "Save",
+                "Quit",
+                "Ctrl+S",
+                "Say \"hi\"\nthen leave",
+            ]
+        );
+
+        let translations: Vec<Option<String>> = segments
+            .iter()
+            .map(|segment| Some(format!("[{}]", segment)))
+            .collect();
+        let rendered = document.render(&translations).unwrap();
+        assert_eq!(
+            rendered,
+            r#"{
+  "title": "[Hello world]",
+  "count": 3,
+  "ratio": 1.50,
+  "enabled": true,
+  "missing": null,
+  "menu": {
+    "items": ["[Open file]", "[Save]", 42, false, "  [Quit]  "],
+    "shortcut": "[Ctrl+S]"
+  },
+  "escaped": "[Say \"hi\"\nthen leave]"
+}"#
+        );
+
+        // 结构与非字符串的值不变
+        let before: serde_json::Value = serde_json::from_str(NESTED).unwrap();
+        let after: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(after["count"], before["count"]);
+        assert_eq!(after["ratio"], before["ratio"]);
+        assert_eq!(after["enabled"], true);
+        assert_eq!(after["missing"], serde_json::Value::Null);
+        assert_eq!(after["menu"]["items"][2], 42);
+        assert_eq!(
+            after.as_object().unwrap().keys().collect::<Vec<_>>(),
+            before.as_object().unwrap().keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_translate_keys() {
+        let input = r#"{"name": "Alice", "tags": {"first": "a b", "second": "c"}}"#;
+        let targets = Targets {
+            strings: false,
+            keys: true,
+        };
+        let document = JsonDocument::parse(input, targets).unwrap();
+        assert_eq!(document.segments(), vec!["name", "tags", "first", "second"]);
+
+        // 同一对象中译文重复的键保留原文
+        let warnings = Warnings::new(true);
+        let translations = vec![
+            Some("姓名".to_string()),
+            Some("标签".to_string()),
+            Some("项".to_string()),
+            Some("项".to_string()),
+        ];
+        let translations = document.check_keys(translations, &warnings);
+        assert_eq!(
+            document.render(&translations).unwrap(),
+            r#"{"姓名": "Alice", "标签": {"first": "a b", "second": "c"}}"#
+        );
+        assert_eq!(warnings.to_vec().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert!(JsonDocument::parse("{\"a\": ", strings()).is_err());
+    }
+
+    #[test]
+    fn test_batches_round_trip() {
+        let segments: Vec<String> = (0..25).map(|i| format!("item {}", i)).collect();
+        let batches = batch_segments(&segments);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].indices.len(), MAX_BATCH_SEGMENTS);
+        assert!(
+            batches[0]
+                .text
+                .starts_with("<<<1>>>\nitem 0\n<<<2>>>\nitem 1\n")
+        );
+
+        // 模型保留了标记，但段落的换行有所变化
+        let translations = batches
+            .iter()
+            .map(|batch| Some(batch.text.replace("item", "条目").replace('\n', "\n\n")))
+            .collect();
+        let (results, retry) = split_batches(&batches, translations, segments.len());
+        assert!(retry.is_empty());
+        assert_eq!(results[0].as_deref(), Some("条目 0"));
+        assert_eq!(results[24].as_deref(), Some("条目 24"));
+    }
+
+    #[test]
+    fn test_mangled_batch_is_retried() {
+        let segments: Vec<String> = ["one", "two", "three"].map(String::from).to_vec();
+        let batches = batch_segments(&segments);
+        assert_eq!(batches.len(), 1);
+
+        // 模型合并了段落，丢失了一个标记
+        let translations = vec![Some("<<<1>>>\n一\n<<<2>>>\n二 三\n".to_string())];
+        let (results, retry) = split_batches(&batches, translations, segments.len());
+        assert_eq!(retry, vec![0, 1, 2]);
+        assert!(results.iter().all(Option::is_none));
+
+        // 翻译失败的批次不重新翻译
+        let (_, retry) = split_batches(&batches, vec![None], segments.len());
+        assert!(retry.is_empty());
+    }
+
+    #[test]
+    fn test_long_segment_is_its_own_batch() {
+        let segments = vec![
+            "short".to_string(),
+            "x".repeat(MAX_BATCH_CHARS),
+            "tail".to_string(),
+        ];
+        let batches = batch_segments(&segments);
+        assert_eq!(batches.len(), 3);
+        assert!(!batches[1].is_merged());
+        assert_eq!(batches[1].text, segments[1]);
+    }
+
+    #[tokio::test]
+    async fn test_refused_string_keeps_source() {
+        use crate::batch::OnError;
+        use crate::formats::{SegmentOptions, uppercase_refusing};
+
+        let document = JsonDocument::parse(NESTED, strings()).unwrap();
+        let options = SegmentOptions {
+            on_error: OnError::KeepOriginal,
+            ..SegmentOptions::default()
+        };
+        let warnings = Warnings::new(true);
+        let translations = uppercase_refusing(document.segments(), "Save", &options, &warnings)
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        // 输出仍是合法的 JSON，被拒绝的字符串保留原文
+        let rendered = document.render(&translations).unwrap();
+        let after: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(after["title"], "HELLO WORLD");
+        assert_eq!(after["menu"]["items"][0], "OPEN FILE");
+        assert_eq!(after["menu"]["items"][1], "Save");
+        assert_eq!(after["escaped"], "SAY \"HI\"\nTHEN LEAVE");
+    }
+}
@@ -0,0 +1,702 @@
+//! Markdown 格式处理：只翻译文字，标记与链接地址原样保留
+//!
+//! 使用 pulldown-cmark 解析出各块（段落、标题、列表项、表格单元格等）中文字在原文中的
+//! 位置，每块作为一个片段整体翻译，翻译后按位置替换，其余内容不变。强调、删除线等行内
+//! 标记原样留在片段中；行内代码、HTML、自动链接（`<https://...>`）与链接地址在片段中
+//! 以 `⟦1⟧` 形式的占位标记代替，译文中的占位标记与原文不一致时保留原文。代码块与 HTML
+//! 块中的内容从不翻译。链接文字与图片的替代文本默认随所在的块翻译，链接与图片的标题
+//! （`[文字](地址 "标题")`）在 [`LinkTranslation::All`] 时作为单独的片段翻译。
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use clap::ValueEnum;
+use once_cell::sync::Lazy;
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+
+use super::Document;
+use crate::error::Result;
+use crate::warning::{WarningKind, Warnings};
+
+/// 片段中代替原样保留内容的占位标记
+static PLACEHOLDER: Lazy<regex::Regex> = Lazy::new(|| regex::Regex::new(r"⟦(\d+)⟧").unwrap());
+
+/// 含有占位标记的片段随附的译者注
+pub const PLACEHOLDER_NOTE: &str = "文本中的 ⟦1⟧、⟦2⟧ 等占位标记代表原样保留的内容（代码、链接地址等）。\
+    原样保留每个占位标记，可以按译文语序调整位置，不要增删或修改。";
+
+/// 链接与图片中哪些部分参与翻译
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LinkTranslation {
+    /// 不翻译链接文字、替代文本和标题
+    None,
+    /// 翻译链接文字和图片的替代文本
+    #[default]
+    Text,
+    /// 同时翻译链接与图片的标题
+    All,
+}
+
+/// 一个块中待翻译的文字
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct Block {
+    range: Range<usize>,
+    /// 原样保留的行内内容，按位置排序，片段中依次以 ⟦1⟧、⟦2⟧ … 代替
+    protected: Vec<Range<usize>>,
+    /// 续行开头的容器标记（如引用的 `> `、列表项的缩进），片段中去掉
+    prefixes: Vec<Range<usize>>,
+}
+
+/// 待翻译的一段文字
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Span {
+    /// 块中的文字
+    Block(Block),
+    /// 链接或图片的标题，`quote` 为标题的结束引号，替换时需要转义
+    Title { range: Range<usize>, quote: char },
+}
+
+impl Span {
+    fn range(&self) -> &Range<usize> {
+        match self {
+            Span::Block(block) => &block.range,
+            Span::Title { range, .. } => range,
+        }
+    }
+}
+
+/// 正在收集文字的块
+#[derive(Debug, Default)]
+struct BlockBuilder {
+    block: Block,
+    has_words: bool,
+    /// 上一个换行的结束位置，用于识别下一行开头的容器标记
+    line_start: Option<usize>,
+}
+
+/// 正在解析的链接或图片
+#[derive(Debug)]
+struct OpenLink {
+    range: Range<usize>,
+    /// 链接文字的结束位置
+    text_end: usize,
+}
+
+/// 解析后的 Markdown 文档
+#[derive(Debug, Clone)]
+pub struct MarkdownDocument {
+    source: String,
+    /// 按起始位置排序，与片段一一对应；标题可能位于块的原样保留部分之中
+    spans: Vec<Span>,
+}
+
+impl MarkdownDocument {
+    /// 解析 Markdown 文本
+    pub fn parse(input: &str, links: LinkTranslation) -> Self {
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS;
+        let parser = Parser::new_ext(input, options).into_offset_iter();
+
+        let mut spans = Vec::new();
+
+        // 引用式链接的标题在链接定义中
+        if links == LinkTranslation::All {
+            for (_, definition) in parser.reference_definitions().iter() {
+                if let Some(title) = &definition.title {
+                    spans.extend(find_title(input, definition.span.clone(), title));
+                }
+            }
+        }
+
+        let mut current: Option<BlockBuilder> = None;
+        // 代码块、HTML 块等不翻译的块的层数
+        let mut skipped = 0;
+        // 整体原样保留的行内内容（自动链接、不翻译的链接）中的层数
+        let mut protected = 0;
+        let mut open_links: Vec<OpenLink> = Vec::new();
+
+        for (event, range) in parser {
+            // 块的边界结束当前的块
+            match &event {
+                Event::Start(tag) if !is_inline(tag) => {
+                    spans.extend(finish_block(input, current.take()));
+                    if matches!(
+                        tag,
+                        Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_)
+                    ) {
+                        skipped += 1;
+                    }
+                    continue;
+                }
+                Event::End(tag) if !is_inline_end(tag) => {
+                    spans.extend(finish_block(input, current.take()));
+                    if matches!(
+                        tag,
+                        TagEnd::CodeBlock | TagEnd::HtmlBlock | TagEnd::MetadataBlock(_)
+                    ) {
+                        skipped -= 1;
+                    }
+                    continue;
+                }
+                Event::Rule | Event::Html(_) | Event::DisplayMath(_) => {
+                    spans.extend(finish_block(input, current.take()));
+                    continue;
+                }
+                Event::TaskListMarker(_) => continue,
+                _ if skipped > 0 => continue,
+                _ => {}
+            }
+
+            if protected > 0 {
+                match event {
+                    Event::Start(_) => protected += 1,
+                    Event::End(_) => protected -= 1,
+                    _ => {}
+                }
+                continue;
+            }
+
+            let builder = current.get_or_insert_with(|| BlockBuilder {
+                block: Block {
+                    range: range.start..range.start,
+                    ..Block::default()
+                },
+                ..BlockBuilder::default()
+            });
+            builder.continue_line(input, range.start);
+
+            match event {
+                Event::Start(Tag::Link {
+                    link_type, title, ..
+                })
+                | Event::Start(Tag::Image {
+                    link_type, title, ..
+                }) => {
+                    extend_links(&mut open_links, range.end);
+                    if keeps_link(link_type, links) {
+                        builder.block.protected.push(range.clone());
+                        protected = 1;
+                    } else {
+                        if link_type == LinkType::Inline && links == LinkTranslation::All {
+                            spans.extend(find_title(input, range.clone(), &title));
+                        }
+                        // 链接文字从 `[` 之后开始
+                        let text_start = input[range.clone()]
+                            .find('[')
+                            .map_or(range.start, |i| range.start + i + 1);
+                        open_links.push(OpenLink {
+                            range: range.clone(),
+                            text_end: text_start,
+                        });
+                    }
+                }
+                Event::End(TagEnd::Link | TagEnd::Image) => {
+                    if let Some(link) = open_links.pop() {
+                        // 链接文字之后的 `](地址 "标题")` 或 `[标签]` 原样保留
+                        let tail = input[link.text_end..link.range.end]
+                            .find(']')
+                            .map(|i| link.text_end + i + 1);
+                        if let Some(tail) = tail
+                            && tail < link.range.end
+                        {
+                            builder.block.protected.push(tail..link.range.end);
+                        }
+                    }
+                    extend_links(&mut open_links, range.end);
+                }
+                Event::Text(text) => {
+                    builder.has_words |= super::has_words(&text);
+                    extend_links(&mut open_links, range.end);
+                }
+                Event::SoftBreak | Event::HardBreak => {
+                    builder.line_start = Some(range.end);
+                    extend_links(&mut open_links, range.end);
+                }
+                Event::Code(_)
+                | Event::InlineHtml(_)
+                | Event::InlineMath(_)
+                | Event::FootnoteReference(_) => {
+                    builder.block.protected.push(range.clone());
+                    extend_links(&mut open_links, range.end);
+                }
+                _ => extend_links(&mut open_links, range.end),
+            }
+            let block = &mut builder.block.range;
+            block.end = block.end.max(range.end);
+        }
+        spans.extend(finish_block(input, current));
+
+        spans.sort_by_key(|span| span.range().start);
+
+        Self {
+            source: input.to_string(),
+            spans,
+        }
+    }
+
+    /// 复制原文中的一段，其中的标题替换为译文
+    fn copy(&self, range: Range<usize>, titles: &BTreeMap<usize, (usize, String)>) -> String {
+        let mut output = String::with_capacity(range.len());
+        let mut last = range.start;
+        for (&start, (end, title)) in titles.range(range.clone()) {
+            if *end > range.end {
+                break;
+            }
+            output.push_str(&self.source[last..start]);
+            output.push_str(title);
+            last = *end;
+        }
+        output.push_str(&self.source[last..range.end]);
+        output
+    }
+
+    /// 把块的译文中的占位标记还原为原文，并在每个换行后补回续行开头的容器标记
+    fn restore(
+        &self,
+        block: &Block,
+        translation: &str,
+        titles: &BTreeMap<usize, (usize, String)>,
+    ) -> String {
+        let prefix = block
+            .prefixes
+            .first()
+            .map_or("", |range| &self.source[range.clone()]);
+        let newline = format!("\n{}", prefix);
+        let mut output = String::with_capacity(translation.len());
+        let mut last = 0;
+        for captures in PLACEHOLDER.captures_iter(translation) {
+            let whole = captures.get(0).unwrap();
+            output.push_str(&translation[last..whole.start()].replace('\n', &newline));
+            // 占位标记在检查时已确认与原文一致
+            let index: usize = captures[1].parse().unwrap_or_default();
+            if let Some(range) = index.checked_sub(1).and_then(|i| block.protected.get(i)) {
+                output.push_str(&self.copy(range.clone(), titles));
+            }
+            last = whole.end();
+        }
+        output.push_str(&translation[last..].replace('\n', &newline));
+        output
+    }
+}
+
+impl Document for MarkdownDocument {
+    type Output = String;
+
+    /// 待翻译的片段（按在原文中的顺序）
+    fn segments(&self) -> Vec<String> {
+        self.spans
+            .iter()
+            .map(|span| match span {
+                Span::Block(block) => block_text(&self.source, block),
+                Span::Title { range, .. } => self.source[range.clone()].to_string(),
+            })
+            .collect()
+    }
+
+    /// 含有占位标记的块附带 [`PLACEHOLDER_NOTE`]
+    fn notes(&self) -> Vec<Option<String>> {
+        self.spans
+            .iter()
+            .map(|span| match span {
+                Span::Block(block) if !block.protected.is_empty() => {
+                    Some(PLACEHOLDER_NOTE.to_string())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 检查块的译文中的占位标记，与原文不一致的译文视为失败，记录警告并保留原文
+    fn check(&self, translations: Vec<Option<String>>, warnings: &Warnings) -> Vec<Option<String>> {
+        self.spans
+            .iter()
+            .zip(translations)
+            .enumerate()
+            .map(|(index, (span, translation))| {
+                let translation = translation?;
+                let Span::Block(block) = span else {
+                    return Some(translation);
+                };
+                let mut found: Vec<usize> = PLACEHOLDER
+                    .captures_iter(&translation)
+                    .filter_map(|captures| captures[1].parse().ok())
+                    .collect();
+                found.sort_unstable();
+                if found.iter().copied().eq(1..=block.protected.len()) {
+                    return Some(translation);
+                }
+                warnings.warn(
+                    WarningKind::PlaceholderMismatch,
+                    format!(
+                        "第 {} 个片段的译文中占位标记与原文不一致，已保留原文：{}",
+                        index + 1,
+                        translation
+                    ),
+                );
+                None
+            })
+            .collect()
+    }
+
+    /// 使用翻译结果重新生成 Markdown
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    fn render(&self, translations: &[Option<String>]) -> Result<String> {
+        let titles: BTreeMap<usize, (usize, String)> = self
+            .spans
+            .iter()
+            .zip(translations)
+            .filter_map(|(span, translation)| match (span, translation) {
+                (Span::Title { range, quote }, Some(translation)) => {
+                    Some((range.start, (range.end, escape_title(translation, *quote))))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut output = String::with_capacity(self.source.len());
+        let mut last = 0;
+        for (span, translation) in self.spans.iter().zip(translations) {
+            let Span::Block(block) = span else {
+                continue;
+            };
+            output.push_str(&self.copy(last..block.range.start, &titles));
+            match translation {
+                Some(translation) => {
+                    output.push_str(&self.restore(block, translation.trim(), &titles))
+                }
+                None => output.push_str(&self.copy(block.range.clone(), &titles)),
+            }
+            last = block.range.end;
+        }
+        output.push_str(&self.copy(last..self.source.len(), &titles));
+        Ok(output)
+    }
+}
+
+impl BlockBuilder {
+    /// 换行之后的第一个事件：两者之间只有空白与 `>` 时视为续行开头的容器标记
+    fn continue_line(&mut self, input: &str, start: usize) {
+        let Some(line_start) = self.line_start.take() else {
+            return;
+        };
+        if start > line_start
+            && input[line_start..start]
+                .chars()
+                .all(|c| c == '>' || c.is_whitespace())
+        {
+            self.block.prefixes.push(line_start..start);
+        }
+    }
+}
+
+/// 结束一个块：去掉首尾的空白，没有需要翻译的文字时不翻译
+fn finish_block(input: &str, builder: Option<BlockBuilder>) -> Option<Span> {
+    let builder = builder?;
+    if !builder.has_words {
+        return None;
+    }
+    let mut block = builder.block;
+    let text = &input[block.range.clone()];
+    let start = block.range.start + (text.len() - text.trim_start().len());
+    let end = block.range.start + text.trim_end().len();
+    let protected_start = block.protected.first().map_or(end, |range| range.start);
+    let protected_end = block.protected.last().map_or(start, |range| range.end);
+    block.range = start.min(protected_start)..end.max(protected_end);
+    block.protected.sort_by_key(|range| range.start);
+    Some(Span::Block(block))
+}
+
+/// 块的片段文字：原样保留的内容替换为占位标记，去掉续行开头的容器标记
+fn block_text(input: &str, block: &Block) -> String {
+    let mut cuts: Vec<(Range<usize>, Option<usize>)> = block
+        .protected
+        .iter()
+        .enumerate()
+        .map(|(index, range)| (range.clone(), Some(index + 1)))
+        .chain(block.prefixes.iter().map(|range| (range.clone(), None)))
+        .collect();
+    cuts.sort_by_key(|(range, _)| range.start);
+
+    let mut text = String::new();
+    let mut last = block.range.start;
+    for (range, placeholder) in cuts {
+        if range.start < last {
+            continue;
+        }
+        text.push_str(&input[last..range.start]);
+        if let Some(number) = placeholder {
+            text.push_str(&format!("⟦{}⟧", number));
+        }
+        last = range.end;
+    }
+    text.push_str(&input[last..block.range.end]);
+    text
+}
+
+/// 链接中的事件延伸各层链接文字的结束位置
+fn extend_links(open_links: &mut [OpenLink], end: usize) {
+    for link in open_links {
+        link.text_end = link.text_end.max(end);
+    }
+}
+
+/// 链接是否整体原样保留
+///
+/// 自动链接与邮件地址从不翻译；`[文字]` 与 `[文字][]` 形式的引用链接以文字作为标签，
+/// 翻译后无法对应到链接定义。
+fn keeps_link(link_type: LinkType, links: LinkTranslation) -> bool {
+    links == LinkTranslation::None
+        || matches!(
+            link_type,
+            LinkType::Autolink
+                | LinkType::Email
+                | LinkType::Shortcut
+                | LinkType::ShortcutUnknown
+                | LinkType::Collapsed
+                | LinkType::CollapsedUnknown
+        )
+}
+
+/// 行内标签（强调、链接等）不结束所在的块
+fn is_inline(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Emphasis
+            | Tag::Strong
+            | Tag::Strikethrough
+            | Tag::Superscript
+            | Tag::Subscript
+            | Tag::Link { .. }
+            | Tag::Image { .. }
+    )
+}
+
+/// 行内标签的结束
+fn is_inline_end(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Superscript
+            | TagEnd::Subscript
+            | TagEnd::Link
+            | TagEnd::Image
+    )
+}
+
+/// 在链接或链接定义的原文中找到标题的位置
+///
+/// 标题位于末尾，从后向前查找；标题含有转义字符时原文与解析结果不同，不翻译。
+fn find_title(input: &str, range: Range<usize>, title: &str) -> Option<Span> {
+    if title.trim().is_empty() {
+        return None;
+    }
+    let start = range.start + input[range.clone()].rfind(title)?;
+    let quote = match input[..start].chars().next_back()? {
+        '"' => '"',
+        '\'' => '\'',
+        '(' => ')',
+        _ => return None,
+    };
+    Some(Span::Title {
+        range: start..start + title.len(),
+        quote,
+    })
+}
+
+/// 转义译文中与标题结束引号相同的字符
+fn escape_title(translation: &str, quote: char) -> String {
+    translation.replace(quote, &format!("\\{}", quote))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uppercase_all(doc: &MarkdownDocument) -> String {
+        let translations: Vec<Option<String>> = doc
+            .segments()
+            .into_iter()
+            .map(|s| Some(s.to_uppercase()))
+            .collect();
+        doc.render(&translations).unwrap()
+    }
+
+    #[test]
+    fn test_preserves_structure_and_code() {
+        let input = "# Title\n\nSome *emphasis* and `code`.\n\n```rust\nlet x = 1;\n```\n\n- item one\n- item two\n";
+        let doc = MarkdownDocument::parse(input, LinkTranslation::Text);
+
+        assert_eq!(
+            doc.segments(),
+            ["Title", "Some *emphasis* and ⟦1⟧.", "item one", "item two"]
+        );
+        assert_eq!(
+            uppercase_all(&doc),
+            "# TITLE\n\nSOME *EMPHASIS* AND `code`.\n\n```rust\nlet x = 1;\n```\n\n- ITEM ONE\n- ITEM TWO\n"
+        );
+    }
+
+    #[test]
+    fn test_soft_breaks_stay_in_one_segment() {
+        let doc = MarkdownDocument::parse("first line\nsecond line\n", LinkTranslation::Text);
+        assert_eq!(doc.segments(), ["first line\nsecond line"]);
+    }
+
+    #[test]
+    fn test_link_text_and_image_alt() {
+        let input = "See [the docs](https://example.com/a_(b) \"Docs (v2)\") and ![a cat](cat.png \"My (cute) cat\").\n";
+
+        let doc = MarkdownDocument::parse(input, LinkTranslation::Text);
+        assert_eq!(
+            uppercase_all(&doc),
+            "SEE [THE DOCS](https://example.com/a_(b) \"Docs (v2)\") AND ![A CAT](cat.png \"My (cute) cat\").\n"
+        );
+
+        // 标题中的括号不影响定位
+        let doc = MarkdownDocument::parse(input, LinkTranslation::All);
+        assert_eq!(
+            uppercase_all(&doc),
+            "SEE [THE DOCS](https://example.com/a_(b) \"DOCS (V2)\") AND ![A CAT](cat.png \"MY (CUTE) CAT\").\n"
+        );
+
+        let doc = MarkdownDocument::parse(input, LinkTranslation::None);
+        assert_eq!(doc.segments(), ["See ⟦1⟧ and ⟦2⟧."]);
+    }
+
+    #[test]
+    fn test_reference_style_links() {
+        let input = "Read [the guide][guide] or [FAQ].\n\n[guide]: https://example.com/guide 'Guide title'\n[FAQ]: https://example.com/faq\n";
+
+        let doc = MarkdownDocument::parse(input, LinkTranslation::Text);
+        assert_eq!(
+            uppercase_all(&doc),
+            "READ [THE GUIDE][guide] OR [FAQ].\n\n[guide]: https://example.com/guide 'Guide title'\n[FAQ]: https://example.com/faq\n"
+        );
+
+        let doc = MarkdownDocument::parse(input, LinkTranslation::All);
+        assert_eq!(
+            uppercase_all(&doc),
+            "READ [THE GUIDE][guide] OR [FAQ].\n\n[guide]: https://example.com/guide 'GUIDE TITLE'\n[FAQ]: https://example.com/faq\n"
+        );
+    }
+
+    #[test]
+    fn test_autolinks_and_html_are_never_touched() {
+        let input = "Visit <https://example.com> or <me@example.com>.\n\n<div>raw html</div>\n";
+        let doc = MarkdownDocument::parse(input, LinkTranslation::All);
+
+        assert_eq!(doc.segments(), ["Visit ⟦1⟧ or ⟦2⟧."]);
+        assert_eq!(
+            uppercase_all(&doc),
+            "VISIT <https://example.com> OR <me@example.com>.\n\n<div>raw html</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_failed_segments_keep_original_and_titles_are_escaped() {
+        let input = "[link](url \"title\") text\n";
+        let doc = MarkdownDocument::parse(input, LinkTranslation::All);
+        assert_eq!(doc.segments(), ["[link]⟦1⟧ text", "title"]);
+
+        let rendered = doc.render(&[None, Some("say \"hi\"".to_string())]).unwrap();
+        assert_eq!(rendered, "[link](url \"say \\\"hi\\\"\") text\n");
+    }
+
+    #[test]
+    fn test_inline_markup_stays_in_one_segment() {
+        let input = "Press **Save** to keep [your changes](save.html) and `exit`.\n";
+        let doc = MarkdownDocument::parse(input, LinkTranslation::Text);
+
+        assert_eq!(
+            doc.segments(),
+            ["Press **Save** to keep [your changes]⟦1⟧ and ⟦2⟧."]
+        );
+        assert_eq!(doc.notes(), [Some(PLACEHOLDER_NOTE.to_string())]);
+
+        // 占位标记可以随译文语序调整位置
+        let rendered = doc
+            .render(&[Some("按 **保存** 以 ⟦2⟧ 前保留[你的修改]⟦1⟧。".to_string())])
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "按 **保存** 以 `exit` 前保留[你的修改](save.html)。\n"
+        );
+    }
+
+    #[test]
+    fn test_placeholder_mismatch_keeps_original() {
+        let input = "Run `make` first.\n\nThen relax.\n";
+        let doc = MarkdownDocument::parse(input, LinkTranslation::Text);
+        let warnings = Warnings::new(true);
+
+        let checked = doc.check(
+            vec![
+                Some("先运行 make。".to_string()),
+                Some("然后休息。".to_string()),
+            ],
+            &warnings,
+        );
+        assert_eq!(checked, [None, Some("然后休息。".to_string())]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            doc.render(&checked).unwrap(),
+            "Run `make` first.\n\n然后休息。\n"
+        );
+    }
+
+    #[test]
+    fn test_block_quotes_lists_and_tables() {
+        let input = "> quoted first line\n> quoted second line\n\n- item\n  continued\n- [ ] task\n\n| Name | Note |\n|------|------|\n| a | `b` |\n";
+        let doc = MarkdownDocument::parse(input, LinkTranslation::Text);
+
+        assert_eq!(
+            doc.segments(),
+            [
+                "quoted first line\nquoted second line",
+                "item\ncontinued",
+                "task",
+                "Name",
+                "Note",
+                "a",
+            ]
+        );
+        assert_eq!(
+            uppercase_all(&doc),
+            "> QUOTED FIRST LINE\n> QUOTED SECOND LINE\n\n- ITEM\n  CONTINUED\n- [ ] TASK\n\n| NAME | NOTE |\n|------|------|\n| A | `b` |\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refused_segment_keeps_source() {
+        use crate::batch::OnError;
+        use crate::formats::{SegmentOptions, uppercase_refusing};
+        use crate::warning::Warnings;
+
+        let input = "# Title\n\nSee *this* part.\n\n- refused item\n- other item\n";
+        let doc = MarkdownDocument::parse(input, LinkTranslation::Text);
+        let options = SegmentOptions {
+            on_error: OnError::KeepOriginal,
+            ..SegmentOptions::default()
+        };
+        let warnings = Warnings::new(true);
+        let translations = uppercase_refusing(doc.segments(), "refused item", &options, &warnings)
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        let rendered = doc.render(&translations).unwrap();
+        assert_eq!(
+            rendered,
+            "# TITLE\n\nSEE *THIS* PART.\n\n- refused item\n- OTHER ITEM\n"
+        );
+        // 输出仍是结构相同的 Markdown
+        let reparsed = MarkdownDocument::parse(&rendered, LinkTranslation::Text);
+        assert_eq!(reparsed.segments().len(), doc.segments().len());
+        assert!(reparsed.segments().contains(&"refused item".to_string()));
+    }
+}
@@ -0,0 +1,280 @@
+//! 文档格式处理
+//!
+//! 各格式处理器将文档解析为待翻译的片段，翻译完成后再按原结构重新组装。
+//! 片段翻译失败（如被提供商的内容过滤拒绝）时按 [`SegmentOptions::on_error`] 处理：默认保留
+//! 原文，也可以替换为占位标记，两者都记录警告且不影响文档其余部分；[`OnError::Stop`] 时
+//! 停止翻译并返回错误，不输出不完整的文档。
+
+pub mod code;
+pub mod csv;
+pub mod detect;
+pub mod diff;
+#[cfg(feature = "docx")]
+pub mod docx;
+#[cfg(feature = "html")]
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod properties;
+
+use std::future::Future;
+
+use clap::ValueEnum;
+
+use crate::batch::{self, BatchOptions, DEFAULT_CONCURRENCY, ItemStatus, OnError, SystemClock};
+use crate::error::Result;
+use crate::warning::{WarningKind, Warnings};
+
+/// 输入文本的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// 按 --file 的扩展名与内容推断，无法推断时按纯文本处理
+    #[default]
+    Auto,
+    /// 纯文本，整体翻译
+    Text,
+    /// CSV，仅翻译指定列
+    Csv,
+    /// Markdown，仅翻译文字，保留标记、代码与链接地址
+    Markdown,
+    /// HTML，仅翻译文本节点，保留标签与属性（需启用 html 特性）
+    Html,
+    /// 源代码，仅翻译注释，保留代码与字符串
+    Code,
+    /// Word 文档，仅翻译正文文字，保留格式、表格与超链接（需启用 docx 特性）
+    Docx,
+    /// Java .properties，仅翻译值，保留键、注释与续行
+    Properties,
+    /// .env 风格的 KEY=value 文件，仅翻译值
+    Kv,
+    /// JSON，翻译字符串值（--translate-strings），可选翻译键（--translate-keys）
+    Json,
+}
+
+/// 按片段翻译的文档
+///
+/// 各格式的文档都以同样的流程翻译：取出 [`segments`](Document::segments) 与对应的
+/// [`notes`](Document::notes) 并发翻译，译文经 [`check`](Document::check) 检查后交给
+/// [`render`](Document::render) 按原结构重新组装。
+pub trait Document {
+    /// 重新组装后的文档
+    type Output;
+
+    /// 待翻译的片段
+    fn segments(&self) -> Vec<String>;
+
+    /// 与 `segments()` 一一对应的译者注，格式不提供译者注时为空
+    fn notes(&self) -> Vec<Option<String>> {
+        Vec::new()
+    }
+
+    /// 检查译文，不符合格式要求的译文记录警告并改为 None（保留原文），默认不检查
+    fn check(
+        &self,
+        translations: Vec<Option<String>>,
+        _warnings: &Warnings,
+    ) -> Vec<Option<String>> {
+        translations
+    }
+
+    /// 使用翻译结果重新生成文档
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    fn render(&self, translations: &[Option<String>]) -> Result<Self::Output>;
+}
+
+/// 默认的占位标记
+pub const DEFAULT_ERROR_PLACEHOLDER: &str = "⟦UNTRANSLATED⟧";
+
+/// 翻译文档片段的选项
+#[derive(Debug, Clone)]
+pub struct SegmentOptions {
+    /// 同时进行的最大请求数
+    pub concurrency: usize,
+    /// 片段翻译失败时的处理方式，[`OnError::Continue`] 与 [`OnError::KeepOriginal`] 相同
+    pub on_error: OnError,
+    /// [`OnError::Placeholder`] 时代替译文的标记
+    pub placeholder: String,
+}
+
+impl Default for SegmentOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            on_error: OnError::default(),
+            placeholder: DEFAULT_ERROR_PLACEHOLDER.to_string(),
+        }
+    }
+}
+
+/// 并发翻译文档片段
+///
+/// `notes` 为与片段一一对应的译者注（可以比片段少，缺少的视为没有），随片段一起交给
+/// `translate`。返回与输入一一对应的结果：保留原文的片段为 None，替换为占位标记的片段为
+/// 该标记，两者都记录警告；[`OnError::Stop`] 时第一个失败的片段之后不再派发，返回该错误。
+/// 认证失败时无论 `on_error` 如何设置都返回错误。
+pub async fn translate_segments<F, Fut>(
+    segments: Vec<String>,
+    notes: &[Option<String>],
+    options: &SegmentOptions,
+    warnings: &Warnings,
+    translate: F,
+) -> anyhow::Result<Vec<Option<String>>>
+where
+    F: Fn(String, Option<String>) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+{
+    let batch_options = BatchOptions {
+        concurrency: options.concurrency,
+        on_error: options.on_error,
+        ..BatchOptions::default()
+    };
+    let note = |index: usize| notes.get(index).cloned().flatten();
+    let report =
+        batch::run_batch_indexed(segments, batch_options, &SystemClock, |index, segment| {
+            translate(segment, note(index))
+        })
+        .await;
+
+    // 认证失败时其余片段都没有翻译，输出的文档没有意义
+    if report.auth_failed {
+        let error = report
+            .into_first_error()
+            .unwrap_or_else(|| anyhow::anyhow!("翻译已中止"));
+        return Err(error.context("认证失败，已停止翻译"));
+    }
+
+    let mut translations = Vec::with_capacity(report.items.len());
+    for item in report.items {
+        let translation = match item.status {
+            ItemStatus::Succeeded(translation) => Some(translation),
+            ItemStatus::Failed(e) if options.on_error == OnError::Stop => {
+                return Err(e.context(format!(
+                    "第 {} 个片段翻译失败，已按 --on-error fail 停止",
+                    item.index + 1
+                )));
+            }
+            ItemStatus::Failed(e) if options.on_error == OnError::Placeholder => {
+                warnings.warn(
+                    WarningKind::SegmentFailed,
+                    format!(
+                        "第 {} 个片段翻译失败，已替换为 {}：{}",
+                        item.index + 1,
+                        options.placeholder,
+                        e
+                    ),
+                );
+                Some(options.placeholder.clone())
+            }
+            ItemStatus::Failed(e) => {
+                warnings.warn(
+                    WarningKind::SegmentFailed,
+                    format!("第 {} 个片段翻译失败，已保留原文：{}", item.index + 1, e),
+                );
+                None
+            }
+            ItemStatus::NotStarted => None,
+        };
+        translations.push(translation);
+    }
+    Ok(translations)
+}
+
+/// 是否包含需要翻译的文字，只有空白或标点时不翻译
+pub(crate) fn has_words(text: &str) -> bool {
+    text.chars().any(char::is_alphanumeric)
+}
+
+/// 在译文两侧补回原文首尾的空白
+pub(crate) fn with_surrounding_whitespace(original: &str, translation: &str) -> String {
+    let leading = &original[..original.len() - original.trim_start().len()];
+    let trailing = &original[original.trim_end().len()..];
+    format!("{}{}{}", leading, translation.trim(), trailing)
+}
+
+/// 把片段转为大写，`refused` 片段模拟被内容过滤拒绝而失败
+#[cfg(test)]
+pub(crate) async fn uppercase_refusing(
+    segments: Vec<String>,
+    refused: &str,
+    options: &SegmentOptions,
+    warnings: &Warnings,
+) -> anyhow::Result<Vec<Option<String>>> {
+    translate_segments(segments, &[], options, warnings, |text, _| async move {
+        if text == refused {
+            anyhow::bail!("内容被过滤");
+        }
+        Ok(text.to_uppercase())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_translate_segments_keeps_failures_as_none() {
+        let warnings = Warnings::new(true);
+        let segments = vec!["ok".to_string(), "bad".to_string(), "fine".to_string()];
+
+        let notes = [None, None, Some("感叹".to_string())];
+        let options = SegmentOptions {
+            concurrency: 2,
+            ..SegmentOptions::default()
+        };
+
+        let results = translate_segments(segments, &notes, &options, &warnings, |text, note| {
+            let suffix = note.map_or("", |_| "!");
+            async move {
+                if text == "bad" {
+                    anyhow::bail!("boom");
+                }
+                Ok(format!("{}{}", text.to_uppercase(), suffix))
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            results,
+            vec![Some("OK".to_string()), None, Some("FINE!".to_string())]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings.to_vec()[0].kind, WarningKind::SegmentFailed);
+        assert!(warnings.to_vec()[0].message.contains("第 2 个片段"));
+    }
+    #[tokio::test]
+    async fn test_on_error_placeholder_and_fail() {
+        let segments = || vec!["ok".to_string(), "bad".to_string()];
+
+        let warnings = Warnings::new(true);
+        let options = SegmentOptions {
+            on_error: OnError::Placeholder,
+            ..SegmentOptions::default()
+        };
+        let results = uppercase_refusing(segments(), "bad", &options, &warnings)
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![Some("OK".to_string()), Some("⟦UNTRANSLATED⟧".to_string())]
+        );
+        assert!(
+            warnings.to_vec()[0]
+                .message
+                .contains("已替换为 ⟦UNTRANSLATED⟧")
+        );
+
+        let warnings = Warnings::new(true);
+        let options = SegmentOptions {
+            on_error: OnError::Stop,
+            ..SegmentOptions::default()
+        };
+        let error = uppercase_refusing(segments(), "bad", &options, &warnings)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("第 2 个片段"), "{}", error);
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,768 @@
+//! Java `.properties` 与 `.env` 风格的 key=value 文件：只翻译值
+//!
+//! 键、注释、空行以及键与值之间的分隔原样保留，只替换值。`.properties` 中以 `\` 续行、
+//! 跨越多行的值，译文按原有各行的长度比例重新折行，尽量在空白之后断开；非 ASCII 字符默认写成
+//! `\uXXXX` 转义，使用 `--properties-utf8` 时直接写出 UTF-8。
+//!
+//! 值中的 MessageFormat 占位符（`{0}`、`{1,number}` 等）必须原样出现在译文中，
+//! 不一致的译文保留原文（见 [`PropertiesDocument::check_placeholders`]）。
+
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{Document, has_words, with_surrounding_whitespace};
+use crate::error::{Result, TransomeError};
+use crate::warning::{WarningKind, Warnings};
+
+/// MessageFormat 风格的占位符
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\s*\w[^{}]*\}").unwrap());
+
+/// 折行时在目标位置之后寻找空白的最大距离（字符数）
+const WRAP_SEARCH_CHARS: usize = 20;
+
+/// key=value 文件的语法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// Java `.properties`；`utf8` 为 true 时非 ASCII 字符不转义
+    Properties { utf8: bool },
+    /// `.env` 风格：`KEY=value`，值可以用单引号或双引号括起
+    Kv,
+}
+
+/// 值在原文中的书写方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Style {
+    /// `.properties` 的值：各续行处原有的分隔（`\`、换行与下一行的缩进），
+    /// 以及各行原有的长度（字符数）
+    Continued {
+        continuations: Vec<String>,
+        widths: Vec<usize>,
+    },
+    /// `.env` 中不带引号的值
+    Bare,
+    /// `.env` 中以指定引号括起的值，范围包含引号
+    Quoted(char),
+}
+
+/// 一个待翻译的值
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    /// 值在原文中的范围
+    range: Range<usize>,
+    /// 去掉转义后的值
+    value: String,
+    style: Style,
+}
+
+/// 解析后的 key=value 文件
+#[derive(Debug, Clone)]
+pub struct PropertiesDocument {
+    source: String,
+    syntax: Syntax,
+    /// 与片段一一对应
+    entries: Vec<Entry>,
+}
+
+impl PropertiesDocument {
+    /// 解析文件内容，空值和只有空白或标点的值不参与翻译
+    pub fn parse(input: &str, syntax: Syntax) -> Result<Self> {
+        let entries = match syntax {
+            Syntax::Properties { .. } => parse_properties(input)?,
+            Syntax::Kv => parse_kv(input),
+        };
+        Ok(Self {
+            source: input.to_string(),
+            syntax,
+            entries: entries
+                .into_iter()
+                .filter(|entry| has_words(&entry.value))
+                .collect(),
+        })
+    }
+
+    /// 检查译文中的占位符，与原文不一致的译文视为失败，记录警告并保留原文
+    pub fn check_placeholders(
+        &self,
+        translations: Vec<Option<String>>,
+        warnings: &Warnings,
+    ) -> Vec<Option<String>> {
+        self.entries
+            .iter()
+            .zip(translations)
+            .map(|(entry, translation)| {
+                let translation = translation?;
+                if placeholders(&entry.value) == placeholders(&translation) {
+                    return Some(translation);
+                }
+                warnings.warn(
+                    WarningKind::PlaceholderMismatch,
+                    format!(
+                        "键 '{}' 的译文中占位符与原文不一致，已保留原文：{}",
+                        entry.key, translation
+                    ),
+                );
+                None
+            })
+            .collect()
+    }
+
+    /// 按原值的书写方式写出译文
+    fn encode(&self, style: &Style, text: &str) -> String {
+        let utf8 = match self.syntax {
+            Syntax::Properties { utf8 } => utf8,
+            Syntax::Kv => true,
+        };
+        match style {
+            Style::Continued {
+                continuations,
+                widths,
+            } => {
+                let mut output = String::new();
+                for (index, line) in wrap(text, widths).iter().enumerate() {
+                    if index > 0 {
+                        output.push_str(&continuations[index - 1]);
+                    }
+                    output.push_str(&escape_properties(line, utf8));
+                }
+                output
+            }
+            Style::Quoted('\'') if !text.contains('\'') => format!("'{}'", text),
+            Style::Quoted(_) => format!("\"{}\"", escape_double_quoted(text)),
+            Style::Bare if needs_quotes(text) => format!("\"{}\"", escape_double_quoted(text)),
+            Style::Bare => text.to_string(),
+        }
+    }
+}
+
+impl Document for PropertiesDocument {
+    type Output = String;
+
+    /// 待翻译的片段（按文件中的顺序，去掉首尾空白）
+    fn segments(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| entry.value.trim().to_string())
+            .collect()
+    }
+
+    fn check(&self, translations: Vec<Option<String>>, warnings: &Warnings) -> Vec<Option<String>> {
+        self.check_placeholders(translations, warnings)
+    }
+
+    /// 使用翻译结果重新生成文件
+    ///
+    /// `translations` 与 `segments()` 一一对应，None 表示保留原文。
+    fn render(&self, translations: &[Option<String>]) -> Result<String> {
+        let mut output = String::with_capacity(self.source.len());
+        let mut last = 0;
+        for (entry, translation) in self.entries.iter().zip(translations) {
+            let Some(translation) = translation else {
+                continue;
+            };
+            let text = with_surrounding_whitespace(&entry.value, translation);
+            output.push_str(&self.source[last..entry.range.start]);
+            output.push_str(&self.encode(&entry.style, &text));
+            last = entry.range.end;
+        }
+        output.push_str(&self.source[last..]);
+        Ok(output)
+    }
+}
+
+/// 各行内容的范围，不含换行符（`\n` 或 `\r\n`）
+fn lines(input: &str) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (index, _) in input.match_indices('\n') {
+        let end = if input[start..index].ends_with('\r') {
+            index - 1
+        } else {
+            index
+        };
+        lines.push(start..end);
+        start = index + 1;
+    }
+    if start < input.len() {
+        lines.push(start..input.len());
+    }
+    lines
+}
+
+/// `.properties` 中的空白（空格、制表符与换页符）
+fn is_properties_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\x0c')
+}
+
+/// 行尾是否有续行符（奇数个反斜杠）
+fn ends_with_continuation(line: &str) -> bool {
+    let backslashes = line.len() - line.trim_end_matches('\\').len();
+    backslashes % 2 == 1
+}
+
+/// 解析 `.properties` 文件中的键值对
+fn parse_properties(input: &str) -> Result<Vec<Entry>> {
+    let lines = lines(input);
+    let mut entries = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].clone();
+        index += 1;
+        let text = &input[line.clone()];
+        let first = line.end - text.trim_start_matches(is_properties_whitespace).len();
+        let text = &input[first..line.end];
+        if text.is_empty() || text.starts_with(['#', '!']) {
+            continue;
+        }
+
+        // 逻辑行由各物理行去掉续行符与下一行开头的空白后拼接而成
+        let mut pieces: Vec<Range<usize>> = Vec::new();
+        pieces.push(first..line.end);
+        loop {
+            let last = pieces.last_mut().unwrap();
+            if !ends_with_continuation(&input[last.clone()]) {
+                break;
+            }
+            last.end -= 1;
+            let Some(next) = lines.get(index).cloned() else {
+                break;
+            };
+            index += 1;
+            let next_text = &input[next.clone()];
+            let indent =
+                next_text.len() - next_text.trim_start_matches(is_properties_whitespace).len();
+            pieces.push(next.start + indent..next.end);
+        }
+
+        let mut logical = String::new();
+        let mut piece_starts = Vec::with_capacity(pieces.len());
+        for piece in &pieces {
+            piece_starts.push(logical.len());
+            logical.push_str(&input[piece.clone()]);
+        }
+
+        let (key_end, value_start) = split_key(&logical);
+        // 值的起点恰好在续行处时，取下一行的开头
+        let piece = piece_starts
+            .iter()
+            .rposition(|&start| start <= value_start)
+            .unwrap_or_default();
+        let source_start = pieces[piece].start + (value_start - piece_starts[piece]);
+        let continuations = pieces[piece..]
+            .windows(2)
+            .map(|pair| input[pair[0].end..pair[1].start].to_string())
+            .collect();
+        let widths = pieces[piece..]
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let start = if index == 0 { source_start } else { line.start };
+                let text = &input[start..line.end];
+                unescape_properties(text).map_or(text.chars().count(), |s| s.chars().count())
+            })
+            .collect();
+
+        entries.push(Entry {
+            key: logical[..key_end].to_string(),
+            range: source_start..pieces.last().unwrap().end,
+            value: unescape_properties(&logical[value_start..])?,
+            style: Style::Continued {
+                continuations,
+                widths,
+            },
+        });
+    }
+    Ok(entries)
+}
+
+/// 键的结束位置与值的起始位置
+///
+/// 键在第一个未转义的 `=`、`:` 或空白处结束；其后的空白、至多一个 `=` 或 `:`
+/// 以及再之后的空白都属于分隔。
+fn split_key(line: &str) -> (usize, usize) {
+    let bytes = line.as_bytes();
+    let is_space = |b: u8| matches!(b, b' ' | b'\t' | 0x0c);
+
+    let mut key_end = 0;
+    while key_end < bytes.len() {
+        match bytes[key_end] {
+            b'\\' => key_end += 2,
+            b'=' | b':' => break,
+            b if is_space(b) => break,
+            _ => key_end += 1,
+        }
+    }
+    let key_end = key_end.min(bytes.len());
+
+    let mut value_start = key_end;
+    while value_start < bytes.len() && is_space(bytes[value_start]) {
+        value_start += 1;
+    }
+    if value_start < bytes.len() && matches!(bytes[value_start], b'=' | b':') {
+        value_start += 1;
+        while value_start < bytes.len() && is_space(bytes[value_start]) {
+            value_start += 1;
+        }
+    }
+    (key_end, value_start)
+}
+
+/// 还原 `.properties` 值中的转义，`\uXXXX` 可以组成代理对
+fn unescape_properties(value: &str) -> Result<String> {
+    let mut units: Vec<u16> = Vec::with_capacity(value.len());
+    let mut buf = [0u16; 2];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            units.extend_from_slice(c.encode_utf16(&mut buf));
+            continue;
+        }
+        // 末尾单独的反斜杠被忽略
+        let Some(escaped) = chars.next() else {
+            break;
+        };
+        let unescaped = match escaped {
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let unit = u16::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 4 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .ok_or_else(|| {
+                        TransomeError::format_error(
+                            "properties",
+                            format!("无效的 Unicode 转义 '\\u{}'", hex),
+                        )
+                    })?;
+                units.push(unit);
+                continue;
+            }
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            'f' => '\x0c',
+            other => other,
+        };
+        units.extend_from_slice(unescaped.encode_utf16(&mut buf));
+    }
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// 转义写入 `.properties` 的一行值，行首的空格转义以免读取时被忽略
+fn escape_properties(text: &str, utf8: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut buf = [0u16; 2];
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '\t' => output.push_str("\\t"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\x0c' => output.push_str("\\f"),
+            ' ' if index == 0 => output.push_str("\\ "),
+            c if !utf8 && !c.is_ascii() => {
+                for unit in c.encode_utf16(&mut buf) {
+                    output.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// 按原有各行的长度比例将译文分成至多 `widths.len()` 行，尽量在空白之后断开
+///
+/// 续行在读取时直接拼接，在词中断开也不会改变值。
+fn wrap<'a>(text: &'a str, widths: &[usize]) -> Vec<&'a str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let total = chars.len();
+    let sum = widths.iter().sum::<usize>().max(1);
+    let mut lines = Vec::with_capacity(widths.len());
+    let mut last = 0;
+    let mut width = 0;
+    for line_width in &widths[..widths.len().saturating_sub(1)] {
+        width += line_width;
+        let target = total * width / sum;
+        if target <= last {
+            continue;
+        }
+        let cut = (target..total.min(target + WRAP_SEARCH_CHARS))
+            .find(|&i| chars[i - 1].1.is_whitespace() && !chars[i].1.is_whitespace())
+            .unwrap_or(target);
+        if cut >= total {
+            continue;
+        }
+        lines.push(&text[chars[last].0..chars[cut].0]);
+        last = cut;
+    }
+    lines.push(&text[chars.get(last).map_or(text.len(), |&(offset, _)| offset)..]);
+    lines
+}
+
+/// 解析 `.env` 风格文件中的键值对
+fn parse_kv(input: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    // 跨越多行的引号值之后的位置，其中的行不再单独解析
+    let mut skip_until = 0;
+    for line in lines(input) {
+        if line.start < skip_until {
+            continue;
+        }
+        let text = input[line.clone()].trim_start();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let body = text.strip_prefix("export ").map_or(text, str::trim_start);
+        let Some(equals) = body.find('=') else {
+            continue;
+        };
+        let key = body[..equals].trim().to_string();
+        let after = &body[equals + 1..];
+        let value_start = line.end - after.trim_start().len();
+        let rest = &input[value_start..];
+
+        let entry = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let Some(length) = closing_quote(&rest[1..], quote) else {
+                    continue;
+                };
+                let inner = &rest[1..1 + length];
+                let end = value_start + length + 2;
+                skip_until = end;
+                Entry {
+                    key,
+                    range: value_start..end,
+                    value: if quote == '"' {
+                        unescape_double_quoted(inner)
+                    } else {
+                        inner.to_string()
+                    },
+                    style: Style::Quoted(quote),
+                }
+            }
+            _ => {
+                let value = &input[value_start..line.end];
+                // 空白之后的 `#` 开始注释
+                let value = value
+                    .find(" #")
+                    .or_else(|| value.find("\t#"))
+                    .map_or(value, |comment| &value[..comment])
+                    .trim_end();
+                Entry {
+                    key,
+                    range: value_start..value_start + value.len(),
+                    value: value.to_string(),
+                    style: Style::Bare,
+                }
+            }
+        };
+        entries.push(entry);
+    }
+    entries
+}
+
+/// 引号值中结束引号的位置，双引号值中 `\` 转义下一个字符
+fn closing_quote(rest: &str, quote: char) -> Option<usize> {
+    let mut chars = rest.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            c if c == quote => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 还原双引号值中的转义
+fn unescape_double_quoted(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some(c @ ('"' | '\\')) => output.push(c),
+            Some(other) => {
+                output.push('\\');
+                output.push(other);
+            }
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+/// 转义写入双引号值的内容
+fn escape_double_quoted(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// 不带引号的值写出译文时是否需要加上引号
+fn needs_quotes(text: &str) -> bool {
+    text.contains(['#', '"', '\'', '\\', '\n']) || text.trim() != text
+}
+
+/// 文本中的占位符（排序后比较，不要求顺序一致）
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut found: Vec<&str> = PLACEHOLDER.find_iter(text).map(|m| m.as_str()).collect();
+    found.sort_unstable();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGES: &str = "# 登录页面\r\n\
+        ! 旧式注释\r\n\
+        login.title = Sign in\r\n\
+        login.welcome=Welcome back, {0}!\r\n\
+        login.help : Forgot your password? \\\r\n    \
+        Click the link below \\\r\n    \
+        to reset it.\r\n\
+        \r\n\
+        login.empty =\r\n\
+        login.copyright=\\u00A9 2024 Example\\u2122\r\n\
+        login\\ hint\\:=Press\\tEnter\r\n";
+
+    const PROPERTIES: Syntax = Syntax::Properties { utf8: false };
+
+    fn uppercase_all(doc: &PropertiesDocument) -> String {
+        let translations: Vec<Option<String>> = doc
+            .segments()
+            .into_iter()
+            .map(|s| Some(s.to_uppercase()))
+            .collect();
+        doc.render(&translations).unwrap()
+    }
+
+    #[test]
+    fn test_properties_segments() {
+        let doc = PropertiesDocument::parse(MESSAGES, PROPERTIES).unwrap();
+        assert_eq!(
+            doc.segments(),
+            [
+                "Sign in",
+                "Welcome back, {0}!",
+                "Forgot your password? Click the link below to reset it.",
+                "© 2024 Example™",
+                "Press\tEnter",
+            ]
+        );
+        let keys: Vec<&str> = doc.entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "login.title",
+                "login.welcome",
+                "login.help",
+                "login.copyright",
+                "login\\ hint\\:"
+            ]
+        );
+
+        // 没有译文时原样输出
+        assert_eq!(
+            doc.render(&[None, None, None, None, None]).unwrap(),
+            MESSAGES
+        );
+    }
+
+    #[test]
+    fn test_properties_round_trip() {
+        let doc = PropertiesDocument::parse(MESSAGES, PROPERTIES).unwrap();
+        let output = uppercase_all(&doc);
+        assert_eq!(
+            output,
+            "# 登录页面\r\n\
+            ! 旧式注释\r\n\
+            login.title = SIGN IN\r\n\
+            login.welcome=WELCOME BACK, {0}!\r\n\
+            login.help : FORGOT YOUR PASSWORD? \\\r\n    \
+            CLICK THE LINK BELOW \\\r\n    \
+            TO RESET IT.\r\n\
+            \r\n\
+            login.empty =\r\n\
+            login.copyright=\\u00A9 2024 EXAMPLE\\u2122\r\n\
+            login\\ hint\\:=PRESS\\tENTER\r\n"
+        );
+
+        let reparsed = PropertiesDocument::parse(&output, PROPERTIES).unwrap();
+        let expected: Vec<String> = doc.segments().iter().map(|s| s.to_uppercase()).collect();
+        assert_eq!(reparsed.segments(), expected);
+    }
+
+    #[test]
+    fn test_properties_unicode_escapes_and_utf8() {
+        let input = "greeting=Hello\nemoji=Party \\uD83C\\uDF89\n";
+        let doc = PropertiesDocument::parse(input, PROPERTIES).unwrap();
+        assert_eq!(doc.segments(), ["Hello", "Party 🎉"]);
+
+        let translations = [Some("你好".to_string()), Some("派对 🎉".to_string())];
+        assert_eq!(
+            doc.render(&translations).unwrap(),
+            "greeting=\\u4F60\\u597D\nemoji=\\u6D3E\\u5BF9 \\uD83C\\uDF89\n"
+        );
+
+        let doc = PropertiesDocument::parse(input, Syntax::Properties { utf8: true }).unwrap();
+        assert_eq!(
+            doc.render(&translations).unwrap(),
+            "greeting=你好\nemoji=派对 🎉\n"
+        );
+
+        let error = PropertiesDocument::parse("bad=\\u12G4\n", PROPERTIES).unwrap_err();
+        assert!(error.to_string().contains("\\u12G4"), "{}", error);
+    }
+
+    #[test]
+    fn test_properties_multiline_value_rewrapped() {
+        let input = "notice=First line of the notice \\\n       continues here\n";
+        let doc = PropertiesDocument::parse(input, Syntax::Properties { utf8: true }).unwrap();
+        assert_eq!(doc.segments(), ["First line of the notice continues here"]);
+
+        // 中文没有空白，按原有各行的长度比例断开；续行处的缩进保留
+        let output = doc
+            .render(&[Some("通知的第一行在这里继续".to_string())])
+            .unwrap();
+        assert_eq!(output, "notice=通知的第一行在\\\n       这里继续\n");
+        assert_eq!(
+            PropertiesDocument::parse(&output, PROPERTIES)
+                .unwrap()
+                .segments(),
+            ["通知的第一行在这里继续"]
+        );
+
+        // 译文太短时不产生空的续行
+        let output = doc.render(&[Some("短".to_string())]).unwrap();
+        assert_eq!(output, "notice=短\n");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("one two three four", &[8, 10]),
+            ["one two ", "three four"]
+        );
+        assert_eq!(
+            wrap("one two three four", &[1, 1]),
+            ["one two three ", "four"]
+        );
+        assert_eq!(wrap("abcdef", &[1, 1, 1]), ["ab", "cd", "ef"]);
+        assert_eq!(wrap("ab", &[1, 1, 1]), ["a", "b"]);
+        assert_eq!(wrap("", &[3, 3]), [""]);
+        assert_eq!(wrap("whole", &[0]), ["whole"]);
+    }
+
+    #[test]
+    fn test_placeholders_are_protected() {
+        let doc = PropertiesDocument::parse(
+            "a=Hello {0}, you have {1,number} messages\nb=Bye {0}\n",
+            PROPERTIES,
+        )
+        .unwrap();
+        let warnings = Warnings::new(true);
+        let translations = doc.check_placeholders(
+            vec![
+                Some("{0} 你好，你有 {1,number} 条消息".to_string()),
+                Some("再见 {name}".to_string()),
+            ],
+            &warnings,
+        );
+        assert_eq!(
+            translations,
+            [Some("{0} 你好，你有 {1,number} 条消息".to_string()), None]
+        );
+        let recorded = warnings.to_vec();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].kind, WarningKind::PlaceholderMismatch);
+        assert!(recorded[0].message.contains("'b'"), "{}", recorded[0]);
+    }
+
+    #[test]
+    fn test_kv_round_trip() {
+        let input = "# 应用文案\n\
+            export APP_TITLE=Welcome\n\
+            APP_SUBTITLE = \"Fast, \\\"simple\\\" notes\" # 副标题\n\
+            APP_FOOTER='Made with care'\n\
+            APP_MOTTO=Keep it simple # 标语\n\
+            APP_INTRO=\"Line one\n\
+            line two\"\n\
+            PORT=8080\n\
+            EMPTY=\n";
+        let doc = PropertiesDocument::parse(input, Syntax::Kv).unwrap();
+        assert_eq!(
+            doc.segments(),
+            [
+                "Welcome",
+                "Fast, \"simple\" notes",
+                "Made with care",
+                "Keep it simple",
+                "Line one\nline two",
+                "8080",
+            ]
+        );
+        assert_eq!(
+            doc.render(&[None, None, None, None, None, None]).unwrap(),
+            input
+        );
+
+        let translations = [
+            Some("欢迎".to_string()),
+            Some("快速、\"简单\"的笔记".to_string()),
+            Some("用心'制作'".to_string()),
+            Some("保持 #简单".to_string()),
+            Some("第一行\n第二行".to_string()),
+            None,
+        ];
+        assert_eq!(
+            doc.render(&translations).unwrap(),
+            "# 应用文案\n\
+            export APP_TITLE=欢迎\n\
+            APP_SUBTITLE = \"快速、\\\"简单\\\"的笔记\" # 副标题\n\
+            APP_FOOTER=\"用心'制作'\"\n\
+            APP_MOTTO=\"保持 #简单\" # 标语\n\
+            APP_INTRO=\"第一行\\n第二行\"\n\
+            PORT=8080\n\
+            EMPTY=\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refused_value_keeps_source() {
+        use crate::batch::OnError;
+        use crate::formats::{SegmentOptions, uppercase_refusing};
+
+        let refused = "Forgot your password? Click the link below to reset it.";
+        let doc = PropertiesDocument::parse(MESSAGES, PROPERTIES).unwrap();
+        let options = SegmentOptions {
+            on_error: OnError::KeepOriginal,
+            ..SegmentOptions::default()
+        };
+        let warnings = Warnings::new(true);
+        let translations = uppercase_refusing(doc.segments(), refused, &options, &warnings)
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        // 保留原文的值连同续行原样输出，文件仍能解析出同样的条目
+        let output = doc.render(&translations).unwrap();
+        assert!(output.contains("login.help : Forgot your password? \\\r\n"));
+        let reparsed = PropertiesDocument::parse(&output, PROPERTIES).unwrap();
+        let expected: Vec<String> = doc
+            .segments()
+            .into_iter()
+            .map(|s| if s == refused { s } else { s.to_uppercase() })
+            .collect();
+        assert_eq!(reparsed.segments(), expected);
+    }
+}
@@ -0,0 +1,188 @@
+//! 使用记录
+//!
+//! 每次翻译运行结束后，向状态目录下的 `history.jsonl` 追加一条记录：时间、模型、
+//! 语言对、条目数、翻译记忆命中数、token 用量与耗时，不包含原文或译文。
+//! `transome stats` 读取这些记录进行汇总（见 [`crate::stats`]），`--no-history` 不记录。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::canary::Script;
+use crate::error::{Result, TransomeError};
+use crate::metrics::Metrics;
+use crate::platform;
+use crate::store;
+use crate::warning::Warnings;
+
+/// 使用记录的文件名
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// 一次翻译运行的记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// 运行结束时刻（Unix 时间戳，秒）
+    pub timestamp: u64,
+    pub model: String,
+    /// 检测到的原文语言，无法检测时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// 目标语言，来自 `--to` 或按原文语言推断
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub to: Vec<String>,
+    /// 翻译的条目数
+    #[serde(default)]
+    pub translations: usize,
+    /// 命中翻译记忆的条目数
+    #[serde(default)]
+    pub memory_hits: usize,
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    /// 运行耗时（毫秒）
+    #[serde(default)]
+    pub elapsed_ms: u64,
+    /// 按语言对选择模型时匹配的路由规则（见 [`crate::routing`]）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<String>,
+}
+
+impl HistoryRecord {
+    /// 由运行统计生成记录，时间为当前时刻
+    pub fn new(
+        model: &str,
+        text: &str,
+        to: &[String],
+        metrics: &Metrics,
+        elapsed: Duration,
+    ) -> Self {
+        let summary = metrics.summary(elapsed);
+        let (from, to) = language_pair(text, to);
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs()),
+            model: model.to_string(),
+            from,
+            to,
+            translations: summary.items,
+            memory_hits: metrics.memory_hits(),
+            prompt_tokens: summary.prompt_tokens,
+            completion_tokens: summary.completion_tokens,
+            elapsed_ms: elapsed.as_millis() as u64,
+            route: None,
+        }
+    }
+
+    /// 设置匹配的路由规则
+    pub fn with_route(mut self, route: Option<String>) -> Self {
+        self.route = route;
+        self
+    }
+
+    /// 记录中的语言对，如 `zh→en`；未知的一侧以 `?` 表示
+    pub fn language_pairs(&self) -> Vec<String> {
+        let from = self.from.as_deref().unwrap_or("?");
+        if self.to.is_empty() {
+            return vec![format!("{}→?", from)];
+        }
+        self.to
+            .iter()
+            .map(|to| format!("{}→{}", from, to))
+            .collect()
+    }
+}
+
+/// 检测原文语言；未指定目标语言时，内置提示词为中英互译，目标语言取另一种
+pub(crate) fn language_pair(text: &str, to: &[String]) -> (Option<String>, Vec<String>) {
+    let from = Script::dominant(text);
+    let to = if !to.is_empty() {
+        to.to_vec()
+    } else {
+        match from {
+            Some(Script::Cjk) => vec!["en".to_string()],
+            Some(Script::Latin) => vec!["zh".to_string()],
+            None => Vec::new(),
+        }
+    };
+    let from = from.map(|script| match script {
+        Script::Cjk => "zh".to_string(),
+        Script::Latin => "en".to_string(),
+    });
+    (from, to)
+}
+
+/// 获取默认的使用记录路径
+///
+/// 优先使用 `XDG_STATE_HOME`，否则为主目录下的 `.local/state`。使用记录不放在配置目录中，
+/// 导出配置包时不会带上。
+pub fn default_path() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| platform::home_dir().map(|home| home.join(".local").join("state")))?;
+
+    Some(state_dir.join("transome").join(HISTORY_FILE_NAME))
+}
+
+/// 追加一条记录，目录不存在时创建
+pub fn append(path: &Path, record: &HistoryRecord) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| {
+            TransomeError::io_error_with_context(e, format!("创建目录 '{}'", dir.display()))
+        })?;
+    }
+    store::append_record(path, record)
+}
+
+/// 读取全部记录，无法解析的行会被跳过并记录警告
+pub fn read(path: &Path, warnings: &Warnings) -> Result<Vec<HistoryRecord>> {
+    store::read_records(path, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_pair() {
+        assert_eq!(
+            language_pair("你好，世界", &[]),
+            (Some("zh".to_string()), vec!["en".to_string()])
+        );
+        assert_eq!(
+            language_pair("Hello", &["ja".to_string(), "fr".to_string()]),
+            (
+                Some("en".to_string()),
+                vec!["ja".to_string(), "fr".to_string()]
+            )
+        );
+        assert_eq!(language_pair("12345", &[]), (None, Vec::new()));
+    }
+
+    #[test]
+    fn test_append_creates_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("state")
+            .join("transome")
+            .join(HISTORY_FILE_NAME);
+        let metrics = Metrics::new();
+        metrics.record_item(true);
+        metrics.record_usage(12, 8);
+        let record =
+            HistoryRecord::new("gpt-4o", "你好", &[], &metrics, Duration::from_millis(800));
+        append(&path, &record).unwrap();
+
+        let records = read(&path, &Warnings::new(true)).unwrap();
+        assert_eq!(records, [record]);
+        assert_eq!(records[0].language_pairs(), ["zh→en"]);
+        assert_eq!(records[0].translations, 1);
+        assert_eq!(records[0].prompt_tokens, 12);
+        assert_eq!(records[0].elapsed_ms, 800);
+    }
+}
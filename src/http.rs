@@ -0,0 +1,190 @@
+//! 长时间运行时的 HTTP 连接管理
+//!
+//! 服务端会关闭空闲过久的连接，长时间运行的进程在一段停顿后的第一个请求可能落在
+//! 已失效的连接上，表现为连接被重置或连接在响应前关闭。这类失败发生在服务处理请求之前，
+//! 换用新连接重发一次即可（见 [`is_stale_connection`]），不计入用户设置的重试次数。
+//!
+//! 连接池中的连接只在建立时解析一次域名，[`HttpClient`] 在客户端使用超过
+//! [`MAX_CLIENT_LIFETIME`] 后重建连接池，使服务地址变更后的请求能连到新地址。
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 连接池中空闲连接的保留时间，短于常见服务端的空闲超时
+pub const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 客户端（及其连接池）的最长使用时间，超过后重建以重新解析域名
+pub const MAX_CLIENT_LIFETIME: Duration = Duration::from_secs(5 * 60);
+
+/// 创建 HTTP 客户端的设置，重建连接池时沿用
+#[derive(Debug, Clone, Default)]
+pub struct ClientSettings {
+    /// 在系统根证书之外信任的证书
+    pub root_certificates: Vec<reqwest::Certificate>,
+    /// 不验证服务端的 TLS 证书
+    pub accept_invalid_certs: bool,
+    /// 单个请求的总超时，None 时不限制
+    pub timeout: Option<Duration>,
+}
+
+impl ClientSettings {
+    /// 按设置创建 HTTP 客户端
+    pub fn build(&self) -> reqwest::Result<reqwest::Client> {
+        let builder = self
+            .root_certificates
+            .iter()
+            .fold(reqwest::Client::builder(), |builder, certificate| {
+                builder.add_root_certificate(certificate.clone())
+            })
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT);
+        match self.timeout {
+            Some(timeout) => builder.timeout(timeout).build(),
+            None => builder.build(),
+        }
+    }
+}
+
+/// 使用时间受限的 HTTP 客户端
+///
+/// 克隆得到的客户端共用同一个连接池。
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    settings: ClientSettings,
+    max_lifetime: Duration,
+    current: Arc<Mutex<Generation>>,
+}
+
+/// 当前使用的客户端及其创建时间
+#[derive(Debug)]
+struct Generation {
+    client: reqwest::Client,
+    created: Instant,
+    /// 已创建的客户端数量
+    count: u64,
+}
+
+impl HttpClient {
+    /// 按设置创建客户端
+    pub fn new(settings: ClientSettings) -> reqwest::Result<Self> {
+        let client = settings.build()?;
+        Ok(Self {
+            settings,
+            max_lifetime: MAX_CLIENT_LIFETIME,
+            current: Arc::new(Mutex::new(Generation {
+                client,
+                created: Instant::now(),
+                count: 1,
+            })),
+        })
+    }
+
+    /// 设置客户端的最长使用时间，默认为 [`MAX_CLIENT_LIFETIME`]
+    pub fn with_max_lifetime(mut self, max_lifetime: Duration) -> Self {
+        self.max_lifetime = max_lifetime;
+        self
+    }
+
+    /// 获取用于发送请求的客户端，超过最长使用时间时先重建
+    pub fn get(&self) -> reqwest::Client {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if current.created.elapsed() >= self.max_lifetime {
+            self.rebuild(&mut current);
+        }
+        current.client.clone()
+    }
+
+    /// 丢弃现有连接池，获取使用新连接的客户端
+    pub fn renew(&self) -> reqwest::Client {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        self.rebuild(&mut current);
+        current.client.clone()
+    }
+
+    /// 重建客户端；设置在创建时已验证过，重建失败时继续使用现有客户端
+    fn rebuild(&self, current: &mut Generation) {
+        match self.settings.build() {
+            Ok(client) => {
+                current.client = client;
+                current.count += 1;
+            }
+            Err(error) => tracing::debug!(error = %error, "重建 HTTP 客户端失败"),
+        }
+        current.created = Instant::now();
+    }
+
+    /// 创建客户端的设置
+    pub fn settings(&self) -> &ClientSettings {
+        &self.settings
+    }
+
+    /// 已创建的客户端数量
+    pub fn generation(&self) -> u64 {
+        self.current.lock().unwrap_or_else(|e| e.into_inner()).count
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        // 与 reqwest::Client::new 相同，默认设置下创建失败说明运行环境有问题
+        Self::new(ClientSettings::default()).expect("无法创建 HTTP 客户端")
+    }
+}
+
+/// 失败是否因为连接已被服务端关闭
+///
+/// 连接被重置、管道断开，或连接在收到响应前关闭。
+pub fn is_stale_connection(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if let Some(io_error) = error.downcast_ref::<std::io::Error>()
+            && matches!(
+                io_error.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+            )
+        {
+            return true;
+        }
+        // hyper 不作为直接依赖，只能按错误信息识别
+        if error
+            .to_string()
+            .contains("connection closed before message completed")
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_rebuilt_after_lifetime() {
+        let client = HttpClient::default();
+        client.get();
+        assert_eq!(client.generation(), 1);
+
+        let client = client.with_max_lifetime(Duration::ZERO);
+        client.get();
+        assert_eq!(client.generation(), 2);
+
+        // 克隆出的客户端共用同一个连接池
+        client.clone().renew();
+        assert_eq!(client.generation(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_connect_error_is_not_stale_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let error = reqwest::get(url).await.unwrap_err();
+        assert!(!is_stale_connection(&error));
+    }
+}
@@ -0,0 +1,151 @@
+//! 批量检查 API 密钥
+//!
+//! 对每个已设置密钥环境变量的提供商并发发送一次轻量请求（见 [`Translator::ping`]），
+//! 按结果分为有效、无效与无法连接三类并汇总。
+//!
+//! [`Translator::ping`]: crate::translator::Translator::ping
+
+use std::fmt;
+use std::future::Future;
+
+use futures::future::join_all;
+
+use crate::config::Provider;
+use crate::error::TransomeError;
+
+/// 单个密钥的检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyStatus {
+    /// 密钥可用
+    Valid,
+    /// 服务拒绝了密钥
+    Invalid(String),
+    /// 无法确认密钥是否可用（网络错误、服务异常等）
+    Unreachable(String),
+}
+
+impl KeyStatus {
+    /// 根据检查请求的结果判断密钥状态
+    pub fn from_result(result: anyhow::Result<()>) -> Self {
+        match result {
+            Ok(()) => KeyStatus::Valid,
+            Err(e) => match e.downcast_ref::<TransomeError>() {
+                Some(error) if error.is_auth_error() => KeyStatus::Invalid(e.to_string()),
+                _ => KeyStatus::Unreachable(format!("{:#}", e)),
+            },
+        }
+    }
+}
+
+/// 某个提供商的检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCheck {
+    pub provider: Provider,
+    pub status: KeyStatus,
+}
+
+impl fmt::Display for KeyCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): ", self.provider.name, self.provider.env_var)?;
+        match &self.status {
+            KeyStatus::Valid => write!(f, "有效"),
+            KeyStatus::Invalid(reason) => write!(f, "无效 - {}", reason),
+            KeyStatus::Unreachable(reason) => write!(f, "无法连接 - {}", reason),
+        }
+    }
+}
+
+/// 并发检查已设置环境变量的提供商密钥，按提供商顺序返回结果
+///
+/// 未设置或为空的环境变量会被跳过。
+pub async fn validate_keys<E, F, Fut>(providers: &[Provider], env: E, check: F) -> Vec<KeyCheck>
+where
+    E: Fn(&str) -> Option<String>,
+    F: Fn(Provider, String) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let checks = providers.iter().filter_map(|&provider| {
+        let key = env(provider.env_var).filter(|key| !key.trim().is_empty())?;
+        let check = check(provider, key);
+        Some(async move {
+            KeyCheck {
+                provider,
+                status: KeyStatus::from_result(check.await),
+            }
+        })
+    });
+
+    join_all(checks).await
+}
+
+/// 汇总检查结果，如 `共检查 2 个密钥：1 个有效，1 个无效，0 个无法连接`
+pub fn summary(checks: &[KeyCheck]) -> String {
+    let count = |f: fn(&KeyStatus) -> bool| checks.iter().filter(|c| f(&c.status)).count();
+    format!(
+        "共检查 {} 个密钥：{} 个有效，{} 个无效，{} 个无法连接",
+        checks.len(),
+        count(|s| matches!(s, KeyStatus::Valid)),
+        count(|s| matches!(s, KeyStatus::Invalid(_))),
+        count(|s| matches!(s, KeyStatus::Unreachable(_))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::known_providers;
+    use anyhow::anyhow;
+
+    /// 模拟服务：只接受名为 "valid" 的密钥，"offline" 模拟网络不可达
+    async fn mock_check(_provider: Provider, key: String) -> anyhow::Result<()> {
+        match key.as_str() {
+            "valid" => Ok(()),
+            "offline" => Err(anyhow!("connection refused")),
+            _ => Err(TransomeError::authentication_error("Incorrect API key provided").into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_valid_and_invalid_keys() {
+        let providers = known_providers();
+        let checks = validate_keys(
+            &providers,
+            |var| match var {
+                "OPENAI_API_KEY" => Some("valid".to_string()),
+                "GOOGLE_AI_API_KEY" => Some("wrong".to_string()),
+                _ => None,
+            },
+            mock_check,
+        )
+        .await;
+
+        assert_eq!(checks.len(), 2);
+        for check in &checks {
+            match check.provider.name {
+                "OpenAI" => assert_eq!(check.status, KeyStatus::Valid),
+                _ => assert!(matches!(check.status, KeyStatus::Invalid(_))),
+            }
+        }
+        assert_eq!(
+            summary(&checks),
+            "共检查 2 个密钥：1 个有效，1 个无效，0 个无法连接"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unset_keys_are_skipped() {
+        let providers = known_providers();
+        let checks = validate_keys(
+            &providers,
+            |var| (var == "OPENAI_API_KEY").then(|| "offline".to_string()),
+            mock_check,
+        )
+        .await;
+
+        assert_eq!(checks.len(), 1);
+        assert_eq!(
+            checks[0].to_string(),
+            "OpenAI (OPENAI_API_KEY): 无法连接 - connection refused"
+        );
+    }
+}
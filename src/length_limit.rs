@@ -0,0 +1,170 @@
+//! 译文长度上限
+//!
+//! 界面文案的译文需要放得进原来的控件，`--max-output-ratio` 限制译文与原文的长度之比。
+//! 长度按字素计数，宽字符（CJK 等）计为 2，与控件中的实际占位大致相当：
+//! "设置" 与 "Settings" 的长度分别为 4 和 8。
+//!
+//! 提示词中会加入长度要求；译文仍然超出时，以更严格的要求重新翻译一次，
+//! 再超出则保留第二次的结果并标记为超长。
+
+use std::fmt;
+use std::future::Future;
+
+use anyhow::Result;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::util::char_width;
+
+/// 文本的长度：按字素计数，宽字符计为 2
+pub fn output_length(text: &str) -> usize {
+    text.trim()
+        .graphemes(true)
+        .map(|grapheme| grapheme.chars().next().map_or(0, char_width))
+        .sum()
+}
+
+/// 译文与原文的长度之比，原文为空时为 0
+pub fn length_ratio(source: &str, output: &str) -> f64 {
+    match output_length(source) {
+        0 => 0.0,
+        source_len => output_length(output) as f64 / source_len as f64,
+    }
+}
+
+/// 提示词中的长度要求
+pub fn length_clause(max_ratio: f64) -> String {
+    format!(
+        "译文长度（CJK 字符计为 2 个字符）不超过原文的 {} 倍。",
+        max_ratio
+    )
+}
+
+/// 重新翻译时使用的更严格的长度要求
+fn stricter_length_clause(source: &str, max_ratio: f64) -> String {
+    let limit = (output_length(source) as f64 * max_ratio).floor() as usize;
+    format!(
+        "上一次的译文过长。译文长度必须严格控制在 {} 个字符以内（CJK 字符计为 2 个字符），\
+        必要时使用更简短的说法或常用缩写。",
+        limit
+    )
+}
+
+/// 译文超出长度上限
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthViolation {
+    /// 实际的长度之比
+    pub ratio: f64,
+    /// 允许的上限
+    pub max_ratio: f64,
+}
+
+impl fmt::Display for LengthViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "译文长度为原文的 {:.2} 倍，超过上限 {}",
+            self.ratio, self.max_ratio
+        )
+    }
+}
+
+/// 检查译文是否超出长度上限
+pub fn check(source: &str, output: &str, max_ratio: f64) -> Option<LengthViolation> {
+    let ratio = length_ratio(source, output);
+    (ratio > max_ratio).then_some(LengthViolation { ratio, max_ratio })
+}
+
+/// 翻译并检查长度，超出时以更严格的要求重新翻译一次
+///
+/// `translate` 接收额外的提示词要求（首次为 None）。返回最终译文及其仍然超出的情况。
+pub async fn enforce<F, Fut>(
+    source: &str,
+    max_ratio: f64,
+    translate: F,
+) -> Result<(String, Option<LengthViolation>)>
+where
+    F: Fn(Option<String>) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let output = translate(None).await?;
+    let Some(violation) = check(source, &output, max_ratio) else {
+        return Ok((output, None));
+    };
+
+    tracing::debug!(ratio = violation.ratio, max_ratio, "译文超长，重新翻译");
+    let output = translate(Some(stricter_length_clause(source, max_ratio))).await?;
+    let violation = check(source, &output, max_ratio);
+    Ok((output, violation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_output_length() {
+        assert_eq!(output_length("Settings"), 8);
+        assert_eq!(output_length("设置"), 4);
+        assert_eq!(output_length("保存 file"), 9);
+        // 组合字符与其基字符计为一个字素
+        assert_eq!(output_length("cafe\u{301}"), 4);
+        assert_eq!(output_length("  trimmed  "), 7);
+    }
+
+    #[test]
+    fn test_length_ratio_cjk_and_latin() {
+        // 中译英：4 → 8
+        assert_eq!(length_ratio("设置", "Settings"), 2.0);
+        // 英译中：8 → 4
+        assert_eq!(length_ratio("Settings", "设置"), 0.5);
+        // 中译中的同等长度
+        assert_eq!(length_ratio("取消", "返回"), 1.0);
+        assert_eq!(length_ratio("", "anything"), 0.0);
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check("Save", "保存", 1.3), None);
+        let violation = check("Save", "Enregistrer", 1.3).unwrap();
+        assert_eq!(violation.ratio, 2.75);
+        assert_eq!(
+            violation.to_string(),
+            "译文长度为原文的 2.75 倍，超过上限 1.3"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enforce_retries_once_with_stricter_clause() {
+        let clauses = Mutex::new(Vec::new());
+        let (output, violation) = enforce("Save", 1.3, |clause: Option<String>| {
+            let output = if clause.is_some() {
+                "Sauver"
+            } else {
+                "Enregistrer"
+            };
+            clauses.lock().unwrap().push(clause);
+            async move { Ok(output.to_string()) }
+        })
+        .await
+        .unwrap();
+
+        // 第二次仍然超长（6 / 4 = 1.5），保留结果并标记
+        assert_eq!(output, "Sauver");
+        assert_eq!(violation.unwrap().ratio, 1.5);
+
+        let clauses = clauses.into_inner().unwrap();
+        assert_eq!(clauses.len(), 2);
+        assert!(clauses[0].is_none());
+        assert!(clauses[1].as_ref().unwrap().contains("5 个字符以内"));
+    }
+
+    #[tokio::test]
+    async fn test_enforce_accepts_short_output() {
+        let (output, violation) = enforce("Settings", 1.0, |_| async { Ok("设置".to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(output, "设置");
+        assert_eq!(violation, None);
+    }
+}
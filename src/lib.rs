@@ -1,27 +1,117 @@
 //! Transome - 命令行翻译工具库
 
 // 声明所有模块
+pub mod batch;
+pub mod benchmark;
+#[cfg(feature = "bundle")]
+pub mod bundle;
+pub mod canary;
+pub mod checkpoint;
+pub mod chunk;
 pub mod cli;
 pub mod config;
+pub mod config_file;
+pub mod content;
+pub mod direction;
+pub mod effective_config;
+pub mod empty_response;
 pub mod error;
+pub mod escalation;
+pub mod estimate;
+pub mod exit_code;
+pub mod features;
+pub mod formats;
+pub mod history;
+pub mod http;
+pub mod key_check;
+pub mod length_limit;
+#[cfg(feature = "logging")]
+pub mod logging;
+pub mod logit_bias;
+pub mod metrics;
+pub mod model_weights;
+pub mod multi_target;
+pub mod names;
+pub mod output;
+pub mod output_encoding;
+pub mod platform;
+pub mod postprocess;
+pub mod progress;
+pub mod range;
+pub mod reload;
+pub mod request_id;
+pub mod request_template;
+pub mod response_dump;
+pub mod retry;
+pub mod review;
+pub mod routing;
+pub mod sampling;
+pub mod sandbox;
+pub mod segment_cache;
+pub mod sink;
+pub mod source;
+pub mod stats;
+pub mod store;
+#[cfg(feature = "strip-markdown")]
+pub mod strip_markdown;
+#[cfg(test)]
+mod test_util;
+pub mod tm;
 pub mod translator;
+pub mod util;
+pub mod walk;
+pub mod warning;
+#[cfg(feature = "from-url")]
+pub mod web;
 
 // 重新导出主要的公共接口
 
+// 从 batch 模块导出
+pub use batch::{
+    BatchError, BatchOptions, BatchOutcome, BatchReport, ItemFailure, ItemResult, ItemStatus,
+    run_batch, run_batch_indexed, run_batch_resumed, run_batch_streaming,
+};
+
 // 从 cli 模块导出
-pub use cli::Cli;
+pub use cli::{Cli, Command, ConfigCommand};
 
 // 从 config 模块导出
 pub use config::{
-    ModelConfig, create_model_error_message, get_all_models, get_model_url, get_provider_name,
-    get_supported_model_names, is_model_supported as config_is_model_supported, list_models,
+    ModelConfig, create_model_error_message, deprecation_notice, get_all_models, get_deprecation,
+    get_model_url, get_provider_name, get_supported_model_names,
+    is_model_supported as config_is_model_supported, list_models, provider_for_url,
 };
 
+// 从 config_file 模块导出
+pub use config_file::ConfigFile;
+
+// 从 effective_config 模块导出
+pub use effective_config::{ConfigReport, EffectiveConfig, Provenance};
+
 // 从 error 模块导出
+#[cfg(feature = "serde")]
+pub use error::ErrorInfo;
 pub use error::{Result, TransomeError};
 
+// 从 output 模块导出
+pub use output::{JsonOutput, render_compact_error, render_error};
+
+// 从 request_template 模块导出
+pub use request_template::RequestTemplate;
+
+// 从 retry 模块导出
+pub use retry::RetryPolicy;
+
 // 从 translator 模块导出
-pub use translator::{PROMPT, Translator};
+pub use translator::{
+    PROMPT, TranslateOptions, Translation, TranslationRequest, Translator, directed_prompt,
+};
+
+// 从 util 模块导出
+pub use util::{display_width, parse_duration, wrap_text};
+
+// 从 warning 模块导出
+pub use warning::{Warning, WarningKind, Warnings};
 
 // 类型别名和常量
 /// 版本号
@@ -39,6 +129,8 @@ pub type LibResult<T> = std::result::Result<T, TransomeError>;
 // 功能函数
 
 /// 创建翻译器实例
+///
+/// 设置了 [`translator::TEST_RESPONSE_ENV`] 环境变量时，翻译器原样返回其值，不发送请求。
 pub fn create_translator(
     api_key: String,
     model: String,
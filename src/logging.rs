@@ -0,0 +1,135 @@
+//! 结构化日志（需启用 `logging` 特性）
+
+use clap::ValueEnum;
+use tracing::Subscriber;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::error::{Result, TransomeError};
+
+/// 日志级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogLevel {
+    /// 不输出日志
+    #[default]
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// 创建写入指定目标的日志订阅器
+pub fn subscriber<W>(level: LogLevel, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::from(level))
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_target(false)
+        .finish()
+}
+
+/// 安装写入 stderr 的全局日志订阅器
+///
+/// 日志只写入 stderr，不影响 stdout 上的翻译结果；写入前会擦除进度状态行。
+pub fn init(level: LogLevel) -> Result<()> {
+    tracing::subscriber::set_global_default(subscriber(level, crate::progress::stderr)).map_err(
+        |e| TransomeError::config_error("log-level", format!("初始化日志订阅器失败：{}", e)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translator::Translator;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// 将日志输出收集到内存中的写入器
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl CapturedLogs {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedLogs {
+        type Writer = CapturedLogs;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_translate_error_is_logged_with_span_fields() {
+        let logs = CapturedLogs::default();
+        let _guard = tracing::subscriber::set_default(subscriber(LogLevel::Debug, logs.clone()));
+
+        let translator = Translator::new(
+            "test-key".to_string(),
+            "https://api.openai.com/v1".to_string(),
+            "gpt-4".to_string(),
+        );
+        assert!(translator.translate("   ", None).await.is_err());
+
+        let output = logs.contents();
+        assert!(output.contains("model=gpt-4"));
+        assert!(output.contains("provider=\"OpenAI\""));
+        assert!(output.contains("ERROR"));
+        assert!(output.contains("翻译失败"));
+    }
+
+    #[test]
+    fn test_events_below_level_are_filtered() {
+        let logs = CapturedLogs::default();
+        tracing::subscriber::with_default(subscriber(LogLevel::Warn, logs.clone()), || {
+            tracing::info!("info event");
+            tracing::warn!("warn event");
+        });
+
+        let output = logs.contents();
+        assert!(!output.contains("info event"));
+        assert!(output.contains("warn event"));
+    }
+
+    #[test]
+    fn test_off_level_emits_nothing() {
+        let logs = CapturedLogs::default();
+        tracing::subscriber::with_default(subscriber(LogLevel::Off, logs.clone()), || {
+            tracing::error!("error event");
+        });
+
+        assert!(logs.contents().is_empty());
+    }
+}
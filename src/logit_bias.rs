@@ -0,0 +1,78 @@
+//! 调整指定 token 的出现概率
+//!
+//! `--logit-bias <TOKEN_ID>:<BIAS>` 填充请求的 `logit_bias` 字段，用于避免或强制某些术语。
+//! token ID 依模型的分词器而定；偏置取值范围为 -100 到 100，-100 基本禁止该 token，
+//! 100 基本强制使用。
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// 偏置的最小值
+pub const MIN_BIAS: i32 = -100;
+/// 偏置的最大值
+pub const MAX_BIAS: i32 = 100;
+
+/// 单个 token 的偏置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogitBias {
+    pub token_id: u32,
+    pub bias: i32,
+}
+
+/// 解析 `--logit-bias`：格式为 `<TOKEN_ID>:<BIAS>`
+pub fn parse_logit_bias(s: &str) -> Result<LogitBias, String> {
+    let (token_id, bias) = s
+        .split_once(':')
+        .ok_or_else(|| format!("无效的 logit bias '{}'，格式为 <TOKEN_ID>:<BIAS>", s))?;
+    let token_id = token_id
+        .trim()
+        .parse()
+        .map_err(|_| format!("无效的 token ID '{}'，必须是非负整数", token_id.trim()))?;
+    let bias: i32 = bias
+        .trim()
+        .parse()
+        .map_err(|_| format!("无效的偏置 '{}'，必须是整数", bias.trim()))?;
+    if !(MIN_BIAS..=MAX_BIAS).contains(&bias) {
+        return Err(format!(
+            "偏置必须在 {} 到 {} 之间，当前为 {}",
+            MIN_BIAS, MAX_BIAS, bias
+        ));
+    }
+    Ok(LogitBias { token_id, bias })
+}
+
+/// 转换为请求中的 `logit_bias` 字段，同一 token 重复指定时以最后一次为准
+pub fn to_request_map(biases: &[LogitBias]) -> HashMap<String, Value> {
+    biases
+        .iter()
+        .map(|bias| (bias.token_id.to_string(), Value::from(bias.bias)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logit_bias() {
+        assert_eq!(
+            parse_logit_bias("1234:-100").unwrap(),
+            LogitBias {
+                token_id: 1234,
+                bias: -100
+            }
+        );
+        assert_eq!(parse_logit_bias(" 7 : 5 ").unwrap().bias, 5);
+
+        assert!(parse_logit_bias("1234").unwrap_err().contains("格式"));
+        assert!(parse_logit_bias("abc:5").unwrap_err().contains("token ID"));
+        assert!(parse_logit_bias("-1:5").unwrap_err().contains("token ID"));
+        assert!(parse_logit_bias("12:1.5").unwrap_err().contains("整数"));
+        assert!(
+            parse_logit_bias("12:101")
+                .unwrap_err()
+                .contains("-100 到 100")
+        );
+    }
+}
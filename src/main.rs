@@ -1,68 +1,1769 @@
 //! Transome 命令行程序入口
 
-use anyhow::Result;
-use transome::{Cli, Translator};
+use std::io::{self, IsTerminal};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
+use tokio::sync::Semaphore;
+use transome::batch::{self, OnError, SystemClock};
+use transome::benchmark::ThroughputMeter;
+#[cfg(feature = "bundle")]
+use transome::bundle;
+use transome::checkpoint::{BatchInput, Checkpoint};
+use transome::chunk::{self, CHUNK_SEPARATOR, Chunk, ChunkPlan};
+use transome::direction::DirectionSession;
+use transome::effective_config::Inputs;
+use transome::estimate::{Estimate, FileEstimate};
+#[cfg(feature = "docx")]
+use transome::formats::docx::DocxDocument;
+#[cfg(feature = "html")]
+use transome::formats::html::HtmlDocument;
+use transome::formats::{
+    self, Document, Format,
+    code::CodeDocument,
+    csv::CsvDocument,
+    diff::DiffDocument,
+    json::{self as json_format, JsonDocument, Targets},
+    markdown::MarkdownDocument,
+    properties::{PropertiesDocument, Syntax},
+};
+use transome::history::{self, HistoryRecord};
+use transome::metrics::Metrics;
+use transome::model_weights::ModelWeights;
+use transome::multi_target::{self, MultiTargetJson};
+use transome::names::NameMemory;
+use transome::output_encoding::{FILE_HINT, Transcoder};
+use transome::postprocess;
+use transome::progress::{self, BatchProgress, Spinner};
+use transome::range::FileSlice;
+use transome::reload::{self, ConfigSnapshot, LiveConfig};
+use transome::response_dump::ResponseDump;
+use transome::retry::{DEFAULT_BACKOFF, RetryBudget};
+use transome::routing::LanguagePair;
+use transome::segment_cache::{self, OutputOptions, SegmentCache, SegmentKey};
+use transome::sink::{self, OutputSink};
+use transome::source::{self, LineJson, Origin};
+use transome::tm::TranslationMemory;
+use transome::translator::DEFAULT_TEMPERATURE;
+use transome::{
+    BatchOptions, BatchOutcome, Cli, Command, ConfigCommand, ConfigFile, EffectiveConfig,
+    ItemResult, ItemStatus, JsonOutput, RetryPolicy, TranslateOptions, TranslationRequest,
+    Translator, TransomeError, WarningKind, Warnings, exit_code, render_compact_error,
+    render_error, run_batch_resumed, wrap_text,
+};
+use transome::{canary, config, key_check, platform, stats};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    if let Err(e) = run().await {
-        eprintln!("错误: {}", e);
-        std::process::exit(1);
+    // 解析命令行参数
+    let mut args = Cli::parse();
+    args.apply_pipe();
+    let compact_errors = args.pipe;
+    let show_hints = args.show_hints(io::stderr().is_terminal());
+    let started_at = Instant::now();
+    let metrics = (args.summary || args.records_history()).then(|| Arc::new(Metrics::new()));
+    let show_summary = args.summary && !args.quiet;
+
+    let result = run(args, metrics.clone()).await;
+    if let Some(metrics) = metrics.filter(|_| show_summary) {
+        progress::eprintln(metrics.summary(started_at.elapsed()));
+    }
+
+    match result {
+        Ok(exit_code::SUCCESS) => {}
+        Ok(code) => std::process::exit(code),
+        Err(e) if compact_errors => {
+            progress::eprintln(format_args!("错误: {}", render_compact_error(&e)));
+            std::process::exit(exit_code::FAILURE);
+        }
+        Err(e) => {
+            progress::eprintln(format_args!("错误: {}", render_error(&e, show_hints)));
+            std::process::exit(exit_code::FAILURE);
+        }
     }
     Ok(())
 }
 
-/// 主程序逻辑
-async fn run() -> Result<()> {
-    // 解析命令行参数
-    let args = Cli::parse();
+/// 主程序逻辑，返回进程退出码
+async fn run(mut args: Cli, metrics: Option<Arc<Metrics>>) -> Result<i32> {
+    // 记录启动时刻，作为批量任务时限的起点
+    let started_at = Instant::now();
+
+    // 初始化日志输出
+    #[cfg(feature = "logging")]
+    transome::logging::init(args.log_level)?;
+
+    // 处理子命令
+    match &args.command {
+        Some(Command::Stats { days, json }) => return show_stats(*days, *json, args.quiet),
+        Some(Command::Config { action }) => return run_config_command(&args, action),
+        Some(Command::Features) => {
+            println!("{}", transome::features::report());
+            return Ok(exit_code::SUCCESS);
+        }
+        None => {}
+    }
 
     // 处理模型列表请求
     if args.list_models {
-        handle_list_models();
-        return Ok(());
+        handle_list_models(&args)?;
+        return Ok(exit_code::SUCCESS);
+    }
+
+    // 批量检查 API 密钥
+    if args.validate_keys {
+        return validate_keys().await;
+    }
+
+    // 从 --file 读取待翻译文本，指定了范围时只取范围内的部分
+    let file_slice = args.read_file()?;
+
+    // 从 --from-url 指定的网页读取待翻译文本
+    #[cfg(feature = "from-url")]
+    args.read_url().await?;
+
+    // 从 stdin 读取待翻译文本，或在 --prompt-from-stdin 时读取提示词
+    args.read_stdin(
+        io::stdin().is_terminal(),
+        || io::read_to_string(io::stdin()),
+    )?;
+
+    // 加载配置文件
+    let config_file = ConfigFile::load(args.config.as_deref())?;
+
+    // 配置档中的设置优先级低于命令行参数；指定了不存在的配置档时报错
+    let profile = config_file.profile(args.profile.as_deref())?.cloned();
+
+    // --model-weights：按权重随机选择本次运行的模型，之后与 -m 指定的模型一样解析地址与密钥
+    let weighted_model = match &args.model_weights {
+        Some(weights) => Some(choose_weighted_model(&args, weights, &config_file)?),
+        None => None,
+    };
+
+    // 未指定模型时按路由规则、配置档、配置文件或已设置的密钥选择模型
+    let defaults = Inputs::from(&args);
+    let inputs = Inputs {
+        model: weighted_model.or(defaults.model),
+        languages: LanguagePair::detect(args.text.as_deref().unwrap_or_default(), &args.to),
+        ..defaults
+    };
+    let effective = EffectiveConfig::resolve(&inputs, &config_file, |var| std::env::var(var).ok());
+    if let Some(notice) = effective.model_notice().filter(|_| !args.quiet) {
+        progress::eprintln(format_args!("注意: {}", notice));
+    }
+    if args.verbose
+        && let Some(rule) = effective.route()
+    {
+        progress::eprintln(format_args!(
+            "路由: {} → {}（路由规则 {}）",
+            inputs.languages, effective.model.value, rule
+        ));
+    }
+    args.model = effective.model.value.clone();
+    args.route = effective.route().map(str::to_string);
+    if args.url.is_none() {
+        args.url = profile.as_ref().and_then(|profile| profile.url.clone());
+    }
+    args.key_env = profile.and_then(|profile| profile.key_env);
+    args.timeout = effective.timeout.value;
+    args.retries = Some(effective.retries.value);
+    args.backoff_base = Some(effective.backoff_base.value);
+
+    // 仅显示生效的配置，不发送请求
+    if args.dry_run {
+        println!("{}", effective);
+        return Ok(exit_code::SUCCESS);
+    }
+
+    // 仅显示分块结果，不发送请求
+    if args.show_chunks {
+        let Some(text) = &args.text else {
+            bail!("没有要分块的文本\n\n使用方法: transome --show-chunks <文本>，或从 stdin 输入");
+        };
+        let context_window = args.context_window(&config_file);
+        let chunks = args.chunks(text, chunk::chunk_budget(context_window));
+        println!(
+            "{}",
+            ChunkPlan {
+                chunks: &chunks,
+                context_window,
+            }
+        );
+        chunk::check_fits(&chunks, context_window)?;
+        return Ok(exit_code::SUCCESS);
     }
 
     // 执行全面验证
     args.validate()?;
 
+    // 贯穿整个运行过程的警告收集器
+    let warnings = Warnings::new(args.quiet);
+
+    // 使用已弃用模型时记录警告；--deny-warnings 不因此失败，不影响运行
+    if let Some(notice) = args.deprecation_notice(&config_file) {
+        warnings.warn(WarningKind::Deprecated, notice);
+    }
+
     // 提取验证后的文本 - 验证后已确保安全
     let text = args.text.as_ref().unwrap();
 
     // 从模型或自定义URL解析API地址
     let url = args.resolve_url()?;
 
-    // 执行翻译并提供更好的错误上下文
-    let result = execute_translation(&args, text, &url).await.map_err(|e| {
-        anyhow::anyhow!(
-            "翻译失败: {}\n\n\
-                故障排除建议:\n\
-                - 验证您的 API 密钥是否正确\n\
-                - 检查您的网络连接\n\
-                - 使用 --model <MODEL> 尝试不同的模型\n\
-                - 使用 --list-models 查看可用选项",
-            e
-        )
-    })?;
-
-    println!("{}", result);
+    // 按实际运行时的请求估算用量与费用：--estimate 仅输出估算，--max-cost 超出上限时不运行
+    if args.estimate || args.max_cost.is_some() {
+        let estimate = estimate_run(&args, text, &url, &config_file)?;
+        if args.estimate {
+            if args.json_output() {
+                println!("{}", serde_json::to_string_pretty(&estimate)?);
+            } else {
+                println!("{}", estimate);
+            }
+        }
+        if let Some(max_cost) = args.max_cost {
+            estimate.check_max_cost(max_cost).map_err(|message| {
+                anyhow!(
+                    "{}\n\n使用 --estimate 查看各文件的估算，或提高 --max-cost",
+                    message
+                )
+            })?;
+        }
+        if args.estimate {
+            return Ok(exit_code::SUCCESS);
+        }
+    }
+
+    // 使用解析后的配置创建翻译器实例
+    let deadline = args
+        .deadline
+        .map(|limit| {
+            started_at.checked_add(limit).ok_or_else(|| {
+                TransomeError::validation_error(
+                    "--deadline",
+                    "更短的时限",
+                    transome::util::format_duration(limit),
+                )
+            })
+        })
+        .transpose()?;
+    // 主模型、升级模型、审校模型与重新加载配置后的翻译器共用同一个并发请求上限
+    let inflight = Arc::new(Semaphore::new(args.max_inflight));
+    let translator = create_translator(
+        &args,
+        &url,
+        &config_file,
+        deadline,
+        &warnings,
+        metrics,
+        &inflight,
+    )?;
+
+    // --reload-config：配置文件变化或收到 SIGHUP 时重新加载配置，之后派发的行使用新配置
+    let live = if args.reload_config {
+        Some(LiveTranslator::start(
+            &args,
+            inputs,
+            &translator,
+            deadline,
+            &warnings,
+            inflight,
+        )?)
+    } else {
+        None
+    };
+
+    let code = translate_input(
+        &args,
+        &translator,
+        live.as_ref(),
+        text,
+        file_slice,
+        &config_file,
+        deadline,
+        &warnings,
+    )
+    .await?;
+
+    // 文件格式模式下报告片段缓存的命中率
+    if let Some(cache) = translator.segment_cache().filter(|_| !args.quiet)
+        && cache.hits().lookups > 0
+    {
+        let name = args.file.first().map_or_else(
+            || source::STDIN_NAME.to_string(),
+            |path| path.display().to_string(),
+        );
+        progress::eprintln(format_args!("{}: {}", name, cache.hits()));
+    }
+
+    // 报告专有名词的译名对照表，并写回 --names-file
+    if let Some(names) = translator.names() {
+        if let Some(report) = names.report().filter(|_| !args.quiet) {
+            progress::eprintln(report);
+        }
+        names.save()?;
+    }
+
+    // 运行成功后写入使用记录，写入失败不影响本次运行
+    let mut read_only = translator
+        .segment_cache()
+        .is_some_and(|cache| !cache.persists())
+        && !args.ephemeral;
+    if let Some(metrics) = translator.metrics().filter(|_| args.records_history())
+        && let Err(e) = record_history(&args, text, metrics, started_at.elapsed())
+    {
+        if e.downcast_ref().is_some_and(TransomeError::is_read_only) {
+            read_only = true;
+        } else if !args.quiet {
+            progress::eprintln(format_args!("注意: 未能写入使用记录: {}", e));
+        }
+    }
+
+    // 目录不可写时自动按临时模式运行，只在 --verbose 时说明
+    if read_only && args.verbose {
+        progress::eprintln(
+            "注意: 缓存或使用记录目录不可写（只读文件系统或没有权限），本次运行按 --ephemeral 处理：\
+            片段缓存只保留在内存中，不写入使用记录",
+        );
+    }
+
+    Ok(code)
+}
+
+/// 按格式与参数翻译输入文本并输出结果，返回进程退出码
+#[allow(clippy::too_many_arguments)]
+async fn translate_input(
+    args: &Cli,
+    translator: &Translator,
+    live: Option<&LiveTranslator<'_>>,
+    text: &str,
+    file_slice: Option<FileSlice>,
+    config_file: &ConfigFile,
+    deadline: Option<Instant>,
+    warnings: &Warnings,
+) -> Result<i32> {
+    // 提示词自检，避免整批翻译都被错误的提示词浪费
+    if args.check_prompt {
+        for (lang, prompt) in args.prompt_check_targets(text) {
+            let Some(sample) = canary::Canary::for_target(&lang) else {
+                if args.verbose {
+                    progress::eprintln(format_args!(
+                        "提示词自检: 没有译为 {} 的试探样本，已跳过",
+                        multi_target::language_name(&lang)
+                    ));
+                }
+                continue;
+            };
+            canary::check_prompt(sample, |source| async move {
+                translator.translate(&source, Some(&prompt)).await
+            })
+            .await?;
+        }
+    }
+
+    // 按格式解析并翻译文档
+    match args.format {
+        // 仅翻译指定列，失败的单元格保留原文并给出警告
+        Format::Csv => {
+            let document = CsvDocument::parse(text, &args.columns, args.has_header)?
+                .with_note_column(args.note_column);
+            return run_document(args, translator, &document, warnings).await;
+        }
+        // 只翻译文字，保留标记、代码与链接地址
+        Format::Markdown => {
+            let links = args.markdown_translate_links.unwrap_or_default();
+            let document = MarkdownDocument::parse(text, links);
+            return run_document(args, translator, &document, warnings).await;
+        }
+        Format::Html => return run_html(args, translator, text, warnings).await,
+        // 只翻译注释，保留代码与字符串
+        Format::Code => {
+            // 参数验证时已确保能确定语言
+            let language = args
+                .code_language()
+                .context("无法确定源代码的语言，请使用 --lang 指定")?;
+            let document = CodeDocument::parse(text, language);
+            return run_document(args, translator, &document, warnings).await;
+        }
+        Format::Docx => return run_docx(args, translator, warnings).await,
+        // 只翻译值，占位符与原文不一致的译文保留原文
+        Format::Properties | Format::Kv => {
+            let document = PropertiesDocument::parse(text, properties_syntax(args))?;
+            return run_document(args, translator, &document, warnings).await;
+        }
+        Format::Json => return run_json(args, translator, text, warnings).await,
+        Format::Auto | Format::Text => {}
+    }
+
+    // 差量翻译，仅翻译变化的段落
+    if args.diff_against.is_some() {
+        return run_diff(args, translator, text, warnings).await;
+    }
+
+    // 并发翻译为多种目标语言
+    if !args.to.is_empty() {
+        return run_targets(args, translator, text, warnings).await;
+    }
+
+    // 批量模式逐行翻译
+    if args.lines {
+        return run_lines(args, translator, live, deadline, warnings).await;
+    }
+
+    // 执行翻译，终端上显示已用时间
+    let spinner = Spinner::start(
+        args.show_progress(status_line_supported()),
+        progress::elapsed_status,
+    );
+    let meter = args.benchmark.then(|| ThroughputMeter::start(&SystemClock));
+    let result = translate_chunked(
+        args,
+        translator,
+        text,
+        config_file,
+        meter.as_ref(),
+        warnings,
+    )
+    .await;
+    spinner.finish();
+    let result = result.context("翻译失败")?;
+    if let Some(meter) = &meter {
+        progress::eprintln(meter.report());
+    }
+
+    // 启用 --deny-warnings 时，出现警告则不输出结果
+    warnings.check_denied(args.deny_warnings)?;
+
+    let result = format_result(args, result);
+
+    let mut sink = args.output_sink(warnings)?;
+    if args.json_output() {
+        let output = JsonOutput::new(&args.model, result, warnings.to_vec())
+            .with_temperature(translator.temperature());
+        let json = if args.pretty_json {
+            output.to_json_pretty()?
+        } else {
+            output.to_json()?
+        };
+        sink.write_result(&json)?;
+    } else if let Some(slice) = &file_slice {
+        // 拼接回完整内容，范围之外的内容原样输出
+        sink.write(&slice.splice(&result))?;
+    } else {
+        sink.write_result(&result)?;
+    }
+    sink.finish()?;
+
+    Ok(exit_code::SUCCESS)
+}
 
+/// 向使用记录追加本次运行的统计
+fn record_history(args: &Cli, text: &str, metrics: &Metrics, elapsed: Duration) -> Result<()> {
+    let path = history::default_path().ok_or_else(|| anyhow!("无法确定使用记录的位置"))?;
+    let record = HistoryRecord::new(&args.model, text, &args.to, metrics, elapsed)
+        .with_route(args.route.clone());
+    history::append(&path, &record)?;
     Ok(())
 }
 
+/// 按 `--model-weights` 随机选择模型，`--verbose` 时输出选择结果
+///
+/// 先检查所有模型，避免只在选中某个拼错的模型时才报错。
+fn choose_weighted_model(
+    args: &Cli,
+    weights: &ModelWeights,
+    config_file: &ConfigFile,
+) -> Result<String> {
+    if args.url.is_none()
+        && let Some(model) = weights
+            .models()
+            .find(|model| !config_file.knows_model(model))
+    {
+        bail!(
+            "--model-weights: {}",
+            config::create_model_error_message(model)
+        );
+    }
+    let model = weights.choose().to_string();
+    if args.verbose {
+        progress::eprintln(format_args!(
+            "模型: {}（按 --model-weights 选择，权重 {}/{}）",
+            model,
+            weights.weight(&model).unwrap_or_default(),
+            weights.total()
+        ));
+    }
+    Ok(model)
+}
+
+/// 处理 stats 子命令：汇总最近 `days` 天的使用记录
+fn show_stats(days: u32, json: bool, quiet: bool) -> Result<i32> {
+    let path = history::default_path().ok_or_else(|| anyhow!("无法确定使用记录的位置"))?;
+    let records = history::read(&path, &Warnings::new(quiet))?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let stats = stats::aggregate(&records, now, days);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        println!("{}", stats);
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+/// 处理 `transome config` 的子命令
+fn run_config_command(args: &Cli, action: &ConfigCommand) -> Result<i32> {
+    match action {
+        ConfigCommand::Show { json } => show_config(args, *json),
+        #[cfg(feature = "bundle")]
+        ConfigCommand::Export { bundle } => {
+            let report = bundle::export(&bundle::default_config_dir()?, bundle)?;
+            for skipped in &report.skipped {
+                progress::eprintln(format_args!(
+                    "注意: '{}' 包含密钥，未导出",
+                    skipped.display()
+                ));
+            }
+            println!(
+                "已导出 {} 个文件到 '{}'",
+                report.files.len(),
+                bundle.display()
+            );
+            Ok(exit_code::SUCCESS)
+        }
+        #[cfg(feature = "bundle")]
+        ConfigCommand::Import { bundle, .. } => {
+            let config_dir = bundle::default_config_dir()?;
+            let mode = action.import_mode().unwrap_or_default();
+            let imported = bundle::import(bundle, &config_dir, mode)?;
+            println!(
+                "已导入 {} 个文件到 '{}'",
+                imported.len(),
+                config_dir.display()
+            );
+            Ok(exit_code::SUCCESS)
+        }
+    }
+}
+
+/// 处理 `transome config show`：输出生效的配置，不读取输入也不发送请求
+fn show_config(args: &Cli, json: bool) -> Result<i32> {
+    let config_file = ConfigFile::load(args.config.as_deref())?;
+    // 指定了不存在的配置档时报错
+    config_file.profile(args.profile.as_deref())?;
+    let effective = EffectiveConfig::resolve(&Inputs::from(args), &config_file, |var| {
+        std::env::var(var).ok()
+    });
+    let report = effective.report();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", toml::to_string_pretty(&report)?);
+    }
+    Ok(exit_code::SUCCESS)
+}
+
 /// 处理 --list-models 命令
-fn handle_list_models() {
-    Cli::list_all_models();
+fn handle_list_models(args: &Cli) -> Result<()> {
+    args.list_all_models()
 }
 
-/// 执行翻译
-async fn execute_translation(args: &Cli, text: &str, url: &str) -> Result<String> {
+/// 处理 --validate-keys 命令，有密钥不可用时以失败状态退出
+///
+/// 只检查内置提供商的地址，不使用 --ca-bundle 中的证书。
+async fn validate_keys() -> Result<i32> {
+    let providers = config::known_providers();
+    let checks = key_check::validate_keys(
+        &providers,
+        |var| std::env::var(var).ok(),
+        |provider, key| async move {
+            Translator::new(key, provider.url.to_string(), String::new())
+                .ping()
+                .await
+        },
+    )
+    .await;
+
+    if checks.is_empty() {
+        let env_vars: Vec<&str> = providers.iter().map(|p| p.env_var).collect();
+        bail!(
+            "没有设置任何 API 密钥环境变量\n\n支持的环境变量: {}",
+            env_vars.join(", ")
+        );
+    }
+
+    for check in &checks {
+        println!("{}", check);
+    }
+    println!("{}", key_check::summary(&checks));
+
+    let all_valid = checks
+        .iter()
+        .all(|check| check.status == key_check::KeyStatus::Valid);
+    Ok(if all_valid {
+        exit_code::SUCCESS
+    } else {
+        exit_code::FAILURE
+    })
+}
+
+/// 创建翻译器
+fn create_translator(
+    args: &Cli,
+    url: &str,
+    config_file: &ConfigFile,
+    deadline: Option<Instant>,
+    warnings: &Warnings,
+    metrics: Option<Arc<Metrics>>,
+    inflight: &Arc<Semaphore>,
+) -> Result<Translator> {
     // 智能解析 API 密钥
     let api_key = args.resolve_api_key()?;
 
-    // 使用解析后的配置创建翻译器实例
-    let translator = Translator::new(api_key, url.to_string(), args.model.clone());
+    // 检查密钥格式，不符时仅记录警告
+    args.check_key_format(&api_key, warnings);
+
+    let dump = args.dump_response.as_ref().map(|path| {
+        Arc::new(ResponseDump::new(
+            path.clone(),
+            args.sends_many_requests(),
+            api_key.clone(),
+        ))
+    });
+
+    let mut translator = Translator::new(api_key, url.to_string(), args.model.clone())
+        .with_retry_policy(RetryPolicy {
+            retries: args.retries.unwrap_or_default(),
+            timeout: args.timeout,
+            backoff: args.backoff_base.unwrap_or(DEFAULT_BACKOFF),
+            deadline,
+            budget: Some(RetryBudget::new(args.retry_budget)),
+            idempotent_only: args.retries_only_for_idempotent,
+        })
+        .with_inflight(inflight.clone())
+        .with_system_fallback(!args.no_system_fallback)
+        .with_echo_request_id(args.verbose)
+        .with_echo_prompt(args.echo_prompt)
+        .with_options(TranslateOptions {
+            max_output_ratio: args.max_output_ratio,
+            sandbox_input: args.sandbox_input,
+            // 各格式自行保留片段首尾的空白，译文本身总是去除首尾空白
+            trim_output: true,
+        });
+
+    if let Some(path) = &args.ca_bundle {
+        translator = translator.with_ca_bundle(path)?;
+    }
+
+    if args.insecure_skip_tls_verify {
+        args.check_insecure_tls(url, config_file)?;
+        warnings.warn(
+            WarningKind::InsecureTls,
+            format!(
+                "已关闭 TLS 证书验证（--insecure-skip-tls-verify），\
+                发往 {} 的 API 密钥与内容可能被中间人窃取，切勿在实验环境之外使用",
+                url
+            ),
+        );
+        translator = translator.with_tls_verification_disabled()?;
+    }
+
+    if let Some(dump) = dump {
+        translator = translator.with_response_dump(dump);
+    }
+
+    if let Some(metrics) = &metrics {
+        translator = translator.with_metrics(metrics.clone());
+    }
+
+    if let Some(id) = &args.request_id {
+        translator = translator.with_request_id(id.clone());
+    }
+
+    if let Some(path) = &args.tm {
+        let memory = TranslationMemory::load(path, warnings)?;
+        translator = translator.with_memory(Arc::new(memory));
+    }
+
+    if args.uses_segment_cache() && args.ephemeral {
+        translator = translator.with_segment_cache(Arc::new(SegmentCache::in_memory()));
+    } else if args.uses_segment_cache()
+        && let Some(path) = segment_cache::default_path()
+    {
+        let cache = SegmentCache::load(&path, warnings)?;
+        translator = translator.with_segment_cache(Arc::new(cache));
+    }
+
+    if args.uses_name_memory() {
+        let names = match &args.names_file {
+            Some(path) => NameMemory::load(path, warnings)?,
+            None => NameMemory::new(warnings),
+        };
+        translator = translator.with_names(Arc::new(names));
+    }
+
+    // 配置文件中为该模型指定了提示词排列方式时覆盖提供商默认值
+    if let Some(layout) = config_file.prompt_layout(&args.model) {
+        translator = translator.with_prompt_layout(layout);
+    }
+
+    // 推理模型：配置文件中的标记优先，否则按模型名称判断
+    if let Some(reasoning) = config_file.reasoning(&args.model) {
+        translator = translator.with_reasoning(reasoning);
+    }
+
+    if let Some(temperature) = args.temperature(config_file) {
+        // 默认的固定温度对推理模型静默忽略，明确指定的温度给出警告
+        let explicit = args.creative || config_file.temperature != DEFAULT_TEMPERATURE;
+        if translator.is_reasoning() && explicit {
+            warnings.warn(
+                WarningKind::IgnoredParameter,
+                format!(
+                    "模型 '{}' 是推理模型，不支持采样温度，已忽略温度 {}",
+                    args.model, temperature
+                ),
+            );
+        }
+        translator = translator.with_temperature(temperature);
+    }
+
+    // 配置文件中标记了请求必须包含 max_tokens 的模型，未指定 --max-tokens 时使用默认值
+    if let Some(required) = config_file.max_tokens_required(&args.model) {
+        translator = translator.with_max_tokens_required(required);
+    }
+
+    if let Some(max_tokens) = args.max_tokens {
+        translator = translator.with_max_tokens(max_tokens);
+    }
+
+    // 配置文件中为该模型设置了请求体上限时覆盖提供商默认值，适配上限更小的网关
+    if let Some(limit) = config_file.max_request_bytes(&args.model) {
+        translator = translator.with_max_request_bytes(limit);
+    }
+
+    // 不接受 logit_bias 的提供商与推理模型忽略该参数，避免整个请求被拒绝
+    if !args.logit_bias.is_empty() {
+        if !config::supports_logit_bias(url) || translator.is_reasoning() {
+            warnings.warn(
+                WarningKind::IgnoredParameter,
+                format!(
+                    "模型 '{}' 不支持 logit_bias，已忽略 --logit-bias",
+                    args.model
+                ),
+            );
+        } else {
+            translator = translator.with_logit_bias(args.logit_bias.clone());
+        }
+    }
+
+    // 采样参数同样只发送给接受它们的提供商与模型
+    let mut sampling = args.sampling();
+    for flag in sampling.retain_supported(url, translator.is_reasoning()) {
+        warnings.warn(
+            WarningKind::IgnoredParameter,
+            format!("模型 '{}' 不支持 {}，已忽略", args.model, flag),
+        );
+    }
+    if !sampling.is_empty() {
+        translator = translator.with_sampling(sampling);
+    }
+
+    // 配置文件中为该模型设置了请求模板时应用
+    if let Some(template) = config_file.request_template(&args.model) {
+        translator = translator.with_request_template(template.clone());
+    }
+
+    if let Some(separator) = &args.choice_separator {
+        translator = translator.with_choice_separator(separator.clone());
+    }
+
+    // 升级模型与审校模型一样按自己的模型名解析 API 地址、密钥与配置文件中的设置
+    if let (Some(threshold), Some(model)) = (args.escalate_on_low_quality, &args.escalate_model) {
+        let escalate_args = Cli {
+            model: model.clone(),
+            review_model: None,
+            escalate_on_low_quality: None,
+            escalate_model: None,
+            tm: None,
+            dump_response: None,
+            echo_prompt: false,
+            ..args.clone()
+        };
+        let escalate_url = escalate_args.resolve_url()?;
+        let stronger = create_translator(
+            &escalate_args,
+            &escalate_url,
+            config_file,
+            deadline,
+            warnings,
+            metrics,
+            inflight,
+        )?;
+        translator = translator.with_escalation(threshold, stronger);
+    }
+
+    // 审校模型按自己的模型名解析 API 地址、密钥与配置文件中的设置
+    if let Some(model) = &args.review_model {
+        let review_args = Cli {
+            model: model.clone(),
+            review_model: None,
+            escalate_on_low_quality: None,
+            escalate_model: None,
+            // 翻译记忆只由主模型读写，响应转储只记录主模型的响应，--echo-prompt 只输出翻译的提示词
+            tm: None,
+            dump_response: None,
+            echo_prompt: false,
+            ..args.clone()
+        };
+        let review_url = review_args.resolve_url()?;
+        let reviewer = create_translator(
+            &review_args,
+            &review_url,
+            config_file,
+            deadline,
+            warnings,
+            None,
+            inflight,
+        )?;
+        translator = translator.with_review(reviewer);
+    }
+
+    Ok(translator)
+}
+
+/// 随配置重新加载而更新的翻译器（`--reload-config`）
+///
+/// 配置变化后的第一个请求按新配置创建翻译器，已开始的请求继续使用原来的翻译器。
+struct LiveTranslator<'a> {
+    config: Arc<LiveConfig>,
+    args: &'a Cli,
+    deadline: Option<Instant>,
+    warnings: &'a Warnings,
+    /// 并发请求上限，重新创建的翻译器继续共用
+    inflight: Arc<Semaphore>,
+    current: Mutex<(Arc<ConfigSnapshot>, Arc<Translator>)>,
+    /// 持有期间监视配置文件
+    _watcher: Option<notify::RecommendedWatcher>,
+}
+
+impl<'a> LiveTranslator<'a> {
+    /// 加载配置并开始监视配置文件与 SIGHUP，`translator` 为按当前配置创建的翻译器
+    fn start(
+        args: &'a Cli,
+        inputs: Inputs,
+        translator: &Translator,
+        deadline: Option<Instant>,
+        warnings: &'a Warnings,
+        inflight: Arc<Semaphore>,
+    ) -> Result<Self> {
+        let config = Arc::new(LiveConfig::load(args.config.clone(), inputs)?);
+        let watcher = reload::watch_file(config.clone(), warnings.clone())?;
+        #[cfg(unix)]
+        reload::reload_on_sighup(config.clone(), warnings.clone())?;
+
+        let current = Mutex::new((config.current(), Arc::new(translator.clone())));
+        Ok(Self {
+            config,
+            args,
+            deadline,
+            warnings,
+            inflight,
+            current,
+            _watcher: watcher,
+        })
+    }
+
+    /// 按当前配置创建的翻译器
+    fn translator(&self) -> Result<Arc<Translator>> {
+        let snapshot = self.config.current();
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if !Arc::ptr_eq(&current.0, &snapshot) {
+            let effective = &snapshot.effective;
+            let profile = snapshot
+                .config_file
+                .profile(self.args.profile.as_deref())?
+                .cloned();
+            let args = Cli {
+                model: effective.model.value.clone(),
+                url: effective.url.value.clone(),
+                key_env: profile.and_then(|profile| profile.key_env),
+                timeout: effective.timeout.value,
+                retries: Some(effective.retries.value),
+                backoff_base: Some(effective.backoff_base.value),
+                ..self.args.clone()
+            };
+            let url = args.resolve_url()?;
+            let translator = create_translator(
+                &args,
+                &url,
+                &snapshot.config_file,
+                self.deadline,
+                self.warnings,
+                None,
+                &self.inflight,
+            )?;
+            if self.args.verbose {
+                progress::eprintln(format_args!(
+                    "已重新加载配置，之后的行使用模型 {}",
+                    args.model
+                ));
+            }
+            *current = (snapshot, Arc::new(translator));
+        }
+        Ok(current.1.clone())
+    }
+}
+
+/// 翻译一段文本并执行 --post-process 后处理，译文超出 --max-output-ratio 上限时记录警告
+async fn translate_checked(
+    args: &Cli,
+    translator: &Translator,
+    text: &str,
+    prompt: &str,
+    warnings: &Warnings,
+) -> Result<String> {
+    translate_noted(args, translator, text, prompt, None, warnings).await
+}
+
+/// 附带译者注翻译一段文本，其余同 [`translate_checked`]
+async fn translate_noted(
+    args: &Cli,
+    translator: &Translator,
+    text: &str,
+    prompt: &str,
+    note: Option<&str>,
+    warnings: &Warnings,
+) -> Result<String> {
+    let request = TranslationRequest::new(text, Some(prompt)).with_note(note);
+    let translation = translator.translate_request(request).await;
+    if let Some(metrics) = translator.metrics() {
+        metrics.record_item(translation.is_ok());
+    }
+    let translation = translation?;
+    if args.verbose
+        && let Some(escalation) = &translation.escalation
+    {
+        let preview: String = text.chars().take(30).collect();
+        progress::eprintln(format_args!("{}：'{}'", escalation, preview));
+    }
+    if args.verbose
+        && let Some(review) = &translation.review
+    {
+        let preview: String = text.chars().take(30).collect();
+        progress::eprintln(format_args!("{}：'{}'", review, preview));
+    }
+    if let Some(violation) = translation.length_violation {
+        let preview: String = text.chars().take(30).collect();
+        warnings.warn(
+            WarningKind::LengthExceeded,
+            format!("{}：'{}'", violation, preview),
+        );
+    }
+    let text = postprocess::apply(&args.post_process, translation.text, args.locale);
+    #[cfg(feature = "strip-markdown")]
+    let text = if args.strip_markdown() {
+        transome::strip_markdown::strip_markdown(&text)
+    } else {
+        text
+    };
+    Ok(text)
+}
+
+/// stderr 是否可以显示自我擦除的状态行：需要是终端且支持 ANSI 转义序列
+///
+/// 不支持虚拟终端处理的旧版 Windows 控制台不显示状态行。
+fn status_line_supported() -> bool {
+    io::stderr().is_terminal() && platform::enable_ansi()
+}
+
+/// 为批量模式创建进度记录，并在终端上显示 `n/m` 与预计剩余时间
+fn start_batch_progress(args: &Cli, total: usize) -> (Arc<BatchProgress>, Spinner) {
+    let progress = Arc::new(BatchProgress::new(total, args.concurrency));
+    let status = Arc::clone(&progress);
+    let spinner = Spinner::start(args.show_progress(status_line_supported()), move |_| {
+        status.status()
+    });
+    (progress, spinner)
+}
+
+/// 按需折行输出
+fn format_result(args: &Cli, result: String) -> String {
+    match args.wrap {
+        Some(width) => wrap_text(&result, width),
+        None => result,
+    }
+}
+
+/// 批量模式：逐行翻译并按输入顺序输出，`--unordered` 时按完成顺序输出
+///
+/// 失败的行输出错误到 stderr；达到截止时间时输出已完成的部分并返回对应退出码。
+async fn run_lines(
+    args: &Cli,
+    translator: &Translator,
+    live: Option<&LiveTranslator<'_>>,
+    deadline: Option<Instant>,
+    warnings: &Warnings,
+) -> Result<i32> {
+    let options = BatchOptions {
+        concurrency: args.concurrency,
+        deadline,
+        on_error: args.on_error,
+        abort_after: (args.abort_after > 0).then_some(args.abort_after),
+        unordered: args.unordered,
+        fail_fast_on_auth: true,
+    };
+    let sources = args.batch_sources();
+    let (lines, origins) = source::split_sources(&sources);
+    // --lock-direction：按第一条能判断语言的行固定整批的翻译方向
+    let mut session = DirectionSession::new(args.lock_direction);
+    if let Some(direction) = lines.iter().find_map(|line| session.observe(line).cloned())
+        && args.verbose
+    {
+        progress::eprintln(format_args!("翻译方向已固定: {}", direction));
+    }
+    let prompt = args.composed_prompt_in(&session);
+    let prompt = prompt.as_str();
+    // --resume：读取断点，上次已完成的行不再翻译
+    let checkpoint = match &args.resume {
+        Some(path) => {
+            let input = BatchInput {
+                model: &args.model,
+                prompt,
+                lines: &lines,
+            };
+            Some(Checkpoint::load(path, &input, warnings)?)
+        }
+        None => None,
+    };
+    let done = checkpoint
+        .as_ref()
+        .map(|checkpoint| checkpoint.done().clone())
+        .unwrap_or_default();
+    if !done.is_empty() && args.verbose {
+        progress::eprintln(format_args!(
+            "从断点继续: {} 行已完成，{} 行待翻译",
+            done.len(),
+            lines.len() - done.len()
+        ));
+    }
+    let total = lines.len();
+    // 输入来自多个来源时，错误信息中给出条目所在的来源与行号
+    let located = sources.len() > 1 || args.label;
+    let (progress, spinner) = start_batch_progress(args, lines.len());
+    let progress = progress.as_ref();
+
+    // 结果按输入顺序（--unordered 时按完成顺序）就绪后立即输出；启用 --deny-warnings 时需等整批完成后才能决定是否输出
+    let mut streaming_sink = if args.deny_warnings {
+        None
+    } else {
+        Some(args.output_sink(warnings)?)
+    };
+    let mut emitting = true;
+    let mut emit_error = None;
+    let mut checkpoint_error = None;
+
+    let report = run_batch_resumed(
+        lines,
+        &done,
+        options,
+        &SystemClock,
+        move |line| async move {
+            // 配置重新加载后改用按新配置创建的翻译器
+            let current = live.map(LiveTranslator::translator).transpose()?;
+            let translator = current.as_deref().unwrap_or(translator);
+            progress
+                .track(translate_checked(args, translator, &line, prompt, warnings))
+                .await
+        },
+        |item| {
+            if let (Some(checkpoint), ItemStatus::Succeeded(translation)) =
+                (&checkpoint, &item.status)
+                && checkpoint_error.is_none()
+                && let Err(e) = checkpoint.record(item.index, translation)
+            {
+                checkpoint_error = Some(e);
+            }
+            if let Some(sink) = streaming_sink.as_mut()
+                && emitting
+                && emit_error.is_none()
+            {
+                match emit_item(args, sink.as_mut(), item, &origins, located) {
+                    Ok(more) => emitting = more,
+                    Err(e) => emit_error = Some(e),
+                }
+            }
+        },
+    )
+    .await;
+    spinner.finish();
+
+    // 认证失败时其余条目也无法完成，以该错误结束整批任务
+    if report.auth_failed {
+        let error = report
+            .into_first_error()
+            .unwrap_or_else(|| anyhow!("批量任务已中止"));
+        return Err(error.context("认证失败，已中止批量任务"));
+    }
+
+    // 最先完成的条目全部因同一错误失败，以该错误结束整批任务
+    if report.aborted {
+        let error = report
+            .into_first_error()
+            .unwrap_or_else(|| anyhow!("批量任务已中止"));
+        return Err(error.context(format!(
+            "前 {} 个条目均因同一错误失败，已中止批量任务",
+            args.abort_after
+        )));
+    }
+
+    match streaming_sink {
+        Some(mut sink) => {
+            if let Some(e) = emit_error {
+                return Err(e);
+            }
+            sink.finish()?;
+        }
+        None => {
+            // 启用 --deny-warnings 时，出现警告则不输出结果
+            warnings.check_denied(args.deny_warnings)?;
+
+            let mut sink = args.output_sink(warnings)?;
+            for item in report.items.iter() {
+                if !emit_item(args, sink.as_mut(), item, &origins, located)? {
+                    break;
+                }
+            }
+            sink.finish()?;
+        }
+    }
+
+    if let Some(e) = checkpoint_error {
+        return Err(anyhow::Error::from(e).context("无法写入断点文件"));
+    }
+    if let Some(checkpoint) = checkpoint
+        && report.succeeded() == total
+    {
+        checkpoint.finish()?;
+    }
+
+    if report.stopped_on_error {
+        bail!(
+            "出现翻译失败，已按 --on-error stop 停止，{} 行未处理",
+            report.not_started()
+        );
+    }
+
+    if report.deadline_reached {
+        let resume = if args.resume.is_some() {
+            "，再次以相同的 --resume 运行可继续"
+        } else {
+            ""
+        };
+        progress::eprintln(format_args!(
+            "已达到时限，{} 行已完成，{} 行未处理{}",
+            report.succeeded() + report.failed(),
+            report.not_started(),
+            resume
+        ));
+        return Ok(exit_code::DEADLINE_REACHED);
+    }
+
+    // 失败的行在运行中已逐行输出，结束时按错误类别汇总
+    let failures = report
+        .failures()
+        .map(|failures| format!("：{}", failures))
+        .unwrap_or_default();
+    match report.outcome() {
+        BatchOutcome::AllSucceeded => {}
+        BatchOutcome::PartialSuccess => progress::eprintln(format_args!(
+            "{} 行中有 {} 行翻译失败{}",
+            report.items.len(),
+            report.failed(),
+            failures
+        )),
+        BatchOutcome::AllFailed => bail!("{} 行全部翻译失败{}", report.items.len(), failures),
+    }
+
+    Ok(exit_code::for_outcome(report.outcome()))
+}
+
+/// 输出批量模式中的一个条目并立即写出，失败的条目输出错误到 stderr
+///
+/// 按输入顺序输出时，遇到未处理的条目返回 false，其后的条目不再输出；
+/// `--unordered` 下跳过未处理的条目，译文前加上 `[行号]`。`--label` 时译文前加上
+/// `来源:行号:`，`--json` 时每个条目输出一行 JSON。`located` 为 true 时错误信息给出来源与行号。
+fn emit_item(
+    args: &Cli,
+    sink: &mut dyn OutputSink,
+    item: &ItemResult,
+    origins: &[Origin],
+    located: bool,
+) -> Result<bool> {
+    let origin = &origins[item.index];
+    let location = if located {
+        format!("{} ", origin)
+    } else {
+        format!("第 {} 行", item.index + 1)
+    };
+    match &item.status {
+        ItemStatus::Failed(e) if args.on_error == OnError::KeepOriginal => {
+            progress::eprintln(format!("{}翻译失败，已输出原文: {}", location, e))
+        }
+        ItemStatus::Failed(e) if args.on_error == OnError::Placeholder => {
+            progress::eprintln(format_args!(
+                "{}翻译失败，已输出 {}: {}",
+                location, args.error_placeholder, e
+            ))
+        }
+        ItemStatus::Failed(e) => progress::eprintln(format!("{}翻译失败: {}", location, e)),
+        ItemStatus::NotStarted => return Ok(args.unordered),
+        ItemStatus::Succeeded(_) => {}
+    }
+    let output = match &item.status {
+        ItemStatus::Failed(_) if args.on_error == OnError::Placeholder => {
+            Some(args.error_placeholder.clone())
+        }
+        _ => item
+            .output(args.on_error)
+            .map(|output| format_result(args, output.to_string())),
+    };
+    if let Some(output) = output {
+        progress::clear_status();
+        if args.json_output() {
+            let json = serde_json::to_string(&LineJson {
+                source: &origin.source,
+                line: origin.line,
+                translation: &output,
+            })?;
+            sink.write_line(&json)?;
+        } else if args.label {
+            sink.write_line(&format!("{}:{}", origin, output))?;
+        } else if args.unordered {
+            sink.write_line(&format!("[{}] {}", item.index + 1, output))?;
+        } else {
+            sink.write_line(&output)?;
+        }
+        sink.flush()?;
+    }
+    Ok(true)
+}
+
+/// 逐块翻译超出上下文窗口的文本，或按 --split-on 分块翻译，译文以空行连接
+async fn translate_chunked(
+    args: &Cli,
+    translator: &Translator,
+    text: &str,
+    config_file: &ConfigFile,
+    meter: Option<&ThroughputMeter<'_, SystemClock>>,
+    warnings: &Warnings,
+) -> Result<String> {
+    let prompt = args.composed_prompt();
+    let chunks = plan_chunks(args, text, config_file, translator.max_request_bytes())?;
+    // 请求不使用流式响应，每个分块的译文作为一个增量
+    let record = |translation: &str| {
+        if let Some(meter) = meter {
+            meter.record(translation);
+        }
+    };
+
+    if chunks.len() <= 1 {
+        let translation = translate_checked(args, translator, text, &prompt, warnings).await?;
+        record(&translation);
+        return Ok(translation);
+    }
+
+    let mut translations = Vec::with_capacity(chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        let translation = translate_checked(args, translator, &chunk.text, &prompt, warnings)
+            .await
+            .with_context(|| {
+                format!(
+                    "第 {} 个分块（第 {}-{} 行）翻译失败",
+                    index + 1,
+                    chunk.start,
+                    chunk.end
+                )
+            })?;
+        record(&translation);
+        translations.push(translation);
+    }
+    Ok(translations.join(CHUNK_SEPARATOR))
+}
+
+/// 按上下文窗口对文本分块，翻译文件时还受请求体上限限制；翻译与 --estimate 共用
+fn plan_chunks(
+    args: &Cli,
+    text: &str,
+    config_file: &ConfigFile,
+    max_request_bytes: Option<usize>,
+) -> Result<Vec<Chunk>> {
+    let context_window = args.context_window(config_file);
+    let mut budget = chunk::chunk_budget(context_window);
+    // 翻译文件时按请求体上限缩小分块，单段文本超出上限时由翻译器报告
+    if !args.file.is_empty()
+        && let Some(limit) = max_request_bytes
+    {
+        budget = budget.min(chunk::request_budget(limit, args.composed_prompt().len()));
+    }
+    let chunks = args.chunks(text, budget);
+    chunk::check_fits(&chunks, context_window)?;
+    Ok(chunks)
+}
+
+/// 估算本次运行的请求数、token 用量与费用，不发送请求
+fn estimate_run(args: &Cli, text: &str, url: &str, config_file: &ConfigFile) -> Result<Estimate> {
+    // 与创建翻译器时相同：配置文件中的请求体上限优先于提供商默认值
+    let max_request_bytes = config_file
+        .max_request_bytes(&args.model)
+        .or_else(|| config::max_request_bytes_for_url(url));
+    let prompt = args.composed_prompt();
+    let estimate = |name: String, text: &str| -> Result<FileEstimate> {
+        let requests = request_texts(args, text, config_file, max_request_bytes)?;
+        Ok(FileEstimate::from_requests(
+            name,
+            &requests,
+            &prompt,
+            args.estimate_output_ratio,
+        ))
+    };
+
+    // 批量模式下 stdin、各 --file 与 --dir 中的文件分别估算
+    let files = if args.sources.is_empty() {
+        let name = args.file.first().map_or("(输入文本)".to_string(), |path| {
+            path.display().to_string()
+        });
+        vec![estimate(name, text)?]
+    } else {
+        args.sources
+            .iter()
+            .map(|source| estimate(source.name.clone(), &source.text))
+            .collect::<Result<_>>()?
+    };
+    Ok(Estimate::new(&args.model, files))
+}
+
+/// 实际运行时将要发送的各请求的原文，与翻译使用相同的解析、合并与分块
+///
+/// 失败后的重试、JSON 批次拆分失败后的逐个重发无法预知，不计入。
+fn request_texts(
+    args: &Cli,
+    text: &str,
+    config_file: &ConfigFile,
+    max_request_bytes: Option<usize>,
+) -> Result<Vec<String>> {
+    let requests = match args.format {
+        Format::Csv => CsvDocument::parse(text, &args.columns, args.has_header)?.segments(),
+        Format::Markdown => {
+            let links = args.markdown_translate_links.unwrap_or_default();
+            MarkdownDocument::parse(text, links).segments()
+        }
+        #[cfg(feature = "html")]
+        Format::Html => HtmlDocument::parse(text, &args.translate_attrs).segments(),
+        #[cfg(not(feature = "html"))]
+        Format::Html => bail!("--format html 需使用 --features html 构建"),
+        Format::Code => {
+            let language = args
+                .code_language()
+                .context("无法确定源代码的语言，请使用 --lang 指定")?;
+            CodeDocument::parse(text, language).segments()
+        }
+        #[cfg(feature = "docx")]
+        Format::Docx => {
+            let Some(input) = args.file.first() else {
+                bail!("docx 格式需要通过 --file 指定文档");
+            };
+            let bytes = std::fs::read(input)
+                .with_context(|| format!("无法读取文件 '{}'", input.display()))?;
+            DocxDocument::parse(&bytes)?.segments()
+        }
+        #[cfg(not(feature = "docx"))]
+        Format::Docx => bail!("--format docx 需使用 --features docx 构建"),
+        Format::Properties | Format::Kv => {
+            PropertiesDocument::parse(text, properties_syntax(args))?.segments()
+        }
+        Format::Json => {
+            let targets = Targets {
+                strings: args.translate_strings,
+                keys: args.translate_keys,
+            };
+            let segments = JsonDocument::parse(text, targets)?.segments();
+            json_format::batch_segments(&segments)
+                .into_iter()
+                .map(|batch| batch.text)
+                .collect()
+        }
+        Format::Auto | Format::Text if args.diff_against.is_some() => {
+            // 参数解析时已确保两个路径同时提供
+            let old_source = std::fs::read_to_string(args.diff_against.as_ref().unwrap())?;
+            let old_translation =
+                std::fs::read_to_string(args.previous_translation.as_ref().unwrap())?;
+            DiffDocument::align(&old_source, &old_translation, text)?.segments()
+        }
+        Format::Auto | Format::Text if !args.to.is_empty() => vec![text.to_string(); args.to.len()],
+        Format::Auto | Format::Text if args.lines => batch::split_lines(text),
+        Format::Auto | Format::Text => {
+            let chunks = plan_chunks(args, text, config_file, max_request_bytes)?;
+            if chunks.len() <= 1 {
+                vec![text.to_string()]
+            } else {
+                chunks.into_iter().map(|chunk| chunk.text).collect()
+            }
+        }
+    };
+    Ok(requests)
+}
+
+/// 多目标语言：并发翻译为 --to 指定的每种语言，某一语言失败不影响其他语言
+async fn run_targets(
+    args: &Cli,
+    translator: &Translator,
+    text: &str,
+    warnings: &Warnings,
+) -> Result<i32> {
+    let (progress, spinner) = start_batch_progress(args, args.to.len());
+    let progress = progress.as_ref();
+
+    let mut results = multi_target::translate_targets(&args.to, |lang| async move {
+        let prompt = args.composed_prompt_for(&lang);
+        progress
+            .track(translate_checked(args, translator, text, &prompt, warnings))
+            .await
+    })
+    .await;
+    spinner.finish();
+
+    // 启用 --deny-warnings 时，出现警告则不输出结果
+    warnings.check_denied(args.deny_warnings)?;
+
+    for target in results.iter_mut() {
+        if let Ok(translation) = &mut target.result {
+            *translation = format_result(args, std::mem::take(translation));
+        }
+    }
+
+    match &args.output {
+        // 每种语言写入各自的文件
+        Some(template) if !args.json_output() && !args.clipboard() => {
+            for target in &results {
+                if let Ok(translation) = &target.result {
+                    let path = multi_target::output_path(template, &target.lang);
+                    let trailing_newline = args.trailing_newline().unwrap_or(false);
+                    let transcoder = args
+                        .output_encoding
+                        .and_then(|encoding| Transcoder::new(encoding, warnings, FILE_HINT));
+                    let mut sink = sink::file(&path)?
+                        .trailing_newline(trailing_newline)
+                        .transcoder(transcoder);
+                    sink.write_result(translation)?;
+                    sink.finish()?;
+                }
+            }
+        }
+        _ => {
+            let mut sink = args.output_sink(warnings)?;
+            if args.json_output() {
+                let mut output = MultiTargetJson::new(&args.model, &results, warnings.to_vec());
+                if args.label_languages {
+                    output = output.labeled();
+                }
+                sink.write_result(&output.to_json(args.pretty_json)?)?;
+            } else if args.label_languages {
+                sink.write_result(&multi_target::render_labeled(&results))?;
+            } else {
+                sink.write_result(&multi_target::render_sections(&results))?;
+            }
+            sink.finish()?;
+        }
+    }
+
+    if !args.quiet {
+        progress::eprintln(multi_target::summary(&results));
+    }
+
+    let outcome = multi_target::outcome(&results);
+    if outcome == BatchOutcome::AllFailed {
+        bail!("{} 种目标语言全部翻译失败", results.len());
+    }
+    Ok(exit_code::for_outcome(outcome))
+}
+
+/// 翻译文档并输出：片段翻译失败时按 `--on-error` 处理，译文经格式的检查后按原结构重新组装
+async fn run_document<D: Document<Output = String>>(
+    args: &Cli,
+    translator: &Translator,
+    document: &D,
+    warnings: &Warnings,
+) -> Result<i32> {
+    let translations = translate_document(
+        args,
+        translator,
+        document.segments(),
+        &document.notes(),
+        warnings,
+    )
+    .await?;
+    write_document(args, document, translations, warnings)
+}
+
+/// 按译文重新组装文档并输出
+fn write_document<D: Document<Output = String>>(
+    args: &Cli,
+    document: &D,
+    translations: Vec<Option<String>>,
+    warnings: &Warnings,
+) -> Result<i32> {
+    let output = render_document(args, document, translations, warnings)?;
+
+    let mut sink = args.output_sink(warnings)?;
+    sink.write(&output)?;
+    sink.finish()?;
+
+    Ok(exit_code::SUCCESS)
+}
+
+/// 检查译文并重新组装文档
+fn render_document<D: Document>(
+    args: &Cli,
+    document: &D,
+    translations: Vec<Option<String>>,
+    warnings: &Warnings,
+) -> Result<D::Output> {
+    let translations = document.check(translations, warnings);
+
+    // 启用 --deny-warnings 时，出现警告则不输出结果
+    warnings.check_denied(args.deny_warnings)?;
+
+    Ok(document.render(&translations)?)
+}
+
+/// properties 与 kv 模式的语法
+fn properties_syntax(args: &Cli) -> Syntax {
+    match args.format {
+        Format::Properties => Syntax::Properties {
+            utf8: args.properties_utf8,
+        },
+        _ => Syntax::Kv,
+    }
+}
+
+/// JSON 模式：翻译字符串值（及 `--translate-keys` 时的键），保留结构与其他类型的值
+///
+/// 相邻的短字符串合并为一个请求翻译，无法按标记拆回的批次逐个重新翻译。
+async fn run_json(
+    args: &Cli,
+    translator: &Translator,
+    text: &str,
+    warnings: &Warnings,
+) -> Result<i32> {
+    let targets = Targets {
+        strings: args.translate_strings,
+        keys: args.translate_keys,
+    };
+    let document = JsonDocument::parse(text, targets)?;
+    let segments = document.segments();
+
+    let batches = json_format::batch_segments(&segments);
+    let notes: Vec<Option<String>> = batches
+        .iter()
+        .map(|batch| {
+            batch
+                .is_merged()
+                .then(|| json_format::BATCH_NOTE.to_string())
+        })
+        .collect();
+    let texts = batches.iter().map(|batch| batch.text.clone()).collect();
+    // 占位标记无法按编号拆回，失败的合并批次与拆分失败的批次一样逐个重新翻译
+    let merged = translate_document(args, translator, texts, &notes, warnings).await?;
+    let (mut translations, retry) = json_format::split_batches(&batches, merged, segments.len());
+    if !retry.is_empty() {
+        let texts = retry.iter().map(|&index| segments[index].clone()).collect();
+        let singles = translate_document(args, translator, texts, &[], warnings).await?;
+        for (index, translation) in retry.into_iter().zip(singles) {
+            translations[index] = translation;
+        }
+    }
+    write_document(args, &document, translations, warnings)
+}
+
+/// HTML 模式：只翻译文本节点，保留标签与属性
+#[cfg(feature = "html")]
+async fn run_html(
+    args: &Cli,
+    translator: &Translator,
+    text: &str,
+    warnings: &Warnings,
+) -> Result<i32> {
+    let document = HtmlDocument::parse(text, &args.translate_attrs);
+    run_document(args, translator, &document, warnings).await
+}
+
+/// 未启用 html 特性时，参数验证阶段已报错
+#[cfg(not(feature = "html"))]
+async fn run_html(
+    _args: &Cli,
+    _translator: &Translator,
+    _text: &str,
+    _warnings: &Warnings,
+) -> Result<i32> {
+    bail!("--format html 需使用 --features html 构建")
+}
+
+/// 翻译 Word 文档：重新读取 `--file` 指定的文档，译文写入 `-o` 指定的文件
+#[cfg(feature = "docx")]
+async fn run_docx(args: &Cli, translator: &Translator, warnings: &Warnings) -> Result<i32> {
+    let (Some(input), Some(output)) = (args.file.first(), &args.output) else {
+        bail!("docx 格式需要通过 --file 指定文档，并通过 -o 指定译文的保存位置");
+    };
+    let bytes =
+        std::fs::read(input).with_context(|| format!("无法读取文件 '{}'", input.display()))?;
+    let document = DocxDocument::parse(&bytes)?;
+    let translations =
+        translate_document(args, translator, document.segments(), &[], warnings).await?;
+    let rendered = render_document(args, &document, translations, warnings)?;
+
+    std::fs::write(output, rendered)
+        .with_context(|| format!("无法写入文件 '{}'", output.display()))?;
+
+    Ok(exit_code::SUCCESS)
+}
+
+/// 未启用 docx 特性时，读取文件阶段已报错
+#[cfg(not(feature = "docx"))]
+async fn run_docx(_args: &Cli, _translator: &Translator, _warnings: &Warnings) -> Result<i32> {
+    bail!("--format docx 需使用 --features docx 构建")
+}
+
+/// 并发翻译文档片段并显示进度，失败的片段按 `--on-error` 保留原文（None）或替换为占位标记
+///
+/// `notes` 为与片段对应的译者注，格式不提供译者注时为空。`--on-error fail` 时返回第一个
+/// 失败片段的错误。
+async fn translate_document(
+    args: &Cli,
+    translator: &Translator,
+    segments: Vec<String>,
+    notes: &[Option<String>],
+    warnings: &Warnings,
+) -> Result<Vec<Option<String>>> {
+    let prompt = args.composed_prompt();
+    let prompt = prompt.as_str();
+    let options = segment_options(args, translator);
+    let options = options.as_str();
+    let (progress, spinner) = start_batch_progress(args, segments.len());
+    let progress = progress.as_ref();
+
+    let translations = formats::translate_segments(
+        segments,
+        notes,
+        &args.segment_error_options(),
+        warnings,
+        move |segment, note| async move {
+            let note = note.as_deref();
+            let translate = || translate_noted(args, translator, &segment, prompt, note, warnings);
+            let translation = async {
+                let Some(cache) = translator.segment_cache() else {
+                    return translate().await;
+                };
+                let key = SegmentKey {
+                    model: translator.model_name(),
+                    prompt,
+                    options,
+                    text: &segment,
+                    note,
+                };
+                cache.translate(&key, translate).await
+            };
+            progress.track(translation).await
+        },
+    )
+    .await;
+    spinner.finish();
+    translations
+}
+
+/// 影响片段译文的选项的哈希，作为片段缓存键的一部分
+fn segment_options(args: &Cli, translator: &Translator) -> String {
+    // 枚举选项使用命令行中的名称，与类型的调试输出无关
+    fn name(value: &impl ValueEnum) -> Option<String> {
+        value
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+    }
+    let sampling = args.sampling();
+    OutputOptions {
+        temperature: translator.temperature(),
+        max_tokens: args.max_tokens,
+        logit_bias: args
+            .logit_bias
+            .iter()
+            .map(|bias| (bias.token_id, bias.bias))
+            .collect(),
+        top_p: sampling.top_p,
+        frequency_penalty: sampling.frequency_penalty,
+        presence_penalty: sampling.presence_penalty,
+        choice_separator: args.choice_separator.clone(),
+        max_output_ratio: args.max_output_ratio,
+        sandbox_input: args.sandbox_input,
+        escalate_on_low_quality: args.escalate_on_low_quality,
+        escalate_model: args.escalate_model.clone(),
+        review_model: args.review_model.clone(),
+        post_process: args.post_process.iter().filter_map(name).collect(),
+        locale: args.locale.as_ref().and_then(name),
+        strip_markdown: args.strip_markdown(),
+        names: args.uses_name_memory(),
+    }
+    .digest()
+}
+
+/// 差量翻译：复用未变化段落的旧译文，仅翻译新增或修改的段落
+async fn run_diff(
+    args: &Cli,
+    translator: &Translator,
+    text: &str,
+    warnings: &Warnings,
+) -> Result<i32> {
+    // 参数解析时已确保两个路径同时提供
+    let old_source = std::fs::read_to_string(args.diff_against.as_ref().unwrap())?;
+    let old_translation = std::fs::read_to_string(args.previous_translation.as_ref().unwrap())?;
+
+    let document = DiffDocument::align(&old_source, &old_translation, text)?;
+    let segments = document.segments();
+    let pending = segments.len();
+    let prompt = args.composed_prompt();
+    let prompt = prompt.as_str();
+    let (progress, spinner) = start_batch_progress(args, pending);
+    let progress = progress.as_ref();
+
+    let translations = formats::translate_segments(
+        segments,
+        &[],
+        &args.segment_error_options(),
+        warnings,
+        move |paragraph, _| async move {
+            progress
+                .track(translate_checked(
+                    args, translator, &paragraph, prompt, warnings,
+                ))
+                .await
+        },
+    )
+    .await;
+    spinner.finish();
+    let translations = translations?;
+
+    // 启用 --deny-warnings 时，出现警告则不输出结果
+    warnings.check_denied(args.deny_warnings)?;
+
+    if !args.quiet {
+        progress::eprintln(format_args!(
+            "复用 {} 个段落的旧译文，重新翻译 {} 个段落",
+            document.reused(),
+            pending
+        ));
+    }
+
+    let mut sink = args.output_sink(warnings)?;
+    sink.write_result(&format_result(args, document.render(&translations)))?;
+    sink.finish()?;
 
-    // 使用自定义或默认提示执行翻译
-    translator.translate(text, Some(&args.prompt)).await
+    Ok(exit_code::SUCCESS)
 }
@@ -0,0 +1,192 @@
+//! 运行统计
+//!
+//! `--summary` 在运行结束时于 stderr 输出统计：条目总数、成功与失败数、重试次数、
+//! token 用量与耗时。条目在翻译完成时计数，token 用量来自每个响应的 `usage` 字段，
+//! 重试次数为共享重试预算（见 [`RetryBudget`]）已消耗的次数。
+
+use std::fmt;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::retry::RetryBudget;
+
+/// 运行期间累计的统计
+#[derive(Debug, Default)]
+pub struct Metrics {
+    succeeded: AtomicUsize,
+    failed: AtomicUsize,
+    memory_hits: AtomicUsize,
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+    retry_budget: OnceLock<RetryBudget>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一个条目的翻译结果
+    pub fn record_item(&self, succeeded: bool) {
+        let counter = if succeeded {
+            &self.succeeded
+        } else {
+            &self.failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 翻译一个条目并记录结果
+    pub async fn track<T>(
+        &self,
+        future: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let result = future.await;
+        self.record_item(result.is_ok());
+        result
+    }
+
+    /// 记录一次命中翻译记忆、未发送请求的翻译
+    pub fn record_memory_hit(&self) {
+        self.memory_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 命中翻译记忆的次数
+    pub fn memory_hits(&self) -> usize {
+        self.memory_hits.load(Ordering::Relaxed)
+    }
+
+    /// 记录一个响应的 token 用量
+    pub fn record_usage(&self, prompt_tokens: u32, completion_tokens: u32) {
+        self.prompt_tokens
+            .fetch_add(u64::from(prompt_tokens), Ordering::Relaxed);
+        self.completion_tokens
+            .fetch_add(u64::from(completion_tokens), Ordering::Relaxed);
+    }
+
+    /// 以重试预算的消耗次数作为重试次数；只记录第一次设置的预算
+    pub fn watch_retry_budget(&self, budget: RetryBudget) {
+        let _ = self.retry_budget.set(budget);
+    }
+
+    /// 生成统计摘要
+    pub fn summary(&self, elapsed: Duration) -> RunSummary {
+        let succeeded = self.succeeded.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        RunSummary {
+            items: succeeded + failed,
+            succeeded,
+            failed,
+            retries: self
+                .retry_budget
+                .get()
+                .map_or(0, |budget| budget.total() - budget.remaining()),
+            prompt_tokens: self.prompt_tokens.load(Ordering::Relaxed),
+            completion_tokens: self.completion_tokens.load(Ordering::Relaxed),
+            elapsed,
+        }
+    }
+}
+
+/// 运行结束时的统计摘要
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunSummary {
+    pub items: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub retries: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "运行统计:")?;
+        writeln!(f, "  条目: {}", self.items)?;
+        writeln!(f, "  成功: {}", self.succeeded)?;
+        writeln!(f, "  失败: {}", self.failed)?;
+        writeln!(f, "  重试: {}", self.retries)?;
+        writeln!(
+            f,
+            "  token: {}（输入 {}，输出 {}）",
+            self.prompt_tokens + self.completion_tokens,
+            self.prompt_tokens,
+            self.completion_tokens
+        )?;
+        write!(f, "  耗时: {:.1}s", self.elapsed.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::{BatchOptions, SystemClock, run_batch};
+    use crate::retry::{RetryPolicy, run_with_retry};
+
+    #[tokio::test]
+    async fn test_summary_counts_batch_with_one_failure() {
+        let metrics = Metrics::new();
+        let budget = RetryBudget::new(10);
+        metrics.watch_retry_budget(budget.clone());
+        let policy = RetryPolicy {
+            retries: 1,
+            backoff: Duration::ZERO,
+            budget: Some(budget),
+            ..RetryPolicy::default()
+        };
+
+        let lines: Vec<String> = ["one", "two", "fail", "four"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let metrics = &metrics;
+        let policy = &policy;
+        let report = run_batch(
+            lines,
+            BatchOptions::default(),
+            &SystemClock,
+            |line| async move {
+                metrics
+                    .track(run_with_retry(policy, &SystemClock, || {
+                        let line = line.clone();
+                        async move {
+                            if line == "fail" {
+                                anyhow::bail!("服务暂时不可用");
+                            }
+                            metrics.record_usage(10, 5);
+                            Ok(line.to_uppercase())
+                        }
+                    }))
+                    .await
+            },
+        )
+        .await;
+        assert_eq!(report.failed(), 1);
+
+        let summary = metrics.summary(Duration::from_millis(1500));
+        assert_eq!(
+            summary,
+            RunSummary {
+                items: 4,
+                succeeded: 3,
+                failed: 1,
+                retries: 1,
+                prompt_tokens: 30,
+                completion_tokens: 15,
+                elapsed: Duration::from_millis(1500),
+            }
+        );
+
+        let rendered = summary.to_string();
+        assert!(rendered.contains("失败: 1"), "{}", rendered);
+        assert!(
+            rendered.contains("token: 45（输入 30，输出 15）"),
+            "{}",
+            rendered
+        );
+        assert!(rendered.ends_with("耗时: 1.5s"), "{}", rendered);
+    }
+}
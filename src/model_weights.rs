@@ -0,0 +1,170 @@
+//! 按权重随机选择模型
+//!
+//! `--model-weights 'gpt-4o=7,gemini-2.5-flash=3'` 时每次运行按权重随机选择一个模型，用于在
+//! 多个提供商之间做 A/B 对比。选中的模型与 `-m` 指定的模型一样解析 API 地址与密钥，
+//! `--verbose` 时输出选择结果，JSON 输出的 `model` 字段为选中的模型。
+//!
+//! 随机数来自以 UUID v4 为种子的 SplitMix64 生成器，测试中使用固定种子。
+
+use std::fmt;
+
+/// 模型及其权重，按命令行中的顺序排列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelWeights(Vec<(String, u32)>);
+
+impl ModelWeights {
+    /// 权重之和
+    pub fn total(&self) -> u64 {
+        self.0.iter().map(|&(_, weight)| u64::from(weight)).sum()
+    }
+
+    /// 所有模型
+    pub fn models(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(model, _)| model.as_str())
+    }
+
+    /// 模型的权重，未列出的模型为 None
+    pub fn weight(&self, model: &str) -> Option<u32> {
+        self.0
+            .iter()
+            .find(|(name, _)| name == model)
+            .map(|&(_, weight)| weight)
+    }
+
+    /// 使用 `rng` 按权重选择一个模型
+    pub fn pick(&self, rng: &mut Rng) -> &str {
+        let mut roll = rng.below(self.total());
+        for (model, weight) in &self.0 {
+            let weight = u64::from(*weight);
+            if roll < weight {
+                return model;
+            }
+            roll -= weight;
+        }
+        unreachable!("权重之和大于 0")
+    }
+
+    /// 按权重随机选择一个模型
+    pub fn choose(&self) -> &str {
+        self.pick(&mut Rng::from_entropy())
+    }
+}
+
+impl fmt::Display for ModelWeights {
+    /// 与命令行相同的写法，如 `gpt-4o=7,gemini-2.5-flash=3`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (model, weight)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", model, weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// 解析 `--model-weights`：逗号分隔的 `模型=权重`，权重为正整数，模型不能重复
+pub fn parse_model_weights(s: &str) -> Result<ModelWeights, String> {
+    let mut weights: Vec<(String, u32)> = Vec::new();
+    for entry in s
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let Some((model, weight)) = entry.split_once('=') else {
+            return Err(format!(
+                "无效的模型权重 '{}'，应为 模型=权重（如 gpt-4o=7,gemini-2.5-flash=3）",
+                entry
+            ));
+        };
+        let model = model.trim();
+        let weight: u32 = match weight.trim().parse() {
+            Ok(weight) if weight > 0 => weight,
+            _ => return Err(format!("模型 '{}' 的权重必须为正整数", model)),
+        };
+        if model.is_empty() {
+            return Err(format!("无效的模型权重 '{}'，缺少模型名称", entry));
+        }
+        if weights.iter().any(|(name, _)| name == model) {
+            return Err(format!("模型 '{}' 重复出现", model));
+        }
+        weights.push((model.to_string(), weight));
+    }
+    if weights.is_empty() {
+        return Err("至少需要一个 模型=权重".to_string());
+    }
+    Ok(ModelWeights(weights))
+}
+
+/// SplitMix64 伪随机数生成器
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// 使用固定种子，相同的种子产生相同的序列
+    pub fn seeded(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// 以随机 UUID 为种子
+    pub fn from_entropy() -> Self {
+        let bits = uuid::Uuid::new_v4().as_u128();
+        Self((bits >> 64) as u64 ^ bits as u64)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// `0..n` 之间的随机数，`n` 相对 2^64 很小，取模的偏差可以忽略
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model_weights() {
+        let weights = parse_model_weights("gpt-4o=7, gemini-2.5-flash = 3").unwrap();
+        assert_eq!(weights.total(), 10);
+        assert_eq!(
+            weights.models().collect::<Vec<_>>(),
+            ["gpt-4o", "gemini-2.5-flash"]
+        );
+        assert_eq!(weights.weight("gemini-2.5-flash"), Some(3));
+        assert_eq!(weights.to_string(), "gpt-4o=7,gemini-2.5-flash=3");
+
+        assert!(parse_model_weights("gpt-4o").is_err());
+        assert!(parse_model_weights("gpt-4o=0").is_err());
+        assert!(parse_model_weights("gpt-4o=-1").is_err());
+        assert!(parse_model_weights("=3").is_err());
+        assert!(parse_model_weights("gpt-4o=1,gpt-4o=2").is_err());
+        assert!(parse_model_weights(" , ").is_err());
+    }
+
+    #[test]
+    fn test_distribution_matches_weights() {
+        let weights = parse_model_weights("gpt-4o=7,gemini-2.5-flash=3").unwrap();
+        let mut rng = Rng::seeded(42);
+        let iterations = 10_000;
+        let picked = (0..iterations)
+            .filter(|_| weights.pick(&mut rng) == "gpt-4o")
+            .count();
+        let share = picked as f64 / iterations as f64;
+        assert!((share - 0.7).abs() < 0.02, "{}", share);
+
+        // 相同的种子选择相同的模型
+        let sequence = |seed| {
+            let mut rng = Rng::seeded(seed);
+            (0..20).map(|_| weights.pick(&mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(7), sequence(7));
+    }
+}
@@ -0,0 +1,433 @@
+//! 一次调用翻译为多种目标语言
+//!
+//! `--to en --to ja --to ko` 将同一输入并发翻译为每种目标语言，每种语言使用各自的提示词。
+//! 某一语言失败不影响其他语言，结果按 `--to` 的顺序输出，并汇总每种语言的情况。
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use serde::Serialize;
+
+use crate::batch::BatchOutcome;
+use crate::error::{Result, TransomeError};
+use crate::translator::PROMPT;
+use crate::warning::Warning;
+
+/// `--output` 中代表目标语言的占位符
+pub const LANG_PLACEHOLDER: &str = "{lang}";
+
+/// 常见语言代码及其中文名称
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("zh", "简体中文"),
+    ("zh-tw", "繁体中文"),
+    ("en", "英文"),
+    ("ja", "日文"),
+    ("ko", "韩文"),
+    ("fr", "法文"),
+    ("de", "德文"),
+    ("es", "西班牙文"),
+    ("pt", "葡萄牙文"),
+    ("it", "意大利文"),
+    ("ru", "俄文"),
+    ("ar", "阿拉伯文"),
+];
+
+/// 语言代码对应的名称，未知的代码原样返回
+pub fn language_name(lang: &str) -> &str {
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(lang))
+        .map_or(lang, |&(_, name)| name)
+}
+
+/// 解析 `--to` 的语言代码
+pub fn parse_lang(s: &str) -> std::result::Result<String, String> {
+    let lang = s.trim();
+    if lang.is_empty()
+        || !lang
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("无效的语言代码 '{}'，示例: en、ja、zh-tw", s));
+    }
+    Ok(lang.to_ascii_lowercase())
+}
+
+/// 某一目标语言的提示词
+///
+/// 使用默认提示词时改用面向该语言的预设；自定义提示词后附加目标语言要求。
+pub fn target_prompt(prompt: &str, lang: &str) -> String {
+    let name = language_name(lang);
+    if prompt == PROMPT {
+        format!(
+            "你是一个极简翻译工具，接下来我将输入一段内容，请将它翻译成{}。\
+            仅输出翻译后的内容，不要携带其他内容。",
+            name
+        )
+    } else {
+        format!("{}\n请翻译成{}。", prompt, name)
+    }
+}
+
+/// 提示词中常见的语言称呼，用于推断自定义提示词的目标语言
+const LANGUAGE_ALIASES: &[(&str, &[&str])] = &[
+    ("zh-tw", &["繁体中文", "繁體中文", "traditional chinese"]),
+    ("zh", &["简体中文", "中文", "汉语", "chinese"]),
+    ("en", &["英文", "英语", "english"]),
+    ("ja", &["日文", "日语", "japanese"]),
+    ("ko", &["韩文", "韩语", "korean"]),
+    ("fr", &["法文", "法语", "french"]),
+    ("de", &["德文", "德语", "german"]),
+    ("es", &["西班牙文", "西班牙语", "spanish"]),
+    ("pt", &["葡萄牙文", "葡萄牙语", "portuguese"]),
+    ("it", &["意大利文", "意大利语", "italian"]),
+    ("ru", &["俄文", "俄语", "russian"]),
+    ("ar", &["阿拉伯文", "阿拉伯语", "arabic"]),
+];
+
+/// 语言称呼之前表示翻译目标的词，如「翻译成日文」「into Japanese」
+const TARGET_MARKERS: &[&str] = &["成", "为", "into", "to"];
+
+/// 自定义提示词要求的目标语言，无法确定时为 None
+///
+/// 提示词只提到一种语言时取该语言；提到多种时取紧跟在「翻译成」「into」等词之后的那一种。
+pub fn prompt_target(prompt: &str) -> Option<&'static str> {
+    let lowercase = prompt.to_ascii_lowercase();
+    let mut mentions: Vec<(usize, usize, &'static str)> = Vec::new();
+    for &(code, aliases) in LANGUAGE_ALIASES {
+        for alias in aliases {
+            for (start, _) in lowercase.match_indices(alias) {
+                mentions.push((start, start + alias.len(), code));
+            }
+        }
+    }
+    // 「繁体中文」中的「中文」不单独计入
+    let mentions: Vec<_> = mentions
+        .iter()
+        .filter(|&&(start, end, _)| {
+            !mentions
+                .iter()
+                .any(|&(s, e, _)| s <= start && end <= e && e - s > end - start)
+        })
+        .collect();
+
+    let single = |mut codes: Vec<&'static str>| {
+        codes.sort_unstable();
+        codes.dedup();
+        match codes.as_slice() {
+            [code] => Some(*code),
+            _ => None,
+        }
+    };
+
+    let marked: Vec<_> = mentions
+        .iter()
+        .filter(|&&&(start, _, _)| {
+            let before = lowercase[..start].trim_end();
+            TARGET_MARKERS.iter().any(|marker| before.ends_with(marker))
+        })
+        .map(|&&(_, _, code)| code)
+        .collect();
+    if !marked.is_empty() {
+        return single(marked);
+    }
+
+    single(mentions.iter().map(|&&(_, _, code)| code).collect())
+}
+
+/// 将 `--output` 中的 `{lang}` 替换为语言代码
+pub fn output_path(template: &Path, lang: &str) -> PathBuf {
+    PathBuf::from(template.to_string_lossy().replace(LANG_PLACEHOLDER, lang))
+}
+
+/// 某一目标语言的翻译结果
+#[derive(Debug)]
+pub struct TargetResult {
+    pub lang: String,
+    pub result: anyhow::Result<String>,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for TargetResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.result {
+            Ok(translation) => write!(
+                f,
+                "{}: 成功，{} 个字符，用时 {:.1}s",
+                self.lang,
+                translation.chars().count(),
+                self.elapsed.as_secs_f64()
+            ),
+            Err(e) => write!(f, "{}: 失败 - {}", self.lang, e),
+        }
+    }
+}
+
+/// 并发翻译为每种目标语言，结果按 `targets` 的顺序返回
+///
+/// `translate` 接收目标语言代码。某一语言失败不影响其他语言。
+pub async fn translate_targets<F, Fut>(targets: &[String], translate: F) -> Vec<TargetResult>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+{
+    let translate = &translate;
+    join_all(targets.iter().map(|lang| async move {
+        let start = Instant::now();
+        let result = translate(lang.clone()).await;
+        TargetResult {
+            lang: lang.clone(),
+            result,
+            elapsed: start.elapsed(),
+        }
+    }))
+    .await
+}
+
+/// 整体结果
+pub fn outcome(results: &[TargetResult]) -> BatchOutcome {
+    let succeeded = results.iter().filter(|r| r.result.is_ok()).count();
+    BatchOutcome::from_counts(succeeded, results.len() - succeeded)
+}
+
+/// 按语言分节输出的文本，失败的语言不输出
+///
+/// 只有一种目标语言时不加标题。
+pub fn render_sections(results: &[TargetResult]) -> String {
+    let succeeded: Vec<(&str, &str)> = results
+        .iter()
+        .filter_map(|r| Some((r.lang.as_str(), r.result.as_deref().ok()?)))
+        .collect();
+
+    if results.len() == 1 {
+        return succeeded
+            .first()
+            .map_or(String::new(), |(_, text)| text.to_string());
+    }
+
+    succeeded
+        .iter()
+        .map(|(lang, text)| format!("== {} ==\n{}", lang, text))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 语言标签，如 `[es]`
+pub fn language_label(lang: &str) -> String {
+    format!("[{}]", lang)
+}
+
+/// 每行译文前加上语言标签（`--label-languages`），按 `--to` 的顺序输出，失败的语言不输出
+///
+/// 多行的译文每行都加上标签，便于按语言筛选。
+pub fn render_labeled(results: &[TargetResult]) -> String {
+    results
+        .iter()
+        .filter_map(|r| Some((language_label(&r.lang), r.result.as_deref().ok()?)))
+        .flat_map(|(label, text)| {
+            text.lines()
+                .map(move |line| format!("{} {}", label, line))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 汇总每种语言的情况，如 `3 种目标语言：2 种成功，1 种失败`
+pub fn summary(results: &[TargetResult]) -> String {
+    let failed = results.iter().filter(|r| r.result.is_err()).count();
+    let mut lines = vec![format!(
+        "{} 种目标语言：{} 种成功，{} 种失败",
+        results.len(),
+        results.len() - failed,
+        failed
+    )];
+    lines.extend(results.iter().map(|r| format!("  {}", r)));
+    lines.join("\n")
+}
+
+/// 多目标语言模式下 `--json` 输出的结构
+#[derive(Debug, Clone, Serialize)]
+pub struct MultiTargetJson {
+    pub model: String,
+    /// 语言代码到译文
+    pub translations: BTreeMap<String, String>,
+    /// 语言代码到错误信息，没有失败时省略
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+    pub warnings: Vec<Warning>,
+}
+
+impl MultiTargetJson {
+    pub fn new(model: impl Into<String>, results: &[TargetResult], warnings: Vec<Warning>) -> Self {
+        let mut translations = BTreeMap::new();
+        let mut errors = BTreeMap::new();
+        for r in results {
+            match &r.result {
+                Ok(text) => translations.insert(r.lang.clone(), text.clone()),
+                Err(e) => errors.insert(r.lang.clone(), format!("{:#}", e)),
+            };
+        }
+        Self {
+            model: model.into(),
+            translations,
+            errors,
+            warnings,
+        }
+    }
+
+    /// 以语言标签（如 `[es]`）作为译文与错误信息的键（`--label-languages`）
+    pub fn labeled(mut self) -> Self {
+        let relabel = |map: BTreeMap<String, String>| {
+            map.into_iter()
+                .map(|(lang, text)| (language_label(&lang), text))
+                .collect()
+        };
+        self.translations = relabel(self.translations);
+        self.errors = relabel(self.errors);
+        self
+    }
+
+    /// 序列化为 JSON 字符串，`pretty` 为 true 时缩进
+    pub fn to_json(&self, pretty: bool) -> Result<String> {
+        let json = if pretty {
+            serde_json::to_string_pretty(self)
+        } else {
+            serde_json::to_string(self)
+        };
+        json.map_err(|e| TransomeError::json_error_with_context(e, "序列化翻译结果"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+
+    fn targets(langs: &[&str]) -> Vec<String> {
+        langs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_lang_and_names() {
+        assert_eq!(parse_lang("EN").unwrap(), "en");
+        assert_eq!(parse_lang("zh-TW").unwrap(), "zh-tw");
+        assert!(parse_lang("").is_err());
+        assert!(parse_lang("en ja").is_err());
+
+        assert_eq!(language_name("ja"), "日文");
+        assert_eq!(language_name("tlh"), "tlh");
+    }
+
+    #[test]
+    fn test_target_prompt() {
+        let preset = target_prompt(PROMPT, "ja");
+        assert!(preset.contains("翻译成日文"));
+        assert!(!preset.contains("反之亦然"));
+
+        assert_eq!(
+            target_prompt("使用正式语气", "ko"),
+            "使用正式语气\n请翻译成韩文。"
+        );
+    }
+
+    #[test]
+    fn test_prompt_target() {
+        assert_eq!(prompt_target("请用正式语气翻译成日文"), Some("ja"));
+        assert_eq!(prompt_target("Translate into French."), Some("fr"));
+        assert_eq!(prompt_target("将中文翻译成繁体中文"), Some("zh-tw"));
+        assert_eq!(prompt_target("把下面的英文译为韩语，保留术语"), Some("ko"));
+        // 只提到一种语言
+        assert_eq!(prompt_target("输出德文，不要解释"), Some("de"));
+        // 没有提到语言，或无法判断哪一种是目标
+        assert_eq!(prompt_target("使用正式语气"), None);
+        assert_eq!(prompt_target("中文和英文混排时保留原样"), None);
+    }
+
+    #[test]
+    fn test_output_path() {
+        assert_eq!(
+            output_path(Path::new("docs/out.{lang}.md"), "ja"),
+            PathBuf::from("docs/out.ja.md")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failure_in_one_target_does_not_abort_others() {
+        let results = translate_targets(&targets(&["en", "ja", "ko"]), |lang| async move {
+            if lang == "ja" {
+                bail!("quota exceeded");
+            }
+            Ok(format!("hello in {}", lang))
+        })
+        .await;
+
+        let langs: Vec<&str> = results.iter().map(|r| r.lang.as_str()).collect();
+        assert_eq!(langs, ["en", "ja", "ko"]);
+        assert_eq!(outcome(&results), BatchOutcome::PartialSuccess);
+
+        assert_eq!(
+            render_sections(&results),
+            "== en ==\nhello in en\n\n== ko ==\nhello in ko"
+        );
+
+        let summary = summary(&results);
+        assert!(summary.starts_with("3 种目标语言：2 种成功，1 种失败"));
+        assert!(summary.contains("  ja: 失败 - quota exceeded"));
+        assert!(summary.contains("  en: 成功，11 个字符"));
+
+        let json: serde_json::Value = serde_json::from_str(
+            &MultiTargetJson::new("gpt-4o", &results, Vec::new())
+                .to_json(false)
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["translations"]["en"], "hello in en");
+        assert_eq!(json["translations"]["ko"], "hello in ko");
+        assert_eq!(json["errors"]["ja"], "quota exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_language_labels() {
+        let results = translate_targets(&targets(&["es", "ja"]), |lang| async move {
+            Ok(match lang.as_str() {
+                "es" => "hola".to_string(),
+                _ => "こんにちは\nさようなら".to_string(),
+            })
+        })
+        .await;
+
+        assert_eq!(
+            render_labeled(&results),
+            "[es] hola\n[ja] こんにちは\n[ja] さようなら"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(
+            &MultiTargetJson::new("gpt-4o", &results, Vec::new())
+                .labeled()
+                .to_json(false)
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["translations"]["[es]"], "hola");
+        assert_eq!(json["translations"]["[ja]"], "こんにちは\nさようなら");
+    }
+
+    #[tokio::test]
+    async fn test_single_target_has_no_heading() {
+        let results = translate_targets(&targets(&["ja"]), |_| async {
+            Ok("こんにちは".to_string())
+        })
+        .await;
+        assert_eq!(render_sections(&results), "こんにちは");
+
+        let json = MultiTargetJson::new("gpt-4o", &results, Vec::new())
+            .to_json(false)
+            .unwrap();
+        assert!(!json.contains("errors"));
+    }
+}
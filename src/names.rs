@@ -0,0 +1,245 @@
+//! 人名与专有名词的译名一致性
+//!
+//! `--consistent-names` 时要求模型以 JSON 返回译文及其中人名、地名等专有名词的译名
+//! （`{"translation": "...", "names": {"Müller": "穆勒"}}`）。运行中积累的译名对照表像术语表
+//! 一样加入之后每个请求的提示词，长文档中同一名字保持同一译名。模型给出与对照表不同的译名时
+//! 记录 [`WarningKind::InconsistentName`] 警告，对照表保留最先确定的译名。
+//!
+//! `--names-file` 指定的 JSON 文件（`{"Müller": "穆勒"}`）在运行开始时载入对照表，运行结束时
+//! 写回（见 [`store::write_atomic`]），跨运行保持译名一致。模型没有按 JSON 回答时按纯文本使用
+//! 响应，不影响译文。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Deserialize;
+
+use crate::error::{Result, TransomeError};
+use crate::store;
+use crate::warning::{WarningKind, Warnings};
+
+/// 模型按 JSON 返回的译文与专有名词译名
+#[derive(Debug, Deserialize)]
+struct NamedTranslation {
+    translation: String,
+    #[serde(default)]
+    names: BTreeMap<String, String>,
+}
+
+/// 一次运行的专有名词译名对照表
+#[derive(Debug)]
+pub struct NameMemory {
+    path: Option<PathBuf>,
+    names: Mutex<BTreeMap<String, String>>,
+    warnings: Warnings,
+}
+
+impl NameMemory {
+    /// 创建空的对照表，只在本次运行中使用
+    pub fn new(warnings: &Warnings) -> Self {
+        Self {
+            path: None,
+            names: Mutex::default(),
+            warnings: warnings.clone(),
+        }
+    }
+
+    /// 读取对照表文件，文件不存在时从空表开始，运行结束时由 [`NameMemory::save`] 写回
+    pub fn load(path: &Path, warnings: &Warnings) -> Result<Self> {
+        let names = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                TransomeError::json_error_with_context(
+                    e,
+                    format!("解析名称对照表 '{}'", path.display()),
+                )
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(TransomeError::io_error_with_context(
+                    e,
+                    format!("读取名称对照表 '{}'", path.display()),
+                ));
+            }
+        };
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            names: Mutex::new(names),
+            warnings: warnings.clone(),
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// 当前对照表的副本，按原文排序
+    pub fn names(&self) -> BTreeMap<String, String> {
+        self.lock().clone()
+    }
+
+    /// 在提示词后追加已确定的译名与 JSON 回答格式的要求
+    pub fn extend_prompt(&self, prompt: &str) -> String {
+        let mut extended = format!(
+            "{}\n\n译文中的人名、地名等专有名词须使用一致的译名。",
+            prompt
+        );
+        let names = self.lock();
+        if !names.is_empty() {
+            extended.push_str("以下名称已确定译名，必须沿用：\n");
+            for (source, rendering) in names.iter() {
+                extended.push_str(&format!("- {} → {}\n", source, rendering));
+            }
+        }
+        extended.push_str(
+            "只输出一个 JSON 对象，不要输出其他内容：\
+            {\"translation\": \"译文\", \"names\": {\"原文中的专有名词\": \"译名\"}}",
+        );
+        extended
+    }
+
+    /// 从模型的响应中取出译文并记录其中的译名
+    ///
+    /// 与对照表冲突的译名记录警告，不覆盖已有译名；响应不是约定的 JSON 时原样返回。
+    pub fn accept(&self, response: &str) -> String {
+        let Some(parsed) = parse_response(response) else {
+            tracing::debug!("响应不是约定的 JSON，按纯文本使用");
+            return response.to_string();
+        };
+
+        let mut names = self.lock();
+        for (source, rendering) in parsed.names {
+            let (source, rendering) = (source.trim(), rendering.trim());
+            if source.is_empty() || rendering.is_empty() {
+                continue;
+            }
+            match names.get(source) {
+                Some(known) if known != rendering => self.warnings.warn(
+                    WarningKind::InconsistentName,
+                    format!(
+                        "名称 '{}' 的译名不一致：对照表中为 '{}'，本次译为 '{}'",
+                        source, known, rendering
+                    ),
+                ),
+                Some(_) => {}
+                None => {
+                    names.insert(source.to_string(), rendering.to_string());
+                }
+            }
+        }
+        parsed.translation
+    }
+
+    /// 对照表的文字报告，对照表为空时为 None
+    pub fn report(&self) -> Option<String> {
+        let names = self.lock();
+        if names.is_empty() {
+            return None;
+        }
+        let mut report = String::from("名称对照表:");
+        for (source, rendering) in names.iter() {
+            report.push_str(&format!("\n  {} → {}", source, rendering));
+        }
+        Some(report)
+    }
+
+    /// 把对照表写回 `--names-file`，没有指定文件时不做任何事
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.lock())
+            .map_err(|e| TransomeError::json_error_with_context(e, "序列化名称对照表"))?;
+        store::write_atomic(path, format!("{}\n", json).as_bytes())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, String>> {
+        self.names.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 解析 JSON 响应，去除模型有时添加的代码块标记
+fn parse_response(response: &str) -> Option<NamedTranslation> {
+    let body = response.trim();
+    let body = body
+        .strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|body| body.strip_suffix("```"))
+        .unwrap_or(body);
+    serde_json::from_str(body.trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_names_are_injected_into_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.json");
+        fs::write(&path, r#"{"Müller": "穆勒"}"#).unwrap();
+
+        let memory = NameMemory::load(&path, &Warnings::new(true)).unwrap();
+        let prompt = memory.extend_prompt("翻译成中文");
+        assert!(prompt.starts_with("翻译成中文\n\n"));
+        assert!(prompt.contains("- Müller → 穆勒\n"), "{}", prompt);
+        assert!(prompt.contains("\"translation\""));
+
+        let empty = NameMemory::new(&Warnings::new(true));
+        assert!(!empty.extend_prompt("翻译成中文").contains("→"));
+    }
+
+    #[test]
+    fn test_conflicting_rendering_is_flagged() {
+        let warnings = Warnings::new(true);
+        let memory = NameMemory::new(&warnings);
+
+        let first =
+            memory.accept(r#"{"translation": "穆勒先生来了。", "names": {"Müller": "穆勒"}}"#);
+        assert_eq!(first, "穆勒先生来了。");
+        assert!(warnings.is_empty());
+
+        let second = memory.accept(
+            "```json\n{\"translation\": \"米勒走了。\", \"names\": {\"Müller\": \"米勒\", \"Berlin\": \"柏林\"}}\n```",
+        );
+        assert_eq!(second, "米勒走了。");
+        let flagged = warnings.to_vec();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].kind, WarningKind::InconsistentName);
+        assert!(flagged[0].message.contains("'穆勒'"));
+
+        // 冲突的译名不覆盖对照表，新的名称照常加入
+        assert_eq!(memory.names()["Müller"], "穆勒");
+        assert_eq!(memory.names()["Berlin"], "柏林");
+        assert_eq!(
+            memory.report().unwrap(),
+            "名称对照表:\n  Berlin → 柏林\n  Müller → 穆勒"
+        );
+    }
+
+    #[test]
+    fn test_plain_response_is_used_as_is() {
+        let memory = NameMemory::new(&Warnings::new(true));
+        assert_eq!(memory.accept("Hello"), "Hello");
+        assert!(memory.names().is_empty());
+        assert_eq!(memory.report(), None);
+    }
+
+    #[test]
+    fn test_names_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.json");
+
+        let memory = NameMemory::load(&path, &Warnings::new(true)).unwrap();
+        memory.accept(r#"{"translation": "穆勒", "names": {"Müller": "穆勒"}}"#);
+        memory.save().unwrap();
+
+        let reloaded = NameMemory::load(&path, &Warnings::new(true)).unwrap();
+        assert_eq!(reloaded.names(), memory.names());
+
+        fs::write(&path, "not json").unwrap();
+        assert!(NameMemory::load(&path, &Warnings::new(true)).is_err());
+    }
+}
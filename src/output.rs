@@ -0,0 +1,258 @@
+//! 翻译结果的输出格式化
+
+use serde::Serialize;
+
+use crate::error::{Result, TransomeError};
+use crate::warning::Warning;
+
+/// 渲染命令行输出的错误信息
+///
+/// 错误链以 `: ` 连接；`show_hints` 为 true 且错误来自 [`TransomeError`] 时，
+/// 在末尾附加针对该错误类型的故障排除建议。
+pub fn render_error(error: &anyhow::Error, show_hints: bool) -> String {
+    let mut rendered = format!("{:#}", error);
+
+    let hints = match error.downcast_ref::<TransomeError>() {
+        Some(e) if show_hints => e.hints(),
+        _ => Vec::new(),
+    };
+    if !hints.is_empty() {
+        rendered.push_str("\n\n故障排除建议:");
+        for hint in hints {
+            rendered.push_str("\n- ");
+            rendered.push_str(hint);
+        }
+    }
+
+    rendered
+}
+
+/// 渲染单行的错误信息，用于 `--pipe`
+///
+/// 只保留错误链本身，去掉其后的使用方法等说明，多行内容合并为一行。
+pub fn render_compact_error(error: &anyhow::Error) -> String {
+    let rendered = format!("{:#}", error);
+    let message = rendered.split("\n\n").next().unwrap_or_default();
+    message.lines().map(str::trim).collect::<Vec<_>>().join(" ")
+}
+
+/// `--json` 模式下输出的结构
+///
+/// 字段名是 `--json` 输出的兼容性约定；启用 serde 特性时可以反序列化。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct JsonOutput {
+    pub model: String,
+    pub translation: String,
+    /// 请求使用的采样温度，未固定时省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    pub warnings: Vec<Warning>,
+}
+
+impl JsonOutput {
+    /// 创建 JSON 输出结构
+    pub fn new(
+        model: impl Into<String>,
+        translation: impl Into<String>,
+        warnings: Vec<Warning>,
+    ) -> Self {
+        Self {
+            model: model.into(),
+            translation: translation.into(),
+            temperature: None,
+            warnings,
+        }
+    }
+
+    /// 记录请求使用的采样温度
+    pub fn with_temperature(mut self, temperature: Option<f32>) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// 序列化为单行的 JSON 字符串
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self)
+            .map_err(|e| TransomeError::json_error_with_context(e, "序列化翻译结果"))
+    }
+
+    /// 序列化为缩进的 JSON 字符串
+    pub fn to_json_pretty(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| TransomeError::json_error_with_context(e, "序列化翻译结果"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warning::WarningKind;
+
+    fn translation_error(error: TransomeError) -> anyhow::Error {
+        anyhow::Error::new(error).context("翻译失败")
+    }
+
+    #[test]
+    fn test_render_auth_error() {
+        let error = translation_error(TransomeError::authentication_error("Incorrect API key"));
+        assert_eq!(
+            render_error(&error, true),
+            "翻译失败: 认证失败：Incorrect API key\n\n\
+            故障排除建议:\n\
+            - 验证您的 API 密钥是否正确，并具有访问该模型的权限\n\
+            - OpenAI 密钥通常以 'sk-' 开头，Google AI 密钥通常以 'AIza' 开头\n\
+            - 使用 -k <KEY> 直接提供密钥，或检查对应的环境变量"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_network_error() {
+        let source = reqwest::Client::new()
+            .get("http://")
+            .send()
+            .await
+            .unwrap_err();
+        let error = translation_error(TransomeError::NetworkError { source });
+        let rendered = render_error(&error, true);
+
+        assert!(rendered.starts_with("翻译失败: 网络错误："));
+        assert!(rendered.ends_with(
+            "故障排除建议:\n\
+            - 检查您的网络连接\n\
+            - 如果通过代理访问，确认 HTTPS_PROXY / HTTP_PROXY 环境变量设置正确\n\
+            - 如果使用了 --url，确认该端点可以访问"
+        ));
+    }
+
+    #[test]
+    fn test_render_api_errors() {
+        let error = translation_error(TransomeError::api_call_failed(
+            "https://api.openai.com/v1",
+            Some(429),
+            "Rate limit reached",
+        ));
+        assert_eq!(
+            render_error(&error, true),
+            "翻译失败: API 调用 'https://api.openai.com/v1' 失败，状态码 429：Rate limit reached\n\n\
+            故障排除建议:\n\
+            - 稍后再试，或使用 --concurrency 降低并发数\n\
+            - 如果频繁出现，请考虑升级您的 API 套餐"
+        );
+
+        let error = translation_error(TransomeError::api_call_failed(
+            "https://api.openai.com/v1",
+            Some(404),
+            "model not found",
+        ));
+        assert_eq!(
+            render_error(&error, true),
+            "翻译失败: API 调用 'https://api.openai.com/v1' 失败，状态码 404：model not found\n\n\
+            故障排除建议:\n\
+            - 确认模型名称正确，且您有权使用该模型\n\
+            - 使用 --list-models 查看可用选项"
+        );
+    }
+
+    #[test]
+    fn test_render_error_without_hints() {
+        let error = translation_error(TransomeError::authentication_error("Incorrect API key"));
+        assert_eq!(
+            render_error(&error, false),
+            "翻译失败: 认证失败：Incorrect API key"
+        );
+
+        // 非 TransomeError 的错误不附加建议
+        let error = anyhow::anyhow!("要翻译的文本不能为空");
+        assert_eq!(render_error(&error, true), "要翻译的文本不能为空");
+
+        // 没有建议的错误类型不输出空的建议段落
+        let error = anyhow::Error::new(TransomeError::format_error("CSV", "bad row"));
+        assert_eq!(render_error(&error, true), "处理 CSV 格式失败：bad row");
+    }
+
+    #[test]
+    fn test_render_compact_error() {
+        let error = anyhow::anyhow!(
+            "要翻译的文本是必需的\n\n使用方法: transome [选项] <文本>\n\n获取更多信息，使用: transome --help"
+        );
+        assert_eq!(render_compact_error(&error), "要翻译的文本是必需的");
+
+        let error = translation_error(TransomeError::authentication_error("Incorrect\nAPI key"));
+        assert_eq!(
+            render_compact_error(&error),
+            "翻译失败: 认证失败：Incorrect API key"
+        );
+    }
+
+    #[test]
+    fn test_json_output_includes_warnings() {
+        let output = JsonOutput::new(
+            "gpt-4",
+            "你好",
+            vec![Warning::new(WarningKind::KeyFormat, "key looks wrong")],
+        );
+        let json: serde_json::Value = serde_json::from_str(&output.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["model"], "gpt-4");
+        assert_eq!(json["translation"], "你好");
+        assert_eq!(json["warnings"][0]["kind"], "key_format");
+        assert_eq!(json["warnings"][0]["message"], "key looks wrong");
+    }
+
+    #[test]
+    fn test_json_output_without_warnings() {
+        let output = JsonOutput::new("gpt-4", "hello", Vec::new());
+        let json: serde_json::Value = serde_json::from_str(&output.to_json().unwrap()).unwrap();
+        assert_eq!(json["warnings"], serde_json::json!([]));
+        assert!(json.get("temperature").is_none());
+    }
+
+    #[test]
+    fn test_json_compact_and_pretty() {
+        let output = JsonOutput::new("gpt-4", "第一行\n第二行", Vec::new());
+
+        let compact = output.to_json().unwrap();
+        assert!(!compact.contains('\n'));
+
+        let pretty = output.to_json_pretty().unwrap();
+        assert!(pretty.contains("\n  \"model\": \"gpt-4\""));
+
+        // 两种格式表示同一结果
+        let compact: serde_json::Value = serde_json::from_str(&compact).unwrap();
+        let pretty: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(compact, pretty);
+    }
+
+    #[test]
+    fn test_json_output_includes_temperature() {
+        let output = JsonOutput::new("gpt-4", "hello", Vec::new()).with_temperature(Some(0.5));
+        let json: serde_json::Value = serde_json::from_str(&output.to_json().unwrap()).unwrap();
+        assert_eq!(json["temperature"], 0.5);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_output_round_trip() {
+        let output = JsonOutput::new(
+            "gpt-4",
+            "你好",
+            vec![Warning::new(WarningKind::SegmentFailed, "kept source")],
+        )
+        .with_temperature(Some(0.5));
+        let json = output.to_json().unwrap();
+        // 字段名与顺序是 --json 输出的兼容性约定
+        assert_eq!(
+            json,
+            r#"{"model":"gpt-4","translation":"你好","temperature":0.5,"warnings":[{"kind":"segment_failed","message":"kept source"}]}"#
+        );
+        assert_eq!(serde_json::from_str::<JsonOutput>(&json).unwrap(), output);
+
+        let without_temperature = JsonOutput::new("gpt-4", "hello", Vec::new());
+        let json = without_temperature.to_json().unwrap();
+        assert_eq!(
+            serde_json::from_str::<JsonOutput>(&json).unwrap(),
+            without_temperature
+        );
+    }
+}
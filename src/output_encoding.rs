@@ -0,0 +1,251 @@
+//! 按终端的字符编码输出
+//!
+//! 译文总是以 UTF-8 处理。终端使用其他编码（如 Windows 旧版控制台的 GBK 代码页 936，或
+//! `LANG=zh_CN.GB18030` 的终端）时，直接写出 UTF-8 字节会显示为乱码。标准输出是终端时按
+//! 终端的编码转码输出：Windows 按控制台的输出代码页，其他平台按 `LC_ALL`、`LC_CTYPE`、`LANG`
+//! 中的字符集。`--output-encoding` 明确指定编码时，标准输出与 `--output` 文件都按该编码写出。
+//!
+//! 目标编码无法表示的字符替换为 `?`，并记录一条 [`WarningKind::LossyOutput`] 警告，
+//! 建议执行 `chcp 65001` 或用 `--output` 写入文件。标准输出被重定向时不自动转码，
+//! 下游工具总是收到 UTF-8；encoding_rs 不支持的代码页（如 437）同样按 UTF-8 输出。
+
+use std::io::{self, IsTerminal};
+
+use encoding_rs::{EncoderResult, Encoding, UTF_8};
+
+use crate::platform;
+use crate::warning::{WarningKind, Warnings};
+
+/// 标准输出转码有损时的建议
+pub const CONSOLE_HINT: &str = "可执行 chcp 65001 将控制台切换为 UTF-8，或使用 --output 写入文件";
+
+/// 文件转码有损时的建议
+pub const FILE_HINT: &str = "请改用能表示这些字符的编码，如 utf-8 或 gb18030";
+
+/// Windows 代码页与 encoding_rs 的编码标签
+const CODE_PAGES: &[(u32, &str)] = &[
+    (866, "ibm866"),
+    (874, "windows-874"),
+    (932, "shift_jis"),
+    (936, "gbk"),
+    (949, "euc-kr"),
+    (950, "big5"),
+    (1250, "windows-1250"),
+    (1251, "windows-1251"),
+    (1252, "windows-1252"),
+    (1253, "windows-1253"),
+    (1254, "windows-1254"),
+    (1255, "windows-1255"),
+    (1256, "windows-1256"),
+    (1257, "windows-1257"),
+    (1258, "windows-1258"),
+    (20866, "koi8-r"),
+    (54936, "gb18030"),
+    (65001, "utf-8"),
+];
+
+/// 解析 `--output-encoding`：编码标签，如 `utf-8`、`gbk`、`gb18030`、`big5`、`shift_jis`
+pub fn parse_encoding(s: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(s.trim().as_bytes())
+        .map(Encoding::output_encoding)
+        .ok_or_else(|| {
+            format!(
+                "未知的字符编码 '{}'，可用 utf-8、gbk、gb18030、big5、shift_jis 等",
+                s
+            )
+        })
+}
+
+/// Windows 代码页对应的编码，encoding_rs 不支持的代码页为 None
+pub fn code_page_encoding(code_page: u32) -> Option<&'static Encoding> {
+    let &(_, label) = CODE_PAGES.iter().find(|&&(page, _)| page == code_page)?;
+    Encoding::for_label(label.as_bytes())
+}
+
+/// POSIX 区域设置（如 `zh_CN.GB18030@pinyin`）中的字符集，没有字符集或无法识别时为 None
+pub fn locale_encoding(locale: &str) -> Option<&'static Encoding> {
+    let (_, charset) = locale.split_once('.')?;
+    let charset = charset.split('@').next().unwrap_or(charset);
+    Encoding::for_label(charset.as_bytes())
+}
+
+/// 把 UTF-8 文本转码为 `encoding`，返回转码后的字节及替换为 `?` 的字符数
+pub fn transcode(text: &str, encoding: &'static Encoding) -> (Vec<u8>, usize) {
+    if encoding == UTF_8 {
+        return (text.as_bytes().to_vec(), 0);
+    }
+
+    let mut encoder = encoding.new_encoder();
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text;
+    let mut replaced = 0;
+    loop {
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(rest, &mut bytes, true);
+        rest = &rest[read..];
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => bytes.reserve(rest.len().max(16)),
+            EncoderResult::Unmappable(_) => {
+                replaced += 1;
+                bytes.push(b'?');
+            }
+        }
+    }
+    (bytes, replaced)
+}
+
+/// 写出前把输出转码为非 UTF-8 编码，第一次出现无法表示的字符时记录警告
+#[derive(Debug, Clone)]
+pub struct Transcoder {
+    encoding: &'static Encoding,
+    warnings: Warnings,
+    hint: &'static str,
+    warned: bool,
+    /// 转码后再转回 UTF-8，只替换无法表示的字符：Windows 控制台由标准库按 UTF-16 写入，
+    /// 不接受其他编码的字节
+    utf8_output: bool,
+}
+
+impl Transcoder {
+    /// 创建转码器，`encoding` 为 UTF-8 时不需要转码，返回 None
+    pub fn new(
+        encoding: &'static Encoding,
+        warnings: &Warnings,
+        hint: &'static str,
+    ) -> Option<Self> {
+        (encoding != UTF_8).then(|| Self {
+            encoding,
+            warnings: warnings.clone(),
+            hint,
+            warned: false,
+            utf8_output: false,
+        })
+    }
+
+    /// 转码后再转回 UTF-8 写出
+    pub fn utf8_output(mut self) -> Self {
+        self.utf8_output = true;
+        self
+    }
+
+    /// 转码一段输出
+    pub fn encode(&mut self, text: &str) -> Vec<u8> {
+        let (bytes, replaced) = transcode(text, self.encoding);
+        if replaced > 0 && !self.warned {
+            self.warned = true;
+            self.warnings.warn(
+                WarningKind::LossyOutput,
+                format!(
+                    "输出编码 {} 无法表示部分字符，已替换为 '?'；{}",
+                    self.encoding.name(),
+                    self.hint
+                ),
+            );
+        }
+        if self.utf8_output {
+            let (text, _) = self.encoding.decode_without_bom_handling(&bytes);
+            return text.into_owned().into_bytes();
+        }
+        bytes
+    }
+}
+
+/// 标准输出的转码器：明确指定的编码优先，否则按终端的编码；不需要转码时为 None
+pub fn stdout_transcoder(
+    explicit: Option<&'static Encoding>,
+    warnings: &Warnings,
+) -> Option<Transcoder> {
+    // Windows 控制台：标准库按 UTF-16 写入，只替换代码页无法表示的字符
+    if let Some(code_page) = platform::console_output_code_page() {
+        let encoding = explicit.or_else(|| code_page_encoding(code_page))?;
+        return Transcoder::new(encoding, warnings, CONSOLE_HINT).map(Transcoder::utf8_output);
+    }
+
+    let encoding = explicit.or_else(|| {
+        if !io::stdout().is_terminal() {
+            return None;
+        }
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|locale| locale_encoding(&locale))
+    })?;
+    Transcoder::new(encoding, warnings, CONSOLE_HINT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_encoding() {
+        assert_eq!(parse_encoding("GBK").unwrap().name(), "GBK");
+        assert_eq!(parse_encoding(" utf8 ").unwrap(), UTF_8);
+        // GB2312 按 GBK 处理，UTF-16 输出时按 UTF-8
+        assert_eq!(parse_encoding("gb2312").unwrap().name(), "GBK");
+        assert_eq!(parse_encoding("utf-16le").unwrap(), UTF_8);
+        assert!(parse_encoding("klingon").is_err());
+    }
+
+    #[test]
+    fn test_detect_terminal_encoding() {
+        assert_eq!(code_page_encoding(936).unwrap().name(), "GBK");
+        assert_eq!(code_page_encoding(65001), Some(UTF_8));
+        assert_eq!(code_page_encoding(437), None);
+
+        assert_eq!(
+            locale_encoding("zh_CN.GB18030@pinyin").unwrap().name(),
+            "gb18030"
+        );
+        assert_eq!(locale_encoding("en_US.UTF-8"), Some(UTF_8));
+        assert_eq!(locale_encoding("C"), None);
+        assert_eq!(locale_encoding("POSIX"), None);
+    }
+
+    #[test]
+    fn test_transcode() {
+        let gbk = parse_encoding("gbk").unwrap();
+        let (bytes, replaced) = transcode("你好，world", gbk);
+        assert_eq!(replaced, 0);
+        assert_eq!(gbk.decode(&bytes).0, "你好，world");
+        assert_eq!(bytes.len(), 11);
+
+        let (bytes, replaced) = transcode("Hi", UTF_8);
+        assert_eq!((bytes.as_slice(), replaced), (&b"Hi"[..], 0));
+    }
+
+    #[test]
+    fn test_lossy_replacement_warns_once() {
+        let warnings = Warnings::new(true);
+        let latin1 = parse_encoding("windows-1252").unwrap();
+        let mut transcoder = Transcoder::new(latin1, &warnings, FILE_HINT).unwrap();
+
+        assert_eq!(transcoder.encode("café"), b"caf\xe9");
+        assert!(warnings.is_empty());
+
+        // 无法表示的字符逐个替换为 ?，警告只记录一次
+        assert_eq!(transcoder.encode("你好 😀 ok"), b"?? ? ok");
+        assert_eq!(transcoder.encode("再见"), b"??");
+        let recorded = warnings.to_vec();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].kind, WarningKind::LossyOutput);
+        assert!(recorded[0].message.contains("windows-1252"));
+        assert!(recorded[0].message.contains(FILE_HINT));
+    }
+
+    #[test]
+    fn test_utf8_output_keeps_representable_text() {
+        let warnings = Warnings::new(true);
+        let gbk = parse_encoding("gbk").unwrap();
+        let mut transcoder = Transcoder::new(gbk, &warnings, CONSOLE_HINT)
+            .unwrap()
+            .utf8_output();
+
+        assert_eq!(transcoder.encode("你好 😀"), "你好 ?".as_bytes());
+        assert!(warnings.to_vec()[0].message.contains("chcp 65001"));
+
+        assert!(Transcoder::new(UTF_8, &warnings, CONSOLE_HINT).is_none());
+    }
+}
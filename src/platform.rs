@@ -0,0 +1,186 @@
+//! 平台相关的行为
+//!
+//! 设置环境变量的提示、主目录与 `~` 展开、终端 ANSI 转义序列在 Windows 与类 Unix 系统上
+//! 各不相同。选择逻辑接受显式的 [`Shell`] 或主目录参数，便于在任意平台上测试。
+
+use std::path::{Path, PathBuf};
+
+/// 提示用户执行命令时使用的 shell 语法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// sh、bash、zsh 等
+    Posix,
+    /// Windows 的 cmd 与 PowerShell
+    Windows,
+}
+
+impl Shell {
+    /// 当前平台的 shell
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Self::Windows
+        } else {
+            Self::Posix
+        }
+    }
+
+    /// 设置环境变量的命令
+    pub fn set_env_var(self, name: &str, value: &str) -> String {
+        match self {
+            Self::Posix => format!("export {}={}", name, value),
+            Self::Windows => format!(
+                "setx {} {}（cmd，新开窗口后生效）或 $env:{}=\"{}\"（PowerShell）",
+                name, value, name, value
+            ),
+        }
+    }
+}
+
+/// 用户主目录
+///
+/// 优先使用 `HOME` 环境变量（包括 Windows 上的 Git Bash 等环境），
+/// 否则由系统查询（Windows 上为 `%USERPROFILE%`）。
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf()))
+}
+
+/// 展开路径开头的 `~`
+///
+/// cmd 与 PowerShell 不会展开 `~`，配置文件与环境变量中的路径也不经过 shell，
+/// 因此路径参数统一在这里展开。
+pub fn expand_home(path: &Path) -> PathBuf {
+    expand_home_with(path, home_dir().as_deref())
+}
+
+fn expand_home_with(path: &Path, home: Option<&Path>) -> PathBuf {
+    let Some(home) = home else {
+        return path.to_path_buf();
+    };
+    let mut components = path.components();
+    match components.next() {
+        Some(first) if first.as_os_str() == "~" => home.join(components.as_path()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// 解析路径参数，展开开头的 `~`
+pub fn parse_path(s: &str) -> Result<PathBuf, String> {
+    Ok(expand_home(Path::new(s)))
+}
+
+/// 让 stderr 所在的终端支持 ANSI 转义序列，返回是否支持
+///
+/// Windows 控制台需要开启虚拟终端处理；旧版控制台不支持时返回 false，
+/// 调用方应退化为不使用转义序列的输出。
+pub fn enable_ansi() -> bool {
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+        use windows_sys::Win32::System::Console::{
+            ENABLE_VIRTUAL_TERMINAL_PROCESSING, GetConsoleMode, GetStdHandle, STD_ERROR_HANDLE,
+            SetConsoleMode,
+        };
+
+        // SAFETY: 仅查询和设置当前进程 stderr 句柄的控制台模式
+        unsafe {
+            let handle = GetStdHandle(STD_ERROR_HANDLE);
+            if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+                return false;
+            }
+            let mut mode = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                return false;
+            }
+            mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        true
+    }
+}
+
+/// 标准输出所在 Windows 控制台的输出代码页，标准输出不是控制台或不是 Windows 时为 None
+pub fn console_output_code_page() -> Option<u32> {
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+        use windows_sys::Win32::System::Console::{
+            GetConsoleMode, GetConsoleOutputCP, GetStdHandle, STD_OUTPUT_HANDLE,
+        };
+
+        // SAFETY: 仅查询当前进程 stdout 句柄的控制台模式与控制台的输出代码页
+        unsafe {
+            let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+            if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+                return None;
+            }
+            let mut mode = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                return None;
+            }
+            match GetConsoleOutputCP() {
+                0 => None,
+                code_page => Some(code_page),
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_env_var_syntax() {
+        assert_eq!(
+            Shell::Posix.set_env_var("OPENAI_API_KEY", "<key>"),
+            "export OPENAI_API_KEY=<key>"
+        );
+
+        let windows = Shell::Windows.set_env_var("OPENAI_API_KEY", "<key>");
+        assert!(windows.contains("setx OPENAI_API_KEY <key>"));
+        assert!(windows.contains("$env:OPENAI_API_KEY=\"<key>\""));
+
+        let expected = if cfg!(windows) {
+            Shell::Windows
+        } else {
+            Shell::Posix
+        };
+        assert_eq!(Shell::current(), expected);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = Path::new("/home/user");
+        let expand = |path: &str| expand_home_with(Path::new(path), Some(home));
+
+        assert_eq!(expand("~"), home);
+        assert_eq!(expand("~/tm.jsonl"), home.join("tm.jsonl"));
+        assert_eq!(
+            expand("~/.config/transome/config.toml"),
+            home.join(".config").join("transome").join("config.toml")
+        );
+
+        // 只展开开头单独的 ~
+        assert_eq!(expand("~user/tm.jsonl"), Path::new("~user/tm.jsonl"));
+        assert_eq!(expand("data/~/tm.jsonl"), Path::new("data/~/tm.jsonl"));
+        assert_eq!(expand("/abs/tm.jsonl"), Path::new("/abs/tm.jsonl"));
+
+        // 无法确定主目录时保持原样
+        assert_eq!(
+            expand_home_with(Path::new("~/tm.jsonl"), None),
+            Path::new("~/tm.jsonl")
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! 译文后处理
+//!
+//! 译文输出前依次经过 `--post-process` 指定的处理器，默认不做任何处理。
+//!
+//! `localize-numbers` 按目标语言调整数字与日期的写法：
+//!
+//! | 原写法 | 目标为英文 | 目标为中文 |
+//! |--------|-----------|-----------|
+//! | `12345.5` / `12,345.5` | `12,345.5` | `12345.5` |
+//! | `2024年3月5日` / `March 5, 2024` | `March 5, 2024` | `2024年3月5日` |
+//! | `2024年3月` / `March 2024` | `March 2024` | `2024年3月` |
+//!
+//! 不带小数的四位整数可能是年份，不加千位分隔符。行内代码（反引号）、占位符（`{name}`、
+//! `%1$s`）与 HTML 标签中的内容保持不变。
+
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use regex::{Captures, Regex};
+
+use crate::canary::Script;
+
+/// 后处理器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PostProcessor {
+    /// 按目标语言调整数字与日期的写法
+    LocalizeNumbers,
+}
+
+/// 后处理使用的目标语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Locale {
+    /// 中文
+    Zh,
+    /// 英文
+    En,
+}
+
+impl Locale {
+    /// 按文本中占多数的文字推断语言
+    pub fn detect(text: &str) -> Option<Self> {
+        match Script::dominant(text)? {
+            Script::Cjk => Some(Locale::Zh),
+            Script::Latin => Some(Locale::En),
+        }
+    }
+}
+
+/// 依次执行后处理器
+///
+/// `locale` 未指定时按译文推断，无法推断时跳过与语言相关的处理。
+pub fn apply(processors: &[PostProcessor], text: String, locale: Option<Locale>) -> String {
+    processors
+        .iter()
+        .fold(text, |text, processor| match processor {
+            PostProcessor::LocalizeNumbers => match locale.or_else(|| Locale::detect(&text)) {
+                Some(locale) => localize_numbers(&text, locale),
+                None => text,
+            },
+        })
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+struct Patterns {
+    zh_date: Regex,
+    zh_month: Regex,
+    en_date: Regex,
+    en_month: Regex,
+    plain_number: Regex,
+    grouped_number: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let month = r"(January|February|March|April|May|June|July|August|September|October|November|December|Jan|Feb|Mar|Apr|Jun|Jul|Aug|Sept|Sep|Oct|Nov|Dec)\.?";
+        Patterns {
+            zh_date: Regex::new(r"(\d{4})\s*年\s*(\d{1,2})\s*月\s*(\d{1,2})\s*日").unwrap(),
+            zh_month: Regex::new(r"(\d{4})\s*年\s*(\d{1,2})\s*月").unwrap(),
+            en_date: Regex::new(&format!(r"\b{} (\d{{1,2}})(?:st|nd|rd|th)?, (\d{{4}})\b", month))
+                .unwrap(),
+            en_month: Regex::new(&format!(r"\b{} (\d{{4}})\b", month)).unwrap(),
+            plain_number: Regex::new(r"[0-9]+(\.[0-9]+)?").unwrap(),
+            grouped_number: Regex::new(r"[0-9]{1,3}(,[0-9]{3})+(\.[0-9]+)?").unwrap(),
+        }
+    })
+}
+
+/// 按目标语言调整数字与日期的写法，受保护的片段保持不变
+pub fn localize_numbers(text: &str, locale: Locale) -> String {
+    split_protected(text)
+        .into_iter()
+        .map(|(protected, segment)| {
+            if protected {
+                segment.to_string()
+            } else {
+                localize_segment(segment, locale)
+            }
+        })
+        .collect()
+}
+
+fn localize_segment(text: &str, locale: Locale) -> String {
+    let patterns = patterns();
+    match locale {
+        Locale::En => {
+            let text = replace_valid(&patterns.zh_date, text, |caps| {
+                let (year, month, day) = (&caps[1], month_name(&caps[2])?, day(&caps[3])?);
+                Some(format!("{} {}, {}", month, day, year))
+            });
+            let text = replace_valid(&patterns.zh_month, &text, |caps| {
+                Some(format!("{} {}", month_name(&caps[2])?, &caps[1]))
+            });
+            replace_numbers(&patterns.plain_number, &text, |number| {
+                let (int, frac) = number.split_once('.').map_or((number, ""), |(i, f)| (i, f));
+                // 不带小数的四位整数可能是年份
+                if int.len() < 4 || (int.len() == 4 && frac.is_empty()) {
+                    return None;
+                }
+                let mut grouped = group_thousands(int);
+                if !frac.is_empty() {
+                    grouped.push('.');
+                    grouped.push_str(frac);
+                }
+                Some(grouped)
+            })
+        }
+        Locale::Zh => {
+            let text = replace_valid(&patterns.en_date, text, |caps| {
+                let (month, day) = (month_number(&caps[1])?, day(&caps[2])?);
+                Some(format!("{}年{}月{}日", &caps[3], month, day))
+            });
+            let text = replace_valid(&patterns.en_month, &text, |caps| {
+                Some(format!("{}年{}月", &caps[2], month_number(&caps[1])?))
+            });
+            replace_numbers(&patterns.grouped_number, &text, |number| {
+                Some(number.replace(',', ""))
+            })
+        }
+    }
+}
+
+/// 替换匹配项，`replace` 返回 None 时保留原文
+fn replace_valid(
+    regex: &Regex,
+    text: &str,
+    replace: impl Fn(&Captures) -> Option<String>,
+) -> String {
+    regex
+        .replace_all(text, |caps: &Captures| {
+            replace(caps).unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// 替换独立的数字：前后紧邻英文字母、数字或数字分隔符的（如 `v1234`、`1.2.3`）保持不变
+fn replace_numbers(regex: &Regex, text: &str, replace: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for m in regex.find_iter(text) {
+        let before = text[..m.start()].chars().next_back();
+        let mut after = text[m.end()..].chars();
+        let next = after.next();
+        let joined_before =
+            before.is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ','));
+        let joined_after = match next {
+            Some('.' | ',') => after.next().is_some_and(|c| c.is_ascii_digit()),
+            Some(c) => c.is_ascii_alphanumeric() || c == '_',
+            None => false,
+        };
+        let standalone = !joined_before && !joined_after;
+
+        if let Some(replacement) = standalone.then(|| replace(m.as_str())).flatten() {
+            result.push_str(&text[last..m.start()]);
+            result.push_str(&replacement);
+            last = m.end();
+        }
+    }
+
+    result.push_str(&text[last..]);
+    result
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+fn month_name(month: &str) -> Option<&'static str> {
+    let month: usize = month.parse().ok()?;
+    MONTHS.get(month.checked_sub(1)?).copied()
+}
+
+fn month_number(name: &str) -> Option<usize> {
+    let name = name.trim_end_matches('.');
+    let name = if name == "Sept" { "Sep" } else { name };
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(name))
+        .map(|index| index + 1)
+}
+
+fn day(day: &str) -> Option<u32> {
+    day.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+/// 将文本拆分为 (是否受保护, 片段)
+///
+/// 受保护的片段：反引号包围的代码、`{...}` 占位符、`%1$s` 等格式说明符与 HTML 标签。
+/// 未闭合的反引号之后的内容全部视为受保护。
+fn split_protected(text: &str) -> Vec<(bool, &str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        let protected_len = match rest.as_bytes()[0] {
+            b'`' => {
+                let fence = rest.len() - rest.trim_start_matches('`').len();
+                let closing = &rest[..fence];
+                Some(
+                    rest[fence..]
+                        .find(closing)
+                        .map_or(rest.len(), |end| fence + end + fence),
+                )
+            }
+            b'{' => rest.find('}').map(|end| end + 1),
+            b'<' if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') => {
+                rest.find('>').map(|end| end + 1)
+            }
+            b'%' => format_specifier_len(rest),
+            _ => None,
+        };
+
+        match protected_len {
+            Some(len) => {
+                if start < i {
+                    segments.push((false, &text[start..i]));
+                }
+                segments.push((true, &text[i..i + len]));
+                i += len;
+                start = i;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+
+    if start < text.len() {
+        segments.push((false, &text[start..]));
+    }
+    segments
+}
+
+/// `%` 开头的格式说明符（如 `%d`、`%1$s`、`%.2f`）的长度
+fn format_specifier_len(text: &str) -> Option<usize> {
+    static SPECIFIER: OnceLock<Regex> = OnceLock::new();
+    let specifier = SPECIFIER
+        .get_or_init(|| Regex::new(r"^%(\d+\$)?[-+ 0#]*\d*(\.\d+)?[sdifuxXeEgGcp@]").unwrap());
+    specifier.find(text).map(|m| m.end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_table_to_english() {
+        let table = [
+            ("共 12345 条记录", "共 12,345 条记录"),
+            ("共12345条", "共12,345条"),
+            ("价格为 1234.5 元", "价格为 1,234.5 元"),
+            ("1234567.891", "1,234,567.891"),
+            ("12,345 items", "12,345 items"),
+            ("in 2024", "in 2024"),
+            ("3.14159", "3.14159"),
+            ("发布于 2024年3月5日", "发布于 March 5, 2024"),
+            ("2024 年 12 月 31 日", "December 31, 2024"),
+            ("2024年3月", "March 2024"),
+            ("2024年13月5日", "2024年13月5日"),
+            ("version v12345 and 1.2.3456", "version v12345 and 1.2.3456"),
+            ("id_12345", "id_12345"),
+        ];
+        for (input, expected) in table {
+            assert_eq!(localize_numbers(input, Locale::En), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_conversion_table_to_chinese() {
+        let table = [
+            ("共 12,345 条记录", "共 12345 条记录"),
+            ("1,234,567.89", "1234567.89"),
+            ("1,234.5", "1234.5"),
+            ("March 5, 2024", "2024年3月5日"),
+            ("发布于 Dec. 1st, 2023", "发布于 2023年12月1日"),
+            ("Sept 30, 2024", "2024年9月30日"),
+            ("March 2024", "2024年3月"),
+            ("May 32, 2024", "May 32, 2024"),
+            ("1,23", "1,23"),
+            ("12345", "12345"),
+        ];
+        for (input, expected) in table {
+            assert_eq!(localize_numbers(input, Locale::Zh), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_protected_spans_are_untouched() {
+        assert_eq!(
+            localize_numbers("设置 `timeout = 30000` 后共 12345 次", Locale::En),
+            "设置 `timeout = 30000` 后共 12,345 次"
+        );
+        assert_eq!(
+            localize_numbers("```\nlet n = 100000;\n```\n100000", Locale::En),
+            "```\nlet n = 100000;\n```\n100,000"
+        );
+        assert_eq!(
+            localize_numbers("{count_12345} of 12,345 on {2024年3月5日}", Locale::Zh),
+            "{count_12345} of 12345 on {2024年3月5日}"
+        );
+        assert_eq!(
+            localize_numbers("%1$s 共 12345 条，%10000d", Locale::En),
+            "%1$s 共 12,345 条，%10000d"
+        );
+        assert_eq!(
+            localize_numbers("<img width=\"12345\"> 12345", Locale::En),
+            "<img width=\"12345\"> 12,345"
+        );
+        // 未闭合的反引号之后全部保留
+        assert_eq!(
+            localize_numbers("12345 `unterminated 12345", Locale::En),
+            "12,345 `unterminated 12345"
+        );
+    }
+
+    #[test]
+    fn test_apply_detects_locale() {
+        let processors = [PostProcessor::LocalizeNumbers];
+        assert_eq!(
+            apply(&processors, "Total: 12345".to_string(), None),
+            "Total: 12,345"
+        );
+        assert_eq!(
+            apply(&processors, "共 12,345 条".to_string(), None),
+            "共 12345 条"
+        );
+        assert_eq!(
+            apply(&processors, "共 12,345 条".to_string(), Some(Locale::En)),
+            "共 12,345 条"
+        );
+        assert_eq!(apply(&[], "12345".to_string(), Some(Locale::En)), "12345");
+    }
+}
@@ -0,0 +1,230 @@
+//! 终端进度提示
+//!
+//! 长时间的请求在 stderr 上显示一行可自我擦除的状态（单次翻译显示已用时间，
+//! 批量模式显示 `n/m` 与预计剩余时间）。日志与警告同样通过 [`StderrWriter`] 输出：
+//! 写入前先擦除状态行，下一次刷新时再重绘，两者不会混在同一行。
+
+use std::future::Future;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinHandle;
+
+/// 状态行的刷新间隔
+const TICK: Duration = Duration::from_millis(100);
+
+/// 旋转动画帧
+const FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// 清除当前行并回到行首
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// stderr 上是否正显示着状态行；所有 stderr 输出都持有此锁
+static STATUS_SHOWN: Mutex<bool> = Mutex::new(false);
+
+fn lock_status() -> std::sync::MutexGuard<'static, bool> {
+    STATUS_SHOWN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 与进度状态行协调的 stderr 写入器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StderrWriter;
+
+/// 创建 stderr 写入器，可直接作为日志订阅器的输出目标
+pub fn stderr() -> StderrWriter {
+    StderrWriter
+}
+
+impl Write for StderrWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut shown = lock_status();
+        let mut stderr = io::stderr().lock();
+        if *shown {
+            stderr.write_all(CLEAR_LINE.as_bytes())?;
+            *shown = false;
+        }
+        stderr.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// 擦除状态行，用于在终端上向 stdout 输出结果之前
+///
+/// 状态行会在下一次刷新时重绘到输出之后。
+pub fn clear_status() {
+    let mut shown = lock_status();
+    if *shown {
+        let _ = io::stderr().lock().write_all(CLEAR_LINE.as_bytes());
+        *shown = false;
+    }
+}
+
+/// 在状态行之外向 stderr 输出一行
+pub fn eprintln(message: impl std::fmt::Display) {
+    let _ = writeln!(StderrWriter, "{}", message);
+}
+
+/// 单次翻译的状态文本
+pub fn elapsed_status(elapsed: Duration) -> String {
+    format!("翻译中… {:.1}s", elapsed.as_secs_f64())
+}
+
+/// 批量翻译的进度
+#[derive(Debug)]
+pub struct BatchProgress {
+    total: usize,
+    concurrency: usize,
+    done: AtomicUsize,
+    latency: Mutex<(Duration, u32)>,
+}
+
+impl BatchProgress {
+    pub fn new(total: usize, concurrency: usize) -> Self {
+        Self {
+            total,
+            concurrency: concurrency.max(1),
+            done: AtomicUsize::new(0),
+            latency: Mutex::new((Duration::ZERO, 0)),
+        }
+    }
+
+    /// 记录一个已完成的条目及其耗时
+    pub fn record(&self, latency: Duration) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+        let mut guard = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        guard.0 += latency;
+        guard.1 += 1;
+    }
+
+    /// 执行一个条目并记录其耗时
+    pub async fn track<T>(&self, future: impl Future<Output = T>) -> T {
+        let start = Instant::now();
+        let output = future.await;
+        self.record(start.elapsed());
+        output
+    }
+
+    /// 预计剩余时间：平均耗时 × 剩余条目数 ÷ 并发数
+    pub fn eta(&self) -> Option<Duration> {
+        let (total, count) = *self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        if count == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.done.load(Ordering::Relaxed));
+        let batches = remaining.div_ceil(self.concurrency) as u32;
+        Some(total / count * batches)
+    }
+
+    /// 状态文本，如 `3/10 · 预计剩余 12s`
+    pub fn status(&self) -> String {
+        let done = self.done.load(Ordering::Relaxed);
+        match self.eta() {
+            Some(eta) => format!("{}/{} · 预计剩余 {}s", done, self.total, eta.as_secs()),
+            None => format!("{}/{}", done, self.total),
+        }
+    }
+}
+
+/// 定时刷新的状态行，结束或丢弃时自动擦除
+pub struct Spinner {
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    /// 开始显示状态行；`enabled` 为 false 时不输出任何内容
+    pub fn start<F>(enabled: bool, status: F) -> Self
+    where
+        F: Fn(Duration) -> String + Send + 'static,
+    {
+        if !enabled {
+            return Self { handle: None };
+        }
+
+        let started = Instant::now();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK);
+            for frame in FRAMES.iter().cycle() {
+                interval.tick().await;
+                let line = format!("{}{} {}", CLEAR_LINE, frame, status(started.elapsed()));
+
+                let mut shown = lock_status();
+                let mut stderr = io::stderr().lock();
+                if stderr.write_all(line.as_bytes()).is_ok() {
+                    let _ = stderr.flush();
+                    *shown = true;
+                }
+            }
+        });
+
+        Self {
+            handle: Some(handle),
+        }
+    }
+
+    /// 停止刷新并擦除状态行
+    pub fn finish(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+            let mut shown = lock_status();
+            if *shown {
+                let _ = io::stderr().write_all(CLEAR_LINE.as_bytes());
+                *shown = false;
+            }
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elapsed_status() {
+        assert_eq!(elapsed_status(Duration::from_millis(7240)), "翻译中… 7.2s");
+    }
+
+    #[test]
+    fn test_batch_status_and_eta() {
+        let progress = BatchProgress::new(10, 2);
+        assert_eq!(progress.status(), "0/10");
+        assert_eq!(progress.eta(), None);
+
+        progress.record(Duration::from_secs(2));
+        progress.record(Duration::from_secs(4));
+        // 平均 3 秒，剩余 8 条，并发 2：预计 4 轮 × 3 秒
+        assert_eq!(progress.eta(), Some(Duration::from_secs(12)));
+        assert_eq!(progress.status(), "2/10 · 预计剩余 12s");
+    }
+
+    #[tokio::test]
+    async fn test_track_records_completion() {
+        let progress = BatchProgress::new(1, 1);
+        let value = progress.track(async { 42 }).await;
+        assert_eq!(value, 42);
+        assert_eq!(progress.status(), "1/1 · 预计剩余 0s");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_spinner_spawns_nothing() {
+        let spinner = Spinner::start(false, elapsed_status);
+        assert!(spinner.handle.is_none());
+        spinner.finish();
+        assert!(!*lock_status());
+    }
+}
@@ -0,0 +1,249 @@
+//! 只翻译文件中的一段
+//!
+//! `--range 120:180` 按行号（从 1 开始，包含两端）选取，`--range-bytes 0:65536`
+//! 按字节偏移（包含起点、不包含终点）选取。行以 `\n` 分隔，CRLF 文件中的 `\r\n`
+//! 计为一个换行。按行选取时不包含最后一行的换行符，拼接回原文件时换行符保持不变。
+//!
+//! `--in-place-range` 将译文替换回原文件内容中的对应位置后输出，范围之外的内容逐字节保留。
+
+use std::fmt;
+use std::ops::Range;
+
+/// 要翻译的范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceRange {
+    /// 行号范围，从 1 开始，包含两端
+    Lines { start: usize, end: usize },
+    /// 字节偏移范围，包含起点、不包含终点
+    Bytes { start: usize, end: usize },
+}
+
+impl fmt::Display for SourceRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceRange::Lines { start, end } => write!(f, "第 {} 到 {} 行", start, end),
+            SourceRange::Bytes { start, end } => write!(f, "字节 {}..{}", start, end),
+        }
+    }
+}
+
+/// 解析 `START:END` 形式的一对数字
+fn parse_pair(s: &str) -> Result<(usize, usize), String> {
+    let (start, end) = s
+        .split_once(':')
+        .ok_or_else(|| format!("无效的范围 '{}'，格式为 START:END", s))?;
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("无效的范围 '{}'，START 与 END 必须是非负整数", s))
+    };
+    Ok((parse(start)?, parse(end)?))
+}
+
+/// 解析 `--range`：行号从 1 开始，包含两端
+pub fn parse_line_range(s: &str) -> Result<SourceRange, String> {
+    let (start, end) = parse_pair(s)?;
+    if start == 0 {
+        return Err("行号从 1 开始".to_string());
+    }
+    if start > end {
+        return Err(format!("起始行 {} 大于结束行 {}", start, end));
+    }
+    Ok(SourceRange::Lines { start, end })
+}
+
+/// 解析 `--range-bytes`：包含起点、不包含终点
+pub fn parse_byte_range(s: &str) -> Result<SourceRange, String> {
+    let (start, end) = parse_pair(s)?;
+    if start >= end {
+        return Err(format!("起始偏移 {} 必须小于结束偏移 {}", start, end));
+    }
+    Ok(SourceRange::Bytes { start, end })
+}
+
+/// 统计行数：以 `\n` 分隔，末尾的换行不开始新的一行
+pub fn line_count(content: &str) -> usize {
+    if content.is_empty() {
+        return 0;
+    }
+    content.matches('\n').count() + usize::from(!content.ends_with('\n'))
+}
+
+impl SourceRange {
+    /// 在文件内容中定位范围对应的字节区间
+    ///
+    /// 范围超出文件或字节偏移不在字符边界上时返回错误，错误信息包含文件名与文件长度。
+    pub fn locate(&self, content: &str, file_name: &str) -> Result<Range<usize>, String> {
+        match *self {
+            SourceRange::Lines { start, end } => {
+                let lines = line_count(content);
+                if end > lines {
+                    return Err(format!(
+                        "范围（{}）超出文件 '{}' 的长度（共 {} 行）",
+                        self, file_name, lines
+                    ));
+                }
+                Ok(line_offset(content, start)..line_end(content, end))
+            }
+            SourceRange::Bytes { start, end } => {
+                if end > content.len() {
+                    return Err(format!(
+                        "范围（{}）超出文件 '{}' 的长度（共 {} 字节）",
+                        self,
+                        file_name,
+                        content.len()
+                    ));
+                }
+                for offset in [start, end] {
+                    if !content.is_char_boundary(offset) {
+                        return Err(format!(
+                            "字节偏移 {} 位于文件 '{}' 中一个多字节字符的中间",
+                            offset, file_name
+                        ));
+                    }
+                }
+                Ok(start..end)
+            }
+        }
+    }
+}
+
+/// 第 `line` 行（从 1 开始）的起始偏移
+fn line_offset(content: &str, line: usize) -> usize {
+    if line == 1 {
+        return 0;
+    }
+    content
+        .match_indices('\n')
+        .nth(line - 2)
+        .map_or(content.len(), |(i, _)| i + 1)
+}
+
+/// 第 `line` 行（从 1 开始）不含换行符的结束偏移
+fn line_end(content: &str, line: usize) -> usize {
+    let end = content
+        .match_indices('\n')
+        .nth(line - 1)
+        .map_or(content.len(), |(i, _)| i);
+    if content[..end].ends_with('\r') {
+        end - 1
+    } else {
+        end
+    }
+}
+
+/// 从文件中截取的待翻译部分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSlice {
+    /// 文件的完整内容
+    pub content: String,
+    /// 截取部分在内容中的字节区间
+    pub span: Range<usize>,
+}
+
+impl FileSlice {
+    /// 截取的文本
+    pub fn text(&self) -> &str {
+        &self.content[self.span.clone()]
+    }
+
+    /// 用译文替换截取部分，返回完整的文件内容
+    pub fn splice(&self, replacement: &str) -> String {
+        splice(&self.content, self.span.clone(), replacement)
+    }
+}
+
+/// 用译文替换原文件内容中的区间，区间之外的内容保持不变
+pub fn splice(content: &str, span: Range<usize>, replacement: &str) -> String {
+    let mut spliced = String::with_capacity(content.len() - span.len() + replacement.len());
+    spliced.push_str(&content[..span.start]);
+    spliced.push_str(replacement);
+    spliced.push_str(&content[span.end..]);
+    spliced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ranges() {
+        assert_eq!(
+            parse_line_range("120:180").unwrap(),
+            SourceRange::Lines {
+                start: 120,
+                end: 180
+            }
+        );
+        assert_eq!(
+            parse_byte_range("0:65536").unwrap(),
+            SourceRange::Bytes {
+                start: 0,
+                end: 65536
+            }
+        );
+        assert!(parse_line_range("0:3").is_err());
+        assert!(parse_line_range("5:3").is_err());
+        assert!(parse_line_range("5").is_err());
+        assert!(parse_byte_range("4:4").is_err());
+        assert!(parse_byte_range("a:4").is_err());
+    }
+
+    #[test]
+    fn test_locate_lines_with_crlf() {
+        let content = "one\r\ntwo\r\nthree\r\nfour";
+        assert_eq!(line_count(content), 4);
+        assert_eq!(line_count("one\ntwo\n"), 2);
+
+        let span = parse_line_range("2:3")
+            .unwrap()
+            .locate(content, "a.txt")
+            .unwrap();
+        assert_eq!(&content[span], "two\r\nthree");
+
+        let span = parse_line_range("4:4")
+            .unwrap()
+            .locate(content, "a.txt")
+            .unwrap();
+        assert_eq!(&content[span], "four");
+    }
+
+    #[test]
+    fn test_locate_errors_name_file_length() {
+        let error = parse_line_range("3:9")
+            .unwrap()
+            .locate("a\nb\n", "notes.txt")
+            .unwrap_err();
+        assert!(error.contains("notes.txt"), "{}", error);
+        assert!(error.contains("共 2 行"), "{}", error);
+
+        let error = parse_byte_range("0:100")
+            .unwrap()
+            .locate("abc", "notes.txt")
+            .unwrap_err();
+        assert!(error.contains("共 3 字节"), "{}", error);
+
+        let error = parse_byte_range("0:1")
+            .unwrap()
+            .locate("你好", "notes.txt")
+            .unwrap_err();
+        assert!(error.contains("多字节字符"), "{}", error);
+    }
+
+    #[test]
+    fn test_splice_round_trip_preserves_outside_bytes() {
+        let content = "header\r\n第一行\r\n第二行\r\n\tfooter  \r\n";
+        let span = parse_line_range("2:3")
+            .unwrap()
+            .locate(content, "a.txt")
+            .unwrap();
+        let original = &content[span.clone()];
+
+        let spliced = splice(content, span.clone(), "first\r\nsecond");
+        assert_eq!(spliced, "header\r\nfirst\r\nsecond\r\n\tfooter  \r\n");
+
+        // 拼接回原文得到原文件
+        let new_span = span.start..span.start + "first\r\nsecond".len();
+        assert_eq!(splice(&spliced, new_span, original), content);
+    }
+}
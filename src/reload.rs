@@ -0,0 +1,358 @@
+//! 批量模式中重新加载配置
+//!
+//! 只用于 `--lines --reload-config`：长时间运行的批量任务通过 [`LiveConfig`] 读取配置，其他模式
+//! 不会重新加载。重新加载时重新读取配置文件，并重新解析模型、地址与密钥所在的环境变量，然后
+//! 整体替换当前配置。已开始的请求继续使用取得的旧快照；发生变化的配置项输出到 stderr，新配置
+//! 无效时保留旧配置并给出警告。
+//!
+//! 重新加载由 SIGHUP（[`reload_on_sighup`]）或配置文件的变化（[`watch_file`]，使用系统的文件
+//! 变化通知）触发。
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::Value;
+
+use crate::config_file::{self, ConfigFile};
+use crate::effective_config::{ConfigReport, EffectiveConfig, Inputs};
+use crate::error::{Result, TransomeError};
+use crate::progress;
+use crate::warning::{WarningKind, Warnings};
+
+/// 合并配置文件连续变化的等待时间，编辑器保存一次常触发多个事件
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// 某一时刻生效的配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSnapshot {
+    pub config_file: ConfigFile,
+    pub effective: EffectiveConfig,
+}
+
+/// 可重新加载的配置
+pub struct LiveConfig {
+    /// `--config` 指定的配置文件，None 时使用默认位置
+    path: Option<PathBuf>,
+    inputs: Inputs,
+    env: fn(&str) -> Option<String>,
+    current: RwLock<Arc<ConfigSnapshot>>,
+}
+
+impl LiveConfig {
+    /// 加载配置，环境变量从当前进程读取
+    pub fn load(path: Option<PathBuf>, inputs: Inputs) -> Result<Self> {
+        Self::load_with_env(path, inputs, |var| std::env::var(var).ok())
+    }
+
+    /// 加载配置，从 `env` 读取环境变量
+    pub fn load_with_env(
+        path: Option<PathBuf>,
+        inputs: Inputs,
+        env: fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let snapshot = resolve(path.as_deref(), &inputs, env)?;
+        Ok(Self {
+            path,
+            inputs,
+            env,
+            current: RwLock::new(Arc::new(snapshot)),
+        })
+    }
+
+    /// 当前生效的配置，处理一个请求期间应始终使用同一快照
+    pub fn current(&self) -> Arc<ConfigSnapshot> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 重新加载配置，返回发生变化的配置项；新配置无效时返回错误并保留当前配置
+    pub fn reload(&self) -> Result<Vec<String>> {
+        let snapshot = resolve(self.path.as_deref(), &self.inputs, self.env)?;
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        let changed = changed_keys(&current.effective.report(), &snapshot.effective.report());
+        *current = Arc::new(snapshot);
+        Ok(changed)
+    }
+
+    /// 重新加载配置并报告结果：变化的配置项输出到 stderr（静默模式下不输出），
+    /// 新配置无效时记录警告
+    pub fn reload_reported(&self, warnings: &Warnings) {
+        match self.reload() {
+            Ok(_) if warnings.is_quiet() => {}
+            Ok(changed) if changed.is_empty() => {
+                progress::eprintln("已重新加载配置，没有变化");
+            }
+            Ok(changed) => {
+                progress::eprintln(format_args!(
+                    "已重新加载配置，发生变化的配置项：{}",
+                    changed.join("、")
+                ));
+            }
+            Err(error) => warnings.warn(
+                WarningKind::ConfigRejected,
+                format!("新配置无效，继续使用原配置：{}", error),
+            ),
+        }
+    }
+
+    /// 监视的配置文件：`--config` 指定的文件或默认位置
+    pub fn watched_path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(config_file::default_path)
+    }
+}
+
+/// 读取配置文件并解析生效的配置，指定了不存在的配置档时报错
+fn resolve(
+    path: Option<&std::path::Path>,
+    inputs: &Inputs,
+    env: fn(&str) -> Option<String>,
+) -> Result<ConfigSnapshot> {
+    let config_file = ConfigFile::load(path)?;
+    config_file.profile(inputs.profile.as_deref())?;
+    let effective = EffectiveConfig::resolve(inputs, &config_file, env);
+    Ok(ConfigSnapshot {
+        config_file,
+        effective,
+    })
+}
+
+/// 两份配置之间取值或来源发生变化的配置项
+pub fn changed_keys(old: &ConfigReport, new: &ConfigReport) -> Vec<String> {
+    let object = |report: &ConfigReport| match serde_json::to_value(report) {
+        Ok(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let (old, new) = (object(old), object(new));
+    let mut keys: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+/// 收到 SIGHUP 时重新加载配置，结果通过 `warnings` 报告（见 [`LiveConfig::reload_reported`]）
+#[cfg(unix)]
+pub fn reload_on_sighup(
+    config: Arc<LiveConfig>,
+    warnings: Warnings,
+) -> Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    // 在返回前注册，之后收到的 SIGHUP 不会终止进程
+    let mut hangup = signal(SignalKind::hangup())
+        .map_err(|e| TransomeError::io_error_with_context(e, "监听 SIGHUP"))?;
+    Ok(tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            config.reload_reported(&warnings);
+        }
+    }))
+}
+
+/// 配置文件变化时重新加载配置
+///
+/// 监视配置文件所在的目录而不是文件本身：编辑器保存时常以新文件替换原文件，直接监视文件
+/// 会在替换后失效。配置文件所在的目录不存在时不监视，返回 None；丢弃返回的监视器后停止监视。
+pub fn watch_file(
+    config: Arc<LiveConfig>,
+    warnings: Warnings,
+) -> Result<Option<RecommendedWatcher>> {
+    let Some(path) = config.watched_path() else {
+        return Ok(None);
+    };
+    let Some((dir, path)) = watch_target(&path) else {
+        tracing::debug!(path = %path.display(), "配置文件所在的目录不存在，不监视");
+        return Ok(None);
+    };
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event
+            && !event.kind.is_access()
+            && event.paths.contains(&path)
+        {
+            let _ = sender.send(());
+        }
+    })
+    .map_err(watch_error)?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(watch_error)?;
+
+    tokio::spawn(async move {
+        while receiver.recv().await.is_some() {
+            tokio::time::sleep(WATCH_DEBOUNCE).await;
+            while receiver.try_recv().is_ok() {}
+            config.reload_reported(&warnings);
+        }
+    });
+    Ok(Some(watcher))
+}
+
+/// 要监视的目录与配置文件的绝对路径，与文件变化事件中的路径一致
+fn watch_target(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let dir = dir.canonicalize().ok()?;
+    let path = dir.join(path.file_name()?);
+    Some((dir, path))
+}
+
+fn watch_error(error: notify::Error) -> TransomeError {
+    TransomeError::io_error_with_context(io::Error::other(error), "监视配置文件")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_reload_swaps_snapshot_and_keeps_old_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "default_model = \"gpt-4o\"\n").unwrap();
+
+        let config =
+            LiveConfig::load_with_env(Some(path.clone()), Inputs::default(), no_env).unwrap();
+        let in_flight = config.current();
+        assert_eq!(in_flight.effective.model.value, "gpt-4o");
+
+        std::fs::write(&path, "default_model = \"gemini-2.5-flash\"\n").unwrap();
+        let changed = config.reload().unwrap();
+        assert!(changed.contains(&"model".to_string()), "{:?}", changed);
+        assert!(changed.contains(&"provider".to_string()), "{:?}", changed);
+        assert_eq!(config.current().effective.model.value, "gemini-2.5-flash");
+        // 已取得的快照不受影响
+        assert_eq!(in_flight.effective.model.value, "gpt-4o");
+
+        // 无效的新配置被拒绝，继续使用原配置
+        std::fs::write(&path, "temperature = 5.0\n").unwrap();
+        assert!(config.reload().is_err());
+        assert_eq!(config.current().effective.model.value, "gemini-2.5-flash");
+
+        std::fs::write(&path, "default_model = \"gemini-2.5-flash\"\n").unwrap();
+        assert!(config.reload().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejected_reload_is_reported_as_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "default_model = \"gpt-4o\"\n").unwrap();
+        let config =
+            LiveConfig::load_with_env(Some(path.clone()), Inputs::default(), no_env).unwrap();
+        let warnings = Warnings::new(true);
+
+        std::fs::write(&path, "default_model = \"gpt-4o-mini\"\n").unwrap();
+        config.reload_reported(&warnings);
+        assert!(warnings.is_empty());
+
+        std::fs::write(&path, "temperature = 5.0\n").unwrap();
+        config.reload_reported(&warnings);
+        let reported = warnings.to_vec();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].kind, WarningKind::ConfigRejected);
+        assert!(
+            reported[0]
+                .message
+                .starts_with("新配置无效，继续使用原配置：")
+        );
+        assert_eq!(config.current().effective.model.value, "gpt-4o-mini");
+    }
+
+    #[test]
+    fn test_reload_reevaluates_env() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        static KEY_SET: AtomicBool = AtomicBool::new(false);
+        fn env(var: &str) -> Option<String> {
+            (KEY_SET.load(Ordering::SeqCst) && var == "OPENAI_API_KEY").then(|| "sk-test".into())
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "").unwrap();
+
+        let config = LiveConfig::load_with_env(Some(path), Inputs::default(), env).unwrap();
+        assert_eq!(config.current().effective.key, None);
+
+        // 设置了密钥后重新加载：只设置了 OpenAI 的密钥时自动选择 OpenAI 的默认模型
+        KEY_SET.store(true, Ordering::SeqCst);
+        let changed = config.reload().unwrap();
+        assert!(changed.contains(&"key".to_string()), "{:?}", changed);
+        assert_eq!(config.current().effective.provider, "OpenAI");
+    }
+
+    #[tokio::test]
+    async fn test_file_change_reloads_default_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "default_model = \"gpt-4o\"\n").unwrap();
+        let config = Arc::new(
+            LiveConfig::load_with_env(Some(path.clone()), Inputs::default(), no_env).unwrap(),
+        );
+        let _watcher = watch_file(config.clone(), Warnings::new(true))
+            .unwrap()
+            .unwrap();
+
+        std::fs::write(&path, "default_model = \"gpt-4o-mini\"\n").unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while config.current().effective.model.value != "gpt-4o-mini" {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "修改配置文件后未重新加载"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[test]
+    fn test_missing_config_dir_is_not_watched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing").join("config.toml");
+        assert_eq!(watch_target(&path), None);
+
+        let (watched_dir, watched_path) = watch_target(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(watched_path, watched_dir.join("config.toml"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sighup_reloads_default_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "default_model = \"gpt-4o\"\n").unwrap();
+        let config = Arc::new(
+            LiveConfig::load_with_env(Some(path.clone()), Inputs::default(), no_env).unwrap(),
+        );
+        let _handler = reload_on_sighup(config.clone(), Warnings::new(true)).unwrap();
+
+        std::fs::write(&path, "default_model = \"gpt-4o-mini\"\n").unwrap();
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        // 下一个请求使用新的默认模型
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while config.current().effective.model.value != "gpt-4o-mini" {
+            assert!(std::time::Instant::now() < deadline, "SIGHUP 后未重新加载");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
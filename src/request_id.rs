@@ -0,0 +1,102 @@
+//! 请求 ID
+//!
+//! 每次翻译生成一个 UUID，作为 `X-Request-Id` 请求头随请求发送，便于在网关日志中查找；
+//! 也可以通过 `--request-id` 指定。同一次翻译的重试使用相同的 ID。
+//!
+//! ID 保存在 tokio 任务局部变量中，[`TracedConfig`] 在构建每个请求的请求头时读取，
+//! 因此并发的翻译各自携带自己的 ID。
+
+use std::future::Future;
+
+use async_openai::config::{Config, OpenAIConfig};
+use reqwest::header::{HeaderMap, HeaderValue};
+use secrecy::SecretString;
+
+/// 请求 ID 的请求头名称
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// 生成新的请求 ID
+pub fn generate() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// 解析 `--request-id`：必须是可以作为请求头的值
+pub fn parse_request_id(s: &str) -> Result<String, String> {
+    let id = s.trim();
+    if id.is_empty() {
+        return Err("请求 ID 不能为空".to_string());
+    }
+    HeaderValue::from_str(id)
+        .map(|_| id.to_string())
+        .map_err(|_| format!("请求 ID '{}' 包含不能用于请求头的字符", id))
+}
+
+/// 在 `future` 中发送的请求都携带请求 ID `id`
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// 当前任务的请求 ID
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// 在 OpenAI 配置的请求头之外附加当前任务的请求 ID
+#[derive(Debug, Clone)]
+pub struct TracedConfig(pub OpenAIConfig);
+
+impl Config for TracedConfig {
+    fn headers(&self) -> HeaderMap {
+        let mut headers = self.0.headers();
+        if let Some(value) = current().and_then(|id| HeaderValue::from_str(&id).ok()) {
+            headers.insert(REQUEST_ID_HEADER, value);
+        }
+        headers
+    }
+
+    fn url(&self, path: &str) -> String {
+        self.0.url(path)
+    }
+
+    fn query(&self) -> Vec<(&str, &str)> {
+        self.0.query()
+    }
+
+    fn api_base(&self) -> &str {
+        self.0.api_base()
+    }
+
+    fn api_key(&self) -> &SecretString {
+        self.0.api_key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_header_follows_scope() {
+        let config = TracedConfig(OpenAIConfig::new().with_api_key("test-key"));
+        assert!(config.headers().get(REQUEST_ID_HEADER).is_none());
+
+        let headers = scope("req-42".to_string(), async { config.headers() }).await;
+        assert_eq!(headers[REQUEST_ID_HEADER], "req-42");
+        assert_eq!(headers["authorization"], "Bearer test-key");
+
+        let id = generate();
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+        assert_ne!(id, generate());
+    }
+
+    #[test]
+    fn test_parse_request_id() {
+        assert_eq!(parse_request_id(" trace-1 ").unwrap(), "trace-1");
+        assert!(parse_request_id("").is_err());
+        assert!(parse_request_id("bad\nid").is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! 请求模板：为非标准的 OpenAI 兼容端点调整请求体
+//!
+//! 模板在请求序列化为 JSON 之后应用，按以下顺序处理顶层字段：
+//! 1. `omit`：删除字段
+//! 2. `rename`：重命名字段
+//! 3. `set`：设置字段（覆盖已有值）
+//!
+//! 配置示例：
+//!
+//! ```toml
+//! [models."my-gateway-model".request_template]
+//! omit = ["model"]
+//! rename = { messages = "inputs" }
+//! set = { stream = false }
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::{Result, TransomeError};
+
+/// 请求模板
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RequestTemplate {
+    /// 要删除的字段
+    pub omit: Vec<String>,
+    /// 字段重命名，键为原字段名，值为新字段名
+    pub rename: BTreeMap<String, String>,
+    /// 要设置的字段及其值
+    pub set: BTreeMap<String, Value>,
+}
+
+impl RequestTemplate {
+    /// 检查模板中相互冲突的规则
+    ///
+    /// 以下情况视为冲突：同一字段既删除又重命名、删除后又被设置、
+    /// 多个字段重命名为同一名称、重命名的目标字段又被设置。
+    pub fn validate(&self) -> Result<()> {
+        let conflict =
+            |message: String| Err(TransomeError::config_error("request_template", message));
+
+        for field in &self.omit {
+            if self.rename.contains_key(field) {
+                return conflict(format!("字段 '{}' 不能同时出现在 omit 和 rename 中", field));
+            }
+            if self.set.contains_key(field) {
+                return conflict(format!("字段 '{}' 不能同时出现在 omit 和 set 中", field));
+            }
+        }
+
+        let mut targets = HashSet::new();
+        for (from, to) in &self.rename {
+            if from == to {
+                return conflict(format!("字段 '{}' 不能重命名为自身", from));
+            }
+            if !targets.insert(to) {
+                return conflict(format!("多个字段被重命名为 '{}'", to));
+            }
+            if self.set.contains_key(to) {
+                return conflict(format!("重命名的目标字段 '{}' 不能同时出现在 set 中", to));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 将模板应用到序列化后的请求体
+    ///
+    /// 请求体不是 JSON 对象时保持不变。
+    pub fn apply(&self, body: &mut Value) {
+        let Some(object) = body.as_object_mut() else {
+            return;
+        };
+
+        for field in &self.omit {
+            object.remove(field);
+        }
+
+        let renamed: Vec<(String, Value)> = self
+            .rename
+            .iter()
+            .filter_map(|(from, to)| object.remove(from).map(|value| (to.clone(), value)))
+            .collect();
+        object.extend(renamed);
+
+        for (field, value) in &self.set {
+            object.insert(field.clone(), value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn template(toml: &str) -> RequestTemplate {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_apply_template() {
+        let template = template(
+            r#"
+            omit = ["model"]
+            rename = { messages = "inputs" }
+            set = { stream = false, options = { safe_mode = true } }
+            "#,
+        );
+        assert!(template.validate().is_ok());
+
+        let mut body = json!({
+            "model": "custom-model",
+            "messages": [{"role": "user", "content": "hello"}],
+        });
+        template.apply(&mut body);
+
+        assert_eq!(
+            body,
+            json!({
+                "inputs": [{"role": "user", "content": "hello"}],
+                "stream": false,
+                "options": {"safe_mode": true},
+            })
+        );
+    }
+
+    #[test]
+    fn test_conflicting_rules_are_rejected() {
+        let cases = [
+            r#"omit = ["model"]
+               rename = { model = "engine" }"#,
+            r#"omit = ["model"]
+               set = { model = "x" }"#,
+            r#"rename = { messages = "inputs", prompt = "inputs" }"#,
+            r#"rename = { messages = "inputs" }
+               set = { inputs = [] }"#,
+            r#"rename = { messages = "messages" }"#,
+        ];
+
+        for case in cases {
+            let result = template(case).validate();
+            assert!(result.is_err(), "should reject: {}", case);
+            assert!(result.unwrap_err().is_config_error());
+        }
+    }
+}
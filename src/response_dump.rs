@@ -0,0 +1,132 @@
+//! 保存服务返回的原始响应
+//!
+//! `--dump-response` 把每个响应的原始 JSON 写入文件，便于排查译文异常。
+//! 批量模式等会发送多个请求时按收到的顺序编号（`response.json` → `response.1.json`、
+//! `response.2.json`…），否则每次写入同一文件，最终保留最后一个响应。
+//! 写入前将响应中出现的 API 密钥替换为 [`REDACTED`]。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json::Value;
+
+use crate::error::{Result, TransomeError};
+
+/// 替换 API 密钥的占位文本
+pub const REDACTED: &str = "[REDACTED]";
+
+/// 原始响应的保存位置
+#[derive(Debug)]
+pub struct ResponseDump {
+    path: PathBuf,
+    numbered: bool,
+    secret: String,
+    count: AtomicUsize,
+}
+
+impl ResponseDump {
+    /// `numbered` 为 true 时每个响应写入单独编号的文件；`secret` 为需要隐去的 API 密钥
+    pub fn new(path: PathBuf, numbered: bool, secret: String) -> Self {
+        Self {
+            path,
+            numbered,
+            secret,
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// 写入一个响应，返回写入的文件路径
+    pub fn write(&self, response: &Value) -> Result<PathBuf> {
+        let path = if self.numbered {
+            let n = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+            numbered_path(&self.path, n)
+        } else {
+            self.path.clone()
+        };
+
+        let mut response = response.clone();
+        redact(&mut response, &self.secret);
+        let content = serde_json::to_string_pretty(&response)?;
+        fs::write(&path, content + "\n").map_err(|e| {
+            TransomeError::io_error_with_context(
+                e,
+                format!("无法写入响应文件 '{}'", path.display()),
+            )
+        })?;
+        Ok(path)
+    }
+}
+
+/// 在扩展名之前插入编号：`response.json` → `response.3.json`
+pub fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, n, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// 将 JSON 中所有字符串里出现的 `secret` 替换为 [`REDACTED`]
+pub fn redact(value: &mut Value, secret: &str) {
+    if secret.is_empty() {
+        return;
+    }
+    match value {
+        Value::String(s) if s.contains(secret) => *s = s.replace(secret, REDACTED),
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, secret)),
+        Value::Object(map) => map.values_mut().for_each(|item| redact(item, secret)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_numbered_path() {
+        assert_eq!(
+            numbered_path(Path::new("out/response.json"), 3),
+            Path::new("out/response.3.json")
+        );
+        assert_eq!(
+            numbered_path(Path::new("response"), 1),
+            Path::new("response.1")
+        );
+    }
+
+    #[test]
+    fn test_redact_nested_strings() {
+        let mut value = json!({
+            "echo": {"headers": ["Bearer sk-secret", "other"]},
+            "note": "key sk-secret used",
+            "count": 1
+        });
+        redact(&mut value, "sk-secret");
+        assert_eq!(
+            value,
+            json!({
+                "echo": {"headers": ["Bearer [REDACTED]", "other"]},
+                "note": "key [REDACTED] used",
+                "count": 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_numbered() {
+        let dir = tempfile::tempdir().unwrap();
+        let dump = ResponseDump::new(dir.path().join("r.json"), true, "k".repeat(8));
+
+        let first = dump.write(&json!({"n": 1})).unwrap();
+        let second = dump.write(&json!({"n": 2})).unwrap();
+        assert_eq!(first, dir.path().join("r.1.json"));
+        assert_eq!(second, dir.path().join("r.2.json"));
+
+        let content: Value = serde_json::from_str(&fs::read_to_string(second).unwrap()).unwrap();
+        assert_eq!(content, json!({"n": 2}));
+    }
+}
@@ -0,0 +1,569 @@
+//! 请求重试与超时
+//!
+//! 单次请求可设置超时，失败后按指数退避重试。设置了截止时间时，
+//! 重试循环在下一次尝试预计无法在截止前完成时停止，返回最后一次的错误，
+//! 并注明重试因时限而中止。
+//!
+//! 批量任务中的所有请求可以共享一份重试预算（[`RetryBudget`]）：服务整体不可用时，
+//! 预算用完后其余失败不再重试，避免成千上万次注定失败的重试。
+//!
+//! 设置 [`RetryPolicy::idempotent_only`] 时只重试请求确定未被服务处理的失败
+//! （连接失败、被限流拒绝），超时等服务端可能已完成处理的失败不再重试，
+//! 避免对非幂等的网关重复计费。
+//!
+//! [`count_attempts`] 统计一个操作中经由 [`run_with_retry`] 发起的尝试次数，
+//! 批量任务以此记录每个条目实际发出的请求数。
+
+use crate::batch::Clock;
+use crate::error::{EmptyResponseKind, TransomeError};
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+/// 首次重试前的等待时间，之后每次翻倍
+pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// 批量任务默认的重试预算
+pub const DEFAULT_RETRY_BUDGET: u32 = 50;
+
+/// 多个请求共享的重试次数预算
+///
+/// 克隆得到的预算与原预算共享同一计数。
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    total: u32,
+    remaining: Arc<AtomicU32>,
+}
+
+impl RetryBudget {
+    /// 创建共 `total` 次重试的预算
+    pub fn new(total: u32) -> Self {
+        Self {
+            total,
+            remaining: Arc::new(AtomicU32::new(total)),
+        }
+    }
+
+    /// 预算的总次数
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// 剩余的重试次数
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::SeqCst)
+    }
+
+    /// 消耗一次重试，预算已用完时返回 false
+    pub fn try_spend(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+
+/// 共享同一计数的预算视为相等
+impl PartialEq for RetryBudget {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.remaining, &other.remaining)
+    }
+}
+
+impl Eq for RetryBudget {}
+
+/// 重试策略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 失败后的最大重试次数
+    pub retries: u32,
+    /// 单次请求的超时时间
+    pub timeout: Option<Duration>,
+    /// 首次重试前的等待时间
+    pub backoff: Duration,
+    /// 整个任务的截止时刻
+    pub deadline: Option<Instant>,
+    /// 与其他请求共享的重试预算，用完后不再重试
+    pub budget: Option<RetryBudget>,
+    /// 只重试请求确定未被处理的失败，见 [`failed_before_processing`]
+    pub idempotent_only: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            timeout: None,
+            backoff: DEFAULT_BACKOFF,
+            deadline: None,
+            budget: None,
+            idempotent_only: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次重试前的等待时间（从 0 开始）
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+tokio::task_local! {
+    /// 当前 [`count_attempts`] 范围内的尝试次数
+    static ATTEMPTS: Cell<u32>;
+}
+
+/// 执行操作，同时返回其中经由 [`run_with_retry`] 发起的尝试次数
+///
+/// 没有发出请求（如命中翻译记忆）时次数为 0。
+pub async fn count_attempts<Fut, T>(operation: Fut) -> (T, u32)
+where
+    Fut: Future<Output = T>,
+{
+    ATTEMPTS
+        .scope(Cell::new(0), async {
+            let output = operation.await;
+            (output, ATTEMPTS.with(Cell::get))
+        })
+        .await
+}
+
+/// 判断错误是否值得重试
+///
+/// 认证、配置、参数等由输入决定的错误重试也不会成功。
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<TransomeError>() {
+        Some(TransomeError::ApiCallFailed { status_code, .. }) => {
+            !matches!(status_code, Some(code) if (400..500).contains(code) && *code != 429)
+        }
+        Some(
+            TransomeError::AuthenticationError { .. }
+            | TransomeError::ConfigError { .. }
+            | TransomeError::ValidationError { .. }
+            | TransomeError::ModelNotFound { .. }
+            | TransomeError::FormatError { .. }
+            | TransomeError::SystemRoleRejected { .. }
+            | TransomeError::RequestTooLarge { .. }
+            | TransomeError::EmptyResponse {
+                kind: EmptyResponseKind::Filtered | EmptyResponseKind::Malformed,
+                ..
+            },
+        ) => false,
+        _ => true,
+    }
+}
+
+/// 判断失败是否发生在服务处理请求之前
+///
+/// 只有连接失败与 429 限流可以确定请求未被处理；超时、响应读取中断与服务端错误
+/// 发生时请求可能已在服务端完成。
+pub fn failed_before_processing(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<TransomeError>() {
+        Some(TransomeError::NetworkError { source }) => source.is_connect(),
+        Some(TransomeError::ApiCallFailed { status_code, .. }) => *status_code == Some(429),
+        _ => false,
+    }
+}
+
+/// 按重试策略执行操作
+pub async fn run_with_retry<C, F, Fut, T>(
+    policy: &RetryPolicy,
+    clock: &C,
+    operation: F,
+) -> anyhow::Result<T>
+where
+    C: Clock + ?Sized,
+    F: Fn() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut attempt = 0;
+
+    loop {
+        let start = clock.now();
+        let _ = ATTEMPTS.try_with(|attempts| attempts.set(attempts.get() + 1));
+        let result = match policy.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, operation())
+                .await
+                .unwrap_or_else(|_| {
+                    Err(TransomeError::timeout(format!("请求在 {:?} 内未完成", timeout)).into())
+                }),
+            None => operation().await,
+        };
+
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        if attempt >= policy.retries
+            || !is_retryable(&error)
+            || (policy.idempotent_only && !failed_before_processing(&error))
+        {
+            return Err(error);
+        }
+
+        // 等待退避时间后再完成一次与本次同样耗时的请求，若会超过截止时间则不再重试
+        let delay = policy.delay(attempt);
+        if let Some(deadline) = policy.deadline {
+            let now = clock.now();
+            let elapsed = now.saturating_duration_since(start);
+            if now + delay + elapsed >= deadline {
+                let remaining = policy.retries - attempt;
+                return Err(error.context(format!(
+                    "已达到 --deadline 时限，剩余 {} 次重试未执行",
+                    remaining
+                )));
+            }
+        }
+
+        if let Some(budget) = &policy.budget
+            && !budget.try_spend()
+        {
+            return Err(error.context(format!(
+                "本次运行的重试预算（共 {} 次）已用完，不再重试",
+                budget.total()
+            )));
+        }
+
+        tracing::debug!(
+            attempt = attempt + 1,
+            delay_ms = delay.as_millis() as u64,
+            error = %error,
+            "请求失败，准备重试"
+        );
+        clock.sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// 在截止时刻前完成操作，超过时返回超时错误
+///
+/// 截止时刻可以在多个操作之间共享。
+pub async fn with_deadline<Fut, T>(
+    deadline: tokio::time::Instant,
+    operation: Fut,
+) -> anyhow::Result<T>
+where
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    tokio::time::timeout_at(deadline, operation)
+        .await
+        .unwrap_or_else(|_| Err(TransomeError::timeout("请求未在截止时刻前完成").into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{ManualClock, read_request};
+    use anyhow::anyhow;
+
+    /// 每次调用耗时 `latency` 并失败的操作
+    fn failing<'a>(
+        clock: &'a ManualClock,
+        calls: &'a AtomicU32,
+        latency: Duration,
+    ) -> impl Fn() -> std::future::Ready<anyhow::Result<()>> + 'a {
+        move || {
+            let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            clock.advance(latency);
+            std::future::ready(Err(anyhow!("attempt {} failed", call)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+        let policy = RetryPolicy {
+            retries: 3,
+            ..RetryPolicy::default()
+        };
+
+        let result = run_with_retry(&policy, &clock, || {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(if call < 2 {
+                Err(anyhow!("flaky"))
+            } else {
+                Ok("done")
+            })
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_count_attempts() {
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+        let policy = RetryPolicy {
+            retries: 2,
+            ..RetryPolicy::default()
+        };
+
+        let (result, attempts) = count_attempts(run_with_retry(
+            &policy,
+            &clock,
+            failing(&clock, &calls, Duration::ZERO),
+        ))
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        let (value, attempts) = count_attempts(async { 1 }).await;
+        assert_eq!((value, attempts), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_retries() {
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+        let policy = RetryPolicy {
+            retries: 2,
+            ..RetryPolicy::default()
+        };
+
+        let result = run_with_retry(&policy, &clock, failing(&clock, &calls, Duration::ZERO)).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(result.unwrap_err().to_string(), "attempt 3 failed");
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_errors_fail_immediately() {
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+        let policy = RetryPolicy {
+            retries: 5,
+            ..RetryPolicy::default()
+        };
+
+        let result: anyhow::Result<()> = run_with_retry(&policy, &clock, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(Err(TransomeError::authentication_error("bad key").into()))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_empty_response_retryable_by_kind() {
+        let error = |kind| TransomeError::empty_response("gpt-4o", 200, kind, "", "{}").into();
+        assert!(!is_retryable(&error(EmptyResponseKind::Filtered)));
+        assert!(!is_retryable(&error(EmptyResponseKind::Malformed)));
+        assert!(is_retryable(&error(EmptyResponseKind::Empty)));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_cuts_retries_short() {
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+
+        // 每次请求耗时 2 秒，退避 0.5s、1s、2s……：
+        // 第 1 次失败于 2s，重试于 2.5s，失败于 4.5s；
+        // 第 2 次重试需等待 1s 再耗时 2s，预计 7.5s 完成，超过 6s 的截止时间
+        let policy = RetryPolicy {
+            retries: 10,
+            deadline: Some(clock.now() + Duration::from_secs(6)),
+            ..RetryPolicy::default()
+        };
+
+        let result = run_with_retry(
+            &policy,
+            &clock,
+            failing(&clock, &calls, Duration::from_secs(2)),
+        )
+        .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let error = result.unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "已达到 --deadline 时限，剩余 9 次重试未执行: attempt 2 failed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_budget_shared_across_operations() {
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+        let budget = RetryBudget::new(3);
+        let policy = RetryPolicy {
+            retries: 2,
+            budget: Some(budget.clone()),
+            ..RetryPolicy::default()
+        };
+
+        // 第一个操作用掉 2 次重试
+        let result = run_with_retry(&policy, &clock, failing(&clock, &calls, Duration::ZERO)).await;
+        assert_eq!(result.unwrap_err().to_string(), "attempt 3 failed");
+        assert_eq!(budget.remaining(), 1);
+
+        // 第二个操作只能再重试 1 次
+        let result = run_with_retry(&policy, &clock, failing(&clock, &calls, Duration::ZERO)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+        assert_eq!(
+            format!("{:#}", result.unwrap_err()),
+            "本次运行的重试预算（共 3 次）已用完，不再重试: attempt 5 failed"
+        );
+
+        // 预算用完后立即失败
+        let result = run_with_retry(&policy, &clock, failing(&clock, &calls, Duration::ZERO)).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        assert_eq!(budget.remaining(), 0);
+    }
+
+    #[test]
+    fn test_budget_equality_by_identity() {
+        let budget = RetryBudget::new(5);
+        assert_eq!(budget, budget.clone());
+        assert_ne!(budget, RetryBudget::new(5));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_applies_per_attempt() {
+        let policy = RetryPolicy {
+            timeout: Some(Duration::from_millis(10)),
+            ..RetryPolicy::default()
+        };
+
+        let result: anyhow::Result<()> =
+            run_with_retry(&policy, &crate::batch::SystemClock, || async {
+                std::future::pending::<()>().await;
+                Ok(())
+            })
+            .await;
+
+        let error = result.unwrap_err();
+        assert!(error.to_string().contains("已超时"));
+        assert!(matches!(
+            error.downcast_ref::<TransomeError>(),
+            Some(TransomeError::Timeout { .. })
+        ));
+    }
+
+    /// 以 reqwest 请求 `url` 并读完响应，失败时转换为应用错误
+    async fn fetch(client: &reqwest::Client, url: &str) -> anyhow::Result<()> {
+        let response = client.get(url).send().await.map_err(TransomeError::from)?;
+        response.bytes().await.map_err(TransomeError::from)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_only_retries_connect_errors() {
+        // 端口已关闭，请求在发送前失败
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let client = reqwest::Client::new();
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+        let policy = RetryPolicy {
+            retries: 2,
+            idempotent_only: true,
+            ..RetryPolicy::default()
+        };
+
+        let result = run_with_retry(&policy, &clock, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            fetch(&client, &url)
+        })
+        .await;
+
+        assert!(failed_before_processing(&result.unwrap_err()));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_only_skips_mid_response_timeouts() {
+        use tokio::io::AsyncWriteExt;
+
+        // 服务已接受请求并开始响应，但响应体迟迟未发完
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    read_request(&mut stream).await;
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\n{")
+                        .await;
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                });
+            }
+        });
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let clock = ManualClock::new();
+        let calls = AtomicU32::new(0);
+        let policy = RetryPolicy {
+            retries: 2,
+            idempotent_only: true,
+            ..RetryPolicy::default()
+        };
+        let operation = || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            fetch(&client, &url)
+        };
+
+        let error = run_with_retry(&policy, &clock, operation)
+            .await
+            .unwrap_err();
+        assert!(is_retryable(&error));
+        assert!(!failed_before_processing(&error));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 未设置时同样的失败会重试
+        let policy = RetryPolicy {
+            idempotent_only: false,
+            ..policy
+        };
+        let _ = run_with_retry(&policy, &clock, operation).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_failed_before_processing_by_status() {
+        let error = |status| {
+            TransomeError::api_call_failed("https://api.example.com", Some(status), "").into()
+        };
+        assert!(failed_before_processing(&error(429)));
+        assert!(!failed_before_processing(&error(500)));
+        assert!(!failed_before_processing(
+            &TransomeError::timeout("请求超时").into()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_with_deadline() {
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(20);
+
+        // 在截止时刻前完成
+        let value = with_deadline(deadline, async { Ok(1) }).await.unwrap();
+        assert_eq!(value, 1);
+
+        // 模拟响应慢于截止时刻的请求
+        let result: anyhow::Result<()> = with_deadline(deadline, async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await;
+        let error = result.unwrap_err();
+        assert_eq!(error.to_string(), "请求未在截止时刻前完成，已超时");
+        assert!(matches!(
+            error.downcast_ref::<TransomeError>(),
+            Some(TransomeError::Timeout { .. })
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+//! 用另一个模型审校译文
+//!
+//! `--review-model <MODEL>` 时，每段原文先由 `-m` 指定的模型翻译，再把原文与译文一起发给
+//! 审校模型，请它对照原文修正错译、漏译与不通顺之处，输出审校后的译文。审校模型与 `-m`
+//! 指定的模型一样解析 API 地址与密钥，可以来自不同的提供商；`--verbose` 时报告每段的审校结果。
+
+use std::fmt;
+use std::future::Future;
+
+use anyhow::Result;
+
+/// 发给审校模型的提示词
+pub const REVIEW_PROMPT: &str = "你是一名严谨的翻译审校。接下来我将输入一段原文及其译文，\
+    请对照原文修正译文中的错译、漏译与不通顺之处，保持译文的语言、格式与换行不变。\
+    仅输出修正后的译文，不要携带原文、说明或其他内容；译文没有问题时原样输出。";
+
+/// 原文开始标记
+const SOURCE_HEADER: &str = "【原文】";
+
+/// 译文开始标记
+const TRANSLATION_HEADER: &str = "【译文】";
+
+/// 发给审校模型的内容：原文与待审校的译文
+pub fn review_input(source: &str, translation: &str) -> String {
+    format!(
+        "{}\n{}\n\n{}\n{}",
+        SOURCE_HEADER, source, TRANSLATION_HEADER, translation
+    )
+}
+
+/// 一次审校的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Review {
+    /// 审校使用的模型
+    pub model: String,
+    /// 审校模型是否修改了译文（忽略首尾空白）
+    pub changed: bool,
+}
+
+impl fmt::Display for Review {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changed {
+            write!(f, "审校模型 '{}' 修改了译文", self.model)
+        } else {
+            write!(f, "审校模型 '{}' 未修改译文", self.model)
+        }
+    }
+}
+
+/// 请审校模型修正译文
+///
+/// `send` 使用 [`REVIEW_PROMPT`] 把 [`review_input`] 的内容发给 `model`。返回审校后的译文及审校结果。
+pub async fn review<F, Fut>(
+    model: &str,
+    source: &str,
+    translation: &str,
+    send: F,
+) -> Result<(String, Review)>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let reviewed = send(review_input(source, translation)).await?;
+    let changed = reviewed.trim() != translation.trim();
+    Ok((
+        reviewed,
+        Review {
+            model: model.to_string(),
+            changed,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_review_sends_source_and_translation() {
+        let (reviewed, review) = review(
+            "reviewer",
+            "苹果很好吃",
+            "Apple is delicious",
+            |input| {
+                assert_eq!(
+                    input,
+                    "【原文】\n苹果很好吃\n\n【译文】\nApple is delicious"
+                );
+                async { Ok("Apples are delicious".to_string()) }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reviewed, "Apples are delicious");
+        assert!(review.changed);
+        assert_eq!(review.to_string(), "审校模型 'reviewer' 修改了译文");
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_translation_is_reported() {
+        let (reviewed, result) = review("reviewer", "你好", "Hello", |_| async {
+            Ok("Hello\n".to_string())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(reviewed, "Hello\n");
+        assert!(!result.changed);
+        assert!(result.to_string().contains("未修改"));
+
+        let error = review("reviewer", "你好", "Hello", |_| async {
+            Err(anyhow::anyhow!("服务不可用"))
+        })
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("服务不可用"));
+    }
+}
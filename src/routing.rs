@@ -0,0 +1,258 @@
+//! 按语言对选择模型
+//!
+//! 配置文件的 `[routing]` 表将语言对映射到模型，未通过 `-m` 指定模型时按原文语言与目标语言
+//! 选择模型，优先于配置档与 `default_model`：
+//!
+//! ```toml
+//! [routing]
+//! "en:de" = "gpt-4o"
+//! "*:ja" = "gemini-2.5-pro"
+//! default = "gpt-4o-mini"
+//! ```
+//!
+//! 原文语言按文字检测（中文或英文），目标语言来自 `--to`，未指定时按内置提示词的中英互译推断。
+//! 匹配顺序：`原文:目标` > `*:目标` > `原文:*` > `*:*` > `default`。多个 `--to` 时一次运行
+//! 只使用一个模型，只匹配与目标语言无关的规则（`原文:*`、`*:*` 与 `default`）。
+//!
+//! 规则在加载配置时验证：模型须为内置模型或在 `[models]` 中声明过的模型。
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::error::{Result, TransomeError};
+use crate::history;
+use crate::multi_target::parse_lang;
+
+/// 没有规则匹配时使用的规则名
+pub const DEFAULT_RULE: &str = "default";
+
+/// 匹配任意语言的通配符
+pub const WILDCARD: &str = "*";
+
+/// 语言对到模型的映射
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct RoutingRules(BTreeMap<String, String>);
+
+/// 一次运行的语言对
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguagePair {
+    /// 检测到的原文语言，无法检测时为 None
+    pub from: Option<String>,
+    /// 目标语言
+    pub to: Vec<String>,
+}
+
+impl LanguagePair {
+    /// 检测原文语言；未指定目标语言时按原文语言推断
+    pub fn detect(text: &str, to: &[String]) -> Self {
+        let (from, to) = history::language_pair(text, to);
+        Self { from, to }
+    }
+}
+
+impl fmt::Display for LanguagePair {
+    /// 如 `zh→en`、`en→ja,de`，未知的一侧以 `?` 表示
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let to = if self.to.is_empty() {
+            "?".to_string()
+        } else {
+            self.to.join(",")
+        };
+        write!(f, "{}→{}", self.from.as_deref().unwrap_or("?"), to)
+    }
+}
+
+/// 选中的路由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// 匹配的规则，如 `zh:en`、`*:ja` 或 `default`
+    pub rule: String,
+    pub model: String,
+}
+
+impl RoutingRules {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 验证规则的写法与模型，`is_known` 判断模型是否可用
+    pub fn validate(&self, is_known: impl Fn(&str) -> bool) -> Result<()> {
+        for (rule, model) in &self.0 {
+            let field = || format!("routing.\"{}\"", rule);
+            if rule != DEFAULT_RULE {
+                parse_rule(rule)
+                    .map_err(|message| TransomeError::config_error(field(), message))?;
+            }
+            if !is_known(model) {
+                return Err(TransomeError::config_error(
+                    field(),
+                    format!(
+                        "路由规则 '{}' 的模型 '{}' 未知（transome --list-models 列出支持的模型，\
+                        自定义模型需在 [models] 中声明）",
+                        rule, model
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 按语言对选择模型，没有规则匹配且没有 `default` 时为 None
+    pub fn route(&self, languages: &LanguagePair) -> Option<Route> {
+        let from = languages.from.as_deref().unwrap_or(WILDCARD);
+        let mut candidates = Vec::new();
+        if let [to] = languages.to.as_slice() {
+            candidates.push(format!("{}:{}", from, to));
+            candidates.push(format!("{}:{}", WILDCARD, to));
+        }
+        candidates.push(format!("{}:{}", from, WILDCARD));
+        candidates.push(format!("{}:{}", WILDCARD, WILDCARD));
+        candidates.push(DEFAULT_RULE.to_string());
+
+        candidates.into_iter().find_map(|candidate| {
+            let (rule, model) = self
+                .0
+                .iter()
+                .find(|(rule, _)| normalize(rule) == candidate)?;
+            Some(Route {
+                rule: rule.clone(),
+                model: model.clone(),
+            })
+        })
+    }
+}
+
+/// 解析 `原文:目标` 形式的规则，两侧为语言代码或 `*`
+fn parse_rule(rule: &str) -> std::result::Result<(), String> {
+    let Some((from, to)) = rule.split_once(':') else {
+        return Err(format!(
+            "无效的路由规则 '{}'，应为 \"原文:目标\"（如 \"en:de\"、\"*:ja\"）或 default",
+            rule
+        ));
+    };
+    for lang in [from, to] {
+        if lang.trim() != WILDCARD {
+            parse_lang(lang)?;
+        }
+    }
+    Ok(())
+}
+
+/// 规则的规范写法：去除空白，语言代码转为小写
+fn normalize(rule: &str) -> String {
+    match rule.split_once(':') {
+        Some((from, to)) => format!(
+            "{}:{}",
+            from.trim().to_ascii_lowercase(),
+            to.trim().to_ascii_lowercase()
+        ),
+        None => rule.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(entries: &[(&str, &str)]) -> RoutingRules {
+        RoutingRules(
+            entries
+                .iter()
+                .map(|(rule, model)| (rule.to_string(), model.to_string()))
+                .collect(),
+        )
+    }
+
+    fn pair(from: Option<&str>, to: &[&str]) -> LanguagePair {
+        LanguagePair {
+            from: from.map(str::to_string),
+            to: to.iter().map(|lang| lang.to_string()).collect(),
+        }
+    }
+
+    fn model(rules: &RoutingRules, languages: &LanguagePair) -> Option<String> {
+        rules.route(languages).map(|route| route.model)
+    }
+
+    #[test]
+    fn test_route_precedence_with_wildcards() {
+        let rules = rules(&[
+            ("en:de", "gpt-4o"),
+            ("*:ja", "gemini-2.5-pro"),
+            ("zh:*", "gemini-2.5-flash"),
+            ("*:*", "gpt-4o-mini"),
+            ("default", "gpt-4"),
+        ]);
+
+        assert_eq!(
+            model(&rules, &pair(Some("en"), &["de"])).as_deref(),
+            Some("gpt-4o")
+        );
+        // 目标语言的通配规则优先于原文语言的通配规则
+        let route = rules.route(&pair(Some("zh"), &["ja"])).unwrap();
+        assert_eq!(route.rule, "*:ja");
+        assert_eq!(route.model, "gemini-2.5-pro");
+        assert_eq!(
+            model(&rules, &pair(None, &["ja"])).as_deref(),
+            Some("gemini-2.5-pro")
+        );
+        assert_eq!(
+            model(&rules, &pair(Some("zh"), &["en"])).as_deref(),
+            Some("gemini-2.5-flash")
+        );
+        assert_eq!(
+            model(&rules, &pair(Some("en"), &["zh"])).as_deref(),
+            Some("gpt-4o-mini")
+        );
+        // 多个目标语言时不匹配 *:ja
+        assert_eq!(
+            model(&rules, &pair(Some("en"), &["ja", "de"])).as_deref(),
+            Some("gpt-4o-mini")
+        );
+    }
+
+    #[test]
+    fn test_route_falls_back_to_default() {
+        let rules = rules(&[("EN:De", "gpt-4o"), ("default", "gpt-4o-mini")]);
+        assert_eq!(
+            model(&rules, &pair(Some("en"), &["de"])).as_deref(),
+            Some("gpt-4o")
+        );
+        let route = rules.route(&pair(Some("en"), &["fr"])).unwrap();
+        assert_eq!(route.rule, "default");
+        assert_eq!(route.model, "gpt-4o-mini");
+
+        assert_eq!(RoutingRules::default().route(&pair(None, &[])), None);
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let known = |model: &str| model.starts_with("gpt-");
+        assert!(
+            rules(&[("*:ja", "gpt-4o"), ("default", "gpt-4o")])
+                .validate(known)
+                .is_ok()
+        );
+
+        let error = rules(&[("de:en", "deepl")])
+            .validate(known)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("'deepl' 未知"), "{}", error);
+        assert!(rules(&[("de-en", "gpt-4o")]).validate(known).is_err());
+        assert!(rules(&[("de:", "gpt-4o")]).validate(known).is_err());
+    }
+
+    #[test]
+    fn test_detect_language_pair() {
+        assert_eq!(LanguagePair::detect("你好", &[]), pair(Some("zh"), &["en"]));
+        assert_eq!(
+            LanguagePair::detect("", &["ja".to_string()]),
+            pair(None, &["ja"])
+        );
+        assert_eq!(pair(None, &["ja", "de"]).to_string(), "?→ja,de");
+    }
+}
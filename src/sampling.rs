@@ -0,0 +1,125 @@
+//! 采样温度之外的采样参数
+//!
+//! `--top-p` 填充请求的 `top_p` 字段（核采样，0 到 1），`--frequency-penalty` 与
+//! `--presence-penalty` 填充对应的惩罚字段（-2 到 2），正值降低重复用词的概率。
+//! 推理模型不接受这些参数；部分提供商不接受惩罚参数（见 [`config::supports_penalties`]）。
+//! 不接受的参数发送前去掉并给出警告，避免整个请求被拒绝。
+
+use crate::config;
+
+/// 惩罚参数的最小值
+pub const MIN_PENALTY: f32 = -2.0;
+/// 惩罚参数的最大值
+pub const MAX_PENALTY: f32 = 2.0;
+
+/// 采样参数，未设置的参数不写入请求
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SamplingOptions {
+    pub top_p: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+}
+
+impl SamplingOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 去掉 `url` 对应的提供商或推理模型不接受的参数，返回被去掉的参数对应的命令行选项
+    pub fn retain_supported(&mut self, url: &str, reasoning: bool) -> Vec<&'static str> {
+        let penalties = !reasoning && config::supports_penalties(url);
+        let mut dropped = Vec::new();
+        for (value, flag, supported) in [
+            (&mut self.top_p, "--top-p", !reasoning),
+            (
+                &mut self.frequency_penalty,
+                "--frequency-penalty",
+                penalties,
+            ),
+            (&mut self.presence_penalty, "--presence-penalty", penalties),
+        ] {
+            if !supported && value.take().is_some() {
+                dropped.push(flag);
+            }
+        }
+        dropped
+    }
+}
+
+/// 解析 `--top-p`：0 到 1 之间的数
+pub fn parse_top_p(s: &str) -> Result<f32, String> {
+    let top_p: f32 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("无效的 top_p '{}'，应为 0 到 1 之间的数", s))?;
+    if !(0.0..=1.0).contains(&top_p) {
+        return Err(format!("top_p 必须在 0 到 1 之间，当前为 {}", top_p));
+    }
+    Ok(top_p)
+}
+
+/// 解析 `--frequency-penalty` 与 `--presence-penalty`：-2 到 2 之间的数
+pub fn parse_penalty(s: &str) -> Result<f32, String> {
+    let penalty: f32 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("无效的惩罚值 '{}'，应为 -2 到 2 之间的数", s))?;
+    if !(MIN_PENALTY..=MAX_PENALTY).contains(&penalty) {
+        return Err(format!(
+            "惩罚值必须在 {} 到 {} 之间，当前为 {}",
+            MIN_PENALTY, MAX_PENALTY, penalty
+        ));
+    }
+    Ok(penalty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ranges() {
+        assert_eq!(parse_top_p("0.9"), Ok(0.9));
+        assert_eq!(parse_top_p(" 1 "), Ok(1.0));
+        assert!(parse_top_p("1.1").is_err());
+        assert!(parse_top_p("-0.1").is_err());
+        assert!(parse_top_p("NaN").is_err());
+
+        assert_eq!(parse_penalty("-2"), Ok(-2.0));
+        assert_eq!(parse_penalty("0.5"), Ok(0.5));
+        assert!(parse_penalty("2.5").is_err());
+        assert!(parse_penalty("abc").is_err());
+    }
+
+    #[test]
+    fn test_retain_supported() {
+        let sampling = SamplingOptions {
+            top_p: Some(0.9),
+            frequency_penalty: Some(0.5),
+            presence_penalty: None,
+        };
+
+        let mut openai = sampling;
+        assert!(
+            openai
+                .retain_supported("https://api.openai.com/v1", false)
+                .is_empty()
+        );
+        assert_eq!(openai, sampling);
+
+        let mut gemini = sampling;
+        let gemini_url = config::get_model_url("gemini-2.5-flash").unwrap();
+        assert_eq!(
+            gemini.retain_supported(&gemini_url, false),
+            ["--frequency-penalty"]
+        );
+        assert_eq!(gemini.top_p, Some(0.9));
+
+        let mut reasoning = sampling;
+        assert_eq!(
+            reasoning.retain_supported("https://api.openai.com/v1", true),
+            ["--top-p", "--frequency-penalty"]
+        );
+        assert!(reasoning.is_empty());
+    }
+}
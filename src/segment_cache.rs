@@ -0,0 +1,511 @@
+//! 文档片段的缓存
+//!
+//! 文件格式模式（`--format`）下按片段缓存译文，键为模型、提示词、影响译文的选项、片段原文与
+//! 译者注的 SHA-256 哈希。编辑文件中的一个段落后重新运行，只有该段落发送请求，不需要 `--diff-against`。
+//!
+//! 缓存文件为 JSON Lines 格式（`{"key": "...", "source": "...", "translation": "..."}`），默认位于
+//! 缓存目录下的 `segments.jsonl`，`--no-segment-cache` 时不使用。每个片段翻译完成后立即追加（见
+//! [`store::append_record`]），运行中断时已完成的片段同样保留。记录中同时保存完整的输入，
+//! 命中时逐项核对，哈希碰撞时重新翻译而不是返回其他片段的译文；旧版本写入的没有完整输入的
+//! 条目不再命中。
+//!
+//! 临时模式（`--ephemeral`）下或缓存目录不可写（只读文件系统、没有权限）时只在内存中缓存，
+//! 同一次运行中重复的片段仍然命中，但不写入磁盘。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, TransomeError};
+use crate::platform;
+use crate::store;
+use crate::warning::Warnings;
+
+/// 片段缓存的文件名
+pub const SEGMENT_CACHE_FILE_NAME: &str = "segments.jsonl";
+
+/// 缓存文件中的一条记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    /// 完整的输入（见 [`SegmentKey::source`]），用于核对哈希
+    #[serde(default)]
+    source: String,
+    translation: String,
+}
+
+/// 决定一个片段译文的全部输入
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SegmentKey<'a> {
+    pub model: &'a str,
+    pub prompt: &'a str,
+    /// 影响译文的其余选项（温度、后处理等）的哈希，见 [`OutputOptions::digest`]
+    pub options: &'a str,
+    pub text: &'a str,
+    pub note: Option<&'a str>,
+}
+
+impl SegmentKey<'_> {
+    /// 各项输入的完整表示（JSON），不同的输入不会得到相同的结果
+    pub fn source(&self) -> String {
+        serde_json::to_string(self).expect("片段的输入总能序列化为 JSON")
+    }
+
+    /// 缓存中使用的键：完整输入的 SHA-256 哈希（十六进制）
+    pub fn digest(&self) -> String {
+        format!("{:x}", Sha256::digest(self.source().as_bytes()))
+    }
+}
+
+/// 除模型与提示词外影响片段译文的选项
+///
+/// 按字段顺序序列化为 JSON 后取哈希，与 Rust 版本和调试输出的格式无关。新增影响译文的选项时
+/// 在末尾追加字段；修改字段的名称或顺序会使已有的缓存条目不再命中。
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OutputOptions {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// `--logit-bias` 的 (token ID, 偏置)，按命令行中的顺序
+    pub logit_bias: Vec<(u32, i32)>,
+    pub top_p: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub choice_separator: Option<String>,
+    pub max_output_ratio: Option<f64>,
+    pub sandbox_input: bool,
+    pub escalate_on_low_quality: Option<f64>,
+    pub escalate_model: Option<String>,
+    pub review_model: Option<String>,
+    /// `--post-process` 的后处理器，使用命令行中的名称
+    pub post_process: Vec<String>,
+    /// `--locale`，使用命令行中的名称
+    pub locale: Option<String>,
+    pub strip_markdown: bool,
+    /// 是否使用专有名词对照表
+    pub names: bool,
+}
+
+impl OutputOptions {
+    /// 各选项的完整表示（JSON），字段按声明顺序排列
+    pub fn source(&self) -> String {
+        serde_json::to_string(self).expect("译文选项总能序列化为 JSON")
+    }
+
+    /// 片段缓存键中使用的选项哈希：[`OutputOptions::source`] 的 SHA-256 哈希（十六进制）
+    pub fn digest(&self) -> String {
+        format!("{:x}", Sha256::digest(self.source().as_bytes()))
+    }
+}
+
+/// 片段缓存
+#[derive(Debug)]
+pub struct SegmentCache {
+    path: PathBuf,
+    /// 是否写入缓存文件，目录不可写时在运行中改为 false
+    persist: AtomicBool,
+    /// 按键索引的记录
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    lookups: AtomicUsize,
+    hits: AtomicUsize,
+}
+
+impl SegmentCache {
+    /// 读取缓存文件，文件不存在时从空缓存开始
+    ///
+    /// 无法解析的行会被跳过并记录警告；没有权限读取时改为只在内存中缓存。
+    pub fn load(path: &Path, warnings: &Warnings) -> Result<Self> {
+        let entries = match store::read_records::<CacheEntry>(path, warnings) {
+            Ok(entries) => entries,
+            Err(e) if e.is_read_only() => {
+                tracing::debug!(error = %e, "无法读取片段缓存，只在内存中缓存");
+                return Ok(Self::in_memory());
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            persist: AtomicBool::new(true),
+            entries: Mutex::new(entries.into_iter().map(|e| (e.key.clone(), e)).collect()),
+            lookups: AtomicUsize::new(0),
+            hits: AtomicUsize::new(0),
+        })
+    }
+
+    /// 只在内存中缓存的空缓存，用于临时模式（`--ephemeral`）
+    pub fn in_memory() -> Self {
+        Self {
+            path: PathBuf::new(),
+            persist: AtomicBool::new(false),
+            entries: Mutex::default(),
+            lookups: AtomicUsize::new(0),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// 缓存文件，只在内存中缓存时为 None
+    pub fn path(&self) -> Option<&Path> {
+        self.persists().then_some(self.path.as_path())
+    }
+
+    /// 是否写入缓存文件
+    pub fn persists(&self) -> bool {
+        self.persist.load(Ordering::Relaxed)
+    }
+
+    /// 先查找缓存，未命中时调用 `translate` 并立即把译文写入缓存文件
+    pub async fn translate<F, Fut>(
+        &self,
+        key: &SegmentKey<'_>,
+        translate: F,
+    ) -> anyhow::Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<String>>,
+    {
+        let entry = CacheEntry {
+            key: key.digest(),
+            source: key.source(),
+            translation: String::new(),
+        };
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        let cached = self.entries.lock().unwrap().get(&entry.key).cloned();
+        match cached {
+            Some(cached) if cached.source == entry.source => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(cached.translation);
+            }
+            Some(_) => tracing::debug!(key = %entry.key, "片段缓存的键相同但输入不同，重新翻译"),
+            None => {}
+        }
+
+        let translation = translate().await?;
+        self.record(CacheEntry {
+            translation: translation.clone(),
+            ..entry
+        })?;
+        Ok(translation)
+    }
+
+    /// 记录一条译文并追加到文件，目录不可写时改为只在内存中缓存
+    fn record(&self, entry: CacheEntry) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        if self.persists() {
+            match self.append(&entry) {
+                Err(e) if e.is_read_only() => {
+                    tracing::debug!(error = %e, "片段缓存目录不可写，只在内存中缓存");
+                    self.persist.store(false, Ordering::Relaxed);
+                }
+                result => result?,
+            }
+        }
+        entries.insert(entry.key.clone(), entry);
+        Ok(())
+    }
+
+    /// 追加一条记录到缓存文件，目录不存在时创建
+    fn append(&self, entry: &CacheEntry) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                TransomeError::io_error_with_context(e, format!("创建目录 '{}'", dir.display()))
+            })?;
+        }
+        store::append_record(&self.path, entry)
+    }
+
+    /// 本次运行的命中情况
+    pub fn hits(&self) -> CacheHits {
+        CacheHits {
+            hits: self.hits.load(Ordering::Relaxed),
+            lookups: self.lookups.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 片段缓存的命中情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheHits {
+    pub hits: usize,
+    pub lookups: usize,
+}
+
+impl CacheHits {
+    /// 命中率，没有查找时为 None
+    pub fn rate(&self) -> Option<f64> {
+        (self.lookups > 0).then(|| self.hits as f64 / self.lookups as f64)
+    }
+}
+
+impl fmt::Display for CacheHits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "片段缓存命中 {}/{}", self.hits, self.lookups)?;
+        if let Some(rate) = self.rate() {
+            write!(f, "（{:.1}%）", rate * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// 获取默认的片段缓存路径
+///
+/// 优先使用 `XDG_CACHE_HOME`，否则为主目录下的 `.cache`。缓存不放在配置目录中，
+/// 导出配置包时不会带上，删除后只是重新翻译。
+pub fn default_path() -> Option<PathBuf> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| platform::home_dir().map(|home| home.join(".cache")))?;
+
+    Some(cache_dir.join("transome").join(SEGMENT_CACHE_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{self, Document, SegmentOptions, markdown::MarkdownDocument};
+
+    fn key<'a>(text: &'a str) -> SegmentKey<'a> {
+        SegmentKey {
+            model: "gpt-4o",
+            prompt: "翻译",
+            options: "",
+            text,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_key_covers_every_input() {
+        let base = key("你好");
+        let digests = [
+            base.digest(),
+            SegmentKey {
+                model: "gpt-4o-mini",
+                ..base
+            }
+            .digest(),
+            SegmentKey {
+                prompt: "翻译成日文",
+                ..base
+            }
+            .digest(),
+            SegmentKey {
+                options: "temperature=1.0",
+                ..base
+            }
+            .digest(),
+            SegmentKey {
+                text: "再见",
+                ..base
+            }
+            .digest(),
+            SegmentKey {
+                note: Some("菜单"),
+                ..base
+            }
+            .digest(),
+        ];
+        for (i, digest) in digests.iter().enumerate() {
+            assert_eq!(digests.iter().filter(|d| *d == digest).count(), 1, "{}", i);
+        }
+        assert_eq!(key("你好").digest(), base.digest());
+        // SHA-256 与 Rust 版本无关
+        assert_eq!(
+            key("").digest(),
+            format!("{:x}", Sha256::digest(key("").source().as_bytes()))
+        );
+        assert_eq!(base.digest().len(), 64);
+    }
+
+    #[test]
+    fn test_output_options_are_serialized_in_order() {
+        let options = OutputOptions {
+            temperature: Some(0.5),
+            logit_bias: vec![(50256, -100)],
+            post_process: vec!["localize-numbers".to_string()],
+            locale: Some("zh".to_string()),
+            names: true,
+            ..OutputOptions::default()
+        };
+        assert_eq!(
+            options.source(),
+            r#"{"temperature":0.5,"max_tokens":null,"logit_bias":[[50256,-100]],"top_p":null,"#
+                .to_string()
+                + r#""frequency_penalty":null,"presence_penalty":null,"choice_separator":null,"#
+                + r#""max_output_ratio":null,"sandbox_input":false,"escalate_on_low_quality":null,"#
+                + r#""escalate_model":null,"review_model":null,"post_process":["localize-numbers"],"#
+                + r#""locale":"zh","strip_markdown":false,"names":true}"#
+        );
+        assert_eq!(
+            options.digest(),
+            format!("{:x}", Sha256::digest(options.source().as_bytes()))
+        );
+
+        // 每个选项都会改变哈希
+        let changed = OutputOptions {
+            review_model: Some("gpt-4o".to_string()),
+            ..options.clone()
+        };
+        assert_ne!(changed.digest(), options.digest());
+        assert_ne!(OutputOptions::default().digest(), options.digest());
+    }
+
+    #[tokio::test]
+    async fn test_colliding_key_is_not_served() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SEGMENT_CACHE_FILE_NAME);
+        // 键相同但输入不同的记录（模拟哈希碰撞），以及旧版本写入的没有完整输入的记录
+        let colliding = CacheEntry {
+            key: key("你好").digest(),
+            source: key("再见").source(),
+            translation: "Goodbye".to_string(),
+        };
+        store::append_record(&path, &colliding).unwrap();
+        let stale = serde_json::json!({"key": key("早上好").digest(), "translation": "Stale"});
+        store::append_record(&path, &stale).unwrap();
+
+        let cache = SegmentCache::load(&path, &Warnings::new(true)).unwrap();
+        for (text, expected) in [("你好", "Hello"), ("早上好", "Good morning")] {
+            let translation = cache
+                .translate(&key(text), || async { Ok(expected.to_string()) })
+                .await
+                .unwrap();
+            assert_eq!(translation, expected);
+        }
+        assert_eq!(cache.hits().hits, 0);
+
+        // 重新翻译的结果覆盖碰撞的记录
+        let cache = SegmentCache::load(&path, &Warnings::new(true)).unwrap();
+        let translation = cache
+            .translate(&key("你好"), || async {
+                anyhow::bail!("不应发送请求")
+            })
+            .await
+            .unwrap();
+        assert_eq!(translation, "Hello");
+    }
+
+    /// 翻译文档的全部片段，返回发送的请求数
+    async fn translate_document(cache: &SegmentCache, markdown: &str) -> usize {
+        let requests = AtomicUsize::new(0);
+        let document = MarkdownDocument::parse(markdown, Default::default());
+        let warnings = Warnings::new(true);
+        let requests_ref = &requests;
+        let translations = formats::translate_segments(
+            document.segments(),
+            &[],
+            &SegmentOptions::default(),
+            &warnings,
+            |segment, _| async move {
+                cache
+                    .translate(&key(&segment), || async {
+                        requests_ref.fetch_add(1, Ordering::SeqCst);
+                        Ok(segment.to_uppercase())
+                    })
+                    .await
+            },
+        )
+        .await
+        .unwrap();
+        assert!(translations.iter().all(Option::is_some));
+        requests.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_edited_segment_is_the_only_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache").join(SEGMENT_CACHE_FILE_NAME);
+        let original = "# Title\n\nFirst paragraph.\n\nSecond paragraph.\n\nThird paragraph.\n";
+
+        let cache = SegmentCache::load(&path, &Warnings::new(true)).unwrap();
+        assert_eq!(translate_document(&cache, original).await, 4);
+        assert_eq!(
+            cache.hits(),
+            CacheHits {
+                hits: 0,
+                lookups: 4
+            }
+        );
+
+        // 新的运行从文件读取缓存，只有修改过的段落发送请求
+        let edited = original.replace("Second paragraph.", "Second paragraph, edited.");
+        let cache = SegmentCache::load(&path, &Warnings::new(true)).unwrap();
+        assert_eq!(translate_document(&cache, &edited).await, 1);
+        assert_eq!(
+            cache.hits(),
+            CacheHits {
+                hits: 3,
+                lookups: 4
+            }
+        );
+        assert_eq!(cache.hits().to_string(), "片段缓存命中 3/4（75.0%）");
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_writes_nothing() {
+        let cache = SegmentCache::in_memory();
+        let markdown = "# Title\n\nBody.\n";
+        assert_eq!(translate_document(&cache, markdown).await, 2);
+        // 同一次运行中重复的片段仍然命中
+        assert_eq!(translate_document(&cache, markdown).await, 0);
+        assert!(!cache.persists());
+        assert_eq!(cache.path(), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_read_only_directory_falls_back_to_memory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let protected = dir.path().join("protected");
+        fs::create_dir(&protected).unwrap();
+        fs::set_permissions(&protected, fs::Permissions::from_mode(0o555)).unwrap();
+        // 以 root 运行时目录权限不生效，无法模拟只读目录
+        if fs::create_dir(protected.join("probe")).is_ok() {
+            return;
+        }
+
+        let path = protected.join("cache").join(SEGMENT_CACHE_FILE_NAME);
+        let cache = SegmentCache::load(&path, &Warnings::new(true)).unwrap();
+        assert!(cache.persists());
+        assert_eq!(translate_document(&cache, "# Title\n\nBody.\n").await, 2);
+        assert!(!cache.persists());
+        // 受保护的目录中没有写入任何内容
+        assert_eq!(fs::read_dir(&protected).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_translation_is_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SEGMENT_CACHE_FILE_NAME);
+        let cache = SegmentCache::load(&path, &Warnings::new(true)).unwrap();
+
+        let result = cache
+            .translate(&key("你好"), || async {
+                anyhow::bail!("服务不可用")
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(!path.exists());
+
+        let translation = cache
+            .translate(&key("你好"), || async { Ok("Hello".to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(translation, "Hello");
+        assert_eq!(
+            cache.hits(),
+            CacheHits {
+                hits: 0,
+                lookups: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! 翻译结果的输出目标
+//!
+//! 翻译结果统一写入 [`OutputSink`]，由命令行参数选择标准输出、文件或剪贴板。
+//! 库的使用者可以实现该 trait 提供自定义的输出目标。
+//!
+//! 完整的翻译结果通过 [`OutputSink::write_result`] 写入：标准输出默认在末尾补充换行，
+//! 文件默认原样写入结果的字节，便于下游工具严格读取。
+//!
+//! 输出按需转码为终端或 `--output-encoding` 指定的编码（见 [`output_encoding`]）。
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use encoding_rs::Encoding;
+
+use crate::output_encoding::{self, FILE_HINT, Transcoder};
+use crate::warning::Warnings;
+
+/// 翻译结果的输出目标
+pub trait OutputSink {
+    /// 原样写入一段输出
+    fn write(&mut self, output: &str) -> Result<()>;
+
+    /// 写入一行输出
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        self.write(line)?;
+        self.write("\n")
+    }
+
+    /// 写入完整的翻译结果，是否在末尾补充换行由输出目标决定
+    fn write_result(&mut self, result: &str) -> Result<()> {
+        self.write_line(result)
+    }
+
+    /// 立即写出已缓冲的内容，用于逐条输出的批量模式
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 完成输出，缓冲的内容在此时写出
+    fn finish(&mut self) -> Result<()> {
+        self.flush()
+    }
+}
+
+/// 写入任意 [`io::Write`] 的输出目标
+#[derive(Debug)]
+pub struct WriterSink<W: Write> {
+    writer: W,
+    unbuffered: bool,
+    trailing_newline: bool,
+    transcoder: Option<Transcoder>,
+}
+
+impl<W: Write> WriterSink<W> {
+    /// 创建输出目标，完整结果的末尾默认补充换行
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            unbuffered: false,
+            trailing_newline: true,
+            transcoder: None,
+        }
+    }
+
+    /// 设置 [`OutputSink::write_result`] 是否在结果末尾补充换行
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// 每次写入后立即写出，不等待缓冲区填满
+    pub fn unbuffered(mut self) -> Self {
+        self.unbuffered = true;
+        self
+    }
+
+    /// 写出前按 `transcoder` 转码，未设置时写出 UTF-8
+    pub fn transcoder(mut self, transcoder: Option<Transcoder>) -> Self {
+        self.transcoder = transcoder;
+        self
+    }
+
+    /// 取回内部的写入器
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> OutputSink for WriterSink<W> {
+    fn write(&mut self, output: &str) -> Result<()> {
+        let written = match &mut self.transcoder {
+            Some(transcoder) => self.writer.write_all(&transcoder.encode(output)),
+            None => self.writer.write_all(output.as_bytes()),
+        };
+        written.context("写入输出失败")?;
+        if self.unbuffered {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write_result(&mut self, result: &str) -> Result<()> {
+        if self.trailing_newline {
+            self.write_line(result)
+        } else {
+            self.write(result)
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush().context("写入输出失败")
+    }
+}
+
+/// 输出到标准输出
+pub fn stdout() -> WriterSink<io::Stdout> {
+    WriterSink::new(io::stdout())
+}
+
+/// 输出到文件，文件已存在时覆盖；完整结果原样写入，末尾不补充换行
+pub fn file(path: &Path) -> Result<WriterSink<BufWriter<File>>> {
+    let file =
+        File::create(path).with_context(|| format!("无法创建输出文件 '{}'", path.display()))?;
+    Ok(WriterSink::new(BufWriter::new(file)).trailing_newline(false))
+}
+
+/// 复制到系统剪贴板，输出在 [`OutputSink::finish`] 时一次性写入
+#[derive(Debug, Default)]
+pub struct ClipboardSink {
+    buffer: String,
+}
+
+impl ClipboardSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// 按平台依次尝试的剪贴板命令
+fn clipboard_commands() -> &'static [&'static [&'static str]] {
+    if cfg!(target_os = "macos") {
+        &[&["pbcopy"]]
+    } else if cfg!(windows) {
+        &[&["clip"]]
+    } else {
+        &[
+            &["wl-copy"],
+            &["xclip", "-selection", "clipboard"],
+            &["xsel", "--clipboard", "--input"],
+        ]
+    }
+}
+
+impl OutputSink for ClipboardSink {
+    fn write(&mut self, output: &str) -> Result<()> {
+        self.buffer.push_str(output);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        // 剪贴板中不保留末尾的换行
+        let content = self.buffer.trim_end_matches('\n');
+
+        for command in clipboard_commands() {
+            let Ok(mut child) = Command::new(command[0])
+                .args(&command[1..])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+            else {
+                continue;
+            };
+
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(content.as_bytes())
+                    .context("写入剪贴板失败")?;
+            }
+            if child.wait().context("写入剪贴板失败")?.success() {
+                return Ok(());
+            }
+        }
+
+        let names: Vec<&str> = clipboard_commands().iter().map(|c| c[0]).collect();
+        bail!(
+            "无法写入剪贴板：未找到可用的剪贴板命令（{}）\n\n\
+            请安装其中之一，或改用 --output 写入文件",
+            names.join("、")
+        )
+    }
+}
+
+/// 按参数选择输出目标，`unbuffered` 为 true 时每次写入后立即写出
+///
+/// `trailing_newline` 覆盖完整结果末尾是否补充换行，为 `None` 时使用输出目标的默认行为。
+/// 文件按 `encoding` 转码，标准输出在未指定时按终端的编码转码，转码有损时记录到 `warnings`。
+/// 剪贴板只能一次性写入且不保留末尾换行，不受这些选项影响。
+pub fn select(
+    output: Option<&Path>,
+    clipboard: bool,
+    unbuffered: bool,
+    trailing_newline: Option<bool>,
+    encoding: Option<&'static Encoding>,
+    warnings: &Warnings,
+) -> Result<Box<dyn OutputSink>> {
+    let sink = match (output, clipboard) {
+        (_, true) => return Ok(Box::new(ClipboardSink::new())),
+        (Some(path), false) => {
+            let transcoder =
+                encoding.and_then(|encoding| Transcoder::new(encoding, warnings, FILE_HINT));
+            configure(
+                file(path)?.transcoder(transcoder),
+                unbuffered,
+                trailing_newline,
+            )
+        }
+        (None, false) => {
+            let transcoder = output_encoding::stdout_transcoder(encoding, warnings);
+            configure(
+                stdout().transcoder(transcoder),
+                unbuffered,
+                trailing_newline,
+            )
+        }
+    };
+    Ok(sink)
+}
+
+fn configure<W: Write + 'static>(
+    mut sink: WriterSink<W>,
+    unbuffered: bool,
+    trailing_newline: Option<bool>,
+) -> Box<dyn OutputSink> {
+    if unbuffered {
+        sink = sink.unbuffered();
+    }
+    if let Some(trailing_newline) = trailing_newline {
+        sink = sink.trailing_newline(trailing_newline);
+    }
+    Box::new(sink)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模拟调用方：逐行写入后完成输出
+    fn emit(sink: &mut dyn OutputSink, lines: &[&str]) -> Result<()> {
+        for line in lines {
+            sink.write_line(line)?;
+        }
+        sink.finish()
+    }
+
+    #[test]
+    fn test_memory_sink_captures_output() {
+        let mut sink = WriterSink::new(Vec::new());
+        emit(&mut sink, &["你好", "世界"]).unwrap();
+        sink.write("末尾").unwrap();
+
+        assert_eq!(
+            String::from_utf8(sink.into_inner()).unwrap(),
+            "你好\n世界\n末尾"
+        );
+    }
+
+    #[test]
+    fn test_file_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        std::fs::write(&path, "old content that is longer").unwrap();
+
+        let mut sink = select(
+            Some(path.as_path()),
+            false,
+            false,
+            None,
+            None,
+            &Warnings::default(),
+        )
+        .unwrap();
+        emit(sink.as_mut(), &["hello"]).unwrap();
+        drop(sink);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\n");
+
+        let missing = dir.path().join("missing").join("out.txt");
+        let error = select(
+            Some(missing.as_path()),
+            false,
+            false,
+            None,
+            None,
+            &Warnings::default(),
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().starts_with("无法创建输出文件"));
+    }
+
+    /// 把一次完整结果写入文件，返回文件的字节数
+    fn file_result_len(trailing_newline: Option<bool>, result: &str) -> usize {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let mut sink = select(
+            Some(path.as_path()),
+            false,
+            false,
+            trailing_newline,
+            None,
+            &Warnings::default(),
+        )
+        .unwrap();
+        sink.write_result(result).unwrap();
+        sink.finish().unwrap();
+        drop(sink);
+        std::fs::read(&path).unwrap().len()
+    }
+
+    #[test]
+    fn test_file_result_is_byte_exact() {
+        // "你好" 为 6 字节
+        assert_eq!(file_result_len(None, "你好"), 6);
+        assert_eq!(file_result_len(Some(true), "你好"), 7);
+        assert_eq!(file_result_len(Some(false), "你好"), 6);
+        // 结果自身的换行原样保留
+        assert_eq!(file_result_len(None, "你好\n"), 7);
+    }
+
+    #[test]
+    fn test_stdout_result_ends_with_newline() {
+        let mut sink = WriterSink::new(Vec::new());
+        sink.write_result("你好").unwrap();
+        assert_eq!(sink.into_inner().len(), 7);
+
+        let mut sink = WriterSink::new(Vec::new()).trailing_newline(false);
+        sink.write_result("你好").unwrap();
+        assert_eq!(sink.into_inner().len(), 6);
+    }
+
+    #[test]
+    fn test_file_is_transcoded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let warnings = Warnings::new(true);
+        let gbk = output_encoding::parse_encoding("gbk").unwrap();
+
+        let mut sink = select(
+            Some(path.as_path()),
+            false,
+            false,
+            None,
+            Some(gbk),
+            &warnings,
+        )
+        .unwrap();
+        emit(sink.as_mut(), &["你好", "😀"]).unwrap();
+        drop(sink);
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(gbk.decode(&bytes).0, "你好\n?\n");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    /// 记录每次实际写出的内容
+    #[derive(Debug, Default)]
+    struct FlushLog {
+        pending: Vec<u8>,
+        flushed: Vec<String>,
+    }
+
+    impl Write for FlushLog {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.pending.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            if !self.pending.is_empty() {
+                let pending = std::mem::take(&mut self.pending);
+                self.flushed.push(String::from_utf8(pending).unwrap());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_unbuffered_flushes_every_write() {
+        let mut sink = WriterSink::new(FlushLog::default());
+        sink.write_line("a").unwrap();
+        sink.flush().unwrap();
+        sink.write_line("b").unwrap();
+        sink.write_line("c").unwrap();
+        sink.finish().unwrap();
+        assert_eq!(sink.into_inner().flushed, ["a\n", "b\nc\n"]);
+
+        let mut sink = WriterSink::new(FlushLog::default()).unbuffered();
+        sink.write_line("a").unwrap();
+        assert_eq!(sink.into_inner().flushed, ["a", "\n"]);
+    }
+}
@@ -0,0 +1,145 @@
+//! 批量模式的输入来源
+//!
+//! `--lines` 可同时读取 stdin 与多个 `--file`：stdin（不是终端或使用了 `--pipe` 时）在前，
+//! 其后按命令行中的顺序读取各文件，条目按此顺序编号与输出。
+//! `--label` 时每行译文前加上 `来源:行号:`（同 grep），`--json` 时每个条目带有
+//! `source` 与 `line` 字段。行号为该行在来源中的行号，空行不翻译但计入行号。
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// stdin 作为来源时的名称，与 grep 相同
+pub const STDIN_NAME: &str = "(standard input)";
+
+/// 待翻译文本来自命令行参数时的来源名称
+pub const ARGUMENT_NAME: &str = "(argument)";
+
+/// 一个输入来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+    /// 文本第一行在来源中的行号，只读取了文件的一部分时大于 1
+    pub first_line: usize,
+}
+
+impl Source {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            text: text.into(),
+            first_line: 1,
+        }
+    }
+
+    /// 从 stdin 读取的来源
+    pub fn stdin(text: impl Into<String>) -> Self {
+        Self::new(STDIN_NAME, text)
+    }
+
+    /// 设置文本第一行的行号
+    pub fn starting_at(mut self, first_line: usize) -> Self {
+        self.first_line = first_line;
+        self
+    }
+}
+
+/// 批量条目在输入中的位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Origin {
+    pub source: String,
+    /// 从 1 开始的行号
+    pub line: usize,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.source, self.line)
+    }
+}
+
+/// 按顺序拆分各来源的行，去除首尾空白并跳过空行（与 [`crate::batch::split_lines`] 相同），
+/// 同时返回每行的位置
+pub fn split_sources(sources: &[Source]) -> (Vec<String>, Vec<Origin>) {
+    let mut lines = Vec::new();
+    let mut origins = Vec::new();
+    for source in sources {
+        for (offset, line) in source.text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            lines.push(line.to_string());
+            origins.push(Origin {
+                source: source.name.clone(),
+                line: source.first_line + offset,
+            });
+        }
+    }
+    (lines, origins)
+}
+
+/// 各来源的文本按顺序连接，每个来源以一个换行结尾，用于验证、估算与使用记录
+pub fn joined_text(sources: &[Source]) -> String {
+    let mut text = String::new();
+    for source in sources {
+        text.push_str(source.text.trim_end_matches(['\r', '\n']));
+        text.push('\n');
+    }
+    text
+}
+
+/// `--lines --json` 时每个条目输出的结构（JSON Lines）
+#[derive(Debug, Clone, Serialize)]
+pub struct LineJson<'a> {
+    pub source: &'a str,
+    pub line: usize,
+    pub translation: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::split_lines;
+
+    #[test]
+    fn test_split_sources_keeps_order_and_line_numbers() {
+        let sources = [
+            Source::stdin("main 1\n\nmain 3\n"),
+            Source::new("a.txt", "  a 1  \r\na 2"),
+            Source::new("b.txt", "\nb 2\n").starting_at(10),
+        ];
+        let (lines, origins) = split_sources(&sources);
+        assert_eq!(lines, ["main 1", "main 3", "a 1", "a 2", "b 2"]);
+        let labels: Vec<String> = origins.iter().map(Origin::to_string).collect();
+        assert_eq!(
+            labels,
+            [
+                "(standard input):1",
+                "(standard input):3",
+                "a.txt:1",
+                "a.txt:2",
+                "b.txt:11"
+            ]
+        );
+
+        // 拆分结果与单一来源的逐行拆分一致
+        assert_eq!(lines, split_lines(&joined_text(&sources)));
+    }
+
+    #[test]
+    fn test_line_json() {
+        let origin = Origin {
+            source: "a.txt".to_string(),
+            line: 2,
+        };
+        let json = serde_json::to_string(&LineJson {
+            source: &origin.source,
+            line: origin.line,
+            translation: "你好",
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"source":"a.txt","line":2,"translation":"你好"}"#);
+    }
+}
@@ -0,0 +1,391 @@
+//! 使用统计
+//!
+//! `transome stats` 汇总最近若干天的使用记录（见 [`crate::history`]）：每日翻译条目数、
+//! 常用语言对、各模型的 token 用量与估算费用、翻译记忆命中率与平均耗时。
+//! 汇总是对已解析记录的纯函数，当前时刻由调用方传入。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::history::HistoryRecord;
+use crate::util::display_width;
+
+/// 默认统计的天数
+pub const DEFAULT_DAYS: u32 = 30;
+
+/// 输出中列出的语言对数量上限
+const TOP_PAIRS: usize = 5;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// 模型每百万 token 的参考价格（美元）：(输入, 输出)
+///
+/// 仅用于估算，实际费用以服务商账单为准；未知模型不计费用。
+pub(crate) fn price_per_million(model: &str) -> Option<(f64, f64)> {
+    Some(match model {
+        "gpt-4" => (30.0, 60.0),
+        "gpt-4-turbo" => (10.0, 30.0),
+        "gpt-4o" => (2.5, 10.0),
+        "gpt-4o-mini" => (0.15, 0.6),
+        "gpt-3.5-turbo" => (0.5, 1.5),
+        "gpt-3.5-turbo-16k" => (3.0, 4.0),
+        "o1" => (15.0, 60.0),
+        "o1-mini" | "o3-mini" => (1.1, 4.4),
+        "gemini-2.5-pro" => (1.25, 10.0),
+        "gemini-2.5-flash" => (0.3, 2.5),
+        "gemini-2.5-flash-lite" => (0.1, 0.4),
+        "gemini-1.5-pro" => (1.25, 5.0),
+        "gemini-1.5-flash" => (0.075, 0.3),
+        _ => return None,
+    })
+}
+
+/// 单个模型的用量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelUsage {
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// 估算费用（美元），模型价格未知时为 None
+    pub cost: Option<f64>,
+}
+
+/// 汇总结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    /// 统计区间的第一天（UTC，`YYYY-MM-DD`）
+    pub since: String,
+    pub days: u32,
+    /// 区间内的运行次数
+    pub runs: usize,
+    pub translations: usize,
+    /// 每日翻译条目数，只含有记录的日期，按日期排序
+    pub per_day: Vec<(String, usize)>,
+    /// 按运行次数排序的常用语言对
+    pub top_pairs: Vec<(String, usize)>,
+    /// 按路由规则选择模型的次数（`规则 → 模型`），按次数排序
+    pub routes: Vec<(String, usize)>,
+    /// 按模型名称排序的用量
+    pub models: Vec<ModelUsage>,
+    /// 翻译记忆命中率，没有翻译条目时为 None
+    pub cache_hit_rate: Option<f64>,
+    /// 每次运行的平均耗时（毫秒），没有运行时为 None
+    pub average_latency_ms: Option<u64>,
+}
+
+/// 汇总 `now`（Unix 时间戳，秒）之前 `days` 天内的记录
+///
+/// 区间按 UTC 日期计算，包含今天在内共 `days` 天；区间之外的记录被忽略。
+pub fn aggregate(records: &[HistoryRecord], now: u64, days: u32) -> Stats {
+    let today = now / SECONDS_PER_DAY;
+    let first_day = (today + 1).saturating_sub(u64::from(days));
+    let recent: Vec<&HistoryRecord> = records
+        .iter()
+        .filter(|record| (first_day..=today).contains(&(record.timestamp / SECONDS_PER_DAY)))
+        .collect();
+
+    let mut per_day: BTreeMap<u64, usize> = BTreeMap::new();
+    let mut pairs: HashMap<String, usize> = HashMap::new();
+    let mut routes: HashMap<String, usize> = HashMap::new();
+    let mut models: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for record in &recent {
+        *per_day
+            .entry(record.timestamp / SECONDS_PER_DAY)
+            .or_default() += record.translations;
+        for pair in record.language_pairs() {
+            *pairs.entry(pair).or_default() += 1;
+        }
+        if let Some(rule) = &record.route {
+            *routes
+                .entry(format!("{} → {}", rule, record.model))
+                .or_default() += 1;
+        }
+        let usage = models.entry(&record.model).or_default();
+        usage.0 += record.prompt_tokens;
+        usage.1 += record.completion_tokens;
+    }
+
+    let mut top_pairs: Vec<(String, usize)> = pairs.into_iter().collect();
+    top_pairs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top_pairs.truncate(TOP_PAIRS);
+
+    let mut routes: Vec<(String, usize)> = routes.into_iter().collect();
+    routes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let translations: usize = recent.iter().map(|record| record.translations).sum();
+    let memory_hits: usize = recent.iter().map(|record| record.memory_hits).sum();
+    let total_ms: u64 = recent.iter().map(|record| record.elapsed_ms).sum();
+
+    Stats {
+        since: format_date(first_day),
+        days,
+        runs: recent.len(),
+        translations,
+        per_day: per_day
+            .into_iter()
+            .map(|(day, count)| (format_date(day), count))
+            .collect(),
+        top_pairs,
+        routes,
+        models: models
+            .into_iter()
+            .map(|(model, (prompt_tokens, completion_tokens))| ModelUsage {
+                model: model.to_string(),
+                prompt_tokens,
+                completion_tokens,
+                cost: price_per_million(model).map(|(input, output)| {
+                    (prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1e6
+                }),
+            })
+            .collect(),
+        cache_hit_rate: (translations > 0).then(|| memory_hits as f64 / translations as f64),
+        average_latency_ms: (!recent.is_empty()).then(|| total_ms / recent.len() as u64),
+    }
+}
+
+/// 自 1970-01-01 起的天数对应的日期（`YYYY-MM-DD`）
+fn format_date(days: u64) -> String {
+    // 公历换算，见 Howard Hinnant 的 civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// 左对齐的表格，各列按最宽的单元格对齐（CJK 字符计为两列）
+pub(crate) fn table(rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| display_width(cell))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| {
+                    let padding = width - display_width(cell);
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect();
+            format!("  {}", cells.join("  ").trim_end())
+        })
+        .collect()
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "最近 {} 天（自 {} 起）的使用统计:",
+            self.days, self.since
+        )?;
+        if self.runs == 0 {
+            return write!(f, "  没有使用记录");
+        }
+
+        let hit_rate = self
+            .cache_hit_rate
+            .map_or("-".to_string(), |rate| format!("{:.1}%", rate * 100.0));
+        let latency = self
+            .average_latency_ms
+            .map_or("-".to_string(), |ms| format!("{:.1}s", ms as f64 / 1000.0));
+        let mut lines = table(&[
+            vec!["运行".to_string(), self.runs.to_string()],
+            vec!["翻译条目".to_string(), self.translations.to_string()],
+            vec!["翻译记忆命中率".to_string(), hit_rate],
+            vec!["平均耗时".to_string(), latency],
+        ]);
+
+        lines.push(String::new());
+        lines.push("每日翻译:".to_string());
+        let days: Vec<Vec<String>> = self
+            .per_day
+            .iter()
+            .map(|(day, count)| vec![day.clone(), count.to_string()])
+            .collect();
+        lines.extend(table(&days));
+
+        lines.push(String::new());
+        lines.push("常用语言对:".to_string());
+        let pairs: Vec<Vec<String>> = self
+            .top_pairs
+            .iter()
+            .map(|(pair, runs)| vec![pair.clone(), format!("{} 次", runs)])
+            .collect();
+        lines.extend(table(&pairs));
+
+        if !self.routes.is_empty() {
+            lines.push(String::new());
+            lines.push("路由规则:".to_string());
+            let routes: Vec<Vec<String>> = self
+                .routes
+                .iter()
+                .map(|(route, runs)| vec![route.clone(), format!("{} 次", runs)])
+                .collect();
+            lines.extend(table(&routes));
+        }
+
+        lines.push(String::new());
+        lines.push("模型用量:".to_string());
+        let mut models = vec![vec![
+            "模型".to_string(),
+            "输入 token".to_string(),
+            "输出 token".to_string(),
+            "估算费用".to_string(),
+        ]];
+        models.extend(self.models.iter().map(|usage| {
+            vec![
+                usage.model.clone(),
+                usage.prompt_tokens.to_string(),
+                usage.completion_tokens.to_string(),
+                usage
+                    .cost
+                    .map_or("-".to_string(), |cost| format!("${:.4}", cost)),
+            ]
+        }));
+        lines.extend(table(&models));
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history;
+    use crate::warning::Warnings;
+
+    /// 2024-03-10 12:00:00 UTC
+    const NOW: u64 = 1_710_072_000;
+
+    fn record(days_ago: u64, model: &str, to: &[&str], translations: usize) -> HistoryRecord {
+        HistoryRecord {
+            timestamp: NOW - days_ago * SECONDS_PER_DAY,
+            model: model.to_string(),
+            from: Some("zh".to_string()),
+            to: to.iter().map(|lang| lang.to_string()).collect(),
+            translations,
+            memory_hits: 1,
+            prompt_tokens: 1000,
+            completion_tokens: 500,
+            elapsed_ms: 2000,
+            route: None,
+        }
+    }
+
+    /// 合成的使用记录：区间内 4 次运行，区间外 1 次，另有 2 行损坏的记录
+    fn fixture() -> String {
+        let mut lines: Vec<String> = [
+            record(0, "gpt-4o", &["en"], 4),
+            HistoryRecord {
+                route: Some("zh:en".to_string()),
+                ..record(0, "gpt-4o", &["en"], 2)
+            },
+            record(2, "gemini-2.5-flash", &["ja", "en"], 2),
+            record(29, "custom-model", &[], 2),
+            record(30, "gpt-4o", &["en"], 100),
+        ]
+        .iter()
+        .map(|record| serde_json::to_string(record).unwrap())
+        .collect();
+        lines.insert(2, "{\"timestamp\": \"yesterday\"}".to_string());
+        lines.push("not json".to_string());
+        lines.join("\n")
+    }
+
+    fn fixture_records() -> Vec<HistoryRecord> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(history::HISTORY_FILE_NAME);
+        std::fs::write(&path, fixture()).unwrap();
+        let warnings = Warnings::new(true);
+        let records = history::read(&path, &warnings).unwrap();
+        assert_eq!(warnings.len(), 2);
+        records
+    }
+
+    #[test]
+    fn test_aggregate_fixture() {
+        let stats = aggregate(&fixture_records(), NOW, DEFAULT_DAYS);
+
+        assert_eq!(stats.since, "2024-02-10");
+        assert_eq!(stats.runs, 4);
+        assert_eq!(stats.translations, 10);
+        assert_eq!(
+            stats.per_day,
+            [
+                ("2024-02-10".to_string(), 2),
+                ("2024-03-08".to_string(), 2),
+                ("2024-03-10".to_string(), 6),
+            ]
+        );
+        assert_eq!(
+            stats.top_pairs,
+            [
+                ("zh→en".to_string(), 3),
+                ("zh→?".to_string(), 1),
+                ("zh→ja".to_string(), 1),
+            ]
+        );
+        assert_eq!(stats.routes, [("zh:en → gpt-4o".to_string(), 1)]);
+        assert_eq!(stats.cache_hit_rate, Some(0.4));
+        assert_eq!(stats.average_latency_ms, Some(2000));
+
+        let names: Vec<&str> = stats.models.iter().map(|m| m.model.as_str()).collect();
+        assert_eq!(names, ["custom-model", "gemini-2.5-flash", "gpt-4o"]);
+        let gpt = &stats.models[2];
+        assert_eq!((gpt.prompt_tokens, gpt.completion_tokens), (2000, 1000));
+        assert!((gpt.cost.unwrap() - 0.015).abs() < 1e-9);
+        assert_eq!(stats.models[0].cost, None);
+    }
+
+    #[test]
+    fn test_aggregate_empty() {
+        let stats = aggregate(&[], NOW, 7);
+        assert_eq!(stats.runs, 0);
+        assert!(stats.routes.is_empty());
+        assert_eq!(stats.cache_hit_rate, None);
+        assert_eq!(stats.average_latency_ms, None);
+        assert!(stats.to_string().ends_with("没有使用记录"));
+    }
+
+    #[test]
+    fn test_render_aligned_table() {
+        let rendered = aggregate(&fixture_records(), NOW, DEFAULT_DAYS).to_string();
+        assert!(rendered.contains("  翻译记忆命中率  40.0%"), "{}", rendered);
+        assert!(rendered.contains("  zh→en  3 次"), "{}", rendered);
+        assert!(
+            rendered.contains("路由规则:\n  zh:en → gpt-4o  1 次"),
+            "{}",
+            rendered
+        );
+        assert!(
+            rendered.contains("  gpt-4o            2000        1000        $0.0150"),
+            "{}",
+            rendered
+        );
+        assert!(rendered.contains("  custom-model      1000        500         -"));
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(NOW / SECONDS_PER_DAY), "2024-03-10");
+        assert_eq!(format_date(11_016), "2000-02-29");
+    }
+}
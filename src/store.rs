@@ -0,0 +1,229 @@
+//! 本地数据文件的并发安全读写
+//!
+//! 多个 transome 进程可能同时读写同一份数据文件（如并行执行的批量任务），
+//! 因此：
+//! - 追加记录时持有文件排他锁，每条记录以单次写入完成
+//! - 整体重写文件时先写入同目录下的临时文件，再原子重命名
+//! - 读取时跳过无法解析的记录并给出警告，不中断运行
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use fs2::FileExt;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::error::{Result, TransomeError};
+use crate::warning::{WarningKind, Warnings};
+
+/// 以 JSON Lines 格式追加一条记录
+///
+/// 文件不存在时创建，父目录需已存在。
+pub fn append_record<T: Serialize>(path: &Path, record: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(record)
+        .map_err(|e| TransomeError::json_error_with_context(e, "序列化记录"))?;
+    line.push(b'\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| io_error(e, "打开", path))?;
+
+    file.lock_exclusive()
+        .map_err(|e| io_error(e, "锁定", path))?;
+    let result = file.write_all(&line).map_err(|e| io_error(e, "写入", path));
+    // 文件关闭时锁会自动释放，这里显式释放以尽早让出
+    let _ = FileExt::unlock(&file);
+
+    result
+}
+
+/// 读取 JSON Lines 文件中的全部记录
+///
+/// 文件不存在时返回空列表；无法解析的行会被跳过并记录警告。
+pub fn read_records<T: DeserializeOwned>(path: &Path, warnings: &Warnings) -> Result<Vec<T>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error(e, "打开", path)),
+    };
+
+    file.lock_shared().map_err(|e| io_error(e, "锁定", path))?;
+    let content = io::read_to_string(&file).map_err(|e| io_error(e, "读取", path));
+    let _ = FileExt::unlock(&file);
+    let content = content?;
+
+    let mut records = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(e) => warnings.warn(
+                WarningKind::CorruptEntry,
+                format!(
+                    "'{}' 第 {} 行已损坏，已跳过：{}",
+                    path.display(),
+                    index + 1,
+                    e
+                ),
+            ),
+        }
+    }
+
+    Ok(records)
+}
+
+/// 原子地写入整个文件
+///
+/// 内容先写入同目录下的临时文件并同步到磁盘，再重命名为目标文件，
+/// 读取方只会看到完整的旧内容或新内容。
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let temp_path = temp_path_for(path);
+
+    let write = || -> io::Result<()> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    };
+
+    write().map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        io_error(e, "写入", path)
+    })
+}
+
+/// 生成同目录下、进程内唯一的临时文件路径
+fn temp_path_for(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        unique
+    ))
+}
+
+fn io_error(source: io::Error, action: &str, path: &Path) -> TransomeError {
+    TransomeError::io_error_with_context(source, format!("{} '{}'", action, path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::thread;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        writer: usize,
+        seq: usize,
+        text: String,
+    }
+
+    fn entry(writer: usize, seq: usize) -> Entry {
+        Entry {
+            writer,
+            seq,
+            // 较长的内容更容易暴露交错写入
+            text: format!("{}-{}-{}", writer, seq, "x".repeat(512)),
+        }
+    }
+
+    #[test]
+    fn test_read_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let warnings = Warnings::new(true);
+        let records: Vec<Entry> = read_records(&dir.path().join("none.jsonl"), &warnings).unwrap();
+        assert!(records.is_empty());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_lines_are_skipped_with_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        append_record(&path, &entry(0, 0)).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"writer\": 1, \"seq\n")
+            .unwrap();
+        append_record(&path, &entry(0, 1)).unwrap();
+
+        let warnings = Warnings::new(true);
+        let records: Vec<Entry> = read_records(&path, &warnings).unwrap();
+
+        assert_eq!(records, vec![entry(0, 0), entry(0, 1)]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings.to_vec()[0].kind, WarningKind::CorruptEntry);
+        assert!(warnings.to_vec()[0].message.contains("第 2 行"));
+    }
+
+    #[test]
+    fn test_concurrent_writers_produce_parseable_files() {
+        const WRITERS: usize = 16;
+        const RECORDS: usize = 50;
+
+        let dir = tempfile::tempdir().unwrap();
+        let log_path = dir.path().join("history.jsonl");
+        let snapshot_path = dir.path().join("cache.json");
+
+        thread::scope(|scope| {
+            for writer in 0..WRITERS {
+                let log_path = &log_path;
+                let snapshot_path = &snapshot_path;
+                scope.spawn(move || {
+                    for seq in 0..RECORDS {
+                        append_record(log_path, &entry(writer, seq)).unwrap();
+                        let snapshot = serde_json::to_vec(&entry(writer, seq)).unwrap();
+                        write_atomic(snapshot_path, &snapshot).unwrap();
+                    }
+                });
+            }
+        });
+
+        let warnings = Warnings::new(true);
+        let records: Vec<Entry> = read_records(&log_path, &warnings).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(records.len(), WRITERS * RECORDS);
+        for writer in 0..WRITERS {
+            let seqs: Vec<usize> = records
+                .iter()
+                .filter(|record| record.writer == writer)
+                .map(|record| record.seq)
+                .collect();
+            assert_eq!(seqs, (0..RECORDS).collect::<Vec<_>>());
+        }
+
+        let snapshot: Entry = serde_json::from_slice(&fs::read(&snapshot_path).unwrap()).unwrap();
+        assert_eq!(snapshot.seq, RECORDS - 1);
+
+        // 临时文件全部被重命名或清理
+        let leftovers = fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+}
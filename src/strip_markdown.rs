@@ -0,0 +1,133 @@
+//! 去除译文中的 Markdown 标记（`--strip-markdown`）
+//!
+//! 模型有时会给纯文本的译文加上没有要求的格式，如 `**加粗**`、`` `代码` `` 或链接。
+//! 这里只去掉行内标记：强调、加粗、删除线与行内代码的分隔符，链接与图片只保留文字；
+//! 整段被包在代码块围栏中的内容去掉围栏。标题、列表等块级结构可能本身就是译文的内容，
+//! 原样保留；不构成标记的字符（如 `2 * 3 * 4`、`snake_case`）也不受影响。
+
+use std::ops::Range;
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+
+/// 行内元素及其子内容在原文中的范围
+struct Element {
+    range: Range<usize>,
+    /// 第一个子内容的开始与最后一个子内容的结束，没有子内容时为 None
+    content: Option<Range<usize>>,
+}
+
+/// 去除文本中的行内 Markdown 标记，其余内容原样保留
+pub fn strip_markdown(text: &str) -> String {
+    let parser = Parser::new_ext(text, Options::ENABLE_STRIKETHROUGH).into_offset_iter();
+
+    // 需要删除或替换的范围，互不重叠
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    // 正在处理的元素，块级元素与不需要去除标记的元素为 None
+    let mut stack: Vec<Option<Element>> = Vec::new();
+
+    for (event, range) in parser {
+        if !matches!(event, Event::End(_)) {
+            extend_content(&mut stack, &range);
+        }
+        match event {
+            Event::Start(tag) => {
+                let strip = matches!(
+                    tag,
+                    Tag::Emphasis
+                        | Tag::Strong
+                        | Tag::Strikethrough
+                        | Tag::Link { .. }
+                        | Tag::Image { .. }
+                        | Tag::CodeBlock(CodeBlockKind::Fenced(_))
+                );
+                stack.push(strip.then_some(Element {
+                    range,
+                    content: None,
+                }));
+            }
+            Event::End(_) => {
+                // 没有子内容的元素（如 `[](地址)`）原样保留
+                if let Some(Some(Element {
+                    range,
+                    content: Some(content),
+                })) = stack.pop()
+                {
+                    edits.push((range.start..content.start, String::new()));
+                    edits.push((content.end..range.end, String::new()));
+                }
+            }
+            Event::Code(code) => edits.push((range, code.to_string())),
+            _ => {}
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut stripped = String::with_capacity(text.len());
+    let mut position = 0;
+    for (range, replacement) in edits {
+        if range.start < position {
+            continue;
+        }
+        stripped.push_str(&text[position..range.start]);
+        stripped.push_str(&replacement);
+        position = range.end;
+    }
+    stripped.push_str(&text[position..]);
+
+    // 代码块的结束围栏后通常带有换行，原文没有以换行结尾时不保留
+    if !text.ends_with('\n') {
+        stripped.truncate(stripped.trim_end_matches('\n').len());
+    }
+    stripped
+}
+
+/// 把一个事件的范围计入最内层需要去除标记的元素的子内容
+fn extend_content(stack: &mut [Option<Element>], range: &Range<usize>) {
+    if let Some(Some(element)) = stack.last_mut() {
+        element.content = Some(match element.content.take() {
+            Some(content) => content.start..range.end.max(content.end),
+            None => range.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_inline_markup() {
+        let cases = [
+            ("这是**重要**的通知", "这是重要的通知"),
+            ("*强调*与**加粗**", "强调与加粗"),
+            ("***两者***", "两者"),
+            ("~~删除~~", "删除"),
+            ("运行 `cargo build` 命令", "运行 cargo build 命令"),
+            ("``包含 ` 的代码``", "包含 ` 的代码"),
+            ("参见[文档](https://example.com \"标题\")。", "参见文档。"),
+            ("![示意图](diagram.png)", "示意图"),
+            ("**[`加粗的链接`](url)**", "加粗的链接"),
+            ("```\n整段译文\n```", "整段译文"),
+            ("```text\n第一行\n第二行\n```", "第一行\n第二行"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(strip_markdown(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_content_is_not_mangled() {
+        let unchanged = [
+            "2 * 3 * 4 = 24",
+            "snake_case_name 与 file_name.txt",
+            "价格为 $5，折扣 10%",
+            "- 第一项\n- 第二项",
+            "# 标题\n\n正文",
+            "[](empty-link)",
+            "第一行\n第二行",
+        ];
+        for input in unchanged {
+            assert_eq!(strip_markdown(input), input, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! 测试辅助工具
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::task::JoinHandle;
+
+use crate::batch::Clock;
+
+/// 可手动推进的测试时钟，`sleep` 立即推进时间并返回
+pub struct ManualClock {
+    base: Instant,
+    offset: Mutex<Duration>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            offset: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.offset.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.base + *self.offset.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        self.advance(duration);
+        Box::pin(std::future::ready(()))
+    }
+}
+
+/// 读取一个 HTTP 请求，返回小写的请求头与请求体；连接在请求完整前关闭时返回 None
+pub async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Option<(String, String)> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    let header_end = loop {
+        if let Some(position) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position + 4;
+        }
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buffer[..n]),
+        }
+    };
+    let headers = String::from_utf8_lossy(&request[..header_end - 4]).to_lowercase();
+    let length: usize = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .map_or(0, |value| value.trim().parse().unwrap());
+    while request.len() < header_end + length {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buffer[..n]),
+        }
+    }
+    let body = String::from_utf8_lossy(&request[header_end..]).into_owned();
+    Some((headers, body))
+}
+
+/// 写出一个 HTTP 响应，`keep_alive` 为 false 时要求客户端关闭连接
+pub async fn write_response<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    status: &str,
+    content_type: &str,
+    body: &str,
+    keep_alive: bool,
+) {
+    let connection = if keep_alive {
+        ""
+    } else {
+        "connection: close\r\n"
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\n{}\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        connection,
+        body
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+}
+
+/// 在本地启动只响应一次的服务，以指定的状态与内容响应
+///
+/// 返回地址与收到的请求头（小写）和请求体。
+pub async fn serve_raw(
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+) -> (String, JoinHandle<(String, String)>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let request = read_request(&mut stream).await.unwrap();
+        write_response(&mut stream, status, content_type, &body, false).await;
+        request
+    });
+    (address, handle)
+}
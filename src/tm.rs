@@ -0,0 +1,251 @@
+//! 翻译记忆（TM）文件
+//!
+//! `--tm` 指定的文件为 JSON Lines 格式，每行一条 `{"source": "...", "translation": "..."}`，
+//! 可以直接查看和编辑。翻译前先查找与原文完全相同的条目，命中时不发送请求；
+//! 未命中时翻译完成后把新条目追加到文件末尾。
+//!
+//! 带译者注翻译的条目另有 `note` 字段，查找时原文与译者注都相同才命中。
+//!
+//! 同一原文有多条记录时以最后一条为准，修正译文时追加一行即可。
+//! 追加在进程内依次进行，并持有文件排他锁（见 [`store::append_record`]），
+//! 批量模式下的并发翻译不会交错写入。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::store;
+use crate::warning::Warnings;
+
+/// 翻译记忆中的一条记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TmEntry {
+    pub source: String,
+    /// 翻译时附带的译者注
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub translation: String,
+}
+
+/// 查找条目的键：原文与译者注
+type TmKey = (String, Option<String>);
+
+fn key(source: &str, note: Option<&str>) -> TmKey {
+    (source.to_string(), note.map(str::to_string))
+}
+
+/// 翻译记忆
+#[derive(Debug)]
+pub struct TranslationMemory {
+    path: PathBuf,
+    entries: Mutex<HashMap<TmKey, String>>,
+}
+
+impl TranslationMemory {
+    /// 读取翻译记忆文件，文件不存在时从空记忆开始
+    ///
+    /// 无法解析的行会被跳过并记录警告。
+    pub fn load(path: &Path, warnings: &Warnings) -> Result<Self> {
+        let entries = store::read_records::<TmEntry>(path, warnings)?
+            .into_iter()
+            .map(|entry| ((entry.source, entry.note), entry.translation))
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 记忆中的条目数
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 查找原文与译者注都相同的条目
+    pub fn lookup(&self, source: &str, note: Option<&str>) -> Option<String> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&key(source, note))
+            .cloned()
+    }
+
+    /// 记录一条译文并追加到文件，与已有条目相同时不重复写入
+    pub fn record(&self, source: &str, note: Option<&str>, translation: &str) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let key = key(source, note);
+        if entries
+            .get(&key)
+            .is_some_and(|existing| existing == translation)
+        {
+            return Ok(());
+        }
+
+        store::append_record(
+            &self.path,
+            &TmEntry {
+                source: key.0.clone(),
+                note: key.1.clone(),
+                translation: translation.to_string(),
+            },
+        )?;
+        entries.insert(key, translation.to_string());
+        Ok(())
+    }
+
+    /// 先查找翻译记忆，未命中时调用 `translate` 并记录结果
+    pub async fn translate<T, F, Fut>(
+        &self,
+        source: &str,
+        note: Option<&str>,
+        translate: F,
+    ) -> anyhow::Result<T>
+    where
+        T: From<String> + AsRef<str>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        if let Some(translation) = self.lookup(source, note) {
+            return Ok(T::from(translation));
+        }
+
+        let translation = translate().await?;
+        self.record(source, note, translation.as_ref())?;
+        Ok(translation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn load(path: &Path) -> TranslationMemory {
+        TranslationMemory::load(path, &Warnings::new(true)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_hit_skips_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tm.jsonl");
+        fs::write(
+            &path,
+            "{\"source\": \"你好\", \"translation\": \"Hi\"}\n\
+            {\"source\": \"你好\", \"translation\": \"Hello\"}\n",
+        )
+        .unwrap();
+
+        let tm = load(&path);
+        assert_eq!(tm.len(), 1);
+
+        let requests = AtomicUsize::new(0);
+        let translation: String = tm
+            .translate("你好", None, || async {
+                requests.fetch_add(1, Ordering::SeqCst);
+                Ok("from api".to_string())
+            })
+            .await
+            .unwrap();
+
+        // 以最后一条记录为准，且不发送请求
+        assert_eq!(translation, "Hello");
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_miss_appends_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tm.jsonl");
+        let tm = load(&path);
+
+        let translation: String = tm
+            .translate("世界", None, || async { Ok("world".to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(translation, "world");
+        assert_eq!(tm.lookup("世界", None).as_deref(), Some("world"));
+
+        // 失败的翻译不记录
+        let result: anyhow::Result<String> = tm
+            .translate("失败", None, || async { Err(anyhow::anyhow!("boom")) })
+            .await;
+        assert!(result.is_err());
+
+        // 相同的译文不重复写入
+        tm.record("世界", None, "world").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 1);
+        assert_eq!(load(&path).lookup("世界", None).as_deref(), Some("world"));
+    }
+
+    #[test]
+    fn test_note_is_part_of_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tm.jsonl");
+        let tm = load(&path);
+
+        tm.record("保存", Some("按钮标签，最多 12 个字符"), "Save")
+            .unwrap();
+        tm.record("保存", Some("菜单项"), "Save As…").unwrap();
+        tm.record("保存", None, "Keep").unwrap();
+
+        let reloaded = load(&path);
+        assert_eq!(
+            reloaded
+                .lookup("保存", Some("按钮标签，最多 12 个字符"))
+                .as_deref(),
+            Some("Save")
+        );
+        assert_eq!(
+            reloaded.lookup("保存", Some("菜单项")).as_deref(),
+            Some("Save As…")
+        );
+        assert_eq!(reloaded.lookup("保存", None).as_deref(), Some("Keep"));
+
+        // 原文字段只包含原文，没有译者注的条目不写入 note 字段
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert!(lines[0].contains("\"source\":\"保存\""), "{}", lines[0]);
+        assert!(lines[0].contains("\"note\":\"按钮标签"), "{}", lines[0]);
+        assert!(!lines[2].contains("note"), "{}", lines[2]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_appends_are_serialized() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tm.jsonl");
+        let tm = load(&path);
+
+        let sources: Vec<String> = (0..50).map(|i| format!("原文 {}", i)).collect();
+        futures::future::join_all(sources.iter().map(|source| {
+            tm.translate(source, None, move || async move {
+                tokio::task::yield_now().await;
+                Ok::<_, anyhow::Error>(format!("translation of {}", source))
+            })
+        }))
+        .await;
+
+        let warnings = Warnings::new(true);
+        let reloaded = TranslationMemory::load(&path, &warnings).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(reloaded.len(), 50);
+        assert_eq!(
+            reloaded.lookup("原文 7", None).as_deref(),
+            Some("translation of 原文 7")
+        );
+    }
+}
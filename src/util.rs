@@ -0,0 +1,334 @@
+//! 文本处理工具函数
+
+use std::time::Duration;
+
+/// 不允许出现在行首的中文标点
+const NO_BREAK_BEFORE: &[char] = &[
+    '，', '。', '、', '；', '：', '！', '？', '）', '》', '」', '』', '”', '’', '…',
+];
+
+/// 判断字符是否为宽字符（CJK 等，终端中占两列）
+pub fn is_wide_char(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x20000..=0x2FFFD
+            | 0x30000..=0x3FFFD
+    )
+}
+
+/// 计算单个字符的显示宽度
+pub fn char_width(c: char) -> usize {
+    if c.is_control() {
+        0
+    } else if is_wide_char(c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// 计算字符串的显示宽度（CJK 字符计为 2）
+pub fn display_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// 换行时不可拆分的最小单元
+struct Token<'a> {
+    text: &'a str,
+    /// 原文中该单元前是否有空白
+    space_before: bool,
+}
+
+/// 判断单词是否为 URL（URL 整体不可拆分）
+fn is_url(word: &str) -> bool {
+    word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+}
+
+/// 将一行文本切分为换行单元
+///
+/// 以空白为单词边界；单词内部的 CJK 字符之间允许断行，URL 整体保持完整。
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+
+    for (i, word) in line.split_whitespace().enumerate() {
+        let space_before = i > 0;
+
+        if is_url(word) || !word.chars().any(is_wide_char) {
+            tokens.push(Token {
+                text: word,
+                space_before,
+            });
+            continue;
+        }
+
+        // 拆分 CJK 字符，连续的非 CJK 字符保持为一个单元
+        let mut start = 0;
+        let mut first = true;
+        for (idx, c) in word.char_indices() {
+            let end = idx + c.len_utf8();
+            let next_is_wide = word[end..].chars().next().is_some_and(is_wide_char);
+            let next_no_break = word[end..]
+                .chars()
+                .next()
+                .is_some_and(|n| NO_BREAK_BEFORE.contains(&n));
+
+            if end == word.len() || ((is_wide_char(c) || next_is_wide) && !next_no_break) {
+                tokens.push(Token {
+                    text: &word[start..end],
+                    space_before: space_before && first,
+                });
+                start = end;
+                first = false;
+            }
+        }
+    }
+
+    tokens
+}
+
+/// 对单行文本按显示宽度进行折行
+fn wrap_line(line: &str, width: usize, out: &mut Vec<String>) {
+    let indent_len = line.len() - line.trim_start().len();
+    let mut current = line[..indent_len].to_string();
+    let mut current_width = display_width(&current);
+    let mut has_content = false;
+
+    for token in tokenize(line) {
+        let token_width = display_width(token.text);
+        let gap = usize::from(token.space_before && has_content);
+
+        if has_content && current_width + gap + token_width > width {
+            out.push(std::mem::take(&mut current));
+            current_width = 0;
+        } else if gap == 1 {
+            current.push(' ');
+            current_width += 1;
+        }
+
+        current.push_str(token.text);
+        current_width += token_width;
+        has_content = true;
+    }
+
+    out.push(current);
+}
+
+/// 将文本按指定显示宽度折行
+///
+/// 折行规则：
+/// - 英文在空白处断行，CJK 字符之间可断行，中文句读不出现在行首
+/// - URL 和超过宽度的单词不拆分，单独成行
+/// - 代码块（``` 围栏）内的内容原样保留
+/// - 原有的换行和空行保持不变
+///
+/// `width` 为 0 时不进行折行。
+pub fn wrap_text(s: &str, width: usize) -> String {
+    if width == 0 {
+        return s.to_string();
+    }
+
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+
+    for line in s.lines() {
+        let is_fence = line.trim_start().starts_with("```");
+
+        if in_fence || is_fence || display_width(line) <= width {
+            lines.push(line.to_string());
+        } else {
+            wrap_line(line, width, &mut lines);
+        }
+
+        if is_fence {
+            in_fence = !in_fence;
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// 响应内容的摘录：合并空白，过长时截断
+pub fn body_excerpt(bytes: &[u8]) -> String {
+    const MAX_CHARS: usize = 200;
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return "（空响应）".to_string();
+    }
+    if text.chars().count() > MAX_CHARS {
+        format!("{}…", text.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// 解析时长字符串
+///
+/// 支持 `500ms`、`30s`、`10m`、`1h` 以及不带单位的秒数（如 `30`）。
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("无效的时长 '{}'，示例：500ms、30s、10m、1h", s))?;
+
+    let seconds = |multiplier: u64| {
+        value
+            .checked_mul(multiplier)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("时长 '{}' 过长", s))
+    };
+    let duration = match unit.trim() {
+        "ms" => Duration::from_millis(value),
+        "" | "s" => Duration::from_secs(value),
+        "m" => seconds(60)?,
+        "h" => seconds(3600)?,
+        other => {
+            return Err(format!(
+                "无效的时长单位 '{}'，支持的单位：ms、s、m、h",
+                other
+            ));
+        }
+    };
+
+    if duration.is_zero() {
+        return Err("时长必须大于 0".to_string());
+    }
+
+    Ok(duration)
+}
+
+/// 按 [`parse_duration`] 接受的格式显示时长，选用能精确表示的最大单位
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if !millis.is_multiple_of(1000) {
+        return format!("{}ms", millis);
+    }
+    match duration.as_secs() {
+        secs if secs != 0 && secs.is_multiple_of(3600) => format!("{}h", secs / 3600),
+        secs if secs != 0 && secs.is_multiple_of(60) => format!("{}m", secs / 60),
+        secs => format!("{}s", secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("hello"), 5);
+        assert_eq!(display_width("你好"), 4);
+        assert_eq!(display_width("hi 你好"), 7);
+        assert_eq!(display_width("，"), 2);
+    }
+
+    #[test]
+    fn test_wrap_text_english() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let wrapped = wrap_text(text, 10);
+        assert_eq!(wrapped, "The quick\nbrown fox\njumps over\nthe lazy\ndog");
+        for line in wrapped.lines() {
+            assert!(display_width(line) <= 10);
+        }
+    }
+
+    #[test]
+    fn test_wrap_text_cjk() {
+        let text = "敏捷的棕色狐狸跳过了懒狗";
+        let wrapped = wrap_text(text, 10);
+        assert_eq!(wrapped, "敏捷的棕色\n狐狸跳过了\n懒狗");
+    }
+
+    #[test]
+    fn test_wrap_text_cjk_punctuation_not_at_line_start() {
+        let wrapped = wrap_text("你好世界，再见", 8);
+        assert_eq!(wrapped, "你好世\n界，再见");
+    }
+
+    #[test]
+    fn test_wrap_text_mixed() {
+        let text = "使用 Rust 编写的命令行工具";
+        let wrapped = wrap_text(text, 12);
+        assert_eq!(wrapped, "使用 Rust 编\n写的命令行工\n具");
+        for line in wrapped.lines() {
+            assert!(display_width(line) <= 12);
+        }
+    }
+
+    #[test]
+    fn test_wrap_text_keeps_urls_intact() {
+        let text = "see https://example.com/a/very/long/path for details";
+        let wrapped = wrap_text(text, 10);
+        assert!(
+            wrapped
+                .lines()
+                .any(|line| line == "https://example.com/a/very/long/path")
+        );
+    }
+
+    #[test]
+    fn test_wrap_text_skips_code_fences() {
+        let code_line = "let translated_value = translator.translate(text).await;";
+        let text = format!("```rust\n{}\n```\nshort words here", code_line);
+        let wrapped = wrap_text(&text, 12);
+        assert!(wrapped.contains(code_line));
+        assert!(wrapped.ends_with("short words\nhere"));
+    }
+
+    #[test]
+    fn test_wrap_text_preserves_blank_lines() {
+        let wrapped = wrap_text("first paragraph\n\nsecond", 20);
+        assert_eq!(wrapped, "first paragraph\n\nsecond");
+    }
+
+    #[test]
+    fn test_wrap_text_zero_width_disables_wrapping() {
+        let text = "no wrapping at all here";
+        assert_eq!(wrap_text(text, 0), text);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_format_duration_round_trips() {
+        for text in ["500ms", "1500ms", "30s", "90s", "10m", "1h"] {
+            assert_eq!(format_duration(parse_duration(text).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("10d").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("-5s").is_err());
+        // 换算为秒时溢出
+        assert_eq!(
+            parse_duration("18446744073709551615h"),
+            Err("时长 '18446744073709551615h' 过长".to_string())
+        );
+        assert!(parse_duration("307445734561825861m").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! `--dir` 模式下收集目录中的文件
+//!
+//! 递归遍历目录，按路径排序后作为批量模式的输入。默认遵循目录中的
+//! `.gitignore`、`.ignore` 规则（不要求位于 git 仓库中），跳过隐藏文件与目录；`--exclude`
+//! 按 `.gitignore` 的写法额外排除文件或目录，`--no-ignore` 不读取忽略规则。
+//!
+//! 符号链接默认跳过；`--follow-symlinks` 时进入链接指向的文件与目录，链接指向自身的上级目录
+//! 形成循环时报错。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+
+/// 遍历目录的选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// 额外排除的模式，写法同 `.gitignore`
+    pub exclude: Vec<String>,
+    /// 不读取 `.gitignore`、`.ignore` 等忽略规则
+    pub no_ignore: bool,
+    /// 进入符号链接指向的文件与目录
+    pub follow_symlinks: bool,
+}
+
+/// 收集 `root` 下要翻译的文件，按路径排序
+pub fn collect_files(root: &Path, options: &WalkOptions) -> Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        bail!("'{}' 不是目录", root.display());
+    }
+
+    let mut overrides = OverrideBuilder::new(root);
+    for pattern in &options.exclude {
+        overrides
+            .add(&format!("!{}", pattern))
+            .with_context(|| format!("无效的排除模式 '{}'", pattern))?;
+    }
+
+    let walker = WalkBuilder::new(root)
+        .standard_filters(!options.no_ignore)
+        .hidden(true)
+        .require_git(false)
+        .git_global(false)
+        .follow_links(options.follow_symlinks)
+        .overrides(overrides.build()?)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                if let Some((ancestor, child)) = symlink_loop(&e) {
+                    bail!(
+                        "符号链接 '{}' 指向其上级目录 '{}'，形成循环\n\n\
+                        请使用 --exclude 排除该链接，或去掉 --follow-symlinks",
+                        child.display(),
+                        ancestor.display()
+                    );
+                }
+                return Err(e).with_context(|| format!("遍历目录 '{}' 失败", root.display()));
+            }
+        };
+        if entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// 遍历错误中的符号链接循环：上级目录与指向它的链接
+fn symlink_loop(error: &ignore::Error) -> Option<(&Path, &Path)> {
+    match error {
+        ignore::Error::Loop { ancestor, child } => Some((ancestor, child)),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop(err),
+        ignore::Error::Partial(errors) => errors.iter().find_map(symlink_loop),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// 创建测试用的目录树，返回根目录
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for path in [
+            "b.txt",
+            "a/z.md",
+            "a/y.txt",
+            "build/out.txt",
+            "node_modules/pkg/readme.md",
+            "docs/guide.md",
+            "docs/app.min.js",
+            ".hidden/notes.txt",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "text").unwrap();
+        }
+        fs::write(dir.path().join(".gitignore"), "build/\n").unwrap();
+        fs::write(dir.path().join(".ignore"), "node_modules\n").unwrap();
+        dir
+    }
+
+    /// 相对根目录的路径
+    fn collect(root: &Path, options: WalkOptions) -> Vec<String> {
+        collect_files(root, &options)
+            .unwrap()
+            .iter()
+            .map(|path| {
+                path.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ignore_files_are_respected() {
+        let dir = fixture();
+        assert_eq!(
+            collect(dir.path(), WalkOptions::default()),
+            [
+                "a/y.txt",
+                "a/z.md",
+                "b.txt",
+                "docs/app.min.js",
+                "docs/guide.md"
+            ]
+        );
+
+        // 不读取忽略规则时仍跳过隐藏文件
+        let options = WalkOptions {
+            no_ignore: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            collect(dir.path(), options),
+            [
+                "a/y.txt",
+                "a/z.md",
+                "b.txt",
+                "build/out.txt",
+                "docs/app.min.js",
+                "docs/guide.md",
+                "node_modules/pkg/readme.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_patterns() {
+        let dir = fixture();
+        let options = WalkOptions {
+            exclude: vec!["*.min.js".to_string(), "a".to_string()],
+            ..WalkOptions::default()
+        };
+        assert_eq!(collect(dir.path(), options), ["b.txt", "docs/guide.md"]);
+
+        assert!(
+            collect_files(dir.path().join("b.txt").as_path(), &WalkOptions::default()).is_err()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks() {
+        let dir = fixture();
+        std::os::unix::fs::symlink(dir.path().join("a"), dir.path().join("docs/linked")).unwrap();
+
+        // 默认跳过符号链接
+        assert_eq!(collect(dir.path(), WalkOptions::default()).len(), 5);
+
+        let follow = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let files = collect(dir.path(), follow.clone());
+        assert!(
+            files.contains(&"docs/linked/y.txt".to_string()),
+            "{:?}",
+            files
+        );
+
+        // 指向上级目录的链接形成循环
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("a/loop")).unwrap();
+        let error = collect_files(dir.path(), &follow).unwrap_err();
+        assert!(error.to_string().contains("形成循环"), "{}", error);
+        assert_eq!(collect(dir.path(), WalkOptions::default()).len(), 5);
+    }
+}
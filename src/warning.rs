@@ -0,0 +1,196 @@
+//! 非致命警告的收集与输出
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::error::TransomeError;
+
+/// 警告类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// API 密钥格式与所选提供商不符
+    KeyFormat,
+    /// 文档片段翻译失败，已保留原文
+    SegmentFailed,
+    /// 本地数据文件中的记录已损坏，已跳过
+    CorruptEntry,
+    /// 译文超出 `--max-output-ratio` 的长度上限
+    LengthExceeded,
+    /// 所选模型不支持的参数，已忽略
+    IgnoredParameter,
+    /// 译文中的占位符与原文不一致，已保留原文
+    PlaceholderMismatch,
+    /// 键的译文与同一对象中的其他键重复，已保留原文
+    DuplicateKey,
+    /// 专有名词的译名与此前确定的译名不一致
+    InconsistentName,
+    /// 输出编码无法表示部分字符，已替换为 `?`
+    LossyOutput,
+    /// 已关闭 TLS 证书验证（`--insecure-skip-tls-verify`）
+    InsecureTls,
+    /// 重新加载的配置无效，继续使用原配置（`--reload-config`）
+    ConfigRejected,
+    /// 所选模型已弃用，只作提示，`--deny-warnings` 不因此失败
+    Deprecated,
+}
+
+impl WarningKind {
+    /// 启用 `--deny-warnings` 时该类警告是否导致失败
+    pub fn is_deniable(self) -> bool {
+        self != WarningKind::Deprecated
+    }
+}
+
+/// 单条警告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl Warning {
+    /// 创建新的警告
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// 警告收集器
+///
+/// 在整个运行过程中传递，克隆后共享同一份警告列表。
+/// 警告在记录时立即以 `warning:` 前缀输出到 stderr（静默模式下不输出）。
+#[derive(Debug, Clone, Default)]
+pub struct Warnings {
+    items: Arc<Mutex<Vec<Warning>>>,
+    quiet: bool,
+}
+
+impl Warnings {
+    /// 创建警告收集器，`quiet` 为 true 时不输出到 stderr
+    pub fn new(quiet: bool) -> Self {
+        Self {
+            items: Arc::default(),
+            quiet,
+        }
+    }
+
+    /// 记录一条警告
+    pub fn push(&self, warning: Warning) {
+        if !self.quiet {
+            crate::progress::eprintln(format_args!("warning: {}", warning));
+        }
+        self.lock().push(warning);
+    }
+
+    /// 按类别和消息记录一条警告
+    pub fn warn(&self, kind: WarningKind, message: impl Into<String>) {
+        self.push(Warning::new(kind, message));
+    }
+
+    /// 是否为静默模式，静默模式下其他提示同样不输出
+    pub fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    /// 是否没有任何警告
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// 警告数量
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// 获取所有已记录警告的副本
+    pub fn to_vec(&self) -> Vec<Warning> {
+        self.lock().clone()
+    }
+
+    /// 在启用 `--deny-warnings` 时将警告提升为错误（弃用提示除外，见 [`WarningKind::is_deniable`]）
+    pub fn check_denied(&self, deny: bool) -> crate::error::Result<()> {
+        if !deny {
+            return Ok(());
+        }
+        let denied = self
+            .lock()
+            .iter()
+            .filter(|warning| warning.kind.is_deniable())
+            .count();
+        if denied > 0 {
+            return Err(TransomeError::General {
+                message: format!(
+                    "出现 {} 条警告，且启用了 --deny-warnings\n\n\
+                    解决警告中提到的问题，或去掉 --deny-warnings 参数后重试",
+                    denied
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Warning>> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings_collect_and_share() {
+        let warnings = Warnings::new(true);
+        let shared = warnings.clone();
+
+        shared.warn(WarningKind::KeyFormat, "key looks wrong");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings.to_vec()[0].kind, WarningKind::KeyFormat);
+        assert_eq!(warnings.to_vec()[0].message, "key looks wrong");
+    }
+
+    #[test]
+    fn test_deny_warnings_flips_status() {
+        let warnings = Warnings::new(true);
+        assert!(warnings.check_denied(true).is_ok());
+
+        warnings.warn(WarningKind::KeyFormat, "key looks wrong");
+        assert!(warnings.check_denied(false).is_ok());
+
+        let result = warnings.check_denied(true);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("--deny-warnings"));
+    }
+
+    #[test]
+    fn test_deprecation_notice_never_fails_the_run() {
+        let warnings = Warnings::new(true);
+        warnings.warn(WarningKind::Deprecated, "model is deprecated");
+        assert!(warnings.check_denied(true).is_ok());
+
+        warnings.warn(WarningKind::KeyFormat, "key looks wrong");
+        let error = warnings.check_denied(true).unwrap_err().to_string();
+        assert!(error.contains("出现 1 条警告"), "{}", error);
+    }
+
+    #[test]
+    fn test_warning_serialization() {
+        let warning = Warning::new(WarningKind::KeyFormat, "key looks wrong");
+        let json = serde_json::to_string(&warning).unwrap();
+        assert_eq!(json, r#"{"kind":"key_format","message":"key looks wrong"}"#);
+    }
+}
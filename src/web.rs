@@ -0,0 +1,279 @@
+//! 从网页读取待翻译文本（`--from-url`）
+//!
+//! 下载网页后按 readability 的思路提取正文：优先取 `<article>` 或 `<main>`，否则取 `<body>`；
+//! 跳过脚本、导航、侧栏、页脚与表单等非正文内容，按段落、标题、列表项等块输出，
+//! 块之间空一行。提取出的正文与其他输入一样进入常规的翻译流程。
+//!
+//! 下载使用 `--timeout` 指定的超时（未指定时为 [`DEFAULT_FETCH_TIMEOUT`]），
+//! 网页超过 [`MAX_PAGE_BYTES`] 时停止下载并报错。
+
+use std::time::Duration;
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node};
+
+use crate::error::{Result, TransomeError};
+
+/// 其中内容不属于正文的元素
+const BOILERPLATE_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "nav", "aside", "footer", "form", "iframe", "svg",
+    "button", "select",
+];
+
+/// 未指定 `--timeout` 时下载网页的超时
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 网页大小的上限（字节）
+pub const MAX_PAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// 正文所在的容器，按优先级排列
+const CONTENT_ROOTS: &[&str] = &["article", "main", "body"];
+
+/// 作为一段输出的块元素
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "blockquote",
+    "pre",
+    "figcaption",
+    "dt",
+    "dd",
+    "td",
+    "th",
+];
+
+/// 下载网页并提取正文
+///
+/// 网址不是 http(s) 或网页超过 [`MAX_PAGE_BYTES`] 时返回验证错误，连接失败或超时时返回
+/// 网络错误，服务返回错误状态时返回 [`TransomeError::FetchFailed`]，网页中没有正文时返回格式错误。
+pub async fn read(client: &reqwest::Client, url: &str) -> Result<String> {
+    let html = fetch(client, url, MAX_PAGE_BYTES).await?;
+    extract_text(&html).ok_or_else(|| {
+        TransomeError::format_error("HTML", format!("网页 '{}' 中没有可提取的正文", url))
+    })
+}
+
+/// 下载网页内容，超过 `max_bytes` 字节时停止下载并返回验证错误
+pub async fn fetch(client: &reqwest::Client, url: &str, max_bytes: usize) -> Result<String> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(TransomeError::validation_error(
+            "from_url",
+            "以 http:// 或 https:// 开头的网址",
+            url,
+        ));
+    }
+
+    let mut response = client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(TransomeError::fetch_failed(url, status.as_u16()));
+    }
+
+    let too_large = || {
+        TransomeError::validation_error(
+            "from_url",
+            format!("不超过 {} 字节的网页", max_bytes),
+            format!("网页 '{}' 超过大小上限", url),
+        )
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_bytes as u64)
+    {
+        return Err(too_large());
+    }
+    // 服务可能不声明或少报长度，边下载边检查
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// 提取 HTML 文档中的正文，没有正文时返回 None
+pub fn extract_text(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let root = CONTENT_ROOTS
+        .iter()
+        .find_map(|&name| find_element(&document, name))
+        .unwrap_or_else(|| document.root_element());
+
+    let mut blocks: Vec<String> = root
+        .descendants()
+        .filter(|node| is_element(*node, BLOCK_ELEMENTS) && !is_nested_block(*node, root))
+        .map(block_text)
+        .filter(|text| !text.is_empty())
+        .collect();
+
+    // 没有块元素时（如正文直接写在 body 中）取整个容器的文本
+    if blocks.is_empty() {
+        blocks.push(block_text(*root));
+    }
+
+    let text = blocks.join("\n\n");
+    (!text.is_empty()).then_some(text)
+}
+
+/// 文档中第一个指定名称的元素
+fn find_element<'a>(document: &'a Html, name: &str) -> Option<ElementRef<'a>> {
+    document
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .find(|element| element.value().name() == name)
+}
+
+fn is_element(node: NodeRef<'_, Node>, names: &[&str]) -> bool {
+    node.value()
+        .as_element()
+        .is_some_and(|element| names.contains(&element.name()))
+}
+
+/// 位于其他块或非正文元素之中的块不单独输出
+fn is_nested_block(node: NodeRef<'_, Node>, root: ElementRef<'_>) -> bool {
+    node.ancestors()
+        .take_while(|ancestor| ancestor.id() != root.id())
+        .any(|ancestor| {
+            is_element(ancestor, BLOCK_ELEMENTS) || is_element(ancestor, BOILERPLATE_ELEMENTS)
+        })
+}
+
+/// 块中的可见文本，连续的空白合并为一个空格；`<pre>` 保留原有的换行
+fn block_text(node: NodeRef<'_, Node>) -> String {
+    let text: String = node
+        .descendants()
+        .filter(|descendant| {
+            !descendant
+                .ancestors()
+                .take_while(|ancestor| ancestor.id() != node.id())
+                .any(|ancestor| is_element(ancestor, BOILERPLATE_ELEMENTS))
+        })
+        .filter_map(|descendant| match descendant.value() {
+            Node::Text(text) => Some(&**text),
+            _ => None,
+        })
+        .collect();
+
+    if is_element(node, &["pre"]) {
+        text.trim_matches('\n').to_string()
+    } else {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::serve_raw;
+
+    const ARTICLE: &str = r##"<!DOCTYPE html>
+<html>
+<head><title>示例</title><style>body { color: red; }</style></head>
+<body>
+  <nav><a href="/">首页</a> <a href="/about">关于</a></nav>
+  <article>
+    <h1>Hello  World</h1>
+    <p>First paragraph with <a href="#">a link</a>.</p>
+    <script>trackVisit();</script>
+    <ul><li>One</li><li><p>Two</p></li></ul>
+    <aside>Related articles</aside>
+  </article>
+  <footer>Copyright</footer>
+</body>
+</html>"##;
+
+    #[test]
+    fn test_extract_article() {
+        assert_eq!(
+            extract_text(ARTICLE).unwrap(),
+            "Hello World\n\nFirst paragraph with a link.\n\nOne\n\nTwo"
+        );
+    }
+
+    #[test]
+    fn test_extract_without_article() {
+        let html =
+            "<html><body><nav>Menu</nav><p>Body text</p><footer>Footer</footer></body></html>";
+        assert_eq!(extract_text(html).unwrap(), "Body text");
+
+        let html = "<html><body>Just some text <script>x()</script></body></html>";
+        assert_eq!(extract_text(html).unwrap(), "Just some text");
+
+        assert_eq!(
+            extract_text("<html><body><nav>Menu</nav></body></html>"),
+            None
+        );
+    }
+
+    /// 在本地启动只响应一次的服务
+    async fn serve_once(status: &'static str, body: &'static str) -> String {
+        let (address, _) = serve_raw(status, "text/html; charset=utf-8", body.to_string()).await;
+        address
+    }
+
+    #[tokio::test]
+    async fn test_read_from_server() {
+        let client = reqwest::Client::new();
+        let address = serve_once("200 OK", ARTICLE).await;
+        let text = read(&client, &format!("{}/article", address))
+            .await
+            .unwrap();
+        assert!(text.starts_with("Hello World\n\n"), "{}", text);
+
+        let address = serve_once("404 Not Found", "<p>missing</p>").await;
+        let error = read(&client, &address).await.unwrap_err();
+        assert!(matches!(
+            error,
+            TransomeError::FetchFailed {
+                status_code: 404,
+                ..
+            }
+        ));
+
+        let address = serve_once("200 OK", "<html><body><nav>Menu</nav></body></html>").await;
+        let error = read(&client, &address).await.unwrap_err();
+        assert!(
+            matches!(error, TransomeError::FormatError { .. }),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_limits_size() {
+        let client = reqwest::Client::new();
+        let address = serve_once("200 OK", ARTICLE).await;
+        let error = fetch(&client, &address, 64).await.unwrap_err();
+        assert!(
+            matches!(error, TransomeError::ValidationError { .. }),
+            "{}",
+            error
+        );
+        assert!(error.to_string().contains("超过大小上限"), "{}", error);
+
+        let address = serve_once("200 OK", ARTICLE).await;
+        let html = fetch(&client, &address, ARTICLE.len()).await.unwrap();
+        assert_eq!(html, ARTICLE);
+    }
+
+    #[tokio::test]
+    async fn test_read_rejects_other_schemes() {
+        let client = reqwest::Client::new();
+        let error = read(&client, "file:///etc/passwd").await.unwrap_err();
+        assert!(matches!(error, TransomeError::ValidationError { .. }));
+
+        // 未启动服务的地址
+        let error = read(&client, "http://127.0.0.1:1/").await.unwrap_err();
+        assert!(error.is_network_error(), "{}", error);
+    }
+}